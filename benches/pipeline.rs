@@ -0,0 +1,125 @@
+//! Measures the throughput of each stage of the pipeline over a generated synthetic site: parsing documents' front
+//! matter, extracting the text of their markdown, and traversing the whole contents directory.
+//!
+//! Run with `cargo bench`, optionally setting the number of pages to generate, e.g. `BENCH_PAGES=5000 cargo bench`.
+//! The site is made by the generator behind `gen-fixture` rather than an option of the benchmarks, as Criterion owns
+//! their command line, and so the same site can be generated with `gen-fixture --pages` to look into a slow stage.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use hugo_to_json::extract::{extract_text, ExtractOptions};
+use hugo_to_json::file_location::FileLocation;
+use hugo_to_json::fixture::generate_fixture;
+use hugo_to_json::settings::{FixtureSettings, FrontMatterFormat};
+use hugo_to_json::{convert, process_md_contents, Settings};
+
+/// The environment variable giving the number of pages to generate.
+const PAGES_VAR: &str = "BENCH_PAGES";
+
+/// The number of pages generated when none is given.
+const DEFAULT_PAGES: usize = 1000;
+
+/// How many times a whole site is traversed to measure it, which is fewer than Criterion's default as each takes a while.
+const TRAVERSAL_SAMPLES: usize = 10;
+
+fn pipeline(c: &mut Criterion) {
+    let pages = env::var(PAGES_VAR)
+        .ok()
+        .and_then(|pages| pages.parse().ok())
+        .unwrap_or(DEFAULT_PAGES);
+    let site = tempfile::Builder::new()
+        .prefix("not-a-dotfile-bench")
+        .tempdir()
+        .expect("Couldn't create a directory for the site");
+    let content_dir = site.path().join("content");
+    generate_fixture(&FixtureSettings {
+        out_dir: content_dir.clone(),
+        pages,
+        formats: vec![
+            FrontMatterFormat::Toml,
+            FrontMatterFormat::Yaml,
+            FrontMatterFormat::Json,
+        ],
+    })
+    .expect("Couldn't generate the site");
+    let documents = read_documents(&content_dir);
+    let bytes: usize = documents.iter().map(|(_, contents)| contents.len()).sum();
+    let throughput = Throughput::Bytes(bytes as u64);
+
+    let settings = Settings::default();
+    let mut group = c.benchmark_group("front matter");
+    group.throughput(throughput.clone());
+    group.bench_function("front matter and content", |b| {
+        b.iter(|| {
+            for (file_location, contents) in &documents {
+                black_box(process_md_contents(contents, file_location, &settings).ok());
+            }
+        });
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("strip");
+    group.throughput(throughput.clone());
+    group.bench_function("strip_markdown", |b| {
+        b.iter(|| {
+            for (_, contents) in &documents {
+                black_box(strip_markdown::strip_markdown(contents));
+            }
+        });
+    });
+    group.bench_function("pulldown-cmark", |b| {
+        b.iter(|| {
+            for (_, contents) in &documents {
+                black_box(extract_text(contents, ExtractOptions::default()));
+            }
+        });
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("traversal");
+    group.throughput(throughput);
+    group.sample_size(TRAVERSAL_SAMPLES);
+    group.bench_function("convert", |b| {
+        b.iter(|| {
+            let settings = Settings {
+                scan_path: content_dir.clone(),
+                ..Settings::default()
+            };
+            black_box(convert(settings).expect("Couldn't traverse the site"));
+        });
+    });
+    group.finish();
+}
+
+/// Reads every markdown file beneath the contents directory, along with its location.
+fn read_documents(content_dir: &Path) -> Vec<(FileLocation, String)> {
+    let mut paths = Vec::new();
+    let mut directories = vec![content_dir.to_owned()];
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(&directory).expect("Couldn't read the site") {
+            let path: PathBuf = entry.expect("Couldn't read the site").path();
+            if path.is_dir() {
+                directories.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "md") {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let file_location =
+                FileLocation::from_path(path, content_dir).expect("Couldn't locate a document");
+            let contents = fs::read_to_string(path).expect("Couldn't read a document");
+            (file_location, contents)
+        })
+        .collect()
+}
+
+criterion_group!(benches, pipeline);
+criterion_main!(benches);
@@ -0,0 +1,168 @@
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+
+/// The largest record Algolia accepts on its standard plans, in bytes.
+pub const MAX_RECORD_SIZE: usize = 10_000;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+/// A record ready to be uploaded to [Algolia](https://www.algolia.com/). Pages whose records would be too large are
+/// split into several records that share a `href`, so `href` should be set as the index's `attributeForDistinct`.
+pub struct AlgoliaRecord {
+    /// A unique identifier for the record, made from the page's href and the part.
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+    /// The position of this record's content in the page, starting at 0.
+    pub part: usize,
+    /// The page, with only this record's part of the content.
+    #[serde(flatten)]
+    pub page: PageIndex,
+}
+
+/// Splits a page into records of at most `MAX_RECORD_SIZE` bytes. The content is split between words.
+///
+/// # Errors
+/// Returns a `RecordSize` error if the page's records can't be made small enough by splitting its content, because the
+/// rest of the page, such as its description, takes up too much of each record.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::algolia::{records, MAX_RECORD_SIZE};
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/long.md"), Path::new("/blog/content"))?;
+/// let content = "word ".repeat(5000);
+/// let page = PageIndex::new(Some("Long"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), content, &location, None)?;
+///
+/// let records = records(&page)?;
+/// assert_eq!(records.len(), 3);
+/// assert!(records.iter().all(|record| serde_json::to_string(record).unwrap().len() <= MAX_RECORD_SIZE));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn records(page: &PageIndex) -> Result<Vec<AlgoliaRecord>, HugotoJsonError> {
+    let record = |part: usize, content: String| AlgoliaRecord {
+        object_id: format!("{}-{}", page.href, part),
+        part,
+        page: PageIndex {
+            content,
+            ..page.clone()
+        },
+    };
+
+    // The space left for content once everything else in a record is accounted for, with room for the largest part
+    // number
+    let empty_size = serialized_size(&record(usize::MAX, String::new()));
+    if empty_size > MAX_RECORD_SIZE {
+        return Err(HugotoJsonError::RecordSize {
+            href: page.href.clone(),
+            size: empty_size,
+            limit: MAX_RECORD_SIZE,
+        });
+    }
+    let budget = MAX_RECORD_SIZE - empty_size;
+
+    Ok(split_content(&page.content, budget)
+        .into_iter()
+        .enumerate()
+        .map(|(part, content)| record(part, content))
+        .collect())
+}
+
+/// Splits `content` into chunks whose JSON encoding fits in `budget` bytes, breaking between words where possible.
+fn split_content(content: &str, budget: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_size = 0;
+
+    for word in content.split_whitespace() {
+        for piece in split_word(word, budget) {
+            let piece_size = escaped_size(piece);
+            let separator_size = usize::from(!chunk.is_empty());
+            if !chunk.is_empty() && chunk_size + separator_size + piece_size > budget {
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_size = 0;
+            }
+            if !chunk.is_empty() {
+                chunk.push(' ');
+                chunk_size += 1;
+            }
+            chunk.push_str(piece);
+            chunk_size += piece_size;
+        }
+    }
+
+    if !chunk.is_empty() || chunks.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Splits a word too long to fit in `budget` bytes on its own into pieces that do.
+fn split_word(word: &str, budget: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (index, c) in word.char_indices() {
+        let char_size = escaped_size(&word[index..index + c.len_utf8()]);
+        if size + char_size > budget && index > start {
+            pieces.push(&word[start..index]);
+            start = index;
+            size = 0;
+        }
+        size += char_size;
+    }
+    pieces.push(&word[start..]);
+    pieces
+}
+
+/// The number of bytes `text` takes up inside a JSON string.
+fn escaped_size(text: &str) -> usize {
+    serialized_size(&text) - 2
+}
+
+fn serialized_size<T: serde::Serialize>(value: &T) -> usize {
+    serde_json::to_string(value).map_or(0, |serialized| serialized.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    #[test]
+    fn pages_too_large_without_their_content_are_errors() {
+        let page = TestPage::new("long")
+            .title("Long")
+            .description(&"x".repeat(12_000))
+            .content("abc def ghi jkl")
+            .build();
+        match records(&page) {
+            Err(HugotoJsonError::RecordSize { href, size, limit }) => {
+                assert_eq!(href, "/post/long/");
+                assert!(size > limit);
+            }
+            _ => panic!("This should be too large"),
+        }
+    }
+
+    #[test]
+    fn split_content_breaks_between_words() {
+        assert_eq!(
+            split_content("one two three four", 9),
+            vec!["one two", "three", "four"]
+        );
+        assert_eq!(split_content("", 9), vec![""]);
+    }
+
+    #[test]
+    fn split_content_counts_escaped_characters() {
+        assert_eq!(split_content("\"a\" \"b\"", 6), vec!["\"a\"", "\"b\""]);
+    }
+
+    #[test]
+    fn split_content_breaks_long_words() {
+        assert_eq!(split_content("abcdefgh", 3), vec!["abc", "def", "gh"]);
+    }
+}
@@ -0,0 +1,207 @@
+use tokio::fs;
+use tokio::sync::Semaphore;
+use tokio::task::{self, JoinError, JoinSet};
+
+use std::any::Any;
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+use crate::file_location::FileLocation;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::index::Index;
+use crate::operation_result::OperationResult;
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+use crate::traverse::{check_extension, check_size, log_result, process_document, Traverser};
+
+/// Converts a [Hugo](https://gohugo.io/) contents directory to an `Index` like `convert`, but as a future, for
+/// embedding the conversion in async programs, such as a server that rebuilds its index when a webhook is called.
+/// Files are read with `tokio::fs` and parsed on Tokio's blocking threads, at most as many at once as the settings'
+/// threads, so no thread of the runtime is blocked. It has to be awaited on a Tokio runtime, and dropping the future
+/// cancels the conversion. The cache file isn't read or written.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::{convert_async, Settings};
+/// use std::path::PathBuf;
+/// # use hugo_to_json::HugotoJsonError;
+///
+/// async fn rebuild() -> Result<String, HugotoJsonError> {
+///     let settings = Settings {
+///         scan_path: PathBuf::from("/home/example_user/documents/blog/contents/"),
+///         ..Settings::default()
+///     };
+///     let index = convert_async(settings).await?;
+///     Ok(index.to_json()?)
+/// }
+/// ```
+///
+/// # Errors
+/// Errors if the contents directory can't be accessed, or if processing a file panics. Files that fail to process are
+/// collected in `Index::errors`.
+pub async fn convert_async(settings: Settings) -> Result<Index, HugotoJsonError> {
+    convert_with(settings, process_document).await
+}
+
+/// Converts the contents directory like `convert_async`, processing each document's contents with `process`.
+async fn convert_with<P>(settings: Settings, process: P) -> Result<Index, HugotoJsonError>
+where
+    P: Fn(&str, &FileLocation, &Settings) -> Result<PageIndex, OperationResult>
+        + Copy
+        + Send
+        + 'static,
+{
+    let settings = Arc::new(settings);
+    let walking = Arc::clone(&settings);
+    let file_locations =
+        task::spawn_blocking(move || Traverser::new((*walking).clone()).collect_locations())
+            .await
+            .map_err(panicked)??;
+
+    let threads = match settings.threads() {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        threads => threads,
+    };
+    let permits = Arc::new(Semaphore::new(threads));
+    let mut tasks = JoinSet::new();
+    for (position, file_location) in file_locations.into_iter().enumerate() {
+        let permit = Arc::clone(&permits)
+            .acquire_owned()
+            .await
+            .map_err(|error| io::Error::other(error.to_string()))?;
+        let settings = Arc::clone(&settings);
+        tasks.spawn(async move {
+            // Held until the file is processed, which bounds how many are being read and parsed at once
+            let _permit = permit;
+            let contents = match read_document(&file_location, &settings).await {
+                Ok(contents) => contents,
+                Err(error) => return Ok((position, (file_location, Err(error)))),
+            };
+            let located_result = task::spawn_blocking(move || {
+                let result = process(&contents, &file_location, &settings);
+                (file_location, result)
+            })
+            .await?;
+            Ok::<_, JoinError>((position, located_result))
+        });
+    }
+
+    let mut located_results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        located_results.push(joined.and_then(|processed| processed).map_err(panicked)?);
+    }
+    // Files finish in whatever order, so put them back in the order they were walked
+    located_results.sort_unstable_by_key(|&(position, _)| position);
+
+    let mut pages = Vec::new();
+    let mut errors = Vec::new();
+    for (_, (file_location, result)) in located_results {
+        log_result(&file_location, &result);
+        match result {
+            Ok(page_index) => pages.push(page_index),
+            Err(OperationResult::Skip(_)) => (), // Skips don't need to be handled
+            Err(error) => errors.push(error),
+        }
+    }
+    crate::finish_pages(&settings, &mut pages);
+    Ok(Index::new(pages, errors))
+}
+
+/// Reads the document at `file_location`, unless it would be skipped for its extension or size.
+async fn read_document(
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<String, OperationResult> {
+    check_extension(file_location)?;
+    let size = fs::metadata(&file_location.absolute_path).await?.len();
+    check_size(file_location, size, settings)?;
+    let contents = fs::read(&file_location.absolute_path).await?;
+    String::from_utf8(contents)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error).into())
+}
+
+/// Returns the error a task that panicked or was cancelled resolves the conversion to.
+fn panicked(error: JoinError) -> HugotoJsonError {
+    let message = match error.try_into_panic() {
+        Ok(payload) => format!("The conversion panicked: {}", panic_message(&*payload)),
+        Err(_) => String::from("The conversion was cancelled"),
+    };
+    HugotoJsonError::Io(io::Error::other(message))
+}
+
+/// Returns the message a panic was raised with, if it was a string.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("no message")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::path::PathBuf;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn content_dir() -> tempfile::TempDir {
+        let content_dir = tempfile::Builder::new()
+            .prefix("not-a-dotfile-async")
+            .tempdir()
+            .unwrap();
+        std::fs::write(
+            content_dir.path().join("example.md"),
+            "+++\ntitle = \"Example\"\ndate = \"2019-01-25\"\n+++\nContents",
+        )
+        .unwrap();
+        std::fs::write(content_dir.path().join("notes.txt"), "Not a page").unwrap();
+        content_dir
+    }
+
+    #[test]
+    fn converts_on_the_runtime() {
+        let content_dir = content_dir();
+        let settings = Settings {
+            scan_path: content_dir.path().to_owned(),
+            ..Settings::default()
+        };
+        let index = block_on(convert_async(settings)).unwrap();
+        assert_eq!(index.pages.len(), 1);
+        assert_eq!(index.pages[0].href, "/example/");
+        assert!(index.errors.is_empty());
+
+        let settings = Settings {
+            scan_path: PathBuf::from("/does/not/exist"),
+            ..Settings::default()
+        };
+        assert!(block_on(convert_async(settings)).is_err());
+    }
+
+    #[test]
+    fn resolves_to_an_error_if_processing_panics() {
+        let content_dir = content_dir();
+        let settings = Settings {
+            scan_path: content_dir.path().to_owned(),
+            ..Settings::default()
+        };
+        let result = block_on(convert_with(settings, |_, _, _| {
+            panic!("Something went wrong")
+        }));
+        match result {
+            Err(HugotoJsonError::Io(error)) => assert_eq!(
+                error.to_string(),
+                "The conversion panicked: Something went wrong"
+            ),
+            _ => panic!("This should be an error"),
+        }
+    }
+}
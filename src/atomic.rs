@@ -0,0 +1,103 @@
+use std::ffi::OsString;
+use std::fs::{self, create_dir_all, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::constants;
+
+/// A file that's written beside its destination and only moved over it, in a single rename, once it's complete. Readers
+/// of the destination never see a partly written file, and a run that fails leaves the file that was there as it was.
+/// The written file is removed if it's dropped before it's committed.
+pub(crate) struct AtomicFile {
+    file: File,
+    path: PathBuf,
+    destination: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    /// Creates a file to be moved to `destination`, in the same directory so it can be renamed over it.
+    ///
+    /// # Errors
+    /// Errors if the directory can't be created or the file can't be created in it.
+    pub(crate) fn create(destination: &Path) -> io::Result<Self> {
+        create_dir_all(destination.with_file_name(constants::EMPTY_STRING))?;
+        // Hidden and named after the process, so runs writing to the same place don't write to the same file
+        let mut name = OsString::from(".");
+        name.push(destination.file_name().unwrap_or_default());
+        name.push(format!(".{}.tmp", process::id()));
+        let path = destination.with_file_name(name);
+        Ok(Self {
+            file: File::create(&path)?,
+            path,
+            destination: destination.to_path_buf(),
+            committed: false,
+        })
+    }
+
+    /// Returns where the file is written until it's committed.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns where the file is moved to once it's committed.
+    pub(crate) fn destination(&self) -> &Path {
+        &self.destination
+    }
+
+    /// Moves the file over its destination.
+    ///
+    /// # Errors
+    /// Errors if the file can't be flushed to disk or moved.
+    pub(crate) fn commit(mut self) -> io::Result<()> {
+        self.file.sync_all()?;
+        fs::rename(&self.path, &self.destination)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            fs::remove_file(&self.path).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destinations_are_only_replaced_once_committed() {
+        let directory = tempfile::tempdir().unwrap();
+        let destination = directory.path().join("index.json");
+        fs::write(&destination, "[]").unwrap();
+
+        let mut abandoned = AtomicFile::create(&destination).unwrap();
+        abandoned.write_all(b"[{").unwrap();
+        let abandoned_path = abandoned.path().to_path_buf();
+        drop(abandoned);
+        assert!(!abandoned_path.exists());
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "[]");
+
+        let mut file = AtomicFile::create(&destination).unwrap();
+        file.write_all(b"[{}]").unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "[]");
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "[{}]");
+        assert_eq!(fs::read_dir(directory.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,295 @@
+use serde_json::Value as JsonValue;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+
+use crate::constants;
+use crate::error_report::ErrorRecord;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::operation_result::OperationResult;
+use crate::page_index::PageIndex;
+use crate::settings::{CheckSettings, Settings};
+use crate::table::lookup;
+use crate::traverse::{date_normalizer, Traverser};
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+/// The rules the `check` subcommand checks the front matter of each page against, read from the `[check]` table of the
+/// config file, e.g. `required = ["description"]`.
+pub struct CheckRules {
+    /// Front matter fields every page must have, such as `description`. Empty fields and lists count as missing.
+    #[serde(default)]
+    pub required: Vec<String>,
+    /// The only tags pages may have. Pages may have any tags if this is empty.
+    #[serde(default)]
+    pub allowed_tags: Vec<String>,
+    /// The fewest characters a description may have. Pages without a description aren't checked.
+    #[serde(default)]
+    pub min_description_length: Option<usize>,
+    /// The most characters a description may have.
+    #[serde(default)]
+    pub max_description_length: Option<usize>,
+}
+
+impl CheckRules {
+    /// Adds the options of the `check` subcommand to the rules. Description lengths given as options replace those in
+    /// the rules.
+    #[must_use]
+    pub fn with_options(mut self, options: &CheckSettings) -> Self {
+        self.required.extend(options.required.iter().cloned());
+        self.allowed_tags
+            .extend(options.allowed_tags.iter().cloned());
+        self.min_description_length = options
+            .min_description_length
+            .or(self.min_description_length);
+        self.max_description_length = options
+            .max_description_length
+            .or(self.max_description_length);
+        self
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+/// A rule broken by a page, or a file that couldn't be processed so couldn't be checked.
+pub struct Violation {
+    /// The absolute path of the file.
+    pub path: String,
+    /// The 1-based line of the front matter key that broke the rule, if it's there.
+    pub line: Option<usize>,
+    /// The rule that was broken.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path, line, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Checks every page in the contents directory against the rules in the settings and the options of the `check`
+/// subcommand, printing each rule broken as `path:line: message`. Files that fail to process break the rules too.
+///
+/// # Errors
+/// Errors if the contents directory can't be read or any rule is broken.
+pub fn run_check(settings: &Settings, options: &CheckSettings) -> Result<(), HugotoJsonError> {
+    info!("Checking {:?}", settings.scan_paths());
+    let rules = settings.check_rules.clone().with_options(options);
+    let violations = check(settings, &rules)?;
+    for violation in &violations {
+        println!("{violation}");
+    }
+
+    if violations.is_empty() {
+        info!("Every page passed the check");
+        Ok(())
+    } else {
+        let files: BTreeSet<&str> = violations
+            .iter()
+            .map(|violation| violation.path.as_str())
+            .collect();
+        Err(HugotoJsonError::Check {
+            violations: violations.len(),
+            files: files.len(),
+        })
+    }
+}
+
+/// Checks every page in the contents directory against `rules`, returning each rule broken in the order the files were
+/// walked.
+///
+/// # Errors
+/// Errors if the contents directory can't be read.
+pub fn check(settings: &Settings, rules: &CheckRules) -> Result<Vec<Violation>, HugotoJsonError> {
+    // Every extra field is kept so any of them can be required, so pages cached for the index can't be used
+    let settings = Settings {
+        all_fields: true,
+        cache_file: None,
+        ..settings.clone()
+    };
+    let mut violations = Vec::new();
+    for (file_location, result) in Traverser::new(settings.clone()).traverse_locations()? {
+        match result {
+            Ok(page) => {
+                // The file is read again to find the lines of the keys that break rules
+                let contents = fs::read_to_string(&file_location.absolute_path).unwrap_or_default();
+                violations.extend(check_page(
+                    &page,
+                    &file_location.absolute_path,
+                    &contents,
+                    rules,
+                    &settings,
+                ));
+            }
+            Err(OperationResult::Skip(_)) => (), // Skipped files, such as drafts, aren't published
+            Err(ref error) => {
+                let record = ErrorRecord::new(&file_location, error);
+                violations.push(Violation {
+                    path: record.path,
+                    line: record.line,
+                    message: record.message,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+/// Checks a page read from the file at `path` against `rules`. `contents` is the file's contents, used to find the
+/// line of each key that breaks a rule.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::check::{check_page, CheckRules};
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::settings::Settings;
+/// use std::path::Path;
+/// # use hugo_to_json::operation_result::OperationResult;
+///
+/// let contents = "+++\ntitle = \"Rust\"\ndate = \"2019-01-25\"\ntags = [\"rust\", \"go\"]\n+++\nContents";
+/// let location = FileLocation::from_path(Path::new("/blog/content/rust.md"), Path::new("/blog/content"))?;
+/// let tags = vec![String::from("rust"), String::from("go")];
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), tags, Vec::new(), String::new(), &location, None)?;
+///
+/// let rules = CheckRules {
+///     required: vec![String::from("description")],
+///     allowed_tags: vec![String::from("rust")],
+///     ..CheckRules::default()
+/// };
+/// let violations = check_page(&page, "/blog/content/rust.md", contents, &rules, &Settings::default());
+/// assert_eq!(violations[0].to_string(), "/blog/content/rust.md: `description` is required");
+/// assert_eq!(violations[1].to_string(), "/blog/content/rust.md:4: tag `go` isn't allowed");
+/// # Ok::<(), OperationResult>(())
+/// ```
+pub fn check_page(
+    page: &PageIndex,
+    path: &str,
+    contents: &str,
+    rules: &CheckRules,
+    settings: &Settings,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut violation = |field: &str, message: String| {
+        violations.push(Violation {
+            path: path.to_owned(),
+            line: key_line(contents, settings.fields.key(field)),
+            message,
+        });
+    };
+
+    let record = serde_json::to_value(page).unwrap_or_default();
+    for field in &rules.required {
+        if lookup(&record, field).is_none_or(is_empty) {
+            violation(field, format!("`{field}` is required"));
+        }
+    }
+
+    let normalizer = date_normalizer(settings);
+    for (field, date) in &[
+        (constants::DATE, &page.date),
+        (constants::LASTMOD, &page.lastmod),
+    ] {
+        if !date.is_empty() && normalizer.to_instant(date).is_none() {
+            violation(field, format!("`{field}` `{date}` can't be parsed"));
+        }
+    }
+
+    if !rules.allowed_tags.is_empty() {
+        for tag in &page.tags {
+            if !rules.allowed_tags.contains(tag) {
+                violation(constants::TAGS, format!("tag `{tag}` isn't allowed"));
+            }
+        }
+    }
+
+    let description_length = page.description.chars().count();
+    if description_length > 0 {
+        if let Some(min) = rules
+            .min_description_length
+            .filter(|&min| description_length < min)
+        {
+            violation(
+                constants::DESCRIPTION,
+                format!("description is {description_length} characters, fewer than {min}"),
+            );
+        }
+        if let Some(max) = rules
+            .max_description_length
+            .filter(|&max| description_length > max)
+        {
+            violation(
+                constants::DESCRIPTION,
+                format!("description is {description_length} characters, more than {max}"),
+            );
+        }
+    }
+
+    violations
+}
+
+fn is_empty(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => true,
+        JsonValue::String(text) => text.trim().is_empty(),
+        JsonValue::Array(items) => items.is_empty(),
+        JsonValue::Object(fields) => fields.is_empty(),
+        JsonValue::Bool(_) | JsonValue::Number(_) => false,
+    }
+}
+
+/// Finds the 1-based line of `key` in the front matter at the start of `contents`, written as TOML, YAML or JSON.
+fn key_line(contents: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{key}\"");
+    let mut lines = contents.lines().enumerate();
+    let fence = lines.next().map(|(_, line)| line.trim_end())?;
+    for (index, line) in lines {
+        let line = line.trim();
+        if line == fence || (fence == "{" && line == "}") {
+            break;
+        }
+        let rest = line
+            .strip_prefix(quoted.as_str())
+            .or_else(|| line.strip_prefix(key));
+        if let Some(rest) = rest {
+            if rest.trim_start().starts_with(&['=', ':'][..]) {
+                return Some(index + 1);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_keys_in_each_front_matter_format() {
+        let toml = "+++\ntitle = \"Rust\"\ndescription=\"A\"\n+++\ndescription = \"B\"";
+        assert_eq!(key_line(toml, "description"), Some(3));
+        let yaml = "---\ntitle: Rust\ntags:\n  - rust\n---\n";
+        assert_eq!(key_line(yaml, "tags"), Some(3));
+        let json = "{\n  \"title\": \"Rust\",\n  \"date\" : \"2019\"\n}\n";
+        assert_eq!(key_line(json, "date"), Some(3));
+        assert_eq!(key_line(toml, "tags"), None);
+    }
+
+    #[test]
+    fn options_add_to_the_configured_rules() {
+        let rules = CheckRules {
+            required: vec![String::from("description")],
+            max_description_length: Some(160),
+            ..CheckRules::default()
+        }
+        .with_options(&CheckSettings {
+            required: vec![String::from("tags")],
+            max_description_length: Some(120),
+            ..CheckSettings::default()
+        });
+        assert_eq!(rules.required, ["description", "tags"]);
+        assert_eq!(rules.max_description_length, Some(120));
+    }
+}
@@ -0,0 +1,171 @@
+use sha2::{Digest, Sha256};
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic::AtomicFile;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::settings::Settings;
+use crate::sign;
+
+/// The extension added to the name of an index for its checksum file, e.g. `index.json.sha256`.
+pub const EXTENSION: &str = "sha256";
+
+/// Returns the SHA-256 digest of the file at `path`, in lowercase hex.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::checksum::sha256;
+/// use std::io::Write;
+///
+/// let mut file = tempfile::NamedTempFile::new()?;
+/// write!(file, "[]")?;
+/// assert_eq!(sha256(file.path())?, "4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// # Errors
+/// Errors if the file can't be read.
+pub fn sha256(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the path of the checksum file of the index at `path`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::checksum::checksum_path;
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(checksum_path(Path::new("public/index.json")), PathBuf::from("public/index.json.sha256"));
+/// ```
+#[must_use]
+pub fn checksum_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// Checks the index is written to a file if the settings ask for its checksum, its signature or for it to be left
+/// untouched when it's unchanged, as none of them can be done for an index written to stdout, posted or uploaded.
+///
+/// # Errors
+/// Errors naming the first of those options given if the index isn't written to a file.
+pub(crate) fn check_written_to_a_file(settings: &Settings) -> Result<(), HugotoJsonError> {
+    let written_to_a_file =
+        settings.output.is_some() && settings.post_url.is_none() && settings.upload.is_none();
+    let options = [
+        ("--checksum", settings.checksum),
+        ("--sign", settings.sign.is_some()),
+        ("--skip-unchanged", settings.skip_unchanged),
+    ];
+    match options.iter().find(|(_, given)| *given) {
+        Some((option, _)) if !written_to_a_file => Err(HugotoJsonError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{option} needs the index written to a path given with --output"),
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Moves a written index over its destination, unless the settings skip unchanged indexes and it's the same as the
+/// index already there, which is left untouched so its modification time is kept. Writes the index's checksum file if
+/// the settings ask for one, in the format `sha256sum --check` reads, and its signature if they ask for it to be
+/// signed.
+///
+/// # Errors
+/// Errors if either index can't be read, the index can't be signed, or the index, its checksum or its signature can't
+/// be written. The index isn't moved if it can't be signed.
+pub(crate) fn commit(settings: &Settings, file: AtomicFile) -> Result<(), HugotoJsonError> {
+    let destination = file.destination().to_path_buf();
+    let signature = match settings.sign {
+        Some(ref signer) => Some(sign::sign(signer, file.path())?),
+        None => None,
+    };
+    if settings.checksum || settings.skip_unchanged {
+        commit_unless_unchanged(settings, file)?;
+    } else {
+        file.commit()?;
+    }
+    if let Some((signature, public_key)) = signature {
+        sign::write_signature(&destination, &signature, &public_key)?;
+    }
+    Ok(())
+}
+
+fn commit_unless_unchanged(settings: &Settings, file: AtomicFile) -> Result<(), HugotoJsonError> {
+    let destination = file.destination().to_path_buf();
+    let digest = sha256(file.path())?;
+    if settings.skip_unchanged && destination.exists() && sha256(&destination)? == digest {
+        info!("Left {} as it's unchanged", destination.to_string_lossy());
+    } else {
+        file.commit()?;
+    }
+
+    if settings.checksum {
+        let mut line = OsString::from(format!("{digest}  "));
+        line.push(destination.file_name().unwrap_or_default());
+        let line = format!("{}\n", line.to_string_lossy());
+        let path = checksum_path(&destination);
+        // Like the index, an unchanged checksum is left untouched
+        if fs::read_to_string(&path).ok().as_ref() != Some(&line) {
+            fs::write(&path, line)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_index(settings: &Settings, destination: &Path, index: &str) {
+        let mut file = AtomicFile::create(destination).unwrap();
+        file.write_all(index.as_bytes()).unwrap();
+        commit(settings, file).unwrap();
+    }
+
+    #[test]
+    fn unchanged_indexes_are_left_untouched() {
+        let directory = tempfile::tempdir().unwrap();
+        let destination = directory.path().join("index.json");
+        let settings = Settings {
+            checksum: true,
+            skip_unchanged: true,
+            ..Settings::default()
+        };
+        write_index(&settings, &destination, "[]");
+        assert_eq!(
+            fs::read_to_string(checksum_path(&destination)).unwrap(),
+            "4f53cda18c2baa0c0354bb5f9a3ecbe5ed12ab4d8e11ba873c2f11161202b945  index.json\n"
+        );
+
+        let modified = |path: &Path| fs::metadata(path).unwrap().modified().unwrap();
+        let written = (
+            modified(&destination),
+            modified(&checksum_path(&destination)),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        write_index(&settings, &destination, "[]");
+        assert_eq!(
+            (
+                modified(&destination),
+                modified(&checksum_path(&destination))
+            ),
+            written
+        );
+        assert_eq!(fs::read_dir(directory.path()).unwrap().count(), 2);
+
+        write_index(&settings, &destination, "[{}]");
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "[{}]");
+        assert!(fs::read_to_string(checksum_path(&destination))
+            .unwrap()
+            .starts_with(&sha256(&destination).unwrap()));
+    }
+}
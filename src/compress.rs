@@ -0,0 +1,162 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::settings::Compression;
+
+/// The size of the buffer Brotli compresses through.
+const BROTLI_BUFFER_SIZE: usize = 4096;
+/// Brotli's highest quality, as the index is compressed once and served many times.
+const BROTLI_QUALITY: u32 = 11;
+/// The base 2 logarithm of Brotli's window size, the value its command line tool uses.
+const BROTLI_WINDOW: u32 = 22;
+
+impl Compression {
+    /// The extension added to compressed files, e.g. `gz` for `index.json.gz`.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Brotli => "br",
+        }
+    }
+}
+
+/// Returns where an index is written: `path`, with the compression's extension added if there is one and it's missing.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::compress::compressed_path;
+/// use hugo_to_json::settings::Compression;
+/// use std::path::{Path, PathBuf};
+///
+/// let path = Path::new("public/index.json");
+/// assert_eq!(compressed_path(path, Some(Compression::Gzip)), PathBuf::from("public/index.json.gz"));
+/// assert_eq!(compressed_path(path, None), PathBuf::from("public/index.json"));
+/// ```
+#[must_use]
+pub fn compressed_path(path: &Path, compression: Option<Compression>) -> PathBuf {
+    match compression {
+        Some(compression) if path.extension() != Some(OsStr::new(compression.extension())) => {
+            let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+            file_name.push(".");
+            file_name.push(compression.extension());
+            path.with_file_name(file_name)
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// A writer that compresses what's written to it, if there's a compression.
+pub enum Encoder<W: Write> {
+    /// Writes without compressing.
+    Plain(W),
+    /// Writes gzip.
+    Gzip(GzEncoder<W>),
+    /// Writes Brotli. Its compressor is boxed as it's much larger than the others.
+    Brotli(Box<brotli::CompressorWriter<W>>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Creates a writer that compresses into `writer`.
+    pub fn new(writer: W, compression: Option<Compression>) -> Self {
+        match compression {
+            None => Encoder::Plain(writer),
+            Some(Compression::Gzip) => {
+                Encoder::Gzip(GzEncoder::new(writer, flate2::Compression::best()))
+            }
+            Some(Compression::Brotli) => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(
+                writer,
+                BROTLI_BUFFER_SIZE,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            ))),
+        }
+    }
+
+    /// Writes the end of the compressed stream and returns the writer it was written to.
+    ///
+    /// # Errors
+    /// Errors if the end of the stream can't be written.
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Plain(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Brotli(mut encoder) => {
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Plain(writer) => writer.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Brotli(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(writer) => writer.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Brotli(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Reads a file written with `compression`, decompressing it.
+///
+/// # Errors
+/// Errors if the file can't be read or decompressed, or isn't UTF-8.
+pub fn read_to_string(path: &Path, compression: Option<Compression>) -> io::Result<String> {
+    let file = File::open(path)?;
+    let mut contents = String::new();
+    match compression {
+        None => io::BufReader::new(file).read_to_string(&mut contents)?,
+        Some(Compression::Gzip) => GzDecoder::new(file).read_to_string(&mut contents)?,
+        Some(Compression::Brotli) => {
+            brotli::Decompressor::new(file, BROTLI_BUFFER_SIZE).read_to_string(&mut contents)?
+        }
+    };
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_each_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        for compression in &[None, Some(Compression::Gzip), Some(Compression::Brotli)] {
+            let path = compressed_path(&dir.path().join("index.json"), *compression);
+            let mut encoder = Encoder::new(File::create(&path).unwrap(), *compression);
+            encoder.write_all(b"[{\"title\":\"Example\"}]").unwrap();
+            encoder.finish().unwrap();
+
+            assert_eq!(
+                read_to_string(&path, *compression).unwrap(),
+                "[{\"title\":\"Example\"}]"
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_path_keeps_an_existing_extension() {
+        assert_eq!(
+            compressed_path(Path::new("index.json.br"), Some(Compression::Brotli)),
+            PathBuf::from("index.json.br")
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::check::CheckRules;
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::lunr::Boosts;
+
+/// The config file read from the current directory when no other is given.
+pub const CONFIG_FILE_NAME: &str = "hugotojson.toml";
+
+/// The hidden config file read from the current directory when there's no `hugotojson.toml`.
+pub const HIDDEN_CONFIG_FILE_NAME: &str = ".hugotojson.toml";
+
+/// The options that choose the profile and the config file it's in, which profiles can't set.
+const PROFILE_OPTIONS: [&str; 2] = ["config", "profile"];
+
+/// The index fields that front matter keys can be mapped to.
+const MAPPABLE_FIELDS: [&str; 17] = [
+    constants::DRAFT,
+    constants::TITLE,
+    constants::SLUG,
+    constants::URL,
+    constants::DATE,
+    constants::PUBLISH_DATE,
+    constants::EXPIRY_DATE,
+    constants::LASTMOD,
+    constants::DESCRIPTION,
+    constants::TAGS,
+    constants::CATEGORIES,
+    constants::SERIES,
+    constants::KEYWORDS,
+    constants::ALIASES,
+    constants::WEIGHT,
+    constants::TYPE,
+    constants::LAYOUT,
+];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+/// Options read from a `hugotojson.toml` file.
+pub struct Config {
+    /// Maps front matter keys to the index fields they're read into.
+    #[serde(default)]
+    pub fields: FieldMapping,
+    /// Options for the Lunr output format.
+    #[serde(default)]
+    pub lunr: LunrConfig,
+    /// The rules the `check` subcommand checks pages against, read from the `[check]` table.
+    #[serde(default)]
+    pub check: CheckRules,
+    /// Named sets of options, chosen with `--profile`, read from the `[profiles]` table.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+/// Options for the Lunr output format, read from the `[lunr]` table.
+pub struct LunrConfig {
+    /// How much matches in each field count, e.g. `boosts = { title = 10, tags = 5, content = 1 }`.
+    #[serde(default)]
+    pub boosts: Boosts,
+}
+
+impl Config {
+    /// Reads and checks a config file.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use hugo_to_json::config::Config;
+    /// use std::path::Path;
+    /// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+    ///
+    /// let config = Config::load(Path::new("hugotojson.toml"))?;
+    /// # Ok::<(), HugotoJsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Errors if the file can't be read, isn't valid TOML or maps a key to a field that doesn't exist.
+    pub fn load(path: &Path) -> Result<Self, HugotoJsonError> {
+        let config_error = |reason: String| HugotoJsonError::Config {
+            path: path.to_string_lossy().into_owned(),
+            reason,
+        };
+
+        let contents = fs::read_to_string(path)?;
+        let config: Self =
+            toml::from_str(&contents).map_err(|err| config_error(err.to_string()))?;
+
+        if let Some(field) = config
+            .fields
+            .0
+            .values()
+            .find(|field| !MAPPABLE_FIELDS.contains(&field.as_str()))
+        {
+            return Err(config_error(format!(
+                "`{field}` is not a field that can be mapped to"
+            )));
+        }
+
+        for (name, profile) in &config.profiles {
+            for (option, value) in &profile.0 {
+                if PROFILE_OPTIONS.contains(&option.replace('_', "-").as_str()) {
+                    return Err(config_error(format!(
+                        "Profile `{name}` can't set `{option}`"
+                    )));
+                }
+                let values = match value {
+                    toml::Value::Array(values) => values.as_slice(),
+                    value => std::slice::from_ref(value),
+                };
+                if values
+                    .iter()
+                    .any(|value| Profile::arg_value(value).is_none())
+                {
+                    return Err(config_error(format!(
+                        "`{option}` in profile `{name}` must be a string, number, boolean or array of them"
+                    )));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the path of the config file in the current directory: `hugotojson.toml`, or `.hugotojson.toml` if only
+    /// it exists.
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        let path = PathBuf::from(CONFIG_FILE_NAME);
+        let hidden_path = PathBuf::from(HIDDEN_CONFIG_FILE_NAME);
+        if !path.exists() && hidden_path.exists() {
+            hidden_path
+        } else {
+            path
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+/// A named set of options, such as `format = "lunr"` or `include = ["post/**"]`, keyed by their long names on the
+/// command line. Flags are set with `true`.
+pub struct Profile(BTreeMap<String, toml::Value>);
+
+impl Profile {
+    /// Returns the long name of each option the profile sets and its value, or `None` for a flag. Options with an
+    /// array of values are returned once for each value, and flags set to `false` aren't returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::config::Profile;
+    ///
+    /// let profile: Profile = toml::from_str("format = \"csv\"\npretty = true\nskip_code_blocks = false\ninclude = [\"post/**\", \"talks/**\"]").unwrap();
+    /// assert_eq!(
+    ///     profile.options(),
+    ///     vec![
+    ///         (String::from("format"), Some(String::from("csv"))),
+    ///         (String::from("include"), Some(String::from("post/**"))),
+    ///         (String::from("include"), Some(String::from("talks/**"))),
+    ///         (String::from("pretty"), None),
+    ///     ]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn options(&self) -> Vec<(String, Option<String>)> {
+        let mut options = Vec::new();
+        for (option, value) in &self.0 {
+            let long = option.replace('_', "-");
+            let values = match value {
+                toml::Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                match value {
+                    toml::Value::Boolean(true) => options.push((long.clone(), None)),
+                    toml::Value::Boolean(false) => (),
+                    value => {
+                        if let Some(value) = Self::arg_value(value) {
+                            options.push((long.clone(), Some(value)));
+                        }
+                    }
+                }
+            }
+        }
+        options
+    }
+
+    /// Returns a value as it's written on the command line, or `None` if it can't be.
+    fn arg_value(value: &toml::Value) -> Option<String> {
+        match value {
+            toml::Value::String(value) => Some(value.clone()),
+            toml::Value::Integer(value) => Some(value.to_string()),
+            toml::Value::Float(value) => Some(value.to_string()),
+            toml::Value::Boolean(value) => Some(value.to_string()),
+            toml::Value::Datetime(value) => Some(value.to_string()),
+            toml::Value::Array(_) | toml::Value::Table(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+/// Maps front matter keys to the index fields they're read into, for themes that use nonstandard key names.
+/// For example, mapping `subtitle` to `description` reads the description from `subtitle`.
+pub struct FieldMapping(BTreeMap<String, String>);
+
+impl FieldMapping {
+    /// Creates a mapping from pairs of front matter keys and index fields.
+    pub fn new<I: IntoIterator<Item = (String, String)>>(mapping: I) -> Self {
+        Self(mapping.into_iter().collect())
+    }
+
+    /// Returns the front matter key that `field` is read from.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::config::FieldMapping;
+    ///
+    /// let mapping = FieldMapping::new(vec![(String::from("subtitle"), String::from("description"))]);
+    /// assert_eq!(mapping.key("description"), "subtitle");
+    /// assert_eq!(mapping.key("title"), "title");
+    /// ```
+    #[must_use]
+    pub fn key<'a>(&'a self, field: &'a str) -> &'a str {
+        self.0
+            .iter()
+            .find(|(_, mapped_field)| mapped_field.as_str() == field)
+            .map_or(field, |(key, _)| key.as_str())
+    }
+
+    /// Returns true if `key` is mapped to an index field.
+    #[must_use]
+    pub fn is_mapped(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn loads_field_mapping() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "[fields]\nsubtitle = \"description\"\ntopics = \"tags\"\n"
+        )
+        .unwrap();
+
+        let config = Config::load(file.path()).unwrap();
+        assert_eq!(config.fields.key(constants::DESCRIPTION), "subtitle");
+        assert_eq!(config.fields.key(constants::TAGS), "topics");
+        assert_eq!(config.fields.key(constants::TITLE), "title");
+    }
+
+    #[test]
+    fn loads_lunr_boosts_with_defaults() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "[lunr]\nboosts = {{ title = 20 }}\n").unwrap();
+
+        let config = Config::load(file.path()).unwrap();
+        assert_eq!(
+            config.lunr.boosts,
+            Boosts {
+                title: 20.0,
+                ..Boosts::default()
+            }
+        );
+    }
+
+    #[test]
+    fn loads_profiles() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "[profiles.search]\nformat = \"lunr\"\nfields = [\"title\", \"href\"]\n[profiles.feed]\nrss = \"feed.xml\"\nrss-limit = 10\n"
+        )
+        .unwrap();
+
+        let config = Config::load(file.path()).unwrap();
+        assert_eq!(
+            config.profiles["feed"].options(),
+            vec![
+                (String::from("rss"), Some(String::from("feed.xml"))),
+                (String::from("rss-limit"), Some(String::from("10"))),
+            ]
+        );
+        assert_eq!(config.profiles["search"].options().len(), 3);
+    }
+
+    #[test]
+    fn rejects_profiles_with_tables() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "[profiles.search]\nformat = {{ name = \"lunr\" }}\n").unwrap();
+
+        match Config::load(file.path()).unwrap_err() {
+            HugotoJsonError::Config { reason, .. } => assert!(reason.contains("format")),
+            err => panic!("Unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "[fields]\nsubtitle = \"subheading\"\n").unwrap();
+
+        match Config::load(file.path()).unwrap_err() {
+            HugotoJsonError::Config { reason, .. } => assert!(reason.contains("subheading")),
+            err => panic!("Unexpected error {}", err),
+        }
+    }
+}
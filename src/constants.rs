@@ -1,16 +1,62 @@
 pub const FORWARD_SLASH: &str = "/";
+pub const BACK_SLASH: &str = "\\";
 pub const EMPTY_STRING: &str = "";
 pub const TOML_FENCE: &str = "+++";
 pub const YAML_FENCE: &str = "---";
 pub const MARKDOWN_EXTENSION: &str = "md";
+pub const ASCIIDOC_EXTENSION: &str = "adoc";
+pub const RST_EXTENSION: &str = "rst";
+pub const ROOT_SECTION_INDEX: &str = "index";
+pub const SECTION_FILE_STEM: &str = "_index";
+pub const BUNDLE_FILE_STEM: &str = "index";
+pub const SUMMARY_DIVIDER: &str = "<!--more-->";
+pub const WORDS_PER_MINUTE: usize = 212;
 
 pub const DRAFT: &str = "draft";
 pub const TITLE: &str = "title";
 pub const SLUG: &str = "slug";
 pub const URL: &str = "url";
+pub const HREF: &str = "href";
 pub const DATE: &str = "date";
+pub const PUBLISH_DATE: &str = "publishDate";
+pub const EXPIRY_DATE: &str = "expiryDate";
+pub const LASTMOD: &str = "lastmod";
 pub const DESCRIPTION: &str = "description";
 pub const TAGS: &str = "tags";
 pub const CATEGORIES: &str = "categories";
 pub const SERIES: &str = "series";
 pub const KEYWORDS: &str = "keywords";
+pub const ALIASES: &str = "aliases";
+pub const AUTHOR: &str = "author";
+pub const AUTHORS: &str = "authors";
+pub const AUTHOR_NAME: &str = "name";
+pub const IMAGES: &str = "images";
+pub const FEATURED_IMAGE: &str = "featured_image";
+pub const COVER: &str = "cover";
+pub const COVER_IMAGE: &str = "image";
+pub const WEIGHT: &str = "weight";
+pub const TYPE: &str = "type";
+pub const LAYOUT: &str = "layout";
+pub const CASCADE: &str = "cascade";
+pub const HEADLESS: &str = "headless";
+pub const BUILD_OPTIONS: &str = "_build";
+pub const PARAMS: &str = "params";
+pub const BUILD_LIST: &str = "list";
+pub const BUILD_RENDER: &str = "render";
+pub const LANGUAGE: &str = "language";
+pub const SITEMAP: &str = "sitemap";
+pub const MENU: &str = "menu";
+pub const MENUS: &str = "menus";
+pub const MENU_NAME: &str = "name";
+pub const MENU_PARENT: &str = "parent";
+pub const MENU_IDENTIFIER: &str = "identifier";
+pub const LINK_TITLE: &str = "linkTitle";
+pub const LOCATION: &str = "location";
+pub const LOCATION_NAME: &str = "name";
+pub const GENERATED_AT: &str = "generated_at";
+pub const GENERATOR_VERSION: &str = "generator_version";
+pub const PAGE_COUNT: &str = "page_count";
+pub const PAGES: &str = "pages";
+pub const CHANGEFREQ: &str = "changefreq";
+pub const PRIORITY: &str = "priority";
+pub const CONTENT: &str = "content";
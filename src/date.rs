@@ -0,0 +1,528 @@
+use chrono::{
+    DateTime, FixedOffset, NaiveDate, NaiveDateTime, Offset, SecondsFormat, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+use std::fmt;
+use std::str::FromStr;
+
+const NAIVE_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const NAIVE_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Formats that are always accepted, tried before any configured formats.
+const DEFAULT_FORMATS: [&str; 3] = [
+    NAIVE_DATE_TIME_FORMAT,
+    "%Y-%m-%d %H:%M:%S",
+    NAIVE_DATE_FORMAT,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The languages month and day names can be written in.
+pub enum DateLocale {
+    /// English. Names are parsed as is.
+    English,
+    /// French.
+    French,
+    /// German.
+    German,
+    /// Spanish.
+    Spanish,
+    /// Italian.
+    Italian,
+    /// Dutch.
+    Dutch,
+    /// Portuguese.
+    Portuguese,
+}
+
+impl DateLocale {
+    /// Pairs of localised names and their English equivalents, including common abbreviations.
+    #[allow(clippy::too_many_lines)]
+    fn names(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            DateLocale::English => &[],
+            DateLocale::French => &[
+                ("janvier", "January"),
+                ("janv", "Jan"),
+                ("février", "February"),
+                ("fevrier", "February"),
+                ("févr", "Feb"),
+                ("mars", "March"),
+                ("avril", "April"),
+                ("avr", "Apr"),
+                ("mai", "May"),
+                ("juin", "June"),
+                ("juillet", "July"),
+                ("juil", "Jul"),
+                ("août", "August"),
+                ("aout", "August"),
+                ("septembre", "September"),
+                ("sept", "Sep"),
+                ("octobre", "October"),
+                ("oct", "Oct"),
+                ("novembre", "November"),
+                ("nov", "Nov"),
+                ("décembre", "December"),
+                ("decembre", "December"),
+                ("déc", "Dec"),
+                ("lundi", "Monday"),
+                ("mardi", "Tuesday"),
+                ("mercredi", "Wednesday"),
+                ("jeudi", "Thursday"),
+                ("vendredi", "Friday"),
+                ("samedi", "Saturday"),
+                ("dimanche", "Sunday"),
+            ],
+            DateLocale::German => &[
+                ("januar", "January"),
+                ("jänner", "January"),
+                ("februar", "February"),
+                ("märz", "March"),
+                ("maerz", "March"),
+                ("mär", "Mar"),
+                ("april", "April"),
+                ("mai", "May"),
+                ("juni", "June"),
+                ("juli", "July"),
+                ("august", "August"),
+                ("september", "September"),
+                ("oktober", "October"),
+                ("okt", "Oct"),
+                ("november", "November"),
+                ("dezember", "December"),
+                ("dez", "Dec"),
+                ("montag", "Monday"),
+                ("dienstag", "Tuesday"),
+                ("mittwoch", "Wednesday"),
+                ("donnerstag", "Thursday"),
+                ("freitag", "Friday"),
+                ("samstag", "Saturday"),
+                ("sonntag", "Sunday"),
+            ],
+            DateLocale::Spanish => &[
+                ("enero", "January"),
+                ("ene", "Jan"),
+                ("febrero", "February"),
+                ("marzo", "March"),
+                ("abril", "April"),
+                ("abr", "Apr"),
+                ("mayo", "May"),
+                ("junio", "June"),
+                ("julio", "July"),
+                ("agosto", "August"),
+                ("ago", "Aug"),
+                ("septiembre", "September"),
+                ("setiembre", "September"),
+                ("octubre", "October"),
+                ("noviembre", "November"),
+                ("diciembre", "December"),
+                ("dic", "Dec"),
+                ("lunes", "Monday"),
+                ("martes", "Tuesday"),
+                ("miércoles", "Wednesday"),
+                ("jueves", "Thursday"),
+                ("viernes", "Friday"),
+                ("sábado", "Saturday"),
+                ("domingo", "Sunday"),
+            ],
+            DateLocale::Italian => &[
+                ("gennaio", "January"),
+                ("gen", "Jan"),
+                ("febbraio", "February"),
+                ("marzo", "March"),
+                ("aprile", "April"),
+                ("maggio", "May"),
+                ("mag", "May"),
+                ("giugno", "June"),
+                ("giu", "Jun"),
+                ("luglio", "July"),
+                ("lug", "Jul"),
+                ("agosto", "August"),
+                ("ago", "Aug"),
+                ("settembre", "September"),
+                ("set", "Sep"),
+                ("ottobre", "October"),
+                ("ott", "Oct"),
+                ("novembre", "November"),
+                ("dicembre", "December"),
+                ("dic", "Dec"),
+                ("lunedì", "Monday"),
+                ("martedì", "Tuesday"),
+                ("mercoledì", "Wednesday"),
+                ("giovedì", "Thursday"),
+                ("venerdì", "Friday"),
+                ("sabato", "Saturday"),
+                ("domenica", "Sunday"),
+            ],
+            DateLocale::Dutch => &[
+                ("januari", "January"),
+                ("februari", "February"),
+                ("maart", "March"),
+                ("mrt", "Mar"),
+                ("april", "April"),
+                ("mei", "May"),
+                ("juni", "June"),
+                ("juli", "July"),
+                ("augustus", "August"),
+                ("september", "September"),
+                ("oktober", "October"),
+                ("okt", "Oct"),
+                ("november", "November"),
+                ("december", "December"),
+                ("maandag", "Monday"),
+                ("dinsdag", "Tuesday"),
+                ("woensdag", "Wednesday"),
+                ("donderdag", "Thursday"),
+                ("vrijdag", "Friday"),
+                ("zaterdag", "Saturday"),
+                ("zondag", "Sunday"),
+            ],
+            DateLocale::Portuguese => &[
+                ("janeiro", "January"),
+                ("fevereiro", "February"),
+                ("fev", "Feb"),
+                ("março", "March"),
+                ("marco", "March"),
+                ("abril", "April"),
+                ("abr", "Apr"),
+                ("maio", "May"),
+                ("junho", "June"),
+                ("julho", "July"),
+                ("agosto", "August"),
+                ("ago", "Aug"),
+                ("setembro", "September"),
+                ("set", "Sep"),
+                ("outubro", "October"),
+                ("out", "Oct"),
+                ("novembro", "November"),
+                ("dezembro", "December"),
+                ("dez", "Dec"),
+                ("segunda-feira", "Monday"),
+                ("terça-feira", "Tuesday"),
+                ("quarta-feira", "Wednesday"),
+                ("quinta-feira", "Thursday"),
+                ("sexta-feira", "Friday"),
+                ("sábado", "Saturday"),
+                ("domingo", "Sunday"),
+            ],
+        }
+    }
+
+    /// Replaces localised month and day names in `date` with their English equivalents.
+    fn translate(self, date: &str) -> String {
+        let names = self.names();
+        let mut translated = String::with_capacity(date.len());
+        let mut word = String::new();
+
+        // Words are runs of letters or hyphens, so `segunda-feira` is treated as one word
+        for c in date.chars().chain(std::iter::once(' ')) {
+            if c.is_alphabetic() || (c == '-' && !word.is_empty()) {
+                word.push(c);
+                continue;
+            }
+
+            if !word.is_empty() {
+                let lowercase = word.to_lowercase();
+                match names.iter().find(|(name, _)| *name == lowercase) {
+                    Some((_, english)) => translated.push_str(english),
+                    None => translated.push_str(&word),
+                }
+                word.clear();
+            }
+            translated.push(c);
+        }
+
+        translated.pop();
+        translated
+    }
+}
+
+impl FromStr for DateLocale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Accept both `fr` and region qualified forms like `fr_FR` or `fr-CA`
+        let language = s
+            .trim()
+            .split(&['_', '-'][..])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_ref() {
+            "en" => Ok(DateLocale::English),
+            "fr" => Ok(DateLocale::French),
+            "de" => Ok(DateLocale::German),
+            "es" => Ok(DateLocale::Spanish),
+            "it" => Ok(DateLocale::Italian),
+            "nl" => Ok(DateLocale::Dutch),
+            "pt" => Ok(DateLocale::Portuguese),
+            _ => Err(format!("Unsupported date locale `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for DateLocale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let locale = match *self {
+            DateLocale::English => "en",
+            DateLocale::French => "fr",
+            DateLocale::German => "de",
+            DateLocale::Spanish => "es",
+            DateLocale::Italian => "it",
+            DateLocale::Dutch => "nl",
+            DateLocale::Portuguese => "pt",
+        };
+        write!(f, "{locale}")
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Parses front matter dates written in any of a set of formats and normalises them to RFC 3339.
+pub struct DateNormalizer {
+    formats: Vec<String>,
+    locale: DateLocale,
+    timezone: Option<Tz>,
+}
+
+impl DateNormalizer {
+    /// Creates a `DateNormalizer` that accepts RFC 3339 dates, `YYYY-MM-DD` dates and any of the given [chrono format strings](https://docs.rs/chrono/0.4/chrono/format/strftime/index.html).
+    /// Month and day names are read in the given locale.
+    #[must_use]
+    pub fn new(formats: Vec<String>, locale: Option<DateLocale>) -> Self {
+        Self {
+            formats,
+            locale: locale.unwrap_or(DateLocale::English),
+            timezone: None,
+        }
+    }
+
+    /// Resolves every date to an offset in `timezone`. Dates and times without an offset are taken to be local to `timezone`, and dates without a time are taken to be midnight.
+    #[must_use]
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Parses `date` and returns it normalised to RFC 3339.
+    /// Without a timezone, dates without a time are returned as `YYYY-MM-DD`, and times without an offset as `YYYY-MM-DDTHH:MM:SS`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::date::{DateLocale, DateNormalizer};
+    ///
+    /// let normalizer = DateNormalizer::new(vec![String::from("%d %B %Y")], Some(DateLocale::French));
+    /// assert_eq!(normalizer.normalize("14 juillet 2019"), Some(String::from("2019-07-14")));
+    /// assert_eq!(normalizer.normalize("not a date"), None);
+    ///
+    /// let normalizer = normalizer.with_timezone("Europe/London".parse().unwrap());
+    /// assert_eq!(normalizer.normalize("14 juillet 2019"), Some(String::from("2019-07-14T00:00:00+01:00")));
+    /// ```
+    #[must_use]
+    pub fn normalize(&self, date: &str) -> Option<String> {
+        let parsed = self.parse(date)?;
+
+        match self.timezone {
+            Some(timezone) => parsed.resolve(timezone).map(|date_time| {
+                date_time
+                    .with_timezone(&date_time.offset().fix())
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, false)
+            }),
+            None => Some(parsed.to_string()),
+        }
+    }
+
+    /// Parses `date` to an instant so it can be compared. Dates without an offset are taken to be local to the
+    /// timezone, or UTC if there isn't one.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::date::DateNormalizer;
+    ///
+    /// let normalizer = DateNormalizer::new(Vec::new(), None);
+    /// let date = normalizer.to_instant("2019-07-14").unwrap();
+    /// assert_eq!(date.to_rfc3339(), "2019-07-14T00:00:00+00:00");
+    /// ```
+    #[must_use]
+    pub fn to_instant(&self, date: &str) -> Option<DateTime<Utc>> {
+        let parsed = self.parse(date)?;
+        let resolved = parsed.resolve(self.timezone.unwrap_or(Tz::UTC))?;
+        Some(resolved.with_timezone(&Utc))
+    }
+
+    /// Parses `date` and returns its calendar date, in its own offset or, for dates without an offset, as written.
+    /// Dates with an offset are moved to the timezone first if there is one.
+    ///
+    /// # Examples
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use hugo_to_json::date::DateNormalizer;
+    ///
+    /// let normalizer = DateNormalizer::new(Vec::new(), None);
+    /// assert_eq!(normalizer.to_local_date("2019-07-14T23:30:00-05:00"), NaiveDate::from_ymd_opt(2019, 7, 14));
+    /// ```
+    #[must_use]
+    pub fn to_local_date(&self, date: &str) -> Option<NaiveDate> {
+        match self.parse(date)? {
+            ParsedDate::Offset(date_time) => Some(match self.timezone {
+                Some(timezone) => date_time.with_timezone(&timezone).naive_local().date(),
+                None => date_time.naive_local().date(),
+            }),
+            ParsedDate::Naive(date_time) => Some(date_time.date()),
+            ParsedDate::Date(date) => Some(date),
+        }
+    }
+
+    fn parse(&self, date: &str) -> Option<ParsedDate> {
+        let date = self.locale.translate(date.trim());
+
+        DateTime::parse_from_rfc3339(&date)
+            .ok()
+            .map(ParsedDate::Offset)
+            .or_else(|| {
+                DEFAULT_FORMATS
+                    .iter()
+                    .copied()
+                    .chain(self.formats.iter().map(String::as_str))
+                    .find_map(|format| parse_with_format(&date, format))
+            })
+    }
+}
+
+/// A date parsed with varying precision.
+enum ParsedDate {
+    Offset(DateTime<FixedOffset>),
+    Naive(NaiveDateTime),
+    Date(NaiveDate),
+}
+
+impl ParsedDate {
+    fn resolve(&self, timezone: Tz) -> Option<DateTime<Tz>> {
+        let local = match *self {
+            ParsedDate::Offset(date_time) => return Some(date_time.with_timezone(&timezone)),
+            ParsedDate::Naive(date_time) => date_time,
+            ParsedDate::Date(date) => date.and_hms_opt(0, 0, 0)?,
+        };
+        // Ambiguous local times resolve to the earlier instant, times skipped by a transition can't be resolved
+        timezone.from_local_datetime(&local).earliest()
+    }
+}
+
+impl fmt::Display for ParsedDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParsedDate::Offset(date_time) => write!(
+                f,
+                "{}",
+                date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            ),
+            ParsedDate::Naive(date_time) => {
+                write!(f, "{}", date_time.format(NAIVE_DATE_TIME_FORMAT))
+            }
+            ParsedDate::Date(date) => write!(f, "{}", date.format(NAIVE_DATE_FORMAT)),
+        }
+    }
+}
+
+fn parse_with_format(date: &str, format: &str) -> Option<ParsedDate> {
+    if let Ok(date_time) = DateTime::parse_from_str(date, format) {
+        return Some(ParsedDate::Offset(date_time));
+    }
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(date, format) {
+        return Some(ParsedDate::Naive(date_time));
+    }
+    NaiveDate::parse_from_str(date, format)
+        .ok()
+        .map(ParsedDate::Date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_standard_formats_without_configuration() {
+        let normalizer = DateNormalizer::new(Vec::new(), None);
+        assert_eq!(
+            normalizer.normalize("2019-01-25T07:52:40Z"),
+            Some(String::from("2019-01-25T07:52:40Z"))
+        );
+        assert_eq!(
+            normalizer.normalize("2019-01-25T07:52:40+01:00"),
+            Some(String::from("2019-01-25T07:52:40+01:00"))
+        );
+        assert_eq!(
+            normalizer.normalize("2019-01-25 07:52:40"),
+            Some(String::from("2019-01-25T07:52:40"))
+        );
+        assert_eq!(
+            normalizer.normalize("2019-01-25"),
+            Some(String::from("2019-01-25"))
+        );
+        assert_eq!(normalizer.normalize("25 January 2019"), None);
+    }
+
+    #[test]
+    fn tries_configured_formats_in_order() {
+        let normalizer = DateNormalizer::new(
+            vec![String::from("%d %B %Y"), String::from("%B %d, %Y %H:%M")],
+            None,
+        );
+        assert_eq!(
+            normalizer.normalize("25 January 2019"),
+            Some(String::from("2019-01-25"))
+        );
+        assert_eq!(
+            normalizer.normalize("January 25, 2019 07:52"),
+            Some(String::from("2019-01-25T07:52:00"))
+        );
+    }
+
+    #[test]
+    fn translates_localised_names() {
+        let normalizer =
+            DateNormalizer::new(vec![String::from("%A %d %B %Y")], Some(DateLocale::German));
+        assert_eq!(
+            normalizer.normalize("Montag 2 Dezember 2019"),
+            Some(String::from("2019-12-02"))
+        );
+
+        let normalizer =
+            DateNormalizer::new(vec![String::from("%d %B %Y")], Some(DateLocale::French));
+        assert_eq!(
+            normalizer.normalize("1 Août 2019"),
+            Some(String::from("2019-08-01"))
+        );
+    }
+
+    #[test]
+    fn resolves_dates_to_the_timezone() {
+        let normalizer =
+            DateNormalizer::new(Vec::new(), None).with_timezone("Europe/London".parse().unwrap());
+        // Date only values are midnight local time, in winter and summer
+        assert_eq!(
+            normalizer.normalize("2019-01-25"),
+            Some(String::from("2019-01-25T00:00:00+00:00"))
+        );
+        assert_eq!(
+            normalizer.normalize("2019-07-25"),
+            Some(String::from("2019-07-25T00:00:00+01:00"))
+        );
+        // Times without an offset are local time
+        assert_eq!(
+            normalizer.normalize("2019-07-25T07:52:40"),
+            Some(String::from("2019-07-25T07:52:40+01:00"))
+        );
+        // Times with an offset are converted
+        assert_eq!(
+            normalizer.normalize("2019-07-25T23:30:00-05:00"),
+            Some(String::from("2019-07-26T05:30:00+01:00"))
+        );
+    }
+
+    #[test]
+    fn parses_locales() {
+        assert_eq!("fr".parse::<DateLocale>(), Ok(DateLocale::French));
+        assert_eq!("pt_BR".parse::<DateLocale>(), Ok(DateLocale::Portuguese));
+        assert_eq!("de-AT".parse::<DateLocale>(), Ok(DateLocale::German));
+        assert!("xx".parse::<DateLocale>().is_err());
+    }
+}
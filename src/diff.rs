@@ -0,0 +1,211 @@
+use serde_json::{Map, Value};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::Path;
+
+use crate::constants::{self, HREF, PAGES};
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+use crate::sort;
+use crate::validate;
+
+/// The name of the file the changes are written to, beside the index.
+pub const CHANGES_FILE_NAME: &str = "changes.json";
+
+/// The records of an index, by href.
+pub type Records = BTreeMap<String, Map<String, Value>>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+/// How an index changed since an earlier run, so a search service can apply the changes rather than reindexing
+/// everything.
+pub struct Changes {
+    /// The hrefs of records that weren't in the earlier index.
+    pub added: Vec<String>,
+    /// The hrefs of records that are no longer in the index.
+    pub removed: Vec<String>,
+    /// The records whose fields have changed.
+    pub updated: Vec<Update>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A record that's changed.
+pub struct Update {
+    /// The href of the record.
+    pub href: String,
+    /// The names of the fields that were added, removed or changed.
+    pub fields: Vec<String>,
+}
+
+impl Changes {
+    /// Compares the records of an earlier index with those of the new one. Hrefs and fields are listed in order.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::diff::Changes;
+    /// use serde_json::{json, Map, Value};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let record = |title: &str| -> Map<String, Value> {
+    ///     json!({"title": title, "content": "Contents"}).as_object().unwrap().clone()
+    /// };
+    /// let mut previous = BTreeMap::new();
+    /// previous.insert(String::from("/a/"), record("A"));
+    /// previous.insert(String::from("/b/"), record("B"));
+    /// let mut current = BTreeMap::new();
+    /// current.insert(String::from("/a/"), record("A, renamed"));
+    /// current.insert(String::from("/c/"), record("C"));
+    ///
+    /// let changes = Changes::between(&previous, &current);
+    /// assert_eq!(changes.added, ["/c/"]);
+    /// assert_eq!(changes.removed, ["/b/"]);
+    /// assert_eq!(changes.updated[0].href, "/a/");
+    /// assert_eq!(changes.updated[0].fields, ["title"]);
+    /// ```
+    #[must_use]
+    pub fn between(previous: &Records, current: &Records) -> Self {
+        let mut changes = Self::default();
+        for (href, record) in current {
+            match previous.get(href) {
+                None => changes.added.push(href.clone()),
+                Some(earlier) => {
+                    let fields: BTreeSet<&String> = record
+                        .keys()
+                        .chain(earlier.keys())
+                        .filter(|field| record.get(*field) != earlier.get(*field))
+                        .collect();
+                    if !fields.is_empty() {
+                        changes.updated.push(Update {
+                            href: href.clone(),
+                            fields: fields.into_iter().cloned().collect(),
+                        });
+                    }
+                }
+            }
+        }
+        changes.removed = previous
+            .keys()
+            .filter(|href| !current.contains_key(*href))
+            .cloned()
+            .collect();
+        changes
+    }
+}
+
+/// Reads the records of an index written as a JSON array, in an envelope, or as newline delimited JSON.
+///
+/// # Errors
+/// Errors if the index can't be read, or isn't JSON records that each have an href.
+pub fn read_records(path: &Path) -> Result<Records, HugotoJsonError> {
+    let index = fs::read_to_string(path)?;
+    let records: Vec<Map<String, Value>> = if index.trim_start().starts_with('[') {
+        serde_json::from_str(&index)?
+    } else {
+        match serde_json::from_str::<Map<String, Value>>(&index) {
+            Ok(mut envelope) if envelope.contains_key(PAGES) => {
+                serde_json::from_value(envelope.remove(PAGES).unwrap_or_default())?
+            }
+            _ => serde_json::from_str(&validate::lines_to_array(&index))?,
+        }
+    };
+    records
+        .into_iter()
+        .map(|record| match record.get(HREF) {
+            Some(Value::String(href)) => Ok((href.clone(), record)),
+            _ => Err(HugotoJsonError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("A record of {} has no href", path.to_string_lossy()),
+            ))),
+        })
+        .collect()
+}
+
+/// Returns the records the pages are written as, by href.
+///
+/// # Errors
+/// Errors if a page can't be serialized.
+pub fn page_records<'a, I>(pages: I) -> Result<Records, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let mut records = Records::new();
+    for page in pages {
+        // Pages are written as a record for each section, if they have any
+        for record in page.clone().into_records() {
+            if let Value::Object(fields) = serde_json::to_value(&record)? {
+                records.insert(record.href, fields);
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Writes the changes between the records of an earlier index and the pages of the new one, sorted and limited by the
+/// settings, beside the settings' output.
+///
+/// # Errors
+/// Errors if the index isn't written to a file or the changes can't be written.
+pub(crate) fn write_changes<'a, I>(
+    settings: &Settings,
+    previous: &Records,
+    pages: I,
+) -> Result<(), HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let output = settings.output.as_ref().ok_or_else(|| {
+        HugotoJsonError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--diff needs the index written to a path given with --output",
+        ))
+    })?;
+    let changes = Changes::between(
+        previous,
+        &page_records(sort::sort_and_limit(settings, pages))?,
+    );
+    let path = output.with_file_name(CHANGES_FILE_NAME);
+    create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+    fs::write(&path, serde_json::to_string_pretty(&changes)?)?;
+    info!(
+        "{} records added, {} removed and {} updated",
+        changes.added.len(),
+        changes.removed.len(),
+        changes.updated.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn records_are_read_from_json_and_ndjson() {
+        let mut array = tempfile::NamedTempFile::new().unwrap();
+        write!(array, "[{{\"href\": \"/a/\", \"title\": \"A\"}}]").unwrap();
+        let mut lines = tempfile::NamedTempFile::new().unwrap();
+        write!(lines, "{{\"href\": \"/a/\", \"title\": \"A\"}}\n\n").unwrap();
+        assert_eq!(
+            read_records(array.path()).unwrap(),
+            read_records(lines.path()).unwrap()
+        );
+        assert_eq!(read_records(array.path()).unwrap()["/a/"]["title"], "A");
+        let mut envelope = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            envelope,
+            "{{\"generated_at\": \"2019-01-25T00:00:00Z\", \"pages\": [{{\"href\": \"/a/\", \"title\": \"A\"}}], \"page_count\": 1}}"
+        )
+        .unwrap();
+        assert_eq!(
+            read_records(envelope.path()).unwrap(),
+            read_records(array.path()).unwrap()
+        );
+
+        let mut without_href = tempfile::NamedTempFile::new().unwrap();
+        write!(without_href, "[{{\"title\": \"A\"}}]").unwrap();
+        assert!(read_records(without_href.path()).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::error_report::ErrorRecord;
+use crate::hugo_to_json_error::ErrorCode;
+use crate::page_index::PageIndex;
+
+#[derive(Debug, Clone, Default)]
+/// Finds pages that have the same href as another page, such as two posts with the same slug, which would overwrite
+/// each other's entries in a search index, and pages with the same title as another page in the same language.
+pub struct Duplicates {
+    /// The path of the first page with each href.
+    hrefs: HashMap<String, String>,
+    /// The path of the first page with each title, in each language.
+    titles: HashMap<(String, String), String>,
+    records: Vec<ErrorRecord>,
+}
+
+impl Duplicates {
+    /// Creates a check without any pages.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a page, recording and logging it if it duplicates the href or title of a page added before it.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::duplicates::Duplicates;
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let first = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+    /// let second = FileLocation::from_path(Path::new("/blog/content/post/rust-again.md"), Path::new("/blog/content"))?;
+    /// let mut duplicates = Duplicates::new();
+    /// for location in &[first, second] {
+    ///     let mut page = PageIndex::new(Some("Rust"), Some("rust"), Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), location, None)?;
+    ///     page.path = location.absolute_path.clone();
+    ///     duplicates.add(&page);
+    /// }
+    ///
+    /// let records = duplicates.records();
+    /// assert_eq!(records.len(), 2);
+    /// assert_eq!(records[0].path, "/blog/content/post/rust-again.md");
+    /// assert_eq!(records[0].message, "Has the same href `/post/rust/` as /blog/content/post/rust.md");
+    /// assert_eq!(records[1].message, "Has the same title `Rust` as /blog/content/post/rust.md");
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    pub fn add(&mut self, page: &PageIndex) {
+        if let Some(first) = self.hrefs.get(&page.href) {
+            let message = format!("Has the same href `{}` as {}", page.href, first);
+            self.record(page, message);
+        } else {
+            self.hrefs.insert(page.href.clone(), page.path.clone());
+        }
+
+        let title = page.title.trim();
+        if title.is_empty() {
+            return;
+        }
+        let key = (page.lang.clone(), title.to_owned());
+        if let Some(first) = self.titles.get(&key) {
+            let message = format!("Has the same title `{title}` as {first}");
+            self.record(page, message);
+        } else {
+            self.titles.insert(key, page.path.clone());
+        }
+    }
+
+    /// Returns a record of each page that duplicates another, in the order they were added.
+    #[must_use]
+    pub fn records(&self) -> &[ErrorRecord] {
+        &self.records
+    }
+
+    fn record(&mut self, page: &PageIndex, message: String) {
+        let record = ErrorRecord {
+            path: page.path.clone(),
+            kind: "duplicate",
+            code: ErrorCode::Duplicate.as_str(),
+            message,
+            line: None,
+            column: None,
+            key: None,
+            snippet: None,
+        };
+        warn!(file = record.path.as_str(), kind = record.kind, code = record.code; "{}", record.message);
+        self.records.push(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    fn page(name: &str, title: &str, lang: &str) -> PageIndex {
+        let mut page = TestPage::new(name).title(title).build();
+        page.path = format!("/blog/content/post/{name}.md");
+        page.lang = lang.to_owned();
+        page
+    }
+
+    #[test]
+    fn titles_are_only_duplicated_in_the_same_language() {
+        let mut duplicates = Duplicates::new();
+        duplicates.add(&page("rust", "Rust", "en"));
+        duplicates.add(&page("rust-fr", "Rust", "fr"));
+        duplicates.add(&page("untitled", " ", "en"));
+        duplicates.add(&page("other", " ", "en"));
+        assert!(duplicates.records().is_empty());
+
+        duplicates.add(&page("rust-2", "Rust ", "en"));
+        let records = duplicates.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, "duplicate");
+        assert_eq!(records[0].code, "E015");
+    }
+}
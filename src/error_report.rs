@@ -0,0 +1,92 @@
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::constants;
+use crate::file_location::FileLocation;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::operation_result::OperationResult;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+/// A file that failed to process, as it's written to an error report.
+pub struct ErrorRecord {
+    /// The absolute path of the file.
+    pub path: String,
+    /// The kind of failure: `io`, `parse`, `skip` or `path`, or `link` for a broken link or `duplicate` for a page
+    /// with the same href or title as another.
+    pub kind: &'static str,
+    /// The stable code of the failure, e.g. `E002`.
+    pub code: &'static str,
+    /// What went wrong.
+    pub message: String,
+    /// The 1-based line in the file where a parse error occurred, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The 1-based column in the file where a parse error occurred, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// The front matter key that caused a parse error, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// The line of the file where a parse error occurred, as it's written, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl ErrorRecord {
+    /// Creates a record of `error`, which happened processing the file at `file_location`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::error_report::ErrorRecord;
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::operation_result::{OperationResult, ParseError};
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post.md"), Path::new("/blog/content"))?;
+    /// let error = OperationResult::from(ParseError::new("/blog/content/post.md", "Bad date").with_position(3, 8));
+    /// let record = ErrorRecord::new(&location, &error);
+    /// assert_eq!(record.kind, "parse");
+    /// assert_eq!(record.code, "E002");
+    /// assert_eq!(record.line, Some(3));
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    pub fn new(file_location: &FileLocation, error: &OperationResult) -> Self {
+        let (message, parse_error) = match error {
+            OperationResult::Io(ref error) => (error.to_string(), None),
+            OperationResult::Parse(ref error) => (error.to_string(), Some(error)),
+            OperationResult::Skip(ref skip) => (skip.to_string(), None),
+            OperationResult::Path(ref error) => (error.to_string(), None),
+        };
+        Self {
+            path: file_location.absolute_path.clone(),
+            kind: error.kind(),
+            code: error.code().as_str(),
+            message,
+            line: parse_error.and_then(super::operation_result::ParseError::line),
+            column: parse_error.and_then(super::operation_result::ParseError::column),
+            key: parse_error.and_then(|error| error.key()).map(String::from),
+            snippet: parse_error
+                .and_then(|error| error.snippet())
+                .map(String::from),
+        }
+    }
+}
+
+/// Writes the records to `path` as a JSON array. An empty array is written if there were no failures, so the report
+/// is always there to be collected.
+///
+/// # Errors
+/// Errors if the report can't be serialized or written.
+pub fn write_error_report(path: &Path, records: &[ErrorRecord]) -> Result<(), HugotoJsonError> {
+    create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, records)?;
+    writer.flush()?;
+    info!(
+        "Wrote {} errors to {}",
+        records.len(),
+        path.to_string_lossy()
+    );
+    Ok(())
+}
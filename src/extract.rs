@@ -0,0 +1,647 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag};
+use regex::Regex;
+use std::sync::LazyLock;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::links::Link;
+use crate::page_index::Heading;
+
+/// Links whose text is kept, e.g. `https://example.com[Example]` and `link:other.html[Other]`.
+static ASCIIDOC_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:link:|https?://)[^\s\[]*\[([^\]]*)\]").unwrap());
+/// Cross references, e.g. `<<install,Installing>>`.
+static ASCIIDOC_XREF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<<[^,>]*,?\s*([^>]*)>>").unwrap());
+static ASCIIDOC_IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"image:[^\s\[]*\[[^\]]*\]").unwrap());
+/// Lines that only hold a block macro, e.g. `image::cat.png[]` or `include::part.adoc[]`.
+static ASCIIDOC_BLOCK_MACRO: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z]+::\S*\[.*\]$").unwrap());
+static ASCIIDOC_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^:!?[\w-]+!?:").unwrap());
+static ASCIIDOC_MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:=+\s+|\*+\s+|-\s+|\.+\s+|\d+\.\s+|(?:NOTE|TIP|IMPORTANT|CAUTION|WARNING):\s+|\.)",
+    )
+    .unwrap()
+});
+static RST_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`([^`<]*?)\s*(?:<[^>]*>)?`__?").unwrap());
+static RST_ROLE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r":[\w-]+:`([^`]*)`").unwrap());
+static RST_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\w+)__?(\s|[.,;:!?)]|$)").unwrap());
+static RST_FIELD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^:[\w\s-]+:(?:\s|$)").unwrap());
+static RST_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[-*+]|#\.|\d+[.)])\s+").unwrap());
+/// Bold, italic and literal text, in either markup.
+static STRONG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\*{1,2}([^*\s](?:[^*]*[^*\s])?)\*{1,2}").unwrap());
+static EMPHASIS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b_{1,2}([^_\s](?:[^_]*[^_\s])?)_{1,2}\b").unwrap());
+static LITERAL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`{1,2}([^`]+)`{1,2}").unwrap());
+/// `ref` and `relref` shortcodes, e.g. `{{< ref "post/rust.md" >}}`, along with the text of the markdown link
+/// they're written in, if there is one.
+static REF_SHORTCODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?:\[([^\]]*)\]\(\s*)?\{\{[<%]\s*(?:rel)?ref\s+"([^"]+)"[^}]*?[>%]\}\}"#).unwrap()
+});
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Controls which parts of a document `extract_text` keeps.
+pub struct ExtractOptions {
+    /// Leaves out the contents of fenced and indented code blocks. Inline code is always kept.
+    pub skip_code_blocks: bool,
+    /// Keeps the alt text of images.
+    pub keep_alt_text: bool,
+}
+
+/// Extracts the plain text of a document's content from its markdown, once its shortcodes have been removed. Implement
+/// it for cleaning the built-in extractors don't do, such as removing `MathJax`, and set it as the settings'
+/// `custom_extractor`. Extractors are shared by the threads that process documents.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::{ContentExtractor, StripMarkdown};
+///
+/// /// Removes `$$` delimited MathJax before stripping the markdown.
+/// #[derive(Debug)]
+/// struct WithoutMath;
+///
+/// impl ContentExtractor for WithoutMath {
+///     fn extract(&self, markdown: &str) -> String {
+///         let text: Vec<&str> = markdown.split("$$").step_by(2).collect();
+///         StripMarkdown::default().extract(&text.concat())
+///     }
+/// }
+///
+/// assert!(!WithoutMath.extract("Euler: $$e^{i\\pi} = -1$$").contains("pi"));
+/// ```
+pub trait ContentExtractor: fmt::Debug + Send + Sync {
+    /// Returns the plain text of a markdown document.
+    fn extract(&self, markdown: &str) -> String;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Extracts text with the [strip_markdown](https://docs.rs/strip_markdown) crate.
+pub struct StripMarkdown {
+    /// Leaves out fenced and indented code blocks.
+    pub skip_code_blocks: bool,
+}
+
+impl ContentExtractor for StripMarkdown {
+    fn extract(&self, markdown: &str) -> String {
+        if self.skip_code_blocks {
+            strip_markdown::strip_markdown(&remove_code_blocks(markdown))
+        } else {
+            strip_markdown::strip_markdown(markdown)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Extracts text with `extract_text`, built on [pulldown-cmark](https://docs.rs/pulldown-cmark) events.
+pub struct PulldownCmark(pub ExtractOptions);
+
+impl ContentExtractor for PulldownCmark {
+    fn extract(&self, markdown: &str) -> String {
+        extract_text(markdown, self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Passes the markdown through as it is, for search engines that do their own cleaning or show the markdown itself.
+pub struct RawMarkdown {
+    /// Leaves out fenced and indented code blocks.
+    pub skip_code_blocks: bool,
+}
+
+impl ContentExtractor for RawMarkdown {
+    fn extract(&self, markdown: &str) -> String {
+        if self.skip_code_blocks {
+            remove_code_blocks(markdown)
+        } else {
+            markdown.to_owned()
+        }
+    }
+}
+
+/// Extracts the text of a markdown document using [pulldown-cmark](https://docs.rs/pulldown-cmark), with each block
+/// such as a paragraph, heading, list item or table row on its own line. Understands the `CommonMark` extensions Hugo
+/// enables: tables, footnotes, strikethrough and task lists. Raw HTML is removed.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::{extract_text, ExtractOptions};
+///
+/// let markdown = "# Title\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n- one\n  - nested[^1]\n\n[^1]: A footnote.";
+/// assert_eq!(
+///     extract_text(markdown, ExtractOptions::default()),
+///     "Title\na b\n1 2\none\nnested\nA footnote."
+/// );
+/// ```
+pub fn extract_text(markdown: &str, options: ExtractOptions) -> String {
+    let mut text = String::new();
+    // Text inside a skipped code block or an image without alt text is left out
+    let mut skipping = 0;
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) if options.skip_code_blocks => skipping += 1,
+            Event::End(Tag::CodeBlock(_)) if options.skip_code_blocks => {
+                skipping -= 1;
+                end_line(&mut text);
+            }
+            Event::Start(Tag::Image(..)) if !options.keep_alt_text => skipping += 1,
+            Event::End(Tag::Image(..)) if !options.keep_alt_text => skipping -= 1,
+            Event::Start(Tag::TableCell)
+                if !text.is_empty() && !text.ends_with(char::is_whitespace) =>
+            {
+                text.push(' ');
+            }
+            Event::Start(ref tag) | Event::End(ref tag) if is_block(tag) => end_line(&mut text),
+            Event::Text(ref fragment) | Event::Code(ref fragment) if skipping == 0 => {
+                text.push_str(fragment);
+            }
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak | Event::Rule => end_line(&mut text),
+            // Raw HTML, footnote labels and task list checkboxes aren't text
+            _ => (),
+        }
+    }
+
+    text.trim_end().to_owned()
+}
+
+/// Removes fenced and indented code blocks from a markdown document, leaving the rest of its markdown as it is.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::remove_code_blocks;
+///
+/// assert_eq!(remove_code_blocks("Before\n\n```\ncode\n```\n\nAfter"), "Before\n\n\n\nAfter");
+/// ```
+#[must_use]
+pub fn remove_code_blocks(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut start = 0;
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        if let Event::Start(Tag::CodeBlock(_)) = event {
+            if range.start >= start {
+                text.push_str(&markdown[start..range.start]);
+                start = range.end;
+            }
+        }
+    }
+    text.push_str(&markdown[start..]);
+    text
+}
+
+/// Extracts only the text of the fenced and indented code blocks in a markdown document, one block after another.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::extract_code_blocks;
+///
+/// let markdown = "Build it:\n\n```sh\ncargo build\n```\n\nThen run it:\n\n    cargo run\n";
+/// assert_eq!(extract_code_blocks(markdown), "cargo build\ncargo run");
+/// ```
+#[must_use]
+pub fn extract_code_blocks(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                end_line(&mut text);
+            }
+            Event::Text(ref fragment) if in_code_block => text.push_str(fragment),
+            _ => (),
+        }
+    }
+
+    text.trim_end().to_owned()
+}
+
+/// Finds the source of the first image in a markdown document, such as `cover.png` in `![A cover](cover.png)`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::first_image;
+///
+/// let markdown = "Some text.\n\n![A diagram](/images/diagram.png \"Diagram\")\n\n![Another](/images/other.png)";
+/// assert_eq!(first_image(markdown).as_deref(), Some("/images/diagram.png"));
+/// assert_eq!(first_image("No images here"), None);
+/// ```
+#[must_use]
+pub fn first_image(markdown: &str) -> Option<String> {
+    Parser::new_ext(markdown, parser_options()).find_map(|event| match event {
+        Event::Start(Tag::Image(_, source, _)) if !source.is_empty() => Some(source.into_string()),
+        _ => None,
+    })
+}
+
+/// Finds the links in a markdown document, along with their text and line, in the order they appear. Images aren't
+/// links, but autolinks such as `<https://example.com>` and Hugo's `ref` and `relref` shortcodes are.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::extract_links;
+///
+/// let markdown = "See [the *other* post](../other/) and <https://example.com>.\n\n![A cat](cat.png)\n\n[Rust]({{< ref \"rust.md\" >}})";
+/// let links = extract_links(markdown);
+/// assert_eq!(links.len(), 3);
+/// assert_eq!((links[0].target.as_str(), links[0].text.as_str()), ("../other/", "the other post"));
+/// assert_eq!(links[1].target, "https://example.com");
+/// assert_eq!((links[2].target.as_str(), links[2].line, links[2].reference), ("rust.md", 5, true));
+/// ```
+pub fn extract_links(markdown: &str) -> Vec<Link> {
+    let line_of = |offset: usize| markdown[..offset].matches('\n').count() + 1;
+    let mut links = Vec::new();
+    let mut current: Option<Link> = None;
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link(_, target, _)) => {
+                current = Some(Link {
+                    target: target.into_string(),
+                    text: String::new(),
+                    line: line_of(range.start),
+                    reference: false,
+                });
+            }
+            Event::Text(ref fragment) | Event::Code(ref fragment) => {
+                if let Some(ref mut link) = current {
+                    link.text.push_str(fragment);
+                }
+            }
+            Event::End(Tag::Link(..)) => {
+                if let Some(mut link) = current.take() {
+                    link.text = String::from(link.text.trim());
+                    links.push(link);
+                }
+            }
+            _ => (),
+        }
+    }
+    for captures in REF_SHORTCODE.captures_iter(markdown) {
+        links.push(Link {
+            target: captures[2].to_owned(),
+            text: captures
+                .get(1)
+                .map_or_else(String::new, |text| text.as_str().trim().to_owned()),
+            line: line_of(captures.get(0).map_or(0, |found| found.start())),
+            reference: true,
+        });
+    }
+    links.sort_by_key(|link| link.line);
+    links
+}
+
+/// Finds the H1 to H3 headings of a markdown document along with the anchors Hugo gives them. A heading's `{#id}`
+/// attribute is its anchor if it has one. Otherwise, like Hugo's default `github` anchors, the anchor is the heading's
+/// text in lowercase with spaces turned into hyphens and punctuation other than `_` and `-` removed, and anchors that
+/// are repeated are numbered, e.g. `setup-1`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::extract_headings;
+///
+/// let markdown = "# Getting *Started*\n\n## What's `new`?\n\n## Setup {#install}\n\n#### Too deep\n\n## Setup";
+/// let anchors: Vec<String> = extract_headings(markdown).into_iter().map(|heading| heading.anchor).collect();
+/// assert_eq!(anchors, ["getting-started", "whats-new", "install", "setup"]);
+/// ```
+#[must_use]
+pub fn extract_headings(markdown: &str) -> Vec<Heading> {
+    split_sections(markdown)
+        .into_iter()
+        .filter_map(|(heading, _)| heading)
+        .collect()
+}
+
+/// Splits a markdown document at its H1 to H3 headings, returning the markdown under each heading, up to the next one,
+/// along with the heading. The markdown before the first heading comes first, without a heading. Headings are anchored
+/// the same way as by `extract_headings`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::split_sections;
+///
+/// let sections = split_sections("Intro\n\n## Install\n\nRun it.\n\n#### Linux\n\nAnd more.\n");
+/// assert_eq!(sections.len(), 2);
+/// assert_eq!(sections[0].1, "Intro\n\n");
+/// assert_eq!(sections[1].0.as_ref().unwrap().anchor, "install");
+/// assert_eq!(sections[1].1, "\nRun it.\n\n#### Linux\n\nAnd more.\n");
+/// ```
+pub fn split_sections(markdown: &str) -> Vec<(Option<Heading>, &str)> {
+    let mut sections = Vec::new();
+    let mut heading = None;
+    let mut start = 0;
+    // The level, `{#id}`, text so far and start of the heading being read
+    let mut current: Option<(usize, Option<String>, String, usize)> = None;
+    let mut anchors: HashMap<String, usize> = HashMap::new();
+    let options = parser_options() | Options::ENABLE_HEADING_ATTRIBUTES;
+    for (event, range) in Parser::new_ext(markdown, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(level, id, _)) if level <= HeadingLevel::H3 => {
+                current = Some((
+                    level as usize,
+                    id.map(String::from),
+                    String::new(),
+                    range.start,
+                ));
+            }
+            Event::Text(ref fragment) | Event::Code(ref fragment) => {
+                if let Some((_, _, ref mut text, _)) = current {
+                    text.push_str(fragment);
+                }
+            }
+            Event::End(Tag::Heading(..)) => {
+                if let Some((level, id, text, heading_start)) = current.take() {
+                    sections.push((heading.take(), &markdown[start..heading_start]));
+                    let text = text.trim().to_owned();
+                    let anchor = id.unwrap_or_else(|| {
+                        let anchor = heading_anchor(&text);
+                        let count = anchors.entry(anchor.clone()).or_insert(0);
+                        *count += 1;
+                        match *count {
+                            1 => anchor,
+                            count => format!("{}-{}", anchor, count - 1),
+                        }
+                    });
+                    heading = Some(Heading {
+                        level,
+                        text,
+                        anchor,
+                    });
+                    start = range.end;
+                }
+            }
+            _ => (),
+        }
+    }
+    sections.push((heading, &markdown[start..]));
+    sections
+}
+
+/// Extracts the text of an [AsciiDoc](https://asciidoc.org/) document, with each line of text on its own line.
+/// Titles, lists, tables and the contents of blocks are kept. Comments, attribute entries, block attributes and
+/// delimiters, and images are removed, and only the text of links and cross references is kept.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::asciidoc_text;
+///
+/// let asciidoc = "= Title\n:toc:\n\n// A comment\nSome *bold* text, see https://example.com[the site].\n\n[source,rust]\n----\nfn main() {}\n----\n\n* One\n* Two";
+/// assert_eq!(
+///     asciidoc_text(asciidoc),
+///     "Title\nSome bold text, see the site.\nfn main() {}\nOne\nTwo"
+/// );
+/// ```
+pub fn asciidoc_text(asciidoc: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_comment = false;
+    for line in asciidoc.lines() {
+        let line = line.trim();
+        if line.starts_with("////") && is_delimiter(line) {
+            in_comment = !in_comment;
+            continue;
+        }
+        if in_comment
+            || line.starts_with("//")
+            || is_delimiter(line)
+            || line.starts_with("|===")
+            || (line.starts_with('[') && line.ends_with(']'))
+            || ASCIIDOC_ATTRIBUTE.is_match(line)
+            || ASCIIDOC_BLOCK_MACRO.is_match(line)
+        {
+            continue;
+        }
+
+        let line = table_row(&ASCIIDOC_MARKER.replace(line, ""));
+        let line = line.trim_end_matches(" +");
+        let line = ASCIIDOC_IMAGE.replace_all(line, "");
+        let line = ASCIIDOC_LINK.replace_all(&line, "$1");
+        let line = ASCIIDOC_XREF.replace_all(&line, "$1");
+        lines.push(inline_formatting(&line));
+    }
+    join_lines(lines)
+}
+
+/// Extracts the text of a [reStructuredText](https://docutils.sourceforge.io/rst.html) document, with each line of
+/// text on its own line. Titles, lists, tables and literal blocks are kept. Comments, directives and their options,
+/// field lists and section adornments are removed, and only the text of links, references and roles is kept.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::extract::rst_text;
+///
+/// let rst = "Title\n=====\n\n.. A comment\n\nSome **bold** text, see `the site <https://example.com>`_.\n\n.. note::\n   :class: small\n\n   Noted.\n\n- One\n- Two";
+/// assert_eq!(rst_text(rst), "Title\nSome bold text, see the site.\nNoted.\nOne\nTwo");
+/// ```
+pub fn rst_text(rst: &str) -> String {
+    let mut lines = Vec::new();
+    // The indentation of a comment, whose indented lines are part of it
+    let mut comment_indent = None;
+    for line in rst.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if let Some(comment_indent) = comment_indent {
+            if line.is_empty() || indent > comment_indent {
+                continue;
+            }
+        }
+        comment_indent = None;
+
+        if line == ".." || line.starts_with(".. ") {
+            // Directives, such as `.. note::`, hold text, but comments and targets don't
+            if !line.contains("::") || line.starts_with(".. _") {
+                comment_indent = Some(indent);
+            }
+            continue;
+        }
+        if line == "::" || is_delimiter(line) || RST_FIELD.is_match(line) {
+            continue;
+        }
+
+        let line = table_row(&RST_MARKER.replace(line, ""));
+        let line = if line.ends_with("::") {
+            &line[..line.len() - 1]
+        } else {
+            &line
+        };
+        let line = RST_LINK.replace_all(line, "$1");
+        let line = RST_ROLE.replace_all(&line, "$1");
+        let line = inline_formatting(&line);
+        lines.push(RST_REFERENCE.replace_all(&line, "$1$2").into_owned());
+    }
+    join_lines(lines)
+}
+
+/// Returns true if the line is made of one punctuation character repeated, such as a block delimiter or a section
+/// adornment, e.g. `----` or `=====`. Grid table borders such as `+---+---+` count too.
+fn is_delimiter(line: &str) -> bool {
+    let mut characters = line.chars().filter(|&c| c != '+');
+    match characters.next() {
+        Some(first) if line.len() >= 2 && first.is_ascii_punctuation() => {
+            characters.all(|c| c == first)
+        }
+        _ => false,
+    }
+}
+
+/// Joins the cells of a table row, e.g. `| a | b |`, with spaces. Other lines are returned as they are.
+fn table_row(line: &str) -> String {
+    if line.starts_with('|') {
+        line.split('|')
+            .map(str::trim)
+            .filter(|cell| !cell.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    } else {
+        line.to_owned()
+    }
+}
+
+/// Removes the markers around bold, italic and literal text.
+fn inline_formatting(line: &str) -> String {
+    let line = LITERAL.replace_all(line, "$1");
+    let line = STRONG.replace_all(&line, "$1");
+    EMPHASIS.replace_all(&line, "$1").into_owned()
+}
+
+/// Joins the lines that have text, one per line.
+fn join_lines(lines: Vec<String>) -> String {
+    lines
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Turns the text of a heading into an anchor the way Hugo's `github` anchors do. Headings without any letters or
+/// digits are anchored at `heading`.
+fn heading_anchor(text: &str) -> String {
+    let anchor: String = text
+        .chars()
+        .filter_map(|c| match c {
+            ' ' | '-' => Some('-'),
+            '_' => Some('_'),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+    if anchor.is_empty() {
+        String::from("heading")
+    } else {
+        anchor
+    }
+}
+
+/// The `CommonMark` extensions Hugo enables.
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+/// Returns true if the tag is a block that starts on a new line.
+fn is_block(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading(..)
+            | Tag::BlockQuote
+            | Tag::CodeBlock(_)
+            | Tag::List(_)
+            | Tag::Item
+            | Tag::FootnoteDefinition(_)
+            | Tag::Table(_)
+            | Tag::TableHead
+            | Tag::TableRow
+    )
+}
+
+/// Ends the current line, unless there's nothing on it.
+fn end_line(text: &mut String) {
+    let trimmed_length = text.trim_end_matches(' ').len();
+    text.truncate(trimmed_length);
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_blocks_can_be_skipped() {
+        let markdown = "Run `cargo`:\n\n```sh\ncargo build\n```\n\nDone.";
+        assert_eq!(
+            extract_text(markdown, ExtractOptions::default()),
+            "Run cargo:\ncargo build\nDone."
+        );
+        let options = ExtractOptions {
+            skip_code_blocks: true,
+            ..ExtractOptions::default()
+        };
+        assert_eq!(extract_text(markdown, options), "Run cargo:\nDone.");
+    }
+
+    #[test]
+    fn alt_text_can_be_kept() {
+        let markdown = "A ![cat on a mat](cat.png) and <b>html</b>.";
+        assert_eq!(
+            extract_text(markdown, ExtractOptions::default()),
+            "A  and html."
+        );
+        let options = ExtractOptions {
+            keep_alt_text: true,
+            ..ExtractOptions::default()
+        };
+        assert_eq!(extract_text(markdown, options), "A cat on a mat and html.");
+    }
+
+    #[test]
+    fn headings_are_anchored_like_hugo() {
+        let markdown = "# Café & Crème\n\nText\n\n## snake_case -- and more\n\n### Setup\n\n### Setup\n\n## ???";
+        let headings = extract_headings(markdown);
+        let anchors: Vec<&str> = headings
+            .iter()
+            .map(|heading| heading.anchor.as_str())
+            .collect();
+        assert_eq!(
+            anchors,
+            [
+                "café--crème",
+                "snake_case----and-more",
+                "setup",
+                "setup-1",
+                "heading"
+            ]
+        );
+        assert_eq!(headings[0].text, "Café & Crème");
+        assert_eq!(headings[2].level, 3);
+    }
+
+    #[test]
+    fn asciidoc_comments_tables_and_cross_references() {
+        let asciidoc = "////\nA comment block\n////\n.A table\n|===\n| Name | Value\n| snake_case | _emphasis_\n|===\n\nNOTE: See <<install,Installing>>. +\nimage::cat.png[A cat]";
+        assert_eq!(
+            asciidoc_text(asciidoc),
+            "A table\nName Value\nsnake_case emphasis\nSee Installing."
+        );
+    }
+
+    #[test]
+    fn rst_comments_roles_and_literal_blocks() {
+        let rst = "..\n   A comment\n   over two lines\n\n:author: Someone\n\nUse :code:`cargo` and see Guide_ for this::\n\n    cargo build\n\n.. _Guide: https://example.com\n\n+---+---+\n| a | b |\n+---+---+";
+        assert_eq!(
+            rst_text(rst),
+            "Use cargo and see Guide for this:\ncargo build\na b"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use std::cmp::Reverse;
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::fs::{self, create_dir_all};
+use std::path::Path;
+
+use crate::constants;
+use crate::date::DateNormalizer;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::{PageIndex, PageKind};
+use crate::site_config::SiteConfig;
+use crate::xml::{element, escape};
+
+/// The number of pages in a feed when neither the settings nor the site config's `rssLimit` give one.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// The extension of feed files that are written as Atom rather than RSS.
+const ATOM_EXTENSION: &str = "atom";
+
+#[derive(Debug, Clone, PartialEq)]
+/// A page as it appears in a feed.
+pub struct FeedItem {
+    /// The title of the page.
+    pub title: String,
+    /// The absolute URL of the page, if the site config has a `baseURL`, otherwise its href.
+    pub link: String,
+    /// When the page was published.
+    pub date: DateTime<Utc>,
+    /// The description of the page, or its summary if it hasn't one.
+    pub description: String,
+}
+
+#[derive(Debug, Clone)]
+/// A feed of the newest regular pages of a site, written as [RSS 2.0](https://www.rssboard.org/rss-specification) or
+/// [Atom](https://tools.ietf.org/html/rfc4287). Drafts are never indexed, so never appear in the feed.
+pub struct Feed {
+    site: SiteConfig,
+    normalizer: DateNormalizer,
+    limit: usize,
+    items: Vec<FeedItem>,
+}
+
+impl Feed {
+    /// Creates a feed of up to `limit` pages, titled and linked from the site config. Page dates are read with
+    /// `normalizer`.
+    #[must_use]
+    pub fn new(site: &SiteConfig, normalizer: DateNormalizer, limit: usize) -> Self {
+        Self {
+            site: site.clone(),
+            normalizer,
+            limit,
+            items: Vec::new(),
+        }
+    }
+
+    /// Adds a page to the feed if it's a regular page. Pages whose date can't be read are left out.
+    pub fn add(&mut self, page: &PageIndex) {
+        if page.kind != PageKind::Page {
+            return;
+        }
+        let Some(date) = self.normalizer.to_instant(&page.date) else {
+            debug!(
+                "Leaving {} out of the feed, its date can't be read",
+                page.href
+            );
+            return;
+        };
+        let description = if page.description.is_empty() {
+            &page.summary
+        } else {
+            &page.description
+        };
+        self.items.push(FeedItem {
+            title: page.title.clone(),
+            link: self.site.absolute_href(&page.href),
+            date,
+            description: description.clone(),
+        });
+        // Only the newest pages are kept, so a feed of a large site stays small
+        if self.items.len() >= self.limit.saturating_mul(2).max(64) {
+            self.sort_and_truncate();
+        }
+    }
+
+    /// Returns the pages in the feed, newest first.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::date::DateNormalizer;
+    /// use hugo_to_json::feed::Feed;
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::site_config::SiteConfig;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let page = |name: &str, date: &str| {
+    ///     let location = FileLocation::from_path(&Path::new("/blog/content").join(name), Path::new("/blog/content"))?;
+    ///     PageIndex::new(Some(name), None, Some(date), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)
+    /// };
+    /// let site = SiteConfig { base_url: Some(String::from("https://example.com/")), ..SiteConfig::default() };
+    /// let mut feed = Feed::new(&site, DateNormalizer::new(Vec::new(), None), 2);
+    /// for (name, date) in &[("a.md", "2019-01-25"), ("b.md", "2019-03-01"), ("c.md", "2019-02-14")] {
+    ///     feed.add(&page(name, date)?);
+    /// }
+    ///
+    /// let links: Vec<&str> = feed.items().iter().map(|item| item.link.as_str()).collect();
+    /// assert_eq!(links, ["https://example.com/b/", "https://example.com/c/"]);
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn items(&self) -> Vec<&FeedItem> {
+        let mut items: Vec<&FeedItem> = self.items.iter().collect();
+        items.sort_by_key(|item| Reverse(item.date));
+        items.truncate(self.limit);
+        items
+    }
+
+    /// Renders the feed as RSS 2.0.
+    #[must_use]
+    pub fn to_rss(&self) -> String {
+        let items = self.items();
+        let mut rss = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        rss.push_str("<rss version=\"2.0\">\n  <channel>\n");
+        element(&mut rss, 4, "title", &self.title());
+        element(&mut rss, 4, "link", &self.link());
+        element(&mut rss, 4, "description", &self.description());
+        if let Some(ref language) = self.site.default_language {
+            element(&mut rss, 4, "language", language);
+        }
+        if let Some(newest) = items.first() {
+            element(&mut rss, 4, "lastBuildDate", &newest.date.to_rfc2822());
+        }
+        for item in items {
+            rss.push_str("    <item>\n");
+            element(&mut rss, 6, "title", &item.title);
+            element(&mut rss, 6, "link", &item.link);
+            element(&mut rss, 6, "guid", &item.link);
+            element(&mut rss, 6, "pubDate", &item.date.to_rfc2822());
+            if !item.description.is_empty() {
+                element(&mut rss, 6, "description", &item.description);
+            }
+            rss.push_str("    </item>\n");
+        }
+        rss.push_str("  </channel>\n</rss>\n");
+        rss
+    }
+
+    /// Renders the feed as Atom. The site's title is the author of every entry, as pages don't have one of their own.
+    pub fn to_atom(&self) -> String {
+        let items = self.items();
+        let updated = items.first().map_or_else(Utc::now, |newest| newest.date);
+        let mut atom = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        match self.site.default_language {
+            Some(ref language) => writeln!(
+                atom,
+                "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"{}\">",
+                escape(language)
+            )
+            .unwrap(),
+            None => atom.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n"),
+        }
+        element(&mut atom, 2, "title", &self.title());
+        element(&mut atom, 2, "subtitle", &self.description());
+        element(&mut atom, 2, "id", &self.link());
+        link(&mut atom, 2, &self.link());
+        element(&mut atom, 2, "updated", &atom_date(updated));
+        writeln!(
+            atom,
+            "  <author>\n    <name>{}</name>\n  </author>",
+            escape(&self.title())
+        )
+        .unwrap();
+        for item in items {
+            atom.push_str("  <entry>\n");
+            element(&mut atom, 4, "title", &item.title);
+            element(&mut atom, 4, "id", &item.link);
+            link(&mut atom, 4, &item.link);
+            element(&mut atom, 4, "updated", &atom_date(item.date));
+            if !item.description.is_empty() {
+                element(&mut atom, 4, "summary", &item.description);
+            }
+            atom.push_str("  </entry>\n");
+        }
+        atom.push_str("</feed>\n");
+        atom
+    }
+
+    /// Writes the feed to `path`, as Atom if its extension is `.atom` and otherwise as RSS 2.0.
+    ///
+    /// # Errors
+    /// Errors if the feed can't be written.
+    pub fn write(&self, path: &Path) -> Result<(), HugotoJsonError> {
+        create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+        let is_atom = path.extension() == Some(OsStr::new(ATOM_EXTENSION));
+        let feed = if is_atom {
+            self.to_atom()
+        } else {
+            self.to_rss()
+        };
+        fs::write(path, feed)?;
+        debug!("Wrote feed to {}", path.to_string_lossy());
+        Ok(())
+    }
+
+    fn sort_and_truncate(&mut self) {
+        self.items.sort_by_key(|item| Reverse(item.date));
+        self.items.truncate(self.limit);
+    }
+
+    fn title(&self) -> String {
+        self.site
+            .title
+            .clone()
+            .unwrap_or_else(|| String::from("Recent content"))
+    }
+
+    fn link(&self) -> String {
+        self.site
+            .base_url
+            .clone()
+            .unwrap_or_else(|| constants::FORWARD_SLASH.to_owned())
+    }
+
+    fn description(&self) -> String {
+        match self.site.title {
+            Some(ref title) => format!("Recent content on {title}"),
+            None => String::from("Recent content"),
+        }
+    }
+}
+
+/// Writes an Atom link to `href` on its own line, indented by `indent` spaces.
+fn link(xml: &mut String, indent: usize, href: &str) {
+    writeln!(
+        xml,
+        "{:indent$}<link href=\"{}\"/>",
+        "",
+        escape(href),
+        indent = indent
+    )
+    .unwrap();
+}
+
+fn atom_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed() -> Feed {
+        let site = SiteConfig {
+            base_url: Some(String::from("https://example.com/")),
+            title: Some(String::from("Tom & Jerry")),
+            ..SiteConfig::default()
+        };
+        let mut feed = Feed::new(&site, DateNormalizer::new(Vec::new(), None), 10);
+        feed.items.push(FeedItem {
+            title: String::from("<Hello>"),
+            link: String::from("https://example.com/hello/"),
+            date: "2019-01-25T07:52:40Z".parse().unwrap(),
+            description: String::new(),
+        });
+        feed
+    }
+
+    #[test]
+    fn renders_rss_with_escaped_text() {
+        let rss = feed().to_rss();
+        assert!(rss.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(rss.contains("<title>&lt;Hello&gt;</title>"));
+        assert!(rss.contains("<pubDate>Fri, 25 Jan 2019 07:52:40 +0000</pubDate>"));
+        assert!(!rss.contains("<description></description>"));
+    }
+
+    #[test]
+    fn renders_atom() {
+        let atom = feed().to_atom();
+        assert!(atom.contains("<updated>2019-01-25T07:52:40Z</updated>"));
+        assert!(atom.contains("<link href=\"https://example.com/hello/\"/>"));
+        assert!(atom.contains("<name>Tom &amp; Jerry</name>"));
+    }
+}
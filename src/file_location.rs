@@ -1,12 +1,15 @@
 use crate::constants;
-use crate::operation_result::*;
+use crate::operation_result::{OperationResult, PathError, Skip, SkipReason};
+use crate::site_config::ContentMount;
 
 use std::fmt;
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path};
 
-use walkdir::DirEntry;
+use serde_json::{Map, Value as JsonValue};
+use walkdir::{DirEntry, WalkDir};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+/// Describes where a document lives, both on disk and relative to the contents directory.
 pub struct FileLocation {
     /// The file's extension
     pub extension: String,
@@ -16,23 +19,52 @@ pub struct FileLocation {
     pub file_name: String,
     /// The name of the file, without the extension. `foo.rs` becomes `foo`
     pub file_stem: String,
-    // The directory relative to the content directory. Given `./blog/content/sub/post/example.md` and a content_dir of `./blog/content` produce `sub/post`
+    /// The contents directory the file is in, which is one of several if the site's content is split across them
+    pub content_dir: String,
+    /// The directory relative to the content directory. Given `./blog/content/sub/post/example.md` and a `content_dir` of `./blog/content` produce `sub/post`
     pub relative_directory_to_content: String,
+    /// The language of the file, from its language suffix or language directory. Empty unless the site has languages.
+    pub lang: String,
+    /// True if the file is in the site's default language, whose hrefs aren't prefixed with the language
+    pub default_lang: bool,
+    /// The front matter values cascaded to the file from the `_index.md` files of the sections it's in. Values the
+    /// file's own front matter sets take precedence.
+    pub cascade: Map<String, JsonValue>,
 }
 
 impl FileLocation {
-    pub fn new(file: &DirEntry, content_dir: &PathBuf) -> Result<Self, OperationResult> {
+    pub(crate) fn new(file: &DirEntry, content_dir: &Path) -> Result<Self, OperationResult> {
         if file.file_type().is_dir() {
             return Err(OperationResult::Skip(Skip::new(
                 file.path().to_str().unwrap_or_default(),
-                "Not a file",
+                SkipReason::Directory,
             )));
         }
 
-        let path = file.path();
+        Self::from_path(file.path(), content_dir)
+    }
+
+    /// Creates a `FileLocation` for a file at `path` inside the `content_dir` contents directory. The file doesn't need to exist.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(
+    ///     Path::new("/blog/content/post/example.md"),
+    ///     Path::new("/blog/content"),
+    /// )?;
+    /// assert_eq!(location.file_stem, "example");
+    /// assert_eq!(location.relative_directory_to_content, "post");
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a `PathError` if the path isn't inside `content_dir` or doesn't have a file name and extension.
+    pub fn from_path(path: &Path, content_dir: &Path) -> Result<Self, OperationResult> {
         let absolute_path = path.to_string_lossy().into_owned();
-        let extension = file
-            .path()
+        let extension = path
             .extension()
             .ok_or_else(|| PathError::new(&absolute_path, "Failed to determine extension."))?;
         let file_name = path
@@ -62,8 +94,220 @@ impl FileLocation {
             file_name,
             file_stem,
             relative_directory_to_content,
+            content_dir: content_dir.to_string_lossy().into_owned(),
+            lang: String::new(),
+            default_lang: false,
+            cascade: Map::new(),
         })
     }
+
+    /// Detects the language of the file for a multilingual site, where `languages` are the site's language codes
+    /// starting with the default language. The language is read from a suffix on the file name, such as `about.fr.md`,
+    /// or from a top level language directory, such as `fr/about.md`, and removed from the file stem or relative
+    /// directory. Files with neither are in the default language. Does nothing if `languages` is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use std::path::Path;
+    ///
+    /// let languages = vec![String::from("en"), String::from("fr")];
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/about.fr.md"), Path::new("/blog/content"))?
+    ///     .with_languages(&languages);
+    /// assert_eq!(location.lang, "fr");
+    /// assert_eq!(location.file_stem, "about");
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn with_languages(mut self, languages: &[String]) -> Self {
+        let Some(default_language) = languages.first() else {
+            return self;
+        };
+        self.lang.clone_from(default_language);
+
+        let (first_directory, rest) = match self
+            .relative_directory_to_content
+            .find(constants::FORWARD_SLASH)
+        {
+            Some(position) => (
+                &self.relative_directory_to_content[..position],
+                &self.relative_directory_to_content[position + 1..],
+            ),
+            None => (
+                self.relative_directory_to_content.as_str(),
+                constants::EMPTY_STRING,
+            ),
+        };
+        if languages.iter().any(|language| language == first_directory) {
+            self.lang = first_directory.to_owned();
+            self.relative_directory_to_content = rest.to_owned();
+        }
+
+        if let Some(position) = self.file_stem.rfind('.') {
+            let suffix = &self.file_stem[position + 1..];
+            if languages.iter().any(|language| language == suffix) {
+                self.lang = suffix.to_owned();
+                self.file_stem.truncate(position);
+            }
+        }
+
+        self.default_lang = &self.lang == default_language;
+        self
+    }
+
+    /// Places a file from a directory mounted into the content by a module mount at the mount's target, and detects its
+    /// language. Files in a mount with a language are in that language, otherwise the language is detected as
+    /// `with_languages` does.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::site_config::ContentMount;
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// let mount = ContentMount { source: PathBuf::from("/blog/docs"), target: String::from("docs"), lang: None };
+    /// let location = FileLocation::from_path(Path::new("/blog/docs/guide/install.md"), Path::new("/blog/docs"))?
+    ///     .mounted_at(&mount, &[]);
+    /// assert_eq!(location.relative_directory_to_content, "docs/guide");
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn mounted_at(mut self, mount: &ContentMount, languages: &[String]) -> Self {
+        self = match mount.lang {
+            Some(ref lang) => {
+                self.default_lang = languages
+                    .first()
+                    .as_ref()
+                    .is_none_or(|&first| first == lang);
+                self.lang.clone_from(lang);
+                self
+            }
+            None => self.with_languages(languages),
+        };
+        if !mount.target.is_empty() {
+            self.relative_directory_to_content = [
+                mount.target.as_str(),
+                self.relative_directory_to_content.as_str(),
+            ]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(constants::FORWARD_SLASH);
+        }
+        self
+    }
+
+    /// Returns the top level directory of the contents directory the file is in, or an empty string for files at the top.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(
+    ///     Path::new("/blog/content/post/2019/example.md"),
+    ///     Path::new("/blog/content"),
+    /// )?;
+    /// assert_eq!(location.section(), "post");
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn section(&self) -> &str {
+        self.relative_directory_to_content
+            .split(constants::FORWARD_SLASH)
+            .next()
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the file is an `_index.md`, which describes the section or taxonomy landing page of its directory
+    /// rather than a page of its own.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/_index.md"), Path::new("/blog/content"))?;
+    /// assert!(location.is_section());
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn is_section(&self) -> bool {
+        self.file_stem == constants::SECTION_FILE_STEM
+    }
+
+    /// Returns true if the file is content Hugo renders into a page: markdown, `AsciiDoc` or reStructuredText.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/example.adoc"), Path::new("/blog/content"))?;
+    /// assert!(location.is_content());
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn is_content(&self) -> bool {
+        matches!(
+            self.extension.as_ref(),
+            constants::MARKDOWN_EXTENSION
+                | constants::ASCIIDOC_EXTENSION
+                | constants::RST_EXTENSION
+        )
+    }
+
+    /// Returns true if the file is the `index.md` of a leaf bundle, a directory holding a page and its resources.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/my-post/index.md"), Path::new("/blog/content"))?;
+    /// assert!(location.is_bundle());
+    /// # Ok::<(), hugo_to_json::operation_result::OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn is_bundle(&self) -> bool {
+        self.file_stem == constants::BUNDLE_FILE_STEM
+            && !self.relative_directory_to_content.is_empty()
+    }
+
+    /// Returns the paths of a leaf bundle's resources, such as images and attachments, relative to the bundle's
+    /// directory. These are every other file in the directory and its subdirectories, except hidden files.
+    /// Files that aren't bundles have no resources.
+    pub fn bundle_resources(&self) -> Vec<String> {
+        let bundle_directory = match Path::new(&self.absolute_path).parent() {
+            Some(directory) if self.is_bundle() => directory,
+            _ => return Vec::new(),
+        };
+
+        WalkDir::new(bundle_directory)
+            .min_depth(1)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .into_iter()
+            .filter_entry(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry.file_type().is_file() && entry.file_name() != self.file_name.as_str()
+            })
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(bundle_directory)
+                    .ok()
+                    .map(|relative| {
+                        relative
+                            .components()
+                            .filter_map(|component| component.as_os_str().to_str())
+                            .collect::<Vec<&str>>()
+                            .join(constants::FORWARD_SLASH)
+                    })
+            })
+            .collect()
+    }
 }
 
 impl fmt::Display for FileLocation {
@@ -73,4 +317,132 @@ impl fmt::Display for FileLocation {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_path_builds_relative_directory() {
+        let location = FileLocation::from_path(
+            Path::new("/home/blog/content/sub/post/Example.md"),
+            Path::new("/home/blog/content"),
+        )
+        .unwrap();
+
+        assert_eq!(location.extension, "md");
+        assert_eq!(location.file_name, "Example.md");
+        assert_eq!(location.file_stem, "Example");
+        assert_eq!(location.relative_directory_to_content, "sub/post");
+        assert_eq!(
+            location.absolute_path,
+            "/home/blog/content/sub/post/Example.md"
+        );
+    }
+
+    #[test]
+    fn from_path_at_content_root_has_empty_relative_directory() {
+        let location = FileLocation::from_path(
+            Path::new("/home/blog/content/example.md"),
+            Path::new("/home/blog/content"),
+        )
+        .unwrap();
+
+        assert_eq!(location.relative_directory_to_content, "");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn from_path_uses_forward_slashes_on_windows() {
+        let location = FileLocation::from_path(
+            Path::new(r"C:\blog\content\post\2021\example.md"),
+            Path::new(r"C:\blog\content"),
+        )
+        .unwrap();
+
+        assert_eq!(location.relative_directory_to_content, "post/2021");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn from_path_accepts_mixed_separators_on_windows() {
+        let location = FileLocation::from_path(
+            Path::new(r"C:\blog\content/post\2021/example.md"),
+            Path::new(r"C:\blog\content"),
+        )
+        .unwrap();
+
+        assert_eq!(location.relative_directory_to_content, "post/2021");
+    }
+
+    #[test]
+    fn with_languages_reads_suffixes_and_directories() {
+        let languages = vec![String::from("en"), String::from("fr")];
+        let location = |path: &str| {
+            FileLocation::from_path(Path::new(path), Path::new("/home/blog/content"))
+                .unwrap()
+                .with_languages(&languages)
+        };
+
+        let suffixed = location("/home/blog/content/post/about.fr.md");
+        assert_eq!(
+            (
+                suffixed.lang.as_str(),
+                suffixed.default_lang,
+                suffixed.file_stem.as_str()
+            ),
+            ("fr", false, "about")
+        );
+        let in_directory = location("/home/blog/content/fr/post/about.md");
+        assert_eq!(in_directory.lang, "fr");
+        assert_eq!(in_directory.relative_directory_to_content, "post");
+        let default = location("/home/blog/content/post/v1.2.md");
+        assert_eq!(
+            (
+                default.lang.as_str(),
+                default.default_lang,
+                default.file_stem.as_str()
+            ),
+            ("en", true, "v1.2")
+        );
+    }
+
+    #[test]
+    fn bundle_resources_lists_other_files_in_the_bundle() {
+        let dir = tempfile::Builder::new().prefix("bundle").tempdir().unwrap();
+        let bundle = dir.path().join("post").join("my-post");
+        std::fs::create_dir_all(bundle.join("images")).unwrap();
+        for file in &["index.md", "cover.jpg", "images/diagram.png", ".DS_Store"] {
+            std::fs::write(bundle.join(file), "").unwrap();
+        }
+
+        let location = FileLocation::from_path(&bundle.join("index.md"), dir.path()).unwrap();
+        assert_eq!(
+            location.bundle_resources(),
+            vec!["cover.jpg", "images/diagram.png"]
+        );
+
+        let location = FileLocation::from_path(&bundle.join("cover.jpg"), dir.path()).unwrap();
+        assert!(location.bundle_resources().is_empty());
+    }
+
+    #[test]
+    fn from_path_outside_content_dir_returns_path_err() {
+        match FileLocation::from_path(
+            Path::new("/home/elsewhere/example.md"),
+            Path::new("/home/blog/content"),
+        ) {
+            Err(OperationResult::Path(_)) => (),
+            _ => panic!("This should fail"),
+        }
+    }
+
+    #[test]
+    fn from_path_without_extension_returns_path_err() {
+        match FileLocation::from_path(
+            Path::new("/home/blog/content/example"),
+            Path::new("/home/blog/content"),
+        ) {
+            Err(OperationResult::Path(_)) => (),
+            _ => panic!("This should fail"),
+        }
+    }
+}
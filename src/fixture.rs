@@ -0,0 +1,280 @@
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::constants::{
+    CATEGORIES, DATE, DESCRIPTION, DRAFT, KEYWORDS, MARKDOWN_EXTENSION, SERIES, SLUG, TAGS, TITLE,
+    TOML_FENCE, URL, YAML_FENCE,
+};
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::settings::{FixtureSettings, FrontMatterFormat};
+
+const SECTIONS: [&str; 4] = ["", "post", "post/2021", "talks"];
+const TAG_POOL: [&str; 6] = ["Rust", "Hugo", "Search", "Café", "C++", "Lunr"];
+
+/// A synthetic document, varied by its index so the generated tree covers every supported front matter shape.
+struct FixturePage {
+    index: usize,
+    title: String,
+    slug: Option<String>,
+    url: Option<String>,
+    date: String,
+    draft: bool,
+    description: Option<String>,
+    tags: Vec<String>,
+    categories: Vec<String>,
+    series: Vec<String>,
+    keywords: Vec<String>,
+}
+
+impl FixturePage {
+    fn new(index: usize) -> Self {
+        let title = if index.is_multiple_of(4) {
+            format!("Café \"Quoted\" Title #{index} \\ Ünïcödé")
+        } else {
+            format!("Example Page {index}")
+        };
+        let list = |offset: usize, length: usize| -> Vec<String> {
+            if index.is_multiple_of(5) {
+                return Vec::new();
+            }
+            (0..length)
+                .map(|i| TAG_POOL[(index + offset + i) % TAG_POOL.len()].to_owned())
+                .collect()
+        };
+        // Alternate between full timestamps and date only values
+        let date = if index.is_multiple_of(2) {
+            format!("2019-{:02}-{:02}T07:52:40Z", index % 12 + 1, index % 28 + 1)
+        } else {
+            format!("2019-{:02}-{:02}", index % 12 + 1, index % 28 + 1)
+        };
+
+        Self {
+            index,
+            title,
+            slug: if index.is_multiple_of(3) {
+                None
+            } else {
+                Some(format!("example-page-{index}"))
+            },
+            url: if index.is_multiple_of(7) {
+                Some(format!("/custom/url/{index}/"))
+            } else {
+                None
+            },
+            date,
+            draft: index.is_multiple_of(11),
+            description: if index.is_multiple_of(2) {
+                Some(format!("A description of page {index}."))
+            } else {
+                None
+            },
+            tags: list(0, 3),
+            categories: list(1, 1),
+            series: if index.is_multiple_of(8) {
+                list(2, 1)
+            } else {
+                Vec::new()
+            },
+            keywords: list(3, 2),
+        }
+    }
+
+    fn file_name(&self, format: FrontMatterFormat) -> String {
+        let stem = if self.index.is_multiple_of(6) {
+            "Page"
+        } else {
+            "page"
+        };
+        format!("{}-{}-{}.{}", stem, self.index, format, MARKDOWN_EXTENSION)
+    }
+
+    fn body(&self) -> String {
+        format!(
+            "# Heading {0}\n\nA paragraph with **bold**, _emphasis_ and a [link](https://example.com/{0}).\n\n* First item\n* Second item\n\n```rust\nfn main() {{ println!(\"{0}\"); }}\n```\n",
+            self.index
+        )
+    }
+
+    fn to_json_map(&self) -> Map<String, Value> {
+        let mut map = Map::new();
+        map.insert(TITLE.to_owned(), json!(self.title));
+        map.insert(DATE.to_owned(), json!(self.date));
+        map.insert(DRAFT.to_owned(), json!(self.draft));
+        if let Some(ref slug) = self.slug {
+            map.insert(SLUG.to_owned(), json!(slug));
+        }
+        if let Some(ref url) = self.url {
+            map.insert(URL.to_owned(), json!(url));
+        }
+        if let Some(ref description) = self.description {
+            map.insert(DESCRIPTION.to_owned(), json!(description));
+        }
+        map.insert(TAGS.to_owned(), json!(self.tags));
+        map.insert(CATEGORIES.to_owned(), json!(self.categories));
+        map.insert(SERIES.to_owned(), json!(self.series));
+        map.insert(KEYWORDS.to_owned(), json!(self.keywords));
+        map
+    }
+
+    fn render(&self, format: FrontMatterFormat) -> String {
+        // Some documents have blank lines before the front matter
+        let leading = if self.index.is_multiple_of(9) {
+            "\n\n"
+        } else {
+            ""
+        };
+        let front_matter = self.to_json_map();
+
+        match format {
+            FrontMatterFormat::Toml => {
+                // JSON strings and arrays of strings are valid TOML values
+                let fields: Vec<String> = front_matter
+                    .iter()
+                    .map(|(key, value)| format!("{key} = {value}"))
+                    .collect();
+                format!(
+                    "{0}{1}\n{2}\n{1}\n{3}",
+                    leading,
+                    TOML_FENCE,
+                    fields.join("\n"),
+                    self.body()
+                )
+            }
+            FrontMatterFormat::Yaml => {
+                // JSON scalars are valid YAML, lists are written in block style
+                let fields: Vec<String> = front_matter
+                    .iter()
+                    .map(|(key, value)| match value {
+                        Value::Array(items) if !items.is_empty() => {
+                            let items: Vec<String> =
+                                items.iter().map(|item| format!("  - {item}")).collect();
+                            format!("{}:\n{}", key, items.join("\n"))
+                        }
+                        _ => format!("{key}: {value}"),
+                    })
+                    .collect();
+                format!(
+                    "{0}{1}\n{2}\n{1}\n{3}",
+                    leading,
+                    YAML_FENCE,
+                    fields.join("\n"),
+                    self.body()
+                )
+            }
+            FrontMatterFormat::Json => format!(
+                "{}{}\n\n{}",
+                leading,
+                serde_json::to_string_pretty(&Value::Object(front_matter))
+                    .expect("A map of strings serializes"),
+                self.body()
+            ),
+        }
+    }
+}
+
+/// Generates a synthetic Hugo content tree in `settings.out_dir`, cycling through the requested front matter formats.
+/// Alongside the documents it writes a hidden file and a non markdown file, both of which should be skipped.
+///
+/// Returns the number of documents written.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::fixture::generate_fixture;
+/// use hugo_to_json::settings::{FixtureSettings, FrontMatterFormat};
+/// use std::path::PathBuf;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let settings = FixtureSettings {
+///     out_dir: PathBuf::from("/tmp/fixture/content"),
+///     pages: 500,
+///     formats: vec![FrontMatterFormat::Toml, FrontMatterFormat::Yaml],
+/// };
+/// let written = generate_fixture(&settings)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors if a directory or file can't be written.
+pub fn generate_fixture(settings: &FixtureSettings) -> Result<usize, HugotoJsonError> {
+    let formats = if settings.formats.is_empty() {
+        vec![FrontMatterFormat::Toml]
+    } else {
+        settings.formats.clone()
+    };
+
+    for index in 0..settings.pages {
+        let page = FixturePage::new(index);
+        let format = formats[index % formats.len()];
+        let directory = settings.out_dir.join(SECTIONS[index % SECTIONS.len()]);
+        create_dir_all(&directory)?;
+        write_file(
+            &directory.join(page.file_name(format)),
+            &page.render(format),
+        )?;
+    }
+
+    create_dir_all(&settings.out_dir)?;
+    write_file(
+        &settings.out_dir.join(".hidden.md"),
+        &FixturePage::new(1).render(FrontMatterFormat::Toml),
+    )?;
+    write_file(&settings.out_dir.join("notes.txt"), "Not a document")?;
+
+    info!(
+        "Wrote {} documents to {}",
+        settings.pages,
+        settings.out_dir.to_string_lossy()
+    );
+    Ok(settings.pages)
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), HugotoJsonError> {
+    File::create(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_location::FileLocation;
+    use crate::operation_result::OperationResult;
+    use crate::settings::Settings;
+    use crate::traverse::process_md_contents;
+
+    fn build_file_location() -> FileLocation {
+        FileLocation::from_path(
+            Path::new("/home/blog/content/post/example.md"),
+            Path::new("/home/blog/content"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn every_format_and_shape_parses() {
+        let formats = [
+            FrontMatterFormat::Toml,
+            FrontMatterFormat::Yaml,
+            FrontMatterFormat::Json,
+        ];
+        for index in 0..100 {
+            let page = FixturePage::new(index);
+            for format in &formats {
+                match process_md_contents(
+                    &page.render(*format),
+                    &build_file_location(),
+                    &Settings::default(),
+                ) {
+                    Ok(page_index) => {
+                        assert_eq!(page_index.title, page.title);
+                        assert_eq!(page_index.tags, page.tags);
+                    }
+                    Err(OperationResult::Skip(_)) => assert!(page.draft),
+                    Err(err) => panic!("{} failed to parse as {}: {}", index, format, err),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::constants::{self, CONTENT, TAGS, TITLE};
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::lunr::Boosts;
+use crate::settings::{OutputFormat, Settings};
+
+/// The name of the file the options are written to, beside the index.
+pub const OPTIONS_FILE_NAME: &str = "fuse-options.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+/// The options to create a [Fuse.js](https://fusejs.io/) search of the index with, e.g.
+/// `new Fuse(documents, options)`.
+pub struct FuseOptions {
+    /// The fields of each page that are searched, and how much matches in them count.
+    pub keys: Vec<FuseKey>,
+    /// Finds matches anywhere in a field, rather than only near its start, as the content of a page is long.
+    pub ignore_location: bool,
+    /// Gives each result the score it was ranked by.
+    pub include_score: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A field searched by Fuse.js.
+pub struct FuseKey {
+    /// The name of the field.
+    pub name: String,
+    /// How much matches in the field count, relative to the other fields.
+    pub weight: f64,
+}
+
+impl FuseOptions {
+    /// Creates the options that search the title, tags and content, weighted by `boosts`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::fuse::FuseOptions;
+    /// use hugo_to_json::lunr::Boosts;
+    ///
+    /// let options = FuseOptions::new(&Boosts::default());
+    /// assert_eq!(options.keys[0].name, "title");
+    /// assert_eq!(options.keys[0].weight, 10.0);
+    /// ```
+    #[must_use]
+    pub fn new(boosts: &Boosts) -> Self {
+        let key = |name: &str, weight| FuseKey {
+            name: name.to_owned(),
+            weight,
+        };
+        Self {
+            keys: vec![
+                key(TITLE, boosts.title),
+                key(TAGS, boosts.tags),
+                key(CONTENT, boosts.content),
+            ],
+            ignore_location: true,
+            include_score: true,
+        }
+    }
+}
+
+/// Returns the path the options are written to, in the directory of `output`.
+#[must_use]
+pub fn options_path(output: &Path) -> PathBuf {
+    output.with_file_name(OPTIONS_FILE_NAME)
+}
+
+/// Writes the options for the index the settings produce beside it, with the weights from the config file's boosts.
+///
+/// # Errors
+/// Errors if the index isn't a `json` index written to a file, as Fuse.js searches an array of pages, or if the options
+/// can't be written.
+pub fn write_options(settings: &Settings) -> Result<(), HugotoJsonError> {
+    let output = match settings.output {
+        Some(ref output) if settings.format == OutputFormat::Json => output,
+        _ => {
+            return Err(HugotoJsonError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--preset fusejs needs a json index written to a path given with --output",
+            )))
+        }
+    };
+    let path = options_path(output);
+    create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+    let options = FuseOptions::new(&settings.boosts);
+    fs::write(&path, serde_json::to_string_pretty(&options)?)?;
+    debug!("Wrote Fuse.js options to {}", path.to_string_lossy());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_written_beside_the_index() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            output: Some(directory.path().join("static").join("index.json")),
+            boosts: Boosts {
+                title: 3.0,
+                ..Boosts::default()
+            },
+            ..Settings::default()
+        };
+        write_options(&settings).unwrap();
+
+        let options: FuseOptions = serde_json::from_str(
+            &fs::read_to_string(directory.path().join("static").join(OPTIONS_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(options, FuseOptions::new(&settings.boosts));
+        assert!((options.keys[0].weight - 3.0).abs() < f64::EPSILON);
+
+        let to_stdout = Settings {
+            output: None,
+            ..settings.clone()
+        };
+        assert!(write_options(&to_stdout).is_err());
+        let lunr = Settings {
+            format: OutputFormat::Lunr,
+            ..settings
+        };
+        assert!(write_options(&lunr).is_err());
+    }
+}
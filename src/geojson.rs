@@ -0,0 +1,243 @@
+use serde_json::{Map, Value as JsonValue};
+
+use std::io::Write;
+
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::index_writer::IndexWriter;
+use crate::page_index::PageIndex;
+
+/// The front matter keys a latitude can be given with.
+const LATITUDE_KEYS: [&str; 2] = ["lat", "latitude"];
+
+/// The front matter keys a longitude can be given with.
+const LONGITUDE_KEYS: [&str; 4] = ["lng", "lon", "long", "longitude"];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Where a page is about, such as the place a travel post was written, from its front matter.
+pub struct Location {
+    /// Degrees north of the equator, from -90 to 90.
+    pub lat: f64,
+    /// Degrees east of the prime meridian, from -180 to 180.
+    pub lng: f64,
+    /// The name of the place, if the front matter gives one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Location {
+    /// Reads a page's location from its front matter. It can be a `location` table, e.g.
+    /// `location = { lat = 51.5, lng = -0.12, name = "London" }`, a `location` string of the coordinates, e.g.
+    /// `"51.5, -0.12"`, or top level `lat` and `lng`, alongside a `location` naming the place. Longitudes can also be
+    /// given as `lon`, `long` or `longitude`, and latitudes as `latitude`, as numbers or strings. Coordinates outside
+    /// their ranges are ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::geojson::Location;
+    /// use serde_json::json;
+    ///
+    /// let front_matter = json!({ "location": "Lisbon", "lat": 38.72, "lon": "-9.14" });
+    /// let location = Location::from_front_matter(front_matter.as_object().unwrap()).unwrap();
+    /// assert_eq!((location.lat, location.lng), (38.72, -9.14));
+    /// assert_eq!(location.name.as_deref(), Some("Lisbon"));
+    ///
+    /// let front_matter = json!({ "location": { "lat": 95, "lng": 0 } });
+    /// assert_eq!(Location::from_front_matter(front_matter.as_object().unwrap()), None);
+    /// ```
+    pub fn from_front_matter(front_matter: &Map<String, JsonValue>) -> Option<Self> {
+        let (coordinates, name) = match front_matter.get(constants::LOCATION) {
+            Some(JsonValue::Object(location)) => (
+                coordinates(location),
+                location
+                    .get(constants::LOCATION_NAME)
+                    .and_then(JsonValue::as_str),
+            ),
+            Some(JsonValue::String(location)) => match parse_pair(location) {
+                Some(coordinates) => (Some(coordinates), None),
+                None => (coordinates(front_matter), Some(location.as_str())),
+            },
+            _ => (coordinates(front_matter), None),
+        };
+        let (lat, lng) = coordinates?;
+        Some(Self {
+            lat,
+            lng,
+            name: name
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_owned),
+        })
+    }
+}
+
+/// Reads a latitude and longitude given under any of their keys, if both are in range.
+fn coordinates(fields: &Map<String, JsonValue>) -> Option<(f64, f64)> {
+    let degrees = |keys: &[&str]| {
+        keys.iter()
+            .filter_map(|key| fields.get(*key))
+            .find_map(|value| match value {
+                JsonValue::Number(number) => number.as_f64(),
+                JsonValue::String(number) => number.trim().parse().ok(),
+                _ => None,
+            })
+    };
+    in_range(degrees(&LATITUDE_KEYS)?, degrees(&LONGITUDE_KEYS)?)
+}
+
+/// Reads coordinates written as `"lat, lng"`.
+fn parse_pair(pair: &str) -> Option<(f64, f64)> {
+    let (lat, lng) = pair.split_once(',')?;
+    in_range(lat.trim().parse().ok()?, lng.trim().parse().ok()?)
+}
+
+fn in_range(lat: f64, lng: f64) -> Option<(f64, f64)> {
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) {
+        Some((lat, lng))
+    } else {
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A [GeoJSON](https://geojson.org/) collection of the pages that have a location.
+pub struct FeatureCollection {
+    /// Always `FeatureCollection`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// A feature for each page.
+    pub features: Vec<Feature>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A page as a `GeoJSON` feature, a point at its location.
+pub struct Feature {
+    /// Always `Feature`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Where the page is.
+    pub geometry: Point,
+    /// The fields of the page a map shows.
+    pub properties: FeatureProperties,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A `GeoJSON` point.
+pub struct Point {
+    /// Always `Point`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The longitude and latitude, in that order as `GeoJSON` has them.
+    pub coordinates: [f64; 2],
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// The fields of a page in its feature.
+pub struct FeatureProperties {
+    /// The title of the page.
+    pub title: String,
+    /// Where the page is.
+    pub href: String,
+    /// The date of the page, as written in its front matter.
+    pub date: String,
+    /// The name of the page's location, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub place: Option<String>,
+    /// The summary of the page, if it has one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+    /// The tags of the page.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Feature {
+    /// Creates the feature of a page, or returns `None` if it doesn't have a location.
+    #[must_use]
+    pub fn new(page: &PageIndex) -> Option<Self> {
+        let location = page.location.as_ref()?;
+        Some(Self {
+            kind: String::from("Feature"),
+            geometry: Point {
+                kind: String::from("Point"),
+                coordinates: [location.lng, location.lat],
+            },
+            properties: FeatureProperties {
+                title: page.title.clone(),
+                href: page.href.clone(),
+                date: page.date.clone(),
+                place: location.name.clone(),
+                summary: page.summary.clone(),
+                tags: page.tags.clone(),
+            },
+        })
+    }
+}
+
+/// Writes the pages that have a location as a `GeoJSON` `FeatureCollection`, e.g. for a map of a travel blog's posts.
+/// Pages without a location are left out.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::geojson::{FeatureCollection, GeoJsonWriter, Location};
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::IndexWriter;
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/lisbon.md"), Path::new("/blog/content"))?;
+/// let mut page = PageIndex::new(Some("Lisbon"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+/// let unlocated = page.clone();
+/// page.location = Some(Location { lat: 38.72, lng: -9.14, name: None });
+///
+/// let mut geojson = GeoJsonWriter::new(Vec::new(), false);
+/// geojson.write_page(&page)?;
+/// geojson.write_page(&unlocated)?;
+/// geojson.finalize()?;
+/// let collection: FeatureCollection = serde_json::from_slice(&geojson.into_inner())?;
+/// assert_eq!(collection.features.len(), 1);
+/// assert_eq!(collection.features[0].geometry.coordinates, [-9.14, 38.72]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct GeoJsonWriter<W: Write> {
+    writer: W,
+    pretty: bool,
+    features: Vec<Feature>,
+}
+
+impl<W: Write> GeoJsonWriter<W> {
+    /// Creates a writer of a collection to `writer`.
+    pub fn new(writer: W, pretty: bool) -> Self {
+        Self {
+            writer,
+            pretty,
+            features: Vec::new(),
+        }
+    }
+
+    /// Returns the writer the collection was written to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> IndexWriter for GeoJsonWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        self.features.extend(Feature::new(page));
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        let collection = FeatureCollection {
+            kind: String::from("FeatureCollection"),
+            features: std::mem::take(&mut self.features),
+        };
+        if self.pretty {
+            serde_json::to_writer_pretty(&mut self.writer, &collection)?;
+        } else {
+            serde_json::to_writer(&mut self.writer, &collection)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
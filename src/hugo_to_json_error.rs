@@ -1,3 +1,4 @@
+use std::fmt;
 use std::io;
 use thiserror::Error;
 
@@ -8,15 +9,197 @@ pub enum HugotoJsonError {
     #[error("IO Error: {0}")]
     Io(#[from] io::Error),
     /// Represents a case of one or more errors traversing and reading files in the contents directory.
-    #[error("An error occurred. Failed to process {total} files.")]
+    #[error(
+        "An error occurred. Failed to process {total} files: {parse} parse, {io} IO and {path} path errors."
+    )]
     Meta {
         /// The number of errors that occurred.
         total: usize,
+        /// The number of files whose front matter couldn't be parsed.
+        parse: usize,
+        /// The number of files that couldn't be read.
+        io: usize,
+        /// The number of files whose paths couldn't be handled.
+        path: usize,
     },
     /// An error trying to serialize to JSON.
     #[error("An error occurred serializing the index: {0}")]
     Serialization(#[from] serde_json::error::Error),
+    /// The written index failed validation.
+    #[error("The index failed validation: {}", .problems.join("; "))]
+    Validation {
+        /// A description of each problem found.
+        problems: Vec<String>,
+    },
+    /// An error watching the contents directory for changes.
+    #[error("An error occurred watching for changes: {0}")]
+    Watch(#[from] notify::Error),
+    /// The include or exclude globs couldn't be compiled.
+    #[error("An error occurred compiling the globs: {0}")]
+    Glob(#[from] globset::Error),
+    /// The ignore file couldn't be read or has a pattern that isn't a valid glob.
+    #[error("An error occurred reading the ignore file: {0}")]
+    Ignore(#[from] ignore::Error),
+    /// The config file couldn't be read.
+    #[error("An error occurred reading the config file {path}: {reason}")]
+    Config {
+        /// The path of the config file.
+        path: String,
+        /// Why the config file couldn't be read.
+        reason: String,
+    },
+    /// The documents couldn't be uploaded to a search engine.
+    #[error("An error occurred uploading the documents: {reason}")]
+    Upload {
+        /// Why the upload failed.
+        reason: String,
+    },
+    /// The index is larger than the size budget.
+    #[error("The index is {size} bytes, which is over the budget of {budget} bytes")]
+    IndexSize {
+        /// The size of the index in bytes.
+        size: u64,
+        /// The largest the index was allowed to be in bytes.
+        budget: u64,
+    },
+    /// A page's records are larger than a search engine accepts, however its content is split.
+    #[error("The record for {href} is {size} bytes, which is over the limit of {limit} bytes")]
+    RecordSize {
+        /// The href of the page.
+        href: String,
+        /// The size of its largest record in bytes.
+        size: usize,
+        /// The largest a record can be in bytes.
+        limit: usize,
+    },
+    /// Pages broke the front matter rules they were checked against.
+    #[error("Found {violations} front matter rule violations in {files} files")]
+    Check {
+        /// The number of rules broken.
+        violations: usize,
+        /// The number of files that broke at least one rule.
+        files: usize,
+    },
+    /// Links in pages' content don't go to a page or file.
+    #[error("Found {links} broken links in {files} files")]
+    BrokenLinks {
+        /// The number of broken links.
+        links: usize,
+        /// The number of files with at least one broken link.
+        files: usize,
+    },
+    /// Pages have the same href or title as another page.
+    #[error("Found {pages} pages with the same href or title as another page")]
+    Duplicates {
+        /// The number of pages that duplicate another.
+        pages: usize,
+    },
+    /// An error writing or reading a `SQLite` database.
+    #[error("An error occurred writing the database: {0}")]
+    Database(#[from] rusqlite::Error),
+    /// An error building a tantivy index.
+    #[cfg(feature = "tantivy")]
+    #[error("An error occurred building the search index: {0}")]
+    SearchIndex(#[from] tantivy::TantivyError),
     /// A catchall for all unknown errors.
     #[error("Unknown error")]
     Unknown,
 }
+
+impl HugotoJsonError {
+    /// Returns the stable `ErrorCode` for this error.
+    #[must_use]
+    pub fn code(&self) -> ErrorCode {
+        match *self {
+            HugotoJsonError::Io(_) => ErrorCode::Io,
+            HugotoJsonError::Meta { .. } => ErrorCode::Meta,
+            HugotoJsonError::Serialization(_) => ErrorCode::Serialization,
+            HugotoJsonError::Validation { .. } => ErrorCode::Validation,
+            HugotoJsonError::Watch(_) => ErrorCode::Watch,
+            HugotoJsonError::Glob(_) | HugotoJsonError::Ignore(_) => ErrorCode::Glob,
+            HugotoJsonError::Config { .. } => ErrorCode::Config,
+            HugotoJsonError::Upload { .. } => ErrorCode::Upload,
+            HugotoJsonError::IndexSize { .. } | HugotoJsonError::RecordSize { .. } => {
+                ErrorCode::IndexSize
+            }
+            HugotoJsonError::Check { .. } => ErrorCode::Check,
+            HugotoJsonError::BrokenLinks { .. } => ErrorCode::BrokenLink,
+            HugotoJsonError::Duplicates { .. } => ErrorCode::Duplicate,
+            HugotoJsonError::Database(_) => ErrorCode::Database,
+            #[cfg(feature = "tantivy")]
+            HugotoJsonError::SearchIndex(_) => ErrorCode::Database,
+            HugotoJsonError::Unknown => ErrorCode::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Stable codes identifying the category of a failure, so embedders can match on them rather than on `Display` output.
+pub enum ErrorCode {
+    /// An IO error.
+    Io,
+    /// A document's front matter couldn't be parsed.
+    Parse,
+    /// A document was skipped.
+    Skip,
+    /// A path couldn't be constructed.
+    Path,
+    /// One or more documents failed to process.
+    Meta,
+    /// The index couldn't be serialized.
+    Serialization,
+    /// The written index failed validation.
+    Validation,
+    /// The contents directory couldn't be watched.
+    Watch,
+    /// The include or exclude globs, or the patterns of an ignore file, were invalid.
+    Glob,
+    /// The config file was invalid.
+    Config,
+    /// The documents couldn't be uploaded to a search engine.
+    Upload,
+    /// The index was over its size budget, or a record was too large.
+    IndexSize,
+    /// Pages broke the front matter rules they were checked against.
+    Check,
+    /// Links in pages' content were broken.
+    BrokenLink,
+    /// Pages had the same href or title as another page.
+    Duplicate,
+    /// A `SQLite` database or tantivy index couldn't be written or read.
+    Database,
+    /// An unknown error.
+    Unknown,
+}
+
+impl ErrorCode {
+    /// Returns the code as a string. These values will not change between releases.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Io => "E001",
+            ErrorCode::Parse => "E002",
+            ErrorCode::Skip => "E003",
+            ErrorCode::Path => "E004",
+            ErrorCode::Meta => "E005",
+            ErrorCode::Serialization => "E006",
+            ErrorCode::Validation => "E007",
+            ErrorCode::Watch => "E008",
+            ErrorCode::Glob => "E009",
+            ErrorCode::Config => "E010",
+            ErrorCode::Upload => "E011",
+            ErrorCode::IndexSize => "E012",
+            ErrorCode::Check => "E013",
+            ErrorCode::BrokenLink => "E014",
+            ErrorCode::Duplicate => "E015",
+            ErrorCode::Database => "E016",
+            ErrorCode::Unknown => "E999",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,56 @@
+use std::io::Write;
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::operation_result::OperationResult;
+use crate::page_index::PageIndex;
+
+/// The result of converting a contents directory: the pages that were indexed and the files that failed to process.
+/// Skipped files, such as drafts, are in neither.
+#[derive(Debug)]
+pub struct Index {
+    /// The indexed pages in the order their files were walked.
+    pub pages: Vec<PageIndex>,
+    /// The errors for each file that failed to process.
+    pub errors: Vec<OperationResult>,
+}
+
+impl Index {
+    /// Creates an index from its pages and errors.
+    #[must_use]
+    pub fn new(pages: Vec<PageIndex>, errors: Vec<OperationResult>) -> Self {
+        Self { pages, errors }
+    }
+
+    /// Returns true if any file failed to process.
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Serializes the pages to a JSON array.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::Index;
+    /// # use hugo_to_json::HugotoJsonError;
+    ///
+    /// let index = Index::new(Vec::new(), Vec::new());
+    /// assert_eq!(index.to_json()?, "[]");
+    /// # Ok::<(), HugotoJsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Errors if the pages can't be serialized.
+    pub fn to_json(&self) -> Result<String, HugotoJsonError> {
+        Ok(serde_json::to_string(&self.pages)?)
+    }
+
+    /// Serializes the pages to a JSON array and writes them to `writer`.
+    ///
+    /// # Errors
+    /// Errors if the pages can't be serialized or written.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), HugotoJsonError> {
+        serde_json::to_writer(writer, &self.pages)?;
+        Ok(())
+    }
+}
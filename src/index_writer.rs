@@ -0,0 +1,442 @@
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use std::io::{self, Write};
+
+use crate::algolia;
+use crate::constants::{GENERATED_AT, GENERATOR_VERSION, PAGES, PAGE_COUNT};
+use crate::geojson::GeoJsonWriter;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::lunr::{self, Boosts};
+use crate::opensearch::SuggestionsWriter;
+use crate::page_index::PageIndex;
+use crate::settings::{OutputFormat, Settings};
+use crate::stork::StorkWriter;
+use crate::table::TableWriter;
+
+/// Where the pages of an index are written, one at a time as they're processed. Implement it to send pages somewhere
+/// other than a file, such as a database or a message queue, and pass it to `convert_into`.
+pub trait IndexWriter {
+    /// Writes a page of the index.
+    ///
+    /// # Errors
+    /// Errors if the page can't be serialized or written.
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError>;
+
+    /// Finishes the index after its last page, e.g. closing a JSON array, and flushes it.
+    ///
+    /// # Errors
+    /// Errors if the end of the index can't be written.
+    fn finalize(&mut self) -> Result<(), HugotoJsonError>;
+}
+
+/// Creates a writer of the index in the settings' format to `writer`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::index_writer::{self, IndexWriter};
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::settings::{OutputFormat, Settings};
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+///
+/// let settings = Settings { format: OutputFormat::Ndjson, ..Settings::default() };
+/// let mut ndjson = Vec::new();
+/// let mut writer = index_writer::for_settings(&mut ndjson, &settings)?;
+/// writer.write_page(&page)?;
+/// writer.write_page(&page)?;
+/// writer.finalize()?;
+/// drop(writer);
+/// assert_eq!(String::from_utf8(ndjson)?.lines().count(), 2);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+/// Errors if the start of the index, such as a table's header row, can't be written, the format is `sqlite` or
+/// `tantivy`, which aren't written to a stream, or an envelope is asked for around an index that isn't `json`.
+pub fn for_settings<'w, W: Write + 'w>(
+    writer: W,
+    settings: &Settings,
+) -> Result<Box<dyn IndexWriter + 'w>, HugotoJsonError> {
+    if settings.envelope && settings.format != OutputFormat::Json {
+        return Err(HugotoJsonError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--envelope can only wrap a json index",
+        )));
+    }
+    let index_writer: Box<dyn IndexWriter + 'w> = match settings.format {
+        OutputFormat::Json if settings.envelope => {
+            Box::new(EnvelopeWriter::new(writer, settings.pretty)?)
+        }
+        OutputFormat::Json => Box::new(JsonArrayWriter::new(writer, settings.pretty)),
+        OutputFormat::Ndjson => Box::new(NdjsonWriter::new(writer)),
+        OutputFormat::Algolia => Box::new(AlgoliaWriter::new(writer, settings.pretty)),
+        OutputFormat::Lunr => Box::new(LunrWriter::new(
+            writer,
+            settings.pretty,
+            settings.boosts.clone(),
+        )),
+        OutputFormat::Csv | OutputFormat::Tsv => Box::new(TableWriter::new(writer, settings)?),
+        OutputFormat::Stork => Box::new(StorkWriter::new(writer)),
+        OutputFormat::GeoJson => Box::new(GeoJsonWriter::new(writer, settings.pretty)),
+        OutputFormat::OpenSearchSuggest => {
+            Box::new(SuggestionsWriter::new(writer, settings.pretty))
+        }
+        // Databases are written to files or directories of their own with `sqlite::SqliteWriter` or
+        // `tantivy_index::TantivyWriter`
+        OutputFormat::Sqlite => return Err(needs_a_path()),
+        #[cfg(feature = "tantivy")]
+        OutputFormat::Tantivy => return Err(needs_a_path()),
+    };
+    Ok(index_writer)
+}
+
+/// The indentation of each level of a pretty printed index, as `serde_json` indents it.
+const INDENT: &str = "  ";
+
+/// The error for a database that would be written somewhere other than a path of its own, such as stdout.
+pub(crate) fn needs_a_path() -> HugotoJsonError {
+    HugotoJsonError::Io(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "A sqlite or tantivy index can only be written to a path given with --output",
+    ))
+}
+
+/// Writes pages as the elements of a JSON array, compact or pretty printed the way `serde_json` prints a whole array.
+pub struct JsonArrayWriter<W: Write> {
+    writer: W,
+    pretty: bool,
+    depth: usize,
+    elements: usize,
+}
+
+impl<W: Write> JsonArrayWriter<W> {
+    /// Creates a writer of an array to `writer`. Nothing is written until the first element or `finalize`.
+    pub fn new(writer: W, pretty: bool) -> Self {
+        Self::nested(writer, pretty, 0)
+    }
+
+    /// Creates a writer of an array that's the value of a field `depth` objects deep, so it's pretty printed at the
+    /// indentation of that field.
+    pub fn nested(writer: W, pretty: bool, depth: usize) -> Self {
+        Self {
+            writer,
+            pretty,
+            depth,
+            elements: 0,
+        }
+    }
+
+    /// Writes any value as the next element of the array.
+    ///
+    /// # Errors
+    /// Errors if the value can't be serialized or written.
+    pub fn write_element<T: Serialize>(&mut self, element: &T) -> Result<(), HugotoJsonError> {
+        let separator = if self.elements == 0 { "[" } else { "," };
+        if self.pretty {
+            // Each line of the element is indented a level, as it's nested in the array
+            let element = serde_json::to_string_pretty(element)?;
+            let indent = INDENT.repeat(self.depth + 1);
+            write!(self.writer, "{separator}")?;
+            for line in element.lines() {
+                write!(self.writer, "\n{indent}{line}")?;
+            }
+        } else {
+            self.writer.write_all(separator.as_bytes())?;
+            serde_json::to_writer(&mut self.writer, element)?;
+        }
+        self.elements += 1;
+        Ok(())
+    }
+
+    /// Returns the writer the array was written to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> IndexWriter for JsonArrayWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        self.write_element(page)
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        let end = match self.elements {
+            0 => String::from("[]"),
+            _ if self.pretty => format!("\n{}]", INDENT.repeat(self.depth)),
+            _ => String::from("]"),
+        };
+        self.writer.write_all(end.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes pages as a JSON array under `pages` in an object of metadata about the index: when it was generated, the
+/// version of `hugo_to_json` that generated it, and its `page_count`, which comes last as it's only known once every
+/// page has been written.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::index_writer::{EnvelopeWriter, IndexWriter};
+/// use hugo_to_json::page_index::PageIndex;
+/// use serde_json::Value;
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+///
+/// let mut envelope = EnvelopeWriter::new(Vec::new(), true)?;
+/// envelope.write_page(&page)?;
+/// envelope.finalize()?;
+/// let index: Value = serde_json::from_slice(&envelope.into_inner())?;
+/// assert_eq!(index["page_count"], 1);
+/// assert_eq!(index["pages"][0]["title"], "Rust");
+/// assert!(index["generated_at"].is_string());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct EnvelopeWriter<W: Write> {
+    array: JsonArrayWriter<W>,
+}
+
+impl<W: Write> EnvelopeWriter<W> {
+    /// Creates a writer of an envelope to `writer`, writing the metadata that comes before the pages.
+    ///
+    /// # Errors
+    /// Errors if the metadata can't be written.
+    pub fn new(mut writer: W, pretty: bool) -> Result<Self, HugotoJsonError> {
+        let generated_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let fields = [
+            (GENERATED_AT, generated_at.as_str()),
+            (GENERATOR_VERSION, env!("CARGO_PKG_VERSION")),
+        ];
+        writer.write_all(b"{")?;
+        for (name, value) in &fields {
+            if pretty {
+                write!(writer, "\n{}\"{}\": {},", INDENT, name, Value::from(*value))?;
+            } else {
+                write!(writer, "\"{}\":{},", name, Value::from(*value))?;
+            }
+        }
+        if pretty {
+            write!(writer, "\n{INDENT}\"{PAGES}\": ")?;
+        } else {
+            write!(writer, "\"{PAGES}\":")?;
+        }
+        Ok(Self {
+            array: JsonArrayWriter::nested(writer, pretty, 1),
+        })
+    }
+
+    /// Returns the writer the envelope was written to.
+    pub fn into_inner(self) -> W {
+        self.array.into_inner()
+    }
+}
+
+impl<W: Write> IndexWriter for EnvelopeWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        self.array.write_element(page)
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        self.array.finalize()?;
+        let pages = self.array.elements;
+        let writer = &mut self.array.writer;
+        if self.array.pretty {
+            write!(writer, ",\n{INDENT}\"{PAGE_COUNT}\": {pages}\n}}")?;
+        } else {
+            write!(writer, ",\"{PAGE_COUNT}\":{pages}}}")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes pages as newline delimited JSON, a compact JSON object on a line of its own for each page, which can be read
+/// or appended to a line at a time.
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// Creates a writer of lines to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write> IndexWriter for NdjsonWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        serde_json::to_writer(&mut self.writer, page)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes pages as a JSON array of Algolia records, splitting pages too large for a record.
+pub struct AlgoliaWriter<W: Write> {
+    array: JsonArrayWriter<W>,
+}
+
+impl<W: Write> AlgoliaWriter<W> {
+    /// Creates a writer of records to `writer`.
+    pub fn new(writer: W, pretty: bool) -> Self {
+        Self {
+            array: JsonArrayWriter::new(writer, pretty),
+        }
+    }
+}
+
+impl<W: Write> IndexWriter for AlgoliaWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        for record in algolia::records(page)? {
+            self.array.write_element(&record)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        self.array.finalize()
+    }
+}
+
+/// Writes a prebuilt Lunr index bundled with its documents. Every page has to be seen before the index can be built,
+/// so the pages are kept until `finalize` writes the bundle.
+pub struct LunrWriter<W: Write> {
+    writer: W,
+    pretty: bool,
+    boosts: Boosts,
+    pages: Vec<PageIndex>,
+}
+
+impl<W: Write> LunrWriter<W> {
+    /// Creates a writer of a bundle built with `boosts` to `writer`.
+    pub fn new(writer: W, pretty: bool, boosts: Boosts) -> Self {
+        Self {
+            writer,
+            pretty,
+            boosts,
+            pages: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> IndexWriter for LunrWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        self.pages.push(page.clone());
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        let bundle = lunr::bundle(&self.pages, &self.boosts);
+        if self.pretty {
+            serde_json::to_writer_pretty(&mut self.writer, &bundle)?;
+        } else {
+            serde_json::to_writer(&mut self.writer, &bundle)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> IndexWriter for TableWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        TableWriter::write_page(self, page)
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    fn page(name: &str) -> PageIndex {
+        TestPage::new(name)
+            .tags(&["rust"])
+            .content("Contents")
+            .build()
+    }
+
+    fn written(pages: &[PageIndex], pretty: bool) -> String {
+        let mut writer = JsonArrayWriter::new(Vec::new(), pretty);
+        for page in pages {
+            writer.write_page(page).unwrap();
+        }
+        writer.finalize().unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    /// Keeps the hrefs of the pages it's given.
+    #[derive(Default)]
+    struct Hrefs {
+        hrefs: Vec<String>,
+        finalized: bool,
+    }
+
+    impl IndexWriter for Hrefs {
+        fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+            self.hrefs.push(page.href.clone());
+            Ok(())
+        }
+
+        fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+            self.finalized = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn custom_writers_are_given_every_page() {
+        let content_dir = tempfile::Builder::new()
+            .prefix("not-a-dotfile-writer")
+            .tempdir()
+            .unwrap();
+        for name in &["one", "two"] {
+            std::fs::write(
+                content_dir.path().join(format!("{name}.md")),
+                format!("+++\ntitle = \"{name}\"\ndate = \"2019-01-25\"\n+++\nContents"),
+            )
+            .unwrap();
+        }
+        let settings = Settings {
+            scan_path: content_dir.path().to_owned(),
+            ..Settings::default()
+        };
+        let mut writer = Hrefs::default();
+        let errors = crate::convert_into(settings, &mut writer).unwrap();
+        assert!(errors.is_empty());
+        assert!(writer.finalized);
+        writer.hrefs.sort();
+        assert_eq!(writer.hrefs, vec!["/one/", "/two/"]);
+    }
+
+    #[test]
+    fn json_arrays_match_serde_json() {
+        for pages in &[
+            Vec::new(),
+            vec![page("one")],
+            vec![page("one"), page("two")],
+        ] {
+            assert_eq!(written(pages, false), serde_json::to_string(pages).unwrap());
+            assert_eq!(
+                written(pages, true),
+                serde_json::to_string_pretty(pages).unwrap()
+            );
+        }
+    }
+}
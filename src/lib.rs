@@ -9,27 +9,261 @@ extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
+/// Contains the Algolia output format.
+pub mod algolia;
+/// Contains converting a contents directory as a future, for async programs.
+#[cfg(feature = "async")]
+pub mod async_convert;
+/// Contains checking front matter against rules, like a linter.
+pub mod check;
+/// Contains the SHA-256 checksums of written indexes.
+pub mod checksum;
+/// Contains writing and reading compressed indexes.
+pub mod compress;
+/// Contains the `hugotojson.toml` config file.
+pub mod config;
+/// Contains date parsing and normalisation.
+pub mod date;
+/// Contains the changes to an index since an earlier run.
+pub mod diff;
+/// Contains finding pages that duplicate the href or title of another.
+pub mod duplicates;
+/// Contains the report of files that failed to process.
+pub mod error_report;
+/// Contains extracting the text of markdown content.
+pub mod extract;
+/// Contains the RSS and Atom feed of the newest pages.
+pub mod feed;
+/// Contains the `FileLocation` data structure.
+pub mod file_location;
+/// Contains a generator for synthetic Hugo content trees.
+pub mod fixture;
+/// Contains the options for searching the index with Fuse.js.
+pub mod fuse;
+/// Contains the locations of pages and the `GeoJSON` output format.
+pub mod geojson;
 /// Contains possible errors.
 pub mod hugo_to_json_error;
+/// Contains the `Index` produced by `convert`.
+pub mod index;
+/// Contains the writers of each index format, and the trait other sinks of pages implement.
+pub mod index_writer;
+/// Contains the link graph of links between pages.
+pub mod links;
+/// Contains setting up logging, as text or structured JSON.
+pub mod logging;
+/// Contains the Lunr output format.
+pub mod lunr;
+/// Contains reading the menus pages are in from their front matter.
+pub mod menu;
+/// Contains lowercasing, stop word removal and stemming of content.
+pub mod normalize;
+/// Contains the `OpenSearch` suggestions output format.
+pub mod opensearch;
 /// Represents the result of trying to parse a file.
 pub mod operation_result;
 /// Contains the `PageIndex` data structure.
 pub mod page_index;
+/// Contains finding the pages related to each page.
+pub mod related;
+/// Contains the JSON Schema of the index.
+pub mod schema;
 /// Contains configuration options.
 pub mod settings;
+/// Contains splitting the index into shards listed in a manifest.
+pub mod shard;
+/// Contains signing indexes.
+pub mod sign;
+/// Contains the parts of Hugo's site config that affect the index.
+pub mod site_config;
+/// Contains the sitemap of every page.
+pub mod sitemap;
+/// Contains making slugs for hrefs the way Hugo does.
+pub mod slug;
+/// Contains sorting and limiting the pages of an index.
+pub mod sort;
+/// Contains writing the index as a `SQLite` database.
+pub mod sqlite;
+/// Contains the totals printed once an index is written.
+pub mod stats;
+/// Contains processing a single document read from stdin.
+pub mod stdin;
+/// Contains the Stork output format.
+pub mod stork;
+/// Contains writing the index as a table of comma or tab separated values.
+pub mod table;
+/// Contains building a tantivy index.
+#[cfg(feature = "tantivy")]
+pub mod tantivy_index;
+/// Contains the taxonomy index, which maps each term to the pages that have it.
+pub mod taxonomy_index;
+/// Contains uploading documents to search engines.
+pub mod upload;
+/// Contains validation of written indexes.
+pub mod validate;
+/// Contains watch mode, which keeps an index up to date as the contents directory changes.
+pub mod watch;
+/// Contains the TF-IDF weights of each page's terms.
+pub mod weights;
 
+mod atomic;
 mod constants;
-mod file_location;
+mod progress;
 mod traverse;
+mod xml;
 
-use std::fs::{create_dir_all, File};
-use std::io::{self, Write};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, create_dir_all};
+use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
 
-use hugo_to_json_error::HugotoJsonError;
+use walkdir::WalkDir;
+
+use atomic::AtomicFile;
+use compress::Encoder;
+use duplicates::Duplicates;
+use error_report::ErrorRecord;
+use feed::Feed;
+use links::{LinkChecker, LinkGraph};
+use sitemap::Sitemap;
+use sqlite::SqliteWriter;
+use stats::Stats;
+use taxonomy_index::TaxonomyIndex;
 use traverse::{TraverseResults, Traverser};
 
-/// Given a contents directory it traverses all matching `.md` files with TOML and YAML frontmatter.
+#[cfg(feature = "async")]
+pub use async_convert::convert_async;
+pub use extract::ContentExtractor;
+pub use file_location::FileLocation;
+pub use hugo_to_json_error::{ErrorCode, HugotoJsonError};
+pub use index::Index;
+pub use index_writer::IndexWriter;
+pub use operation_result::{OperationResult, ParseError, PathError, Skip, SkipReason};
+pub use page_index::{Heading, PageIndex, PageKind, PageSection};
+pub use settings::{
+    Command, ExtractorKind, FailOn, FrontMatterMode, Granularity, Header, LogFormat, Output,
+    OutputFormat, OutputKind, Preset, Settings, Signer, SortKey, SortOrder, UploadTarget,
+};
+pub use traverse::{
+    process_document, process_md_contents, process_md_json_front_matter,
+    process_md_toml_front_matter, process_md_yaml_front_matter, IgnoreFile, IGNORE_FILE_NAME,
+};
+
+/// Converts a [Hugo](https://gohugo.io/) contents directory to an `Index` without writing anything.
+/// This is the entry point for embedding the conversion in other programs, such as build scripts.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::{convert, Settings};
+/// use std::path::PathBuf;
+/// # use hugo_to_json::HugotoJsonError;
+///
+/// let settings = Settings {
+///     scan_path: PathBuf::from("/home/example_user/documents/blog/contents/"),
+///     ..Settings::default()
+/// };
+/// let index = convert(settings)?;
+/// for error in &index.errors {
+///     eprintln!("{}: {}", error.code(), error);
+/// }
+/// std::fs::write("index.json", index.to_json()?)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors if the contents directory can't be accessed. Files that fail to process are collected in `Index::errors`.
+#[allow(clippy::needless_pass_by_value)]
+pub fn convert(settings: Settings) -> Result<Index, HugotoJsonError> {
+    let traverse_results = create_page_index_with_settings(&settings)?;
+    Ok(Index::new(
+        traverse_results.page_index,
+        traverse_results.errors,
+    ))
+}
+
+/// Converts a [Hugo](https://gohugo.io/) contents directory like `convert`, but hands each page to `writer` as soon
+/// as it's processed rather than collecting them, so pages can be sent somewhere other than a file, such as a database
+/// or a message queue. Any taxonomy index, feed, sitemap or link graph the settings ask for is written as well.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::{convert_into, HugotoJsonError, IndexWriter, PageIndex, Settings};
+/// use std::path::PathBuf;
+///
+/// /// Prints the href of each page.
+/// struct Hrefs;
+///
+/// impl IndexWriter for Hrefs {
+///     fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+///         println!("{}", page.href);
+///         Ok(())
+///     }
+///
+///     fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+///         Ok(())
+///     }
+/// }
+///
+/// let settings = Settings {
+///     scan_path: PathBuf::from("/home/example_user/documents/blog/contents/"),
+///     ..Settings::default()
+/// };
+/// let errors = convert_into(settings, &mut Hrefs)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors if the contents directory can't be accessed or `writer` fails. A record of each file that failed to process
+/// is returned.
+#[allow(clippy::needless_pass_by_value)]
+pub fn convert_into<W: IndexWriter + ?Sized>(
+    settings: Settings,
+    writer: &mut W,
+) -> Result<Vec<ErrorRecord>, HugotoJsonError> {
+    for scan_path in settings.scan_paths() {
+        fs::metadata(scan_path)?;
+    }
+    let (errors, outputs) = stream_pages(&settings, &Stats::new(), |page_index| {
+        writer.write_page(&page_index)
+    })?;
+    writer.finalize()?;
+    outputs.write(&settings)?;
+    Ok(errors)
+}
+
+/// Runs the command line tool with the given settings, after applying the config file: a subcommand if one is given,
+/// otherwise processing a single document from stdin with `stdin`, writing the index once or, with `watch`,
+/// continuously, uploading the documents with `upload`, or posting the index with `post_index`.
+///
+/// # Errors
+/// Returns an error if the config file is invalid, otherwise the error of whichever operation was run.
+pub fn run(settings: &Settings) -> Result<(), HugotoJsonError> {
+    for name in &settings.ignored_env_vars {
+        warn!("Ignoring {name}, as there's no option it sets");
+    }
+    let mut settings = settings.clone();
+    settings.load_config()?;
+    settings.apply_outputs();
+    if settings.command.is_none() {
+        checksum::check_written_to_a_file(&settings)?;
+    }
+
+    match settings.command {
+        Some(Command::GenFixture(ref fixture_settings)) => {
+            fixture::generate_fixture(fixture_settings).map(|_| ())
+        }
+        Some(Command::Check(ref check_settings)) => check::run_check(&settings, check_settings),
+        None if settings.stdin => stdin::convert_stdin(&settings),
+        None if settings.watch => watch::watch(&settings),
+        None if settings.upload.is_some() => upload::upload_index(&settings),
+        None if settings.post_url.is_some() => upload::post_index(&settings),
+        None => convert_to_json_and_write_with_settings(&settings),
+    }
+}
+
+/// Given a contents directory it traverses all matching `.md` files with TOML and YAML frontmatter, with the default
+/// settings.
 ///
 ///  # Examples
 /// ```no_run
@@ -51,12 +285,47 @@ use traverse::{TraverseResults, Traverser};
 /// A `HugoToJsonError` should only occur if an IO error occurs trying to access the contents directory.
 /// All other errors are stored in the errors property of the `TraverseResults`.
 pub fn create_page_index(contents_directory: PathBuf) -> Result<TraverseResults, HugotoJsonError> {
-    let traverser = Traverser::new(contents_directory);
+    create_page_index_with_settings(&Settings {
+        scan_path: contents_directory,
+        ..Settings::default()
+    })
+}
+
+/// Given the settings' contents directory it traverses all matching `.md` files with TOML and YAML frontmatter.
+///
+///  # Examples
+/// ```no_run
+/// use hugo_to_json::{create_page_index_with_settings, page_index::PageIndex, operation_result::OperationResult, hugo_to_json_error::HugotoJsonError, settings::Settings};
+/// use std::path::PathBuf;
+///
+/// let settings = Settings {
+///     scan_path: PathBuf::from("/home/example_user/documents/blog/contents/"),
+///     ..Settings::default()
+/// };
+/// let traverse_result = create_page_index_with_settings(&settings)?;
+/// // We can then see if there were any errors.
+/// let indices: Vec<PageIndex> = traverse_result.page_index;
+/// let errors: Vec<OperationResult> = traverse_result.errors;
+///
+/// if traverse_result.error_count > 0 {
+///     panic!("Errors found"); // Don't do this for real!
+/// }
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// A `HugoToJsonError` should only occur if an IO error occurs trying to access the contents directory.
+/// All other errors are stored in the errors property of the `TraverseResults`.
+pub fn create_page_index_with_settings(
+    settings: &Settings,
+) -> Result<TraverseResults, HugotoJsonError> {
+    let traverser = Traverser::new(settings.clone());
     let index = traverser.traverse_files()?;
 
     let (oks, errors): (Vec<_>, Vec<_>) = index.into_iter().partition(Result::is_ok);
-    let index: Vec<_> = oks.into_iter().map(Result::unwrap).collect();
+    let mut index: Vec<_> = oks.into_iter().map(Result::unwrap).collect();
     let errors: Vec<_> = errors.into_iter().map(Result::unwrap_err).collect();
+    finish_pages(settings, &mut index);
 
     Ok(TraverseResults::new(index, errors))
 }
@@ -69,8 +338,9 @@ fn write_page_index<W: Write>(
     Ok(())
 }
 
-/// Converts a [Hugo](https://gohugo.io/) contents directory to JSON and writes it to a given location.
-/// If the output location is provided and it doesn't exist, it will be created. If no output location it will write to stdout.
+/// Converts a [Hugo](https://gohugo.io/) contents directory to JSON and writes it to a given location, with the default
+/// settings. If the output location is provided and it doesn't exist, it will be created. If no output location it will
+/// write to stdout.
 ///
 /// # Examples
 ///
@@ -99,32 +369,716 @@ pub fn convert_to_json_and_write(
     contents_directory: PathBuf,
     output_location: Option<PathBuf>,
 ) -> Result<(), HugotoJsonError> {
-    info!("Scanning {:?}", contents_directory);
-    let traverse_results = create_page_index(contents_directory)?;
-    let index = serde_json::to_string(&traverse_results.page_index)?;
+    convert_to_json_and_write_with_settings(&Settings {
+        scan_path: contents_directory,
+        output: output_location,
+        ..Settings::default()
+    })
+}
+
+/// Converts the settings' [Hugo](https://gohugo.io/) contents directory and writes the index, and anything else the
+/// settings ask for, to the settings' output location. If the output location is provided and it doesn't exist, it
+/// will be created. If no output location it will write to stdout.
+///
+/// # Examples
+///
+/// A basic example that writes to stdout.
+/// ```no_run
+/// use hugo_to_json::{convert_to_json_and_write_with_settings, settings::Settings};
+/// use std::path::PathBuf;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+/// let settings = Settings {
+///     scan_path: PathBuf::from("/home/example_user/documents/blog/contents/"),
+///     ..Settings::default()
+/// };
+/// convert_to_json_and_write_with_settings(&settings)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// An example that writes to a file.
+/// ```no_run
+/// use hugo_to_json::{convert_to_json_and_write_with_settings, settings::Settings};
+/// use std::path::PathBuf;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let settings = Settings {
+///     scan_path: PathBuf::from("/home/example_user/documents/blog/contents/"),
+///     output: Some(PathBuf::from("/home/example_user/documents/blog/static/index.json")),
+///     ..Settings::default()
+/// };
+/// let result = convert_to_json_and_write_with_settings(&settings)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors can occur if there is an error accessing the contents directory, serializing the page index to JSON, or performing IO writing the result out to either stdout or a file.
+/// If `validate` is set, an error is also returned if the written index fails validation.
+pub fn convert_to_json_and_write_with_settings(settings: &Settings) -> Result<(), HugotoJsonError> {
+    let stats = Stats::new();
+    let result = write_indexes(settings, &stats);
+    // The totals are printed even if writing failed, as they may show why
+    if settings.stats {
+        eprintln!("{stats}");
+    }
+    result
+}
 
-    // Logging
-    let writing_to;
-    match output_location {
-        Some(ref path) => writing_to = path.to_string_lossy().into_owned(),
-        None => writing_to = String::from("stdout"),
+/// Writes the indexes and other files the settings ask for, counting what's written in `stats`.
+fn write_indexes(settings: &Settings, stats: &Stats) -> Result<(), HugotoJsonError> {
+    info!("Scanning {:?}", settings.scan_paths());
+    // Check the contents directories exist before creating the output file
+    for scan_path in settings.scan_paths() {
+        fs::metadata(scan_path)?;
+    }
+    if let Some(ref path) = settings.emit_schema {
+        schema::write_schema(settings, path)?;
+    }
+    if settings.preset == Some(Preset::FuseJs) {
+        fuse::write_options(settings)?;
     }
-    info!("Writing index to {}", writing_to);
+    if settings.split_by_section {
+        return write_split_indexes(settings, stats, |file_location, _| {
+            match file_location.section() {
+                "" => constants::ROOT_SECTION_INDEX.to_owned(),
+                section => section.to_owned(),
+            }
+        });
+    }
+    if settings.split_by_lang {
+        return write_split_indexes(settings, stats, |_, page_index| page_index.lang.clone());
+    }
+    if settings.format == OutputFormat::Lunr
+        || settings.format.is_database()
+        || finishes_pages(settings)
+        || settings.sort.is_some()
+        || settings.limit.is_some()
+        || settings.shard_size.is_some()
+        || settings.diff.is_some()
+        || !settings.outputs.is_empty()
+    {
+        // The earlier index is read before it can be overwritten by the new one
+        let previous = match settings.diff {
+            Some(ref path) => Some(diff::read_records(path)?),
+            None => None,
+        };
+        // Every document has to be read before any can be scored, related, weighted or sorted, or written to more than
+        // one index, shards or a database, or compared with an earlier index, so the index can't be streamed
+        let (pages, mut errors) = collect_pages(settings, stats)?;
+        let outputs = PageOutputs::from_pages(settings, &pages);
+        errors.extend(outputs.records());
+        let reported = report_errors(settings, &errors);
+        if reported.is_err() && settings.no_clobber_on_error {
+            // The existing indexes and the files written alongside them are left as they were
+            return reported;
+        }
+        outputs.write(settings)?;
+        stats.add_bytes(write_every_index(settings, &pages)?);
+        if let Some(ref previous) = previous {
+            diff::write_changes(settings, previous, &pages)?;
+        }
+        return reported;
+    }
+
+    let writing_to = describe_output(settings);
+    info!("Writing index to {writing_to}");
 
-    match output_location {
-        Some(path) => {
-            create_dir_all(&path.with_file_name(constants::EMPTY_STRING))?;
-            write_page_index(File::create(&path)?, &index)?
+    let (errors, outputs, size, file) = match settings.output {
+        Some(ref path) => {
+            let path = compress::compressed_path(path, settings.compress);
+            let file = AtomicFile::create(&path)?;
+            let mut encoder = Encoder::new(BufWriter::new(file), settings.compress);
+            let (errors, outputs, size) = stream_page_index(settings, stats, &mut encoder)?;
+            let file = encoder
+                .finish()?
+                .into_inner()
+                .map_err(io::IntoInnerError::into_error)?;
+            if settings.validate {
+                // Read the file back so the artifact on disk is what's checked
+                validate_written_index(
+                    settings,
+                    &compress::read_to_string(file.path(), settings.compress)?,
+                )?;
+            }
+            (errors, outputs, size, Some(file))
+        }
+        None if settings.validate => {
+            // Validation needs the whole index so it's buffered rather than streamed
+            let mut index = Vec::new();
+            let (errors, outputs, size) = stream_page_index(settings, stats, &mut index)?;
+            io::stdout().write_all(&index)?;
+            validate_written_index(settings, &String::from_utf8_lossy(&index))?;
+            (errors, outputs, size, None)
+        }
+        None => {
+            let stdout = io::stdout();
+            let (errors, outputs, size) = stream_page_index(settings, stats, stdout.lock())?;
+            (errors, outputs, size, None)
+        }
+    };
+    stats.add_bytes(size);
+    check_index_size(settings, size)?;
+    let reported = report_errors(settings, &errors);
+    // The index written to a file only replaces the existing one once it's complete and checked, and, if the settings
+    // say so, the run hasn't failed, and the same goes for the files written alongside it
+    if reported.is_ok() || !settings.no_clobber_on_error {
+        if let Some(file) = file {
+            checksum::commit(settings, file)?;
         }
-        None => write_page_index(io::stdout(), &index)?,
+        outputs.write(settings)?;
     }
+    debug!("Succesfully wrote index to {writing_to}");
+    reported
+}
 
-    if traverse_results.error_count > 0 {
-        Err(HugotoJsonError::Meta {
-            total: traverse_results.error_count,
-        })
-    } else {
-        debug!("Succesfully wrote index to {0}", writing_to);
+/// Writes an index for each group of pages into the output directory, named after the group `name_of` puts each page in.
+fn write_split_indexes<F>(
+    settings: &Settings,
+    stats: &Stats,
+    name_of: F,
+) -> Result<(), HugotoJsonError>
+where
+    F: Fn(&FileLocation, &PageIndex) -> String,
+{
+    let output_directory = settings
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let mut groups: BTreeMap<String, Vec<PageIndex>> = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    Traverser::new(settings.clone()).traverse_streaming(|(file_location, result)| {
+        stats.record(&result);
+        match result {
+            Ok(page_index) => groups
+                .entry(name_of(&file_location, &page_index))
+                .or_default()
+                .push(page_index),
+            Err(OperationResult::Skip(_)) => (), // Skips don't need to be handled
+            Err(ref error) => errors.push(ErrorRecord::new(&file_location, error)),
+        }
         Ok(())
+    })?;
+
+    // Pages are related to and weighted against those in every group, not only their own
+    finish_pages(settings, groups.values_mut().flatten());
+    let outputs = PageOutputs::from_pages(settings, groups.values().flatten());
+    errors.extend(outputs.records());
+    let reported = report_errors(settings, &errors);
+    if reported.is_err() && settings.no_clobber_on_error {
+        // The existing indexes and the files written alongside them are left as they were
+        return reported;
+    }
+    create_dir_all(&output_directory)?;
+    for (name, pages) in &groups {
+        let file_name = match settings.format.extension() {
+            Some(extension) => format!("{name}.{extension}"),
+            None => name.clone(),
+        };
+        let group_settings = Settings {
+            output: Some(output_directory.join(file_name)),
+            ..settings.clone()
+        };
+        stats.add_bytes(write_index(&group_settings, pages)?);
     }
+    outputs.write(settings)?;
+    reported
+}
+
+/// The files written alongside the index that the settings ask for: the taxonomy index, feed, sitemap and link graph,
+/// along with the link check and the check for duplicate pages. They're built as pages are added, so they can be built
+/// as the index is streamed.
+struct PageOutputs {
+    taxonomy_index: Option<TaxonomyIndex>,
+    feed: Option<Feed>,
+    sitemap: Option<Sitemap>,
+    link_graph: Option<LinkGraph>,
+    link_checker: Option<LinkChecker>,
+    duplicates: Duplicates,
+}
+
+impl PageOutputs {
+    fn new(settings: &Settings) -> Self {
+        let limit = settings
+            .rss_limit
+            .or(settings.site.rss_limit)
+            .unwrap_or(feed::DEFAULT_LIMIT);
+        Self {
+            taxonomy_index: settings
+                .taxonomy_index
+                .as_ref()
+                .map(|_| TaxonomyIndex::new()),
+            feed: settings
+                .rss
+                .as_ref()
+                .map(|_| Feed::new(&settings.site, traverse::date_normalizer(settings), limit)),
+            sitemap: settings
+                .sitemap
+                .as_ref()
+                .map(|_| Sitemap::new(&settings.site, traverse::date_normalizer(settings))),
+            link_graph: settings
+                .links
+                .as_ref()
+                .map(|_| LinkGraph::new(&settings.site)),
+            link_checker: Some(settings)
+                .filter(|settings| settings.check_links)
+                .map(LinkChecker::new),
+            duplicates: Duplicates::new(),
+        }
+    }
+
+    fn add(&mut self, page: &PageIndex) {
+        if let Some(ref mut taxonomy_index) = self.taxonomy_index {
+            taxonomy_index.add(page);
+        }
+        if let Some(ref mut feed) = self.feed {
+            feed.add(page);
+        }
+        if let Some(ref mut sitemap) = self.sitemap {
+            sitemap.add(page);
+        }
+        if let Some(ref mut link_graph) = self.link_graph {
+            link_graph.add(page);
+        }
+        if let Some(ref mut link_checker) = self.link_checker {
+            link_checker.add(page);
+        }
+        self.duplicates.add(page);
+    }
+
+    /// Creates the files for `pages`.
+    fn from_pages<'a, I>(settings: &Settings, pages: I) -> Self
+    where
+        I: IntoIterator<Item = &'a PageIndex>,
+    {
+        let mut outputs = Self::new(settings);
+        for page in pages {
+            outputs.add(page);
+        }
+        outputs
+    }
+
+    /// Returns a record of each page that duplicates another and, if the links were checked, each broken link.
+    fn records(&self) -> Vec<ErrorRecord> {
+        let mut records = self.duplicates.records().to_vec();
+        if let Some(ref link_checker) = self.link_checker {
+            records.extend(link_checker.check());
+        }
+        records
+    }
+
+    /// Writes the files.
+    fn write(&self, settings: &Settings) -> Result<(), HugotoJsonError> {
+        if let (Some(taxonomy_index), Some(path)) = (&self.taxonomy_index, &settings.taxonomy_index)
+        {
+            taxonomy_index.write(path)?;
+        }
+        if let (Some(feed), Some(path)) = (&self.feed, &settings.rss) {
+            feed.write(path)?;
+        }
+        if let (Some(sitemap), Some(path)) = (&self.sitemap, &settings.sitemap) {
+            sitemap.write(path)?;
+        }
+        if let (Some(link_graph), Some(path)) = (&self.link_graph, &settings.links) {
+            link_graph.write(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the files the settings ask for alongside the index, such as the taxonomy index, feed, sitemap and link graph.
+/// Returns a record of each page that duplicates another, and each broken link if the settings ask for the links to be
+/// checked.
+pub(crate) fn write_page_outputs<'a, I>(
+    settings: &Settings,
+    pages: I,
+) -> Result<Vec<ErrorRecord>, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let outputs = PageOutputs::from_pages(settings, pages);
+    outputs.write(settings)?;
+    Ok(outputs.records())
+}
+
+/// Processes every file in the contents directory, returning the pages and a record of each file that failed. Each
+/// result is counted in `stats`.
+pub(crate) fn collect_pages(
+    settings: &Settings,
+    stats: &Stats,
+) -> Result<(Vec<PageIndex>, Vec<ErrorRecord>), HugotoJsonError> {
+    let mut pages = Vec::new();
+    let mut errors = Vec::new();
+    Traverser::new(settings.clone()).traverse_streaming(|(file_location, result)| {
+        stats.record(&result);
+        match result {
+            Ok(page_index) => pages.push(page_index),
+            Err(OperationResult::Skip(_)) => (), // Skips don't need to be handled
+            Err(ref error) => errors.push(ErrorRecord::new(&file_location, error)),
+        }
+        Ok(())
+    })?;
+    finish_pages(settings, &mut pages);
+    Ok((pages, errors))
+}
+
+/// Returns true if the settings ask for anything that needs every page before it can be worked out, such as related
+/// pages or term weights.
+pub(crate) fn finishes_pages(settings: &Settings) -> bool {
+    settings.related.is_some() || settings.weights
+}
+
+/// Works out what needs every page, if the settings ask for it: each page's related pages and term weights.
+pub(crate) fn finish_pages<'a, I>(settings: &Settings, pages: I)
+where
+    I: IntoIterator<Item = &'a mut PageIndex>,
+{
+    if !finishes_pages(settings) {
+        return;
+    }
+    let mut pages: Vec<&mut PageIndex> = pages.into_iter().collect();
+    if let Some(count) = settings.related {
+        related::relate(
+            pages.iter_mut().map(|page| &mut **page),
+            count,
+            &settings.site.related,
+        );
+    }
+    if settings.weights {
+        weights::weigh(
+            pages.iter_mut().map(|page| &mut **page),
+            settings.stem_weights,
+        );
+    }
+}
+
+/// Writes the error report, if the settings ask for one, and logs how many files failed of each kind, how many links
+/// were broken and how many pages were duplicates. Fails if any of the failures are of a kind the settings fail on, or
+/// there are duplicates and the settings fail on them.
+pub(crate) fn report_errors(
+    settings: &Settings,
+    errors: &[ErrorRecord],
+) -> Result<(), HugotoJsonError> {
+    if let Some(ref path) = settings.error_report {
+        error_report::write_error_report(path, errors)?;
+    }
+
+    let of_kind = |kind: &str| errors.iter().filter(|error| error.kind == kind).count();
+    let broken_links = of_kind("link");
+    let duplicates = of_kind("duplicate");
+    let failed = errors.len() - broken_links - duplicates;
+    let files: BTreeSet<&str> = errors
+        .iter()
+        .filter(|error| error.kind == "link")
+        .map(|error| error.path.as_str())
+        .collect();
+    let failures = vec![
+        (
+            HugotoJsonError::Meta {
+                total: failed,
+                parse: of_kind("parse"),
+                io: of_kind("io"),
+                path: of_kind("path"),
+            },
+            failed,
+            errors
+                .iter()
+                .filter(|error| error.kind != "link" && error.kind != "duplicate")
+                .any(|error| settings.fail_on.fails_on(error.kind)),
+        ),
+        (
+            HugotoJsonError::BrokenLinks {
+                links: broken_links,
+                files: files.len(),
+            },
+            broken_links,
+            settings.fail_on.fails_on("link"),
+        ),
+        (
+            HugotoJsonError::Duplicates { pages: duplicates },
+            duplicates,
+            settings.fail_on_duplicates,
+        ),
+    ];
+    let mut result = Ok(());
+    for (failure, _, fails) in failures.into_iter().filter(|(_, count, _)| *count > 0) {
+        if result.is_ok() && fails {
+            result = Err(failure);
+        } else {
+            warn!("{failure}");
+        }
+    }
+    result
+}
+
+/// Serializes each page to `writer` as soon as it has been processed, so the whole index is never held in memory.
+/// Returns a record of each file that failed to process, the files to write alongside the index and the size of the
+/// index in bytes.
+fn stream_page_index<W: Write>(
+    settings: &Settings,
+    stats: &Stats,
+    writer: W,
+) -> Result<(Vec<ErrorRecord>, PageOutputs, u64), HugotoJsonError> {
+    let mut writer = CountingWriter::new(writer);
+    let (errors, outputs) = {
+        let mut index_writer = index_writer::for_settings(&mut writer, settings)?;
+        let streamed = stream_pages(settings, stats, |page_index| {
+            index_writer.write_page(&page_index)
+        })?;
+        index_writer.finalize()?;
+        streamed
+    };
+    Ok((errors, outputs, writer.count))
+}
+
+/// Processes every file in the contents directory, handing each page to `on_page` as soon as it's ready. Returns a
+/// record of each file that failed to process, and the files to write alongside the index, which are left unwritten
+/// so the caller can decide whether to keep the run. Each result is counted in `stats`.
+fn stream_pages<F>(
+    settings: &Settings,
+    stats: &Stats,
+    mut on_page: F,
+) -> Result<(Vec<ErrorRecord>, PageOutputs), HugotoJsonError>
+where
+    F: FnMut(PageIndex) -> Result<(), HugotoJsonError>,
+{
+    let mut errors = Vec::new();
+    // The taxonomy index, feed, sitemap and link graph are built as the pages go by, as they aren't kept
+    let mut outputs = PageOutputs::new(settings);
+
+    Traverser::new(settings.clone()).traverse_streaming(|(file_location, result)| {
+        stats.record(&result);
+        match result {
+            Ok(page_index) => {
+                outputs.add(&page_index);
+                for record in page_index.into_records() {
+                    on_page(record)?;
+                }
+            }
+            Err(OperationResult::Skip(_)) => (), // Skips don't need to be handled
+            Err(ref error) => errors.push(ErrorRecord::new(&file_location, error)),
+        }
+        Ok(())
+    })?;
+
+    errors.extend(outputs.records());
+    Ok((errors, outputs))
+}
+
+/// Passes writes through to another writer, counting the bytes written.
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Warns if an index of `size` bytes is over the settings' size budget, or errors if the budget is enforced.
+fn check_index_size(settings: &Settings, size: u64) -> Result<(), HugotoJsonError> {
+    match settings.max_index_size {
+        Some(budget) if size > budget => {
+            let error = HugotoJsonError::IndexSize { size, budget };
+            if settings.enforce_max_index_size {
+                return Err(error);
+            }
+            warn!("{error}");
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn describe_output(settings: &Settings) -> String {
+    match settings.output {
+        Some(ref path) => compress::compressed_path(path, settings.compress)
+            .to_string_lossy()
+            .into_owned(),
+        None => String::from("stdout"),
+    }
+}
+
+fn validate_written_index(settings: &Settings, index: &str) -> Result<(), HugotoJsonError> {
+    let total = match settings.format.delimiter() {
+        Some(delimiter) => validate::validate_table(index, delimiter)?,
+        None if settings.format == OutputFormat::Stork => validate::validate_stork(index)?,
+        None if settings.format == OutputFormat::GeoJson => validate::validate_geojson(index)?,
+        None if settings.format == OutputFormat::OpenSearchSuggest => {
+            validate::validate_suggestions(index)?
+        }
+        None => {
+            let index = if settings.format == OutputFormat::Ndjson {
+                Cow::Owned(validate::lines_to_array(index))
+            } else {
+                Cow::Borrowed(index)
+            };
+            let total = validate::validate_index(&index)?;
+            validate::validate_against_schema(&index, &schema::index_schema(settings))?;
+            if settings.format == OutputFormat::Algolia {
+                validate::validate_record_sizes(&index, algolia::MAX_RECORD_SIZE)?;
+            }
+            total
+        }
+    };
+    info!("Validated {total} records");
+    Ok(())
+}
+
+/// Serializes pages in the settings' output format.
+pub(crate) fn serialize_pages<'a, I>(
+    settings: &Settings,
+    pages: I,
+) -> Result<String, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let mut index = Vec::new();
+    write_records(
+        pages,
+        &mut *index_writer::for_settings(&mut index, settings)?,
+    )?;
+    Ok(String::from_utf8_lossy(&index).into_owned())
+}
+
+/// Writes the records of the pages to `writer` and finishes it.
+fn write_records<'a, I>(pages: I, writer: &mut dyn IndexWriter) -> Result<(), HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    // Pages are split into a record for each section only as they're written, so they're sorted and related as pages
+    for page in pages {
+        if page.sections.is_empty() {
+            writer.write_page(page)?;
+        } else {
+            for record in page.clone().into_records() {
+                writer.write_page(&record)?;
+            }
+        }
+    }
+    writer.finalize()
+}
+
+/// Writes the index, or its shards if the settings shard it, then each of the other indexes in the settings' `outputs`
+/// from the same pages. Returns their total size in bytes.
+#[allow(clippy::needless_pass_by_value)]
+pub(crate) fn write_every_index<'a, I>(
+    settings: &Settings,
+    pages: I,
+) -> Result<u64, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex> + Clone,
+{
+    let mut size = match settings.shard_size {
+        Some(shard_size) => shard::write_shards(settings, shard_size, pages.clone())?,
+        None => write_index(settings, pages.clone())?,
+    };
+    for output in &settings.outputs {
+        if let OutputKind::Index(format) = output.kind {
+            let output_settings = Settings {
+                output: Some(output.path.clone()),
+                format,
+                outputs: Vec::new(),
+                ..settings.clone()
+            };
+            size += write_index(&output_settings, pages.clone())?;
+        }
+    }
+    Ok(size)
+}
+
+/// Writes the pages, sorted and limited by the settings, to a `SQLite` database or tantivy index at the settings' output
+/// location, validating a database's records afterwards if requested. Returns the size of the database in bytes.
+fn write_database<'a, I>(settings: &Settings, pages: I) -> Result<u64, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let path = settings
+        .output
+        .as_ref()
+        .ok_or_else(index_writer::needs_a_path)?;
+    info!("Writing index to {}", path.display());
+    // A database is written beside the existing one and moved over it once it's complete and checked, while a tantivy
+    // index is a directory of files that's written in place
+    let staged = match settings.format {
+        OutputFormat::Sqlite => Some(AtomicFile::create(path)?),
+        _ => None,
+    };
+    let written = staged.as_ref().map_or(path.as_path(), AtomicFile::path);
+    let mut writer: Box<dyn IndexWriter> = match settings.format {
+        #[cfg(feature = "tantivy")]
+        OutputFormat::Tantivy => Box::new(tantivy_index::TantivyWriter::create(written)?),
+        _ => Box::new(SqliteWriter::create(written, settings)?),
+    };
+    write_records(sort::sort_and_limit(settings, pages), &mut *writer)?;
+    // Closes the database
+    drop(writer);
+    if settings.validate && settings.format == OutputFormat::Sqlite {
+        validate_written_index(settings, &sqlite::records_json(written)?)?;
+    }
+    let size = WalkDir::new(written)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(std::fs::Metadata::is_file)
+        .map(|metadata| metadata.len())
+        .sum();
+    check_index_size(settings, size)?;
+    if let Some(staged) = staged {
+        checksum::commit(settings, staged)?;
+    }
+    Ok(size)
+}
+
+/// Serializes the pages, sorted and limited by the settings, and writes them to the settings' output location,
+/// validating them afterwards if requested. Returns the size of the index in bytes.
+pub(crate) fn write_index<'a, I>(settings: &Settings, pages: I) -> Result<u64, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    if settings.format.is_database() {
+        return write_database(settings, pages);
+    }
+    let index = serialize_pages(settings, sort::sort_and_limit(settings, pages))?;
+    let writing_to = describe_output(settings);
+    info!("Writing index to {writing_to}");
+
+    let size = index.len() as u64;
+    if let Some(ref path) = settings.output {
+        let path = compress::compressed_path(path, settings.compress);
+        let mut encoder = Encoder::new(AtomicFile::create(&path)?, settings.compress);
+        write_page_index(&mut encoder, &index)?;
+        let file = encoder.finish()?;
+        if settings.validate {
+            validate_written_index(
+                settings,
+                &compress::read_to_string(file.path(), settings.compress)?,
+            )?;
+        }
+        // The existing index is only replaced once the new one is complete and checked
+        check_index_size(settings, size)?;
+        checksum::commit(settings, file)?;
+    } else {
+        write_page_index(io::stdout(), &index)?;
+        if settings.validate {
+            validate_written_index(settings, &index)?;
+        }
+        check_index_size(settings, size)?;
+    }
+
+    debug!("Succesfully wrote index to {writing_to}");
+    Ok(size)
 }
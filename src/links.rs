@@ -0,0 +1,459 @@
+use std::collections::HashSet;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::constants;
+use crate::error_report::ErrorRecord;
+use crate::hugo_to_json_error::{ErrorCode, HugotoJsonError};
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+use crate::site_config::SiteConfig;
+use crate::slug::slugify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A link in a document's content, as it was written.
+pub struct Link {
+    /// Where the link goes, e.g. `../other/`, `/post/rust/#install` or `https://example.com`.
+    pub target: String,
+    /// The link's text.
+    pub text: String,
+    /// The 1-based line of the file the link is on.
+    #[serde(default)]
+    pub line: usize,
+    /// Whether the link is a `ref` or `relref` shortcode, whose target is the path of a content file rather than a URL.
+    #[serde(default)]
+    pub reference: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+/// A link from one page to another page of the site or elsewhere.
+pub struct LinkRecord {
+    /// The href of the page the link is on.
+    pub source: String,
+    /// Where the link goes. Links to the site are resolved to hrefs, keeping any query and fragment, and others, along
+    /// with `ref` and `relref` shortcodes, are kept as they were written.
+    pub target: String,
+    /// Whether the link goes to the site rather than elsewhere.
+    pub internal: bool,
+    /// The link's text.
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+/// The links between the pages of a site, and from them to elsewhere, for working out backlinks and finding pages
+/// nothing links to.
+pub struct LinkGraph {
+    site: SiteConfig,
+    links: Vec<LinkRecord>,
+}
+
+impl LinkGraph {
+    /// Creates a graph without any links. Absolute links under the site config's `baseURL` are internal.
+    #[must_use]
+    pub fn new(site: &SiteConfig) -> Self {
+        Self {
+            site: site.clone(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Adds the links of a page. Relative links are resolved against the page's href. `ref` and `relref` shortcodes are
+    /// always internal.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::links::{Link, LinkGraph};
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::site_config::SiteConfig;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+    /// let mut page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+    /// page.links = vec![
+    ///     Link { target: String::from("../go/#install"), text: String::from("Go"), line: 5, reference: false },
+    ///     Link { target: String::from("https://www.rust-lang.org"), text: String::from("Rust"), line: 6, reference: false },
+    /// ];
+    ///
+    /// let mut graph = LinkGraph::new(&SiteConfig::default());
+    /// graph.add(&page);
+    /// assert_eq!(graph.links()[0].target, "/post/go/#install");
+    /// assert!(graph.links()[0].internal);
+    /// assert!(!graph.links()[1].internal);
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    pub fn add(&mut self, page: &PageIndex) {
+        let source = site_href(&self.site, &page.href);
+        for link in &page.links {
+            let (target, internal) = match internal_target(&self.site, source, link) {
+                Some(target) => (target, true),
+                None => (link.target.clone(), false),
+            };
+            self.links.push(LinkRecord {
+                source: page.href.clone(),
+                target,
+                internal,
+                text: link.text.clone(),
+            });
+        }
+    }
+
+    /// Returns the links in the graph, in the order they were added.
+    #[must_use]
+    pub fn links(&self) -> &[LinkRecord] {
+        &self.links
+    }
+
+    /// Writes the graph to `path` as a JSON array of links.
+    ///
+    /// # Errors
+    /// Errors if the graph can't be written.
+    pub fn write(&self, path: &Path) -> Result<(), HugotoJsonError> {
+        create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, &self.links)?;
+        writer.flush()?;
+        debug!("Wrote link graph to {}", path.to_string_lossy());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Checks that the internal links of every page go somewhere: to a page in the index, one of the pages Hugo makes for
+/// taxonomies and their terms, or a file in the site's static directory or content. `ref` and `relref` shortcodes must
+/// name the content file of a page in the index.
+pub struct LinkChecker {
+    site: SiteConfig,
+    content_dirs: Vec<PathBuf>,
+    /// Directories that links can go to files in: the static directory, then the contents directories.
+    file_dirs: Vec<PathBuf>,
+    hrefs: HashSet<String>,
+    paths: HashSet<PathBuf>,
+    /// Each page's path and href, with its links.
+    links: Vec<(String, String, Link)>,
+}
+
+impl LinkChecker {
+    /// Creates a checker without any pages, for the site, static directory and contents directories in the settings.
+    pub fn new(settings: &Settings) -> Self {
+        let content_dirs: Vec<PathBuf> = settings
+            .scan_paths()
+            .into_iter()
+            .map(normalize_path)
+            .collect();
+        let mut file_dirs = vec![normalize_path(&settings.static_dir())];
+        file_dirs.extend(content_dirs.iter().cloned());
+        Self {
+            site: settings.site.clone(),
+            content_dirs,
+            file_dirs,
+            hrefs: HashSet::new(),
+            paths: HashSet::new(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Adds a page, which links can go to, along with its links to check.
+    pub fn add(&mut self, page: &PageIndex) {
+        let hrefs = page.aliases.iter().chain(Some(&page.href));
+        for href in hrefs {
+            self.hrefs
+                .insert(href_key(site_href(&self.site, href)).to_owned());
+        }
+        let taxonomies = [
+            (constants::TAGS, &page.tags),
+            (constants::CATEGORIES, &page.categories),
+            (constants::SERIES, &page.series),
+        ];
+        let custom = page
+            .taxonomies
+            .iter()
+            .map(|(taxonomy, terms)| (taxonomy.as_str(), terms));
+        for (taxonomy, terms) in taxonomies.iter().copied().chain(custom) {
+            if !terms.is_empty() {
+                self.hrefs.insert(format!("/{taxonomy}"));
+            }
+            for term in terms {
+                self.hrefs
+                    .insert(format!("/{}/{}", taxonomy, slugify(term, self.site.slugs)));
+            }
+        }
+        self.paths.insert(normalize_path(Path::new(&page.path)));
+        for link in &page.links {
+            self.links
+                .push((page.path.clone(), page.href.clone(), link.clone()));
+        }
+    }
+
+    /// Checks the links of every page added, returning a record of each broken link in the order the pages were added.
+    /// Each is logged as it's found.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::links::{Link, LinkChecker};
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::settings::Settings;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+    /// let mut page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+    /// page.path = location.absolute_path.clone();
+    /// page.links = vec![
+    ///     Link { target: String::from("#install"), text: String::from("Installing"), line: 5, reference: false },
+    ///     Link { target: String::from("../go/"), text: String::from("Go"), line: 6, reference: false },
+    ///     Link { target: String::from("rust.md"), text: String::new(), line: 7, reference: true },
+    /// ];
+    ///
+    /// let mut checker = LinkChecker::new(&Settings::default());
+    /// checker.add(&page);
+    /// let broken = checker.check();
+    /// assert_eq!(broken.len(), 1);
+    /// assert_eq!(broken[0].line, Some(6));
+    /// assert_eq!(broken[0].message, "Link to `../go/` doesn't go to a page or file");
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn check(&self) -> Vec<ErrorRecord> {
+        let mut broken = Vec::new();
+        for (path, href, link) in &self.links {
+            let found = if link.reference {
+                self.is_page_file(path, &link.target)
+            } else {
+                match internal_target(&self.site, site_href(&self.site, href), link) {
+                    Some(target) => self.is_page_or_file(&target),
+                    None => true, // Links elsewhere aren't checked
+                }
+            };
+            if !found {
+                let kind = if link.reference { "Ref" } else { "Link" };
+                let record = ErrorRecord {
+                    path: path.clone(),
+                    kind: "link",
+                    code: ErrorCode::BrokenLink.as_str(),
+                    message: format!("{} to `{}` doesn't go to a page or file", kind, link.target),
+                    line: Some(link.line),
+                    column: None,
+                    key: None,
+                    snippet: None,
+                };
+                warn!(file = path.as_str(), line = link.line, kind = record.kind, code = record.code; "{}", record.message);
+                broken.push(record);
+            }
+        }
+        broken
+    }
+
+    /// Returns true if a site relative href is a page, or a file in the static or a contents directory.
+    fn is_page_or_file(&self, href: &str) -> bool {
+        let href = href.split(&['?', '#'][..]).next().unwrap_or_default();
+        self.hrefs.contains(href_key(href))
+            || self
+                .file_dirs
+                .iter()
+                .any(|directory| directory.join(href.trim_start_matches('/')).exists())
+    }
+
+    /// Returns true if the target of a `ref` shortcode on the page at `path` is the content file of a page. Like Hugo,
+    /// targets are looked for relative to the page and then to the top of the contents directories, with or without
+    /// their `.md` extension, and as the `index.md` or `_index.md` of a bundle.
+    fn is_page_file(&self, path: &str, target: &str) -> bool {
+        let target = target.split('#').next().unwrap_or_default();
+        if target.is_empty() {
+            return true;
+        }
+        let page_directory = Path::new(path)
+            .parent()
+            .filter(|_| !target.starts_with('/'));
+        page_directory
+            .into_iter()
+            .chain(self.content_dirs.iter().map(PathBuf::as_path))
+            .map(|directory| normalize_path(&directory.join(target.trim_start_matches('/'))))
+            .any(|file| {
+                let mut markdown = file.clone().into_os_string();
+                markdown.push(".md");
+                [
+                    PathBuf::from(markdown),
+                    file.join("index.md"),
+                    file.join("_index.md"),
+                    file,
+                ]
+                .iter()
+                .any(|candidate| self.paths.contains(candidate))
+            })
+    }
+}
+
+/// Resolves a link on the page at the site relative href `source` to an href, if it goes to the site. The targets of
+/// `ref` and `relref` shortcodes are kept as they were written.
+fn internal_target(site: &SiteConfig, source: &str, link: &Link) -> Option<String> {
+    let target = link.target.as_str();
+    if link.reference {
+        return Some(target.to_owned());
+    }
+    if target.starts_with("//") {
+        return None;
+    }
+    if has_scheme(target) {
+        let base_url = site.base_url.as_deref()?.trim_end_matches('/');
+        let rest = target.strip_prefix(base_url)?;
+        return match rest.chars().next() {
+            None => Some(String::from(constants::FORWARD_SLASH)),
+            Some('/') => Some(resolve(source, rest)),
+            Some('?' | '#') => Some([constants::FORWARD_SLASH, rest].concat()),
+            Some(_) => None,
+        };
+    }
+    Some(resolve(source, target))
+}
+
+/// Removes the site's base URL from an href made absolute with it.
+fn site_href<'a>(site: &SiteConfig, href: &'a str) -> &'a str {
+    site.base_url
+        .as_deref()
+        .and_then(|base_url| href.strip_prefix(base_url.trim_end_matches('/')))
+        .filter(|rest| rest.starts_with('/'))
+        .unwrap_or(href)
+}
+
+/// Hrefs are compared without a trailing slash, as Hugo serves `/post/rust` and `/post/rust/` as the same page.
+fn href_key(href: &str) -> &str {
+    href.trim_end_matches('/')
+}
+
+/// Removes the `.` and `..` components of a path, without looking at the file system.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Returns true if `target` starts with a URL scheme, such as `https:` or `mailto:`.
+fn has_scheme(target: &str) -> bool {
+    target
+        .find(':')
+        .map(|colon| &target[..colon])
+        .filter(|scheme| !scheme.is_empty())
+        .is_some_and(|scheme| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        })
+}
+
+/// Resolves `target` against the href `source`, like a browser would, removing `.` and `..` segments.
+fn resolve(source: &str, target: &str) -> String {
+    let split = target.find(&['?', '#'][..]).unwrap_or(target.len());
+    let (path, rest) = target.split_at(split);
+    if path.is_empty() {
+        return [source, rest].concat();
+    }
+
+    let mut segments: Vec<&str> = if path.starts_with('/') {
+        Vec::new()
+    } else {
+        // Everything after the source's last slash is replaced
+        let directory = source.rfind('/').map_or("", |slash| &source[..slash]);
+        directory.split('/').filter(|s| !s.is_empty()).collect()
+    };
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut href = String::from(constants::FORWARD_SLASH);
+    href.push_str(&segments.join("/"));
+    let is_directory = matches!(path.rsplit('/').next(), Some("" | "." | ".."));
+    if is_directory && !segments.is_empty() {
+        href.push('/');
+    }
+    href.push_str(rest);
+    href
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_are_resolved_against_the_page() {
+        assert_eq!(resolve("/post/rust/", "../go/"), "/post/go/");
+        assert_eq!(
+            resolve("/post/rust/", "images/a.png"),
+            "/post/rust/images/a.png"
+        );
+        assert_eq!(resolve("/post/rust/", "./#install"), "/post/rust/#install");
+        assert_eq!(resolve("/post/rust/", "#install"), "/post/rust/#install");
+        assert_eq!(resolve("/post/rust/", "/about?ref=a"), "/about?ref=a");
+        assert_eq!(resolve("/post/rust/", "../../.."), "/");
+    }
+
+    #[test]
+    fn refs_name_the_content_files_of_pages() {
+        let settings = Settings {
+            scan_path: PathBuf::from("/blog/content"),
+            ..Settings::default()
+        };
+        let mut checker = LinkChecker::new(&settings);
+        checker.paths = [
+            "/blog/content/post/rust.md",
+            "/blog/content/post/go/index.md",
+            "/blog/content/docs/_index.md",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let page = "/blog/content/post/rust.md";
+        assert!(checker.is_page_file(page, "go#install"));
+        assert!(checker.is_page_file(page, "rust.md"));
+        assert!(checker.is_page_file(page, "post/go/index.md"));
+        assert!(checker.is_page_file(page, "/docs"));
+        assert!(checker.is_page_file(page, "../docs/_index.md"));
+        assert!(!checker.is_page_file(page, "/rust.md"));
+        assert!(!checker.is_page_file(page, "missing"));
+    }
+
+    #[test]
+    fn only_links_to_the_site_are_internal() {
+        let site = SiteConfig {
+            base_url: Some(String::from("https://example.com/")),
+            ..SiteConfig::default()
+        };
+        let internal = |target: &str| {
+            let link = Link {
+                target: target.to_owned(),
+                text: String::new(),
+                line: 1,
+                reference: false,
+            };
+            internal_target(&site, "/post/rust/", &link)
+        };
+        assert_eq!(
+            internal("https://example.com/post/go/").as_deref(),
+            Some("/post/go/")
+        );
+        assert_eq!(internal("https://example.com").as_deref(), Some("/"));
+        assert_eq!(internal("https://example.com.au/"), None);
+        assert_eq!(internal("https://www.rust-lang.org/"), None);
+        assert_eq!(internal("mailto:me@example.com"), None);
+        assert_eq!(internal("//cdn.example.com/a.js"), None);
+        assert_eq!(internal("../go/").as_deref(), Some("/post/go/"));
+    }
+}
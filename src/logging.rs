@@ -0,0 +1,73 @@
+use env_logger::fmt::Formatter;
+use env_logger::{Env, Target};
+use log::kv::{self, Key, Value, VisitSource};
+use log::{LevelFilter, Record};
+use serde_json::{Map, Value as JsonValue};
+
+use std::io::{self, Write};
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::settings::{LogFormat, Settings};
+
+/// The environment variable that sets which messages are logged, like `RUST_LOG`. Defaults to `info`.
+pub const LOG_ENV: &str = "HUGO_TO_JSON_LOG";
+
+/// Starts logging to stderr in the settings' log format, so logs never mix with an index written to stdout. Only errors
+/// are logged if the settings are quiet. Only the first call has any effect.
+pub fn init(settings: &Settings) {
+    let mut builder = if settings.quiet {
+        let mut builder = env_logger::Builder::new();
+        builder.filter_level(LevelFilter::Error);
+        builder
+    } else {
+        env_logger::Builder::from_env(Env::new().filter_or(LOG_ENV, "info"))
+    };
+    builder.target(Target::Stderr);
+    if settings.log_format == LogFormat::Json {
+        builder.format(write_json);
+    }
+    builder.try_init().ok();
+}
+
+/// Logs the error the command line tool failed with, along with its code.
+pub fn log_failure(error: &HugotoJsonError) {
+    error!(code = error.code().as_str(); "{error}");
+}
+
+/// Writes a record as a JSON object on a line of its own, with its level, target, message and timestamp, and a field
+/// for each of its key-values, such as the `file` that failed and the `kind` of failure.
+fn write_json(formatter: &mut Formatter, record: &Record) -> io::Result<()> {
+    let mut fields = Map::new();
+    fields.insert(String::from("level"), record.level().to_string().into());
+    fields.insert(String::from("target"), record.target().into());
+    fields.insert(String::from("message"), record.args().to_string().into());
+    fields.insert(
+        String::from("timestamp"),
+        formatter.timestamp().to_string().into(),
+    );
+    record
+        .key_values()
+        .visit(&mut JsonFields(&mut fields))
+        .map_err(|error| io::Error::other(error.to_string()))?;
+    writeln!(formatter, "{}", JsonValue::Object(fields))
+}
+
+/// Collects key-values into the fields of a JSON object. Numbers and booleans are kept as they are, everything else is
+/// written as a string.
+struct JsonFields<'a>(&'a mut Map<String, JsonValue>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(number) = value.to_u64() {
+            number.into()
+        } else if let Some(number) = value.to_i64() {
+            number.into()
+        } else if let Some(boolean) = value.to_bool() {
+            boolean.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+//! Builds a prebuilt [Lunr](https://lunrjs.com/) index, so a site can load it with `lunr.Index.load` rather than
+//! indexing every document in the browser. The index matches what Lunr 2's builder produces for the same documents, with
+//! `title`, `tags` and `content` fields and each page's `href` as its ref.
+
+mod stemmer;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::Serialize;
+
+use crate::constants;
+use crate::page_index::PageIndex;
+
+pub use self::stemmer::stem;
+
+/// The version of Lunr the index is built for.
+pub const LUNR_VERSION: &str = "2.3.9";
+
+/// The BM25 term frequency saturation used by Lunr's builder.
+const K1: f64 = 1.2;
+/// The BM25 field length normalisation used by Lunr's builder.
+const B: f64 = 0.75;
+
+/// The words removed by Lunr's English stop word filter.
+const STOP_WORDS: [&str; 119] = [
+    "a", "able", "about", "across", "after", "all", "almost", "also", "am", "among", "an", "and",
+    "any", "are", "as", "at", "be", "because", "been", "but", "by", "can", "cannot", "could",
+    "dear", "did", "do", "does", "either", "else", "ever", "every", "for", "from", "get", "got",
+    "had", "has", "have", "he", "her", "hers", "him", "his", "how", "however", "i", "if", "in",
+    "into", "is", "it", "its", "just", "least", "let", "like", "likely", "may", "me", "might",
+    "most", "must", "my", "neither", "no", "nor", "not", "of", "off", "often", "on", "only", "or",
+    "other", "our", "own", "rather", "said", "say", "says", "she", "should", "since", "so", "some",
+    "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "tis", "to",
+    "too", "twas", "us", "wants", "was", "we", "were", "what", "when", "where", "which", "while",
+    "who", "whom", "why", "will", "with", "would", "yet", "you", "your",
+];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+/// How much a match in each field counts towards a document's score.
+pub struct Boosts {
+    /// The boost for matches in the title. Defaults to 10.
+    pub title: f64,
+    /// The boost for matches in the tags. Defaults to 5.
+    pub tags: f64,
+    /// The boost for matches in the content. Defaults to 1.
+    pub content: f64,
+}
+
+impl Default for Boosts {
+    fn default() -> Self {
+        Self {
+            title: 10.0,
+            tags: 5.0,
+            content: 1.0,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+/// A Lunr index together with the documents it was built from, which are needed to display search results.
+pub struct LunrBundle<'a> {
+    /// The serialized index, ready for `lunr.Index.load`.
+    pub index: LunrIndex,
+    /// The indexed pages, in the order they were added.
+    pub documents: Vec<&'a PageIndex>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+/// A serialized Lunr index.
+pub struct LunrIndex {
+    version: &'static str,
+    fields: Vec<&'static str>,
+    field_vectors: Vec<(String, FieldVector)>,
+    inverted_index: Vec<(String, Posting)>,
+    pipeline: Vec<&'static str>,
+}
+
+/// The scores of each term in a field of a document, by term index.
+#[derive(Debug)]
+struct FieldVector(Vec<(usize, f64)>);
+
+impl Serialize for FieldVector {
+    // Lunr stores vectors as a flat array of alternating term indexes and scores
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut sequence = serializer.serialize_seq(Some(self.0.len() * 2))?;
+        for (index, score) in &self.0 {
+            sequence.serialize_element(index)?;
+            sequence.serialize_element(score)?;
+        }
+        sequence.end()
+    }
+}
+
+/// The documents each term appears in, by field.
+#[derive(Debug)]
+struct Posting {
+    index: usize,
+    fields: Vec<(&'static str, BTreeSet<String>)>,
+}
+
+impl Serialize for Posting {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len() + 1))?;
+        map.serialize_entry("_index", &self.index)?;
+        for (field, documents) in &self.fields {
+            map.serialize_entry(field, &Documents(documents))?;
+        }
+        map.end()
+    }
+}
+
+/// The documents a term appears in for a field, serialized as Lunr's map of document references to metadata.
+struct Documents<'a>(&'a BTreeSet<String>);
+
+impl Serialize for Documents<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|href| (href, Metadata {})))
+    }
+}
+
+/// The metadata Lunr records for a term in a document, which is empty as no metadata is whitelisted.
+#[derive(Serialize, Debug)]
+struct Metadata {}
+
+/// Builds a Lunr index of `pages` and bundles it with them.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::lunr::{bundle, Boosts};
+/// use hugo_to_json::page_index::PageIndex;
+/// use std::path::Path;
+/// # use hugo_to_json::operation_result::OperationResult;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let page = PageIndex::new(Some("Learning Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::from("Borrowing"), &location, None)?;
+///
+/// let bundle = bundle(vec![&page], &Boosts::default());
+/// let serialized = serde_json::to_value(&bundle).unwrap();
+/// assert_eq!(serialized["index"]["fields"], serde_json::json!(["title", "tags", "content"]));
+/// assert_eq!(serialized["documents"][0]["title"], "Learning Rust");
+/// # Ok::<(), OperationResult>(())
+/// ```
+pub fn bundle<'a, I>(pages: I, boosts: &Boosts) -> LunrBundle<'a>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let documents: Vec<&PageIndex> = pages.into_iter().collect();
+    LunrBundle {
+        index: build_index(&documents, boosts),
+        documents,
+    }
+}
+
+/// Builds a Lunr index of `pages`, scoring terms with BM25 as Lunr's builder does.
+#[allow(clippy::cast_precision_loss)]
+#[must_use]
+pub fn build_index(pages: &[&PageIndex], boosts: &Boosts) -> LunrIndex {
+    let fields = [
+        (constants::TITLE, boosts.title),
+        (constants::TAGS, boosts.tags),
+        (constants::CONTENT, boosts.content),
+    ];
+
+    let mut postings: HashMap<String, Posting> = HashMap::new();
+    let mut field_term_frequencies = Vec::new();
+    let mut field_lengths = [0_usize; 3];
+
+    for page in pages {
+        let field_tokens = [
+            tokenize(&page.title),
+            page.tags.iter().map(|tag| tag.to_lowercase()).collect(),
+            tokenize(&page.content),
+        ];
+
+        for (field_position, tokens) in field_tokens.iter().enumerate() {
+            let terms = run_pipeline(tokens);
+            field_lengths[field_position] += terms.len();
+
+            let mut term_frequencies: BTreeMap<String, usize> = BTreeMap::new();
+            for term in terms {
+                let next_index = postings.len();
+                postings
+                    .entry(term.clone())
+                    .or_insert_with(|| Posting {
+                        index: next_index,
+                        fields: fields
+                            .iter()
+                            .map(|&(field, _)| (field, BTreeSet::new()))
+                            .collect(),
+                    })
+                    .fields[field_position]
+                    .1
+                    .insert(page.href.clone());
+                *term_frequencies.entry(term).or_insert(0) += 1;
+            }
+            field_term_frequencies.push((field_position, page, term_frequencies));
+        }
+    }
+
+    let document_count = pages.len() as f64;
+    let average_field_lengths: Vec<f64> = field_lengths
+        .iter()
+        .map(|&length| length as f64 / document_count)
+        .collect();
+
+    let field_vectors = field_term_frequencies
+        .into_iter()
+        .map(|(field_position, page, term_frequencies)| {
+            let (field, boost) = fields[field_position];
+            let field_length = term_frequencies.values().sum::<usize>() as f64;
+            let mut scores: Vec<(usize, f64)> = term_frequencies
+                .iter()
+                .map(|(term, &frequency)| {
+                    let posting = &postings[term];
+                    let frequency = frequency as f64;
+                    let score = idf(posting, document_count) * (K1 + 1.0) * frequency
+                        / (K1
+                            * (1.0 - B
+                                + B * (field_length / average_field_lengths[field_position]))
+                            + frequency)
+                        * boost;
+                    (posting.index, (score * 1000.0).round() / 1000.0)
+                })
+                .collect();
+            scores.sort_by_key(|&(index, _)| index);
+            (format!("{}/{}", field, page.href), FieldVector(scores))
+        })
+        .collect();
+
+    let mut inverted_index: Vec<(String, Posting)> = postings.into_iter().collect();
+    inverted_index.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    LunrIndex {
+        version: LUNR_VERSION,
+        fields: fields.iter().map(|&(field, _)| field).collect(),
+        field_vectors,
+        inverted_index,
+        // Queries are only stemmed, as the stop word filter and trimmer are only run when indexing
+        pipeline: vec!["stemmer"],
+    }
+}
+
+/// How informative a term is, from the number of documents it appears in across all fields.
+#[allow(clippy::cast_precision_loss)]
+fn idf(posting: &Posting, document_count: f64) -> f64 {
+    let documents_with_term = posting
+        .fields
+        .iter()
+        .map(|(_, documents)| documents.len())
+        .sum::<usize>() as f64;
+    let x = (document_count - documents_with_term + 0.5) / (documents_with_term + 0.5);
+    (1.0 + x.abs()).ln()
+}
+
+/// Splits text into lowercase tokens on whitespace and hyphens, like `lunr.tokenizer`.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|token| !token.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Runs Lunr's default indexing pipeline over tokens: the trimmer, the stop word filter then the stemmer.
+fn run_pipeline(tokens: &[String]) -> Vec<String> {
+    trim_and_filter(tokens).map(stem).collect()
+}
+
+/// Runs the first steps of Lunr's indexing pipeline over tokens: the trimmer then the stop word filter.
+pub(crate) fn trim_and_filter(tokens: &[String]) -> impl Iterator<Item = &str> {
+    tokens
+        .iter()
+        .map(|token| token.trim_matches(|c: char| !(c.is_ascii_alphanumeric() || c == '_')))
+        .filter(|token| !token.is_empty() && !STOP_WORDS.contains(token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    #[test]
+    fn pipeline_trims_filters_and_stems() {
+        let tokens = tokenize("The quick-fox's (jumping) over, a lazy DOG!");
+        assert_eq!(
+            run_pipeline(&tokens),
+            vec!["quick", "fox'", "jump", "over", "lazi", "dog"]
+        );
+    }
+
+    #[test]
+    fn builds_an_index_lunr_can_load() {
+        let rust = TestPage::new("rust")
+            .title("Learning Rust")
+            .tags(&["Rust"])
+            .content("Rust has ownership")
+            .build();
+        let go = TestPage::new("go")
+            .title("Learning Go")
+            .tags(&["Go"])
+            .content("Go has goroutines")
+            .build();
+        let index = serde_json::to_value(build_index(&[&rust, &go], &Boosts::default())).unwrap();
+
+        assert_eq!(index["version"], LUNR_VERSION);
+        assert_eq!(index["pipeline"], serde_json::json!(["stemmer"]));
+        let terms: Vec<&str> = index["invertedIndex"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry[0].as_str().unwrap())
+            .collect();
+        assert_eq!(terms, vec!["go", "goroutin", "learn", "ownership", "rust"]);
+        assert_eq!(
+            index["invertedIndex"][4][1]["title"]["/post/rust/"],
+            serde_json::json!({})
+        );
+
+        let vectors = index["fieldVectors"].as_array().unwrap();
+        assert_eq!(vectors.len(), 6);
+        assert_eq!(vectors[0][0], "title//post/rust/");
+        assert_eq!(vectors[1][0], "tags//post/rust/");
+    }
+
+    #[test]
+    fn boosts_scale_scores() {
+        let rust = TestPage::new("rust").title("Rust").content("Rust").build();
+        let go = TestPage::new("go").title("Go").content("Go").build();
+        let index = serde_json::to_value(build_index(&[&rust, &go], &Boosts::default())).unwrap();
+
+        let title_score = index["fieldVectors"][0][1][1].as_f64().unwrap();
+        let content_score = index["fieldVectors"][2][1][1].as_f64().unwrap();
+        assert!((title_score - content_score * 10.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,204 @@
+//! A port of the Porter stemmer used by [Lunr](https://lunrjs.com/), so terms in a prebuilt index are stemmed the same
+//! way Lunr stems search queries.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+const C: &str = "[^aeiou][^aeiouy]*";
+const V: &str = "[aeiouy][aeiou]*";
+
+static MGR0: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!("^({C})?{V}{C}")).unwrap());
+static MEQ1: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!("^({C})?{V}{C}({V})?$")).unwrap());
+static MGR1: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!("^({C})?{V}{C}{V}{C}")).unwrap());
+static S_V: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!("^({C})?[aeiouy]")).unwrap());
+static RE_1A: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?)(ss|i)es$").unwrap());
+static RE2_1A: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?)([^s])s$").unwrap());
+static RE_1B: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?)eed$").unwrap());
+static RE2_1B: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?)(ed|ing)$").unwrap());
+static RE2_1B_2: LazyLock<Regex> = LazyLock::new(|| Regex::new("(at|bl|iz)$").unwrap());
+static RE4_1B_2: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!("^{C}[aeiouy][^aeiouwxy]$")).unwrap());
+static RE_1C: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?[^aeiou])y$").unwrap());
+static RE_2: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        "^(.+?)(ational|tional|enci|anci|izer|bli|alli|entli|eli|ousli|ization|ation|ator|alism|iveness|fulness|ousness|aliti|iviti|biliti|logi)$",
+    )
+    .unwrap()
+});
+static RE_3: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("^(.+?)(icate|ative|alize|iciti|ical|ful|ness)$").unwrap());
+static RE_4: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        "^(.+?)(al|ance|ence|er|ic|able|ible|ant|ement|ment|ent|ou|ism|ate|iti|ous|ive|ize)$",
+    )
+    .unwrap()
+});
+static RE2_4: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?)(s|t)(ion)$").unwrap());
+static RE_5: LazyLock<Regex> = LazyLock::new(|| Regex::new("^(.+?)e$").unwrap());
+
+fn step2_suffix(suffix: &str) -> &'static str {
+    match suffix {
+        "ational" | "ation" | "ator" => "ate",
+        "tional" => "tion",
+        "enci" => "ence",
+        "anci" => "ance",
+        "izer" | "ization" => "ize",
+        "bli" | "biliti" => "ble",
+        "alli" | "alism" | "aliti" => "al",
+        "entli" => "ent",
+        "eli" => "e",
+        "ousli" | "ousness" => "ous",
+        "iveness" | "iviti" => "ive",
+        "fulness" => "ful",
+        "logi" => "log",
+        _ => "",
+    }
+}
+
+fn step3_suffix(suffix: &str) -> &'static str {
+    match suffix {
+        "icate" | "iciti" | "ical" => "ic",
+        "alize" => "al",
+        _ => "",
+    }
+}
+
+/// Removes the last character of `word`.
+fn without_last(word: &str) -> String {
+    let mut word = word.to_owned();
+    word.pop();
+    word
+}
+
+/// Returns true if `word` ends in a double consonant other than l, s or z.
+fn ends_with_double_consonant(word: &str) -> bool {
+    let mut chars = word.chars().rev();
+    match (chars.next(), chars.next()) {
+        (Some(last), Some(previous)) => last == previous && !"aeiouylsz".contains(last),
+        _ => false,
+    }
+}
+
+/// Stems `word` exactly as Lunr's `lunr.stemmer` does.
+#[must_use]
+pub fn stem(word: &str) -> String {
+    if word.chars().count() < 3 {
+        return word.to_owned();
+    }
+
+    // An initial y is treated as a consonant
+    let starts_with_y = word.starts_with('y');
+    let mut w = if starts_with_y {
+        ["Y", &word[1..]].concat()
+    } else {
+        word.to_owned()
+    };
+
+    // Step 1a
+    if RE_1A.is_match(&w) {
+        w = RE_1A.replace(&w, "$1$2").into_owned();
+    } else if RE2_1A.is_match(&w) {
+        w = RE2_1A.replace(&w, "$1$2").into_owned();
+    }
+
+    // Step 1b
+    if let Some(captures) = RE_1B.captures(&w) {
+        if MGR0.is_match(&captures[1]) {
+            w = without_last(&w);
+        }
+    } else if let Some(captures) = RE2_1B.captures(&w) {
+        let stem = captures[1].to_owned();
+        if S_V.is_match(&stem) {
+            w = stem;
+            if RE2_1B_2.is_match(&w) || (!ends_with_double_consonant(&w) && RE4_1B_2.is_match(&w)) {
+                w.push('e');
+            } else if ends_with_double_consonant(&w) {
+                w = without_last(&w);
+            }
+        }
+    }
+
+    // Step 1c
+    if let Some(captures) = RE_1C.captures(&w) {
+        w = [&captures[1], "i"].concat();
+    }
+
+    // Step 2
+    if let Some(captures) = RE_2.captures(&w) {
+        if MGR0.is_match(&captures[1]) {
+            w = [&captures[1], step2_suffix(&captures[2])].concat();
+        }
+    }
+
+    // Step 3
+    if let Some(captures) = RE_3.captures(&w) {
+        if MGR0.is_match(&captures[1]) {
+            w = [&captures[1], step3_suffix(&captures[2])].concat();
+        }
+    }
+
+    // Step 4
+    if let Some(captures) = RE_4.captures(&w) {
+        if MGR1.is_match(&captures[1]) {
+            w = captures[1].to_owned();
+        }
+    } else if let Some(captures) = RE2_4.captures(&w) {
+        let stem = [&captures[1], &captures[2]].concat();
+        if MGR1.is_match(&stem) {
+            w = stem;
+        }
+    }
+
+    // Step 5
+    if let Some(captures) = RE_5.captures(&w) {
+        let stem = &captures[1];
+        if MGR1.is_match(stem) || (MEQ1.is_match(stem) && !(RE4_1B_2.is_match(stem))) {
+            w = stem.to_owned();
+        }
+    }
+    if w.ends_with("ll") && MGR1.is_match(&w) {
+        w = without_last(&w);
+    }
+
+    if starts_with_y {
+        w = ["y", &w[1..]].concat();
+    }
+    w
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stems_like_lunr() {
+        let cases = [
+            ("consign", "consign"),
+            ("consigned", "consign"),
+            ("consigning", "consign"),
+            ("consignment", "consign"),
+            ("consolation", "consol"),
+            ("knack", "knack"),
+            ("knightly", "knightli"),
+            ("generalizations", "gener"),
+            ("hopping", "hop"),
+            ("filing", "file"),
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("agreed", "agre"),
+            ("happy", "happi"),
+            ("relational", "relat"),
+            ("electricity", "electr"),
+            ("controll", "control"),
+            ("rolling", "roll"),
+            ("youth", "youth"),
+            ("yelling", "yell"),
+            ("at", "at"),
+        ];
+        for (word, expected) in &cases {
+            assert_eq!(&stem(word), expected, "stemming {word}");
+        }
+    }
+}
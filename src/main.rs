@@ -1,15 +1,17 @@
 #![warn(clippy::all, clippy::pedantic)]
-#![macro_use]
-extern crate env_logger;
 
-use env_logger::Env;
-use hugo_to_json::{
-    convert_to_json_and_write, hugo_to_json_error::HugotoJsonError, settings::Settings,
-};
-use structopt::StructOpt;
+use hugo_to_json::{logging, run, HugotoJsonError, LogFormat, Settings};
+use std::process;
 
 fn main() -> Result<(), HugotoJsonError> {
-    env_logger::Builder::from_env(Env::new().filter_or("HUGO_TO_JSON_LOG", "info")).init();
-    let settings = Settings::from_args();
-    convert_to_json_and_write(settings.scan_path, settings.output)
+    let settings = Settings::from_env_and_args()?;
+    logging::init(&settings);
+    match run(&settings) {
+        // Structured logs are kept parseable by logging the failure rather than printing it
+        Err(error) if settings.log_format == LogFormat::Json => {
+            logging::log_failure(&error);
+            process::exit(1)
+        }
+        result => result,
+    }
 }
@@ -0,0 +1,93 @@
+use serde_json::{Map, Value as JsonValue};
+
+use crate::constants;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A menu a page is in, from the [`menu`](https://gohugo.io/content-management/menus/#define-in-front-matter) of its
+/// front matter, e.g. for building a site's navigation alongside its search.
+pub struct MenuEntry {
+    /// The name of the menu, e.g. `main`.
+    pub menu: String,
+    /// The text of the page's entry in the menu. Like Hugo, it falls back to the page's `linkTitle`, then its title.
+    pub name: String,
+    /// Where the entry comes in the menu, lowest first. Like Hugo, it falls back to the page's weight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i64>,
+    /// The identifier of the entry this one is nested under.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// The identifier of the entry, which other entries name as their `parent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+}
+
+/// Reads the menus a page is in from the `menu`, or `menus`, of its front matter. It can name a menu, e.g. `"main"`,
+/// list several, e.g. `["main", "footer"]`, or be a table of menus to the entries in them, e.g.
+/// `{ main = { weight = 10, parent = "docs" } }`. Entries without a name or weight take `name` and `weight`. Menus are
+/// listed in the order they're written, except those in a table, which are listed in order of name.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::menu::read_menus;
+/// use serde_json::json;
+///
+/// let front_matter = json!({ "menu": { "main": { "weight": 10 }, "footer": { "name": "About us" } } });
+/// let menus = read_menus(front_matter.as_object().unwrap(), "About", Some(2));
+/// assert_eq!(menus[0].menu, "footer");
+/// assert_eq!((menus[0].name.as_str(), menus[0].weight), ("About us", Some(2)));
+/// assert_eq!(menus[1].menu, "main");
+/// assert_eq!((menus[1].name.as_str(), menus[1].weight), ("About", Some(10)));
+///
+/// let front_matter = json!({ "menu": ["main", "footer"] });
+/// assert_eq!(read_menus(front_matter.as_object().unwrap(), "About", None).len(), 2);
+/// ```
+pub fn read_menus(
+    front_matter: &Map<String, JsonValue>,
+    name: &str,
+    weight: Option<i64>,
+) -> Vec<MenuEntry> {
+    let entry = |menu: &str| MenuEntry {
+        menu: menu.to_owned(),
+        name: name.to_owned(),
+        weight,
+        parent: None,
+        identifier: None,
+    };
+    let string = |entry: &Map<String, JsonValue>, key: &str| {
+        entry
+            .get(key)
+            .and_then(JsonValue::as_str)
+            .map(str::to_owned)
+    };
+
+    let menus = front_matter
+        .get(constants::MENU)
+        .or_else(|| front_matter.get(constants::MENUS));
+    match menus {
+        Some(JsonValue::String(menu)) => vec![entry(menu)],
+        Some(JsonValue::Array(menus)) => menus
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .map(entry)
+            .collect(),
+        Some(JsonValue::Object(menus)) => menus
+            .iter()
+            .map(|(menu, fields)| {
+                let mut menu_entry = entry(menu);
+                if let Some(fields) = fields.as_object() {
+                    if let Some(name) = string(fields, constants::MENU_NAME) {
+                        menu_entry.name = name;
+                    }
+                    if let Some(weight) = fields.get(constants::WEIGHT).and_then(JsonValue::as_i64)
+                    {
+                        menu_entry.weight = Some(weight);
+                    }
+                    menu_entry.parent = string(fields, constants::MENU_PARENT);
+                    menu_entry.identifier = string(fields, constants::MENU_IDENTIFIER);
+                }
+                menu_entry
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,94 @@
+use rust_stemmers::{Algorithm, Stemmer};
+
+/// The language content is normalised for when neither the page, the settings nor the site config give one.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The languages there are [NLTK](https://www.nltk.org/) stop word lists for, by ISO 639-1 code.
+const STOP_WORD_LANGUAGES: [&str; 23] = [
+    "ar", "az", "da", "de", "el", "en", "es", "fi", "fr", "hu", "id", "it", "kk", "ne", "nl", "no",
+    "pt", "ro", "ru", "sl", "sv", "tg", "tr",
+];
+
+/// Normalises text for search: lowercases it, splits it into words, removes the stop words of `language` and stems
+/// the rest with the [Snowball](https://snowballstem.org/) stemmer for `language`. The language is an ISO 639-1 code
+/// such as `en` or `fr`, optionally with a region such as `en-gb`. Languages without stop words or a stemmer are only
+/// lowercased and split into words.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::normalize::normalize_text;
+///
+/// assert_eq!(normalize_text("The cats are running!", "en"), "cat run");
+/// assert_eq!(normalize_text("Les chats mangeaient", "fr-FR"), "chat mang");
+/// assert_eq!(normalize_text("Kiitos Paljon", "xx"), "kiitos paljon");
+/// ```
+pub fn normalize_text(text: &str, language: &str) -> String {
+    let code = language_code(language);
+    let stop_words: &[&str] = if STOP_WORD_LANGUAGES.contains(&code.as_str()) {
+        stop_words::get(&code)
+    } else {
+        &[]
+    };
+    let stemmer = algorithm(&code).map(Stemmer::create);
+
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| !word.is_empty() && !stop_words.contains(word))
+        .map(|word| match stemmer {
+            Some(ref stemmer) => stemmer.stem(word).into_owned(),
+            None => word.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the lowercase ISO 639-1 code of a language, without its region, e.g. `pt` for `pt-BR`.
+fn language_code(language: &str) -> String {
+    language
+        .split(&['-', '_'][..])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// Returns the Snowball stemmer for a language, if there is one.
+fn algorithm(code: &str) -> Option<Algorithm> {
+    match code {
+        "ar" => Some(Algorithm::Arabic),
+        "da" => Some(Algorithm::Danish),
+        "nl" => Some(Algorithm::Dutch),
+        "en" => Some(Algorithm::English),
+        "fi" => Some(Algorithm::Finnish),
+        "fr" => Some(Algorithm::French),
+        "de" => Some(Algorithm::German),
+        "el" => Some(Algorithm::Greek),
+        "hu" => Some(Algorithm::Hungarian),
+        "it" => Some(Algorithm::Italian),
+        "no" | "nb" | "nn" => Some(Algorithm::Norwegian),
+        "pt" => Some(Algorithm::Portuguese),
+        "ro" => Some(Algorithm::Romanian),
+        "ru" => Some(Algorithm::Russian),
+        "es" => Some(Algorithm::Spanish),
+        "sv" => Some(Algorithm::Swedish),
+        "ta" => Some(Algorithm::Tamil),
+        "tr" => Some(Algorithm::Turkish),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_are_matched_by_their_code() {
+        assert_eq!(language_code("pt_BR"), "pt");
+        assert_eq!(language_code("EN-gb"), "en");
+        assert_eq!(normalize_text("Os gatos correram", "pt-BR"), "gat corr");
+        // Kazakh has stop words but no stemmer
+        assert_eq!(algorithm("kk"), None);
+        assert_eq!(normalize_text("It's THE end", "kk"), "it's the end");
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::index_writer::IndexWriter;
+use crate::page_index::PageIndex;
+
+/// The most characters of a title that are suggested from, so long titles don't add a key for every character.
+pub const MAX_PREFIX_LENGTH: usize = 20;
+
+/// The most pages suggested for a prefix, the number browsers show.
+pub const MAX_SUGGESTIONS: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+/// The response to a query in the [OpenSearch suggestions](https://github.com/dewitt/opensearch/blob/master/mediawiki/Specifications/OpenSearch/Extensions/Suggestions/1.1/Draft%201.wiki)
+/// format: the query, then the titles of the pages suggested for it, their descriptions and their URLs, in the same
+/// order.
+pub struct Suggestion(
+    pub String,
+    pub Vec<String>,
+    pub Vec<String>,
+    pub Vec<String>,
+);
+
+/// The suggestions for each lowercased prefix of the titles, from their first character up to `MAX_PREFIX_LENGTH`.
+pub type Suggestions = BTreeMap<String, Suggestion>;
+
+/// Adds a page to the suggestions for each prefix of its title, unless they already have `MAX_SUGGESTIONS` pages. Its
+/// description is its `description`, or its summary if it hasn't one.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::opensearch::{suggest, Suggestions};
+/// use hugo_to_json::page_index::PageIndex;
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), Some("About Rust"), Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+///
+/// let mut suggestions = Suggestions::new();
+/// suggest(&mut suggestions, &page);
+/// assert_eq!(suggestions.len(), 4);
+/// assert_eq!(suggestions["ru"].0, "ru");
+/// assert_eq!(suggestions["ru"].1, ["Rust"]);
+/// assert_eq!(suggestions["ru"].2, ["About Rust"]);
+/// assert_eq!(suggestions["ru"].3, ["/post/rust/"]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn suggest(suggestions: &mut Suggestions, page: &PageIndex) {
+    let title = page.title.trim().to_lowercase();
+    let description = if page.description.is_empty() {
+        &page.summary
+    } else {
+        &page.description
+    };
+    let mut prefix = String::new();
+    for character in title.chars().take(MAX_PREFIX_LENGTH) {
+        prefix.push(character);
+        let suggestion = suggestions
+            .entry(prefix.clone())
+            .or_insert_with(|| Suggestion(prefix.clone(), Vec::new(), Vec::new(), Vec::new()));
+        if suggestion.1.len() < MAX_SUGGESTIONS {
+            suggestion.1.push(page.title.clone());
+            suggestion.2.push(description.clone());
+            suggestion.3.push(page.href.clone());
+        }
+    }
+}
+
+/// Writes a JSON object of the suggestions for each prefix of the pages' titles, so a site can serve the response to
+/// each query its `OpenSearch` description sends without a search server. Pages are suggested in the order they're
+/// written, so `--sort` chooses which come first. Every page has to be seen before any prefix is complete, so the
+/// suggestions are kept until `finalize` writes them.
+pub struct SuggestionsWriter<W: Write> {
+    writer: W,
+    pretty: bool,
+    suggestions: Suggestions,
+}
+
+impl<W: Write> SuggestionsWriter<W> {
+    /// Creates a writer of suggestions to `writer`.
+    pub fn new(writer: W, pretty: bool) -> Self {
+        Self {
+            writer,
+            pretty,
+            suggestions: Suggestions::new(),
+        }
+    }
+}
+
+impl<W: Write> IndexWriter for SuggestionsWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        suggest(&mut self.suggestions, page);
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        if self.pretty {
+            serde_json::to_writer_pretty(&mut self.writer, &self.suggestions)?;
+        } else {
+            serde_json::to_writer(&mut self.writer, &self.suggestions)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    #[test]
+    fn prefixes_are_limited_and_shared() {
+        let mut writer = SuggestionsWriter::new(Vec::new(), false);
+        for position in 0..12 {
+            writer
+                .write_page(
+                    &TestPage::new(&position.to_string())
+                        .title("Ünïcode and a very long title")
+                        .build(),
+                )
+                .unwrap();
+        }
+        writer
+            .write_page(&TestPage::new("rust").title("Rust").build())
+            .unwrap();
+        writer.finalize().unwrap();
+
+        let suggestions: Suggestions = serde_json::from_slice(&writer.writer).unwrap();
+        assert_eq!(suggestions["ü"].1.len(), MAX_SUGGESTIONS);
+        assert_eq!(suggestions["ü"].3[1], "/post/1/");
+        assert!(suggestions.contains_key("ünïcode and a very l"));
+        assert!(!suggestions.contains_key("ünïcode and a very lo"));
+        assert_eq!(
+            suggestions["r"],
+            Suggestion(
+                String::from("r"),
+                vec![String::from("Rust")],
+                vec![String::new()],
+                vec![String::from("/post/rust/")]
+            )
+        );
+        assert!(String::from_utf8(writer.writer)
+            .unwrap()
+            .contains(r#""r":["r",["Rust"],[""],["/post/rust/"]]"#));
+    }
+}
@@ -2,102 +2,237 @@ use std::error;
 use std::fmt;
 use std::io;
 
-#[derive(Debug)]
+use thiserror::Error;
+
+use crate::hugo_to_json_error::ErrorCode;
+
+type BoxedError = Box<dyn error::Error + Send + Sync + 'static>;
+
+#[derive(Error, Debug)]
+#[error(
+    "Error at {directory}{}. {info}{}{}. Skipping.{}",
+    display_position(.diagnostics.line, .diagnostics.column),
+    display_key(.diagnostics.key.as_deref()),
+    display_cause(.error.as_ref()),
+    display_snippet(.diagnostics.snippet.as_deref(), .diagnostics.column)
+)]
 /// Represents an error that occurs parsing the frontmatter of a doc
 pub struct ParseError {
     info: String,
     directory: String,
-    error: Option<Box<dyn error::Error + Send + Sync + 'static>>,
+    diagnostics: Box<Diagnostics>,
+    #[source]
+    error: Option<BoxedError>,
 }
 
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Error at {}. {}. Skipping.", self.directory, self.info)
-    }
-}
-
-impl error::Error for ParseError {
-    fn description(&self) -> &str {
-        &self.info
-    }
-
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match &self.error {
-            None => None,
-            Some(v) => v.source(),
-        }
-    }
+#[derive(Debug, Default)]
+/// Where in a document a `ParseError` occurred, if it's known. It's boxed so the results of processing a document stay
+/// small.
+struct Diagnostics {
+    line: Option<usize>,
+    column: Option<usize>,
+    key: Option<String>,
+    snippet: Option<String>,
 }
 
 impl ParseError {
     /// Creates a `ParseError`
+    #[must_use]
     pub fn new(directory: &str, info: &str) -> Self {
         Self {
             directory: directory.to_owned(),
             info: info.to_owned(),
+            diagnostics: Box::default(),
             error: None,
         }
     }
-}
 
-#[derive(Debug, PartialEq)]
-/// Represents a document that was skipped over, typically due to being a draft
-pub struct Skip {
-    reason: String,
-    directory: String,
+    /// Attaches the underlying parser error that caused this error.
+    #[must_use]
+    pub fn with_source<E>(mut self, error: E) -> Self
+    where
+        E: Into<BoxedError>,
+    {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Attaches the 1-based line and column in the document where the error occurred.
+    #[must_use]
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.diagnostics.line = Some(line);
+        self.diagnostics.column = Some(column);
+        self
+    }
+
+    /// Attaches the line of the document where the error occurred, as it's written.
+    #[must_use]
+    pub fn with_snippet(mut self, snippet: &str) -> Self {
+        self.diagnostics.snippet = Some(snippet.trim_end().to_owned());
+        self
+    }
+
+    /// Attaches the front matter key that caused the error.
+    #[must_use]
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.diagnostics.key = Some(key.to_owned());
+        self
+    }
+
+    /// A description of what went wrong.
+    #[must_use]
+    pub fn info(&self) -> &str {
+        &self.info
+    }
+
+    /// The path of the document that failed to parse.
+    #[must_use]
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    /// The 1-based line in the document where the error occurred, if known.
+    #[must_use]
+    pub fn line(&self) -> Option<usize> {
+        self.diagnostics.line
+    }
+
+    /// The 1-based column in the document where the error occurred, if known.
+    #[must_use]
+    pub fn column(&self) -> Option<usize> {
+        self.diagnostics.column
+    }
+
+    /// The front matter key that caused the error, if known.
+    #[must_use]
+    pub fn key(&self) -> Option<&str> {
+        self.diagnostics.key.as_deref()
+    }
+
+    /// The line of the document where the error occurred, if known.
+    #[must_use]
+    pub fn snippet(&self) -> Option<&str> {
+        self.diagnostics.snippet.as_deref()
+    }
 }
 
-impl fmt::Display for Skip {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Skipping {}. {}", self.directory, self.reason)
+fn display_position(line: Option<usize>, column: Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!(":{line}:{column}"),
+        (Some(line), None) => format!(":{line}"),
+        _ => String::new(),
     }
 }
 
-impl error::Error for Skip {
-    fn description(&self) -> &str {
-        &self.reason
+fn display_key(key: Option<&str>) -> String {
+    key.map(|key| format!(" (key `{key}`)")).unwrap_or_default()
+}
+
+fn display_cause(error: Option<&BoxedError>) -> String {
+    error.map(|error| format!(": {error}")).unwrap_or_default()
+}
+
+/// Shows the line where the error occurred below the message, with a caret under the column if it's known.
+fn display_snippet(snippet: Option<&str>, column: Option<usize>) -> String {
+    match (snippet, column) {
+        (Some(snippet), Some(column)) => {
+            let indent: String = snippet
+                .chars()
+                .take(column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            format!("\n    {snippet}\n    {indent}^")
+        }
+        (Some(snippet), None) => format!("\n    {snippet}"),
+        _ => String::new(),
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The reason a document was skipped.
+pub enum SkipReason {
+    /// The document is marked as a draft.
+    Draft,
+    /// The document isn't marked as a draft, and only drafts are being indexed.
+    NotDraft,
+    /// The document's publish date is in the future.
+    FutureDated,
+    /// The document's expiry date has passed.
+    Expired,
+    /// The document belongs to a headless bundle.
+    Headless,
+    /// The document's build options keep it out of lists or from being rendered.
+    Unlisted,
+    /// The document was excluded by a filter.
+    Excluded,
+    /// The file doesn't have an extension that can be processed.
+    UnsupportedExtension,
+    /// The entry is a directory rather than a file.
+    Directory,
+    /// The file is larger than the maximum file size.
+    TooLarge,
+}
 
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        None
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match *self {
+            SkipReason::Draft => "Is draft.",
+            SkipReason::NotDraft => "Is not a draft.",
+            SkipReason::FutureDated => "Publish date is in the future.",
+            SkipReason::Expired => "Has expired.",
+            SkipReason::Headless => "Is a headless bundle.",
+            SkipReason::Unlisted => "Is not listed or rendered.",
+            SkipReason::Excluded => "Excluded by filter.",
+            SkipReason::UnsupportedExtension => "Not a compatible file extension.",
+            SkipReason::Directory => "Not a file.",
+            SkipReason::TooLarge => "Is larger than the maximum file size.",
+        };
+        write!(f, "{reason}")
     }
 }
 
+#[derive(Error, Debug, PartialEq)]
+#[error("Skipping {directory}. {reason}")]
+/// Represents a document that was skipped over, typically due to being a draft
+pub struct Skip {
+    reason: SkipReason,
+    directory: String,
+}
+
 impl Skip {
     /// Creates a `Skip`
-    pub fn new(directory: &str, reason: &str) -> Self {
+    #[must_use]
+    pub fn new(directory: &str, reason: SkipReason) -> Self {
         Self {
             directory: directory.to_owned(),
-            reason: reason.to_owned(),
+            reason,
         }
     }
+
+    /// Why the document was skipped.
+    #[must_use]
+    pub fn reason(&self) -> SkipReason {
+        self.reason
+    }
+
+    /// The path of the document that was skipped.
+    #[must_use]
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Error, Debug, PartialEq)]
+#[error("Skipping {directory}. {reason}")]
 /// Represents an error that occurs constructing a path
 pub struct PathError {
     reason: String,
     directory: String,
 }
 
-impl fmt::Display for PathError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Skipping {}. {}", self.directory, self.reason)
-    }
-}
-
-impl error::Error for PathError {
-    fn description(&self) -> &str {
-        &self.reason
-    }
-
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        None
-    }
-}
-
 impl PathError {
     /// Creates a `PathError`
+    #[must_use]
     pub fn new(directory: &str, reason: &str) -> Self {
         Self {
             directory: directory.to_owned(),
@@ -106,66 +241,112 @@ impl PathError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Error, Debug)]
 /// Represents the possible error results of trying to create a `PageIndex` from a document.
 pub enum OperationResult {
     /// An IO error occurred.
-    Io(io::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
     /// A parse error occurred.
-    Parse(ParseError),
+    #[error("Parse error: {0}")]
+    Parse(#[from] ParseError),
     /// It was skipped, possibly due to being a draft - also possibly because its frontmatter wasn't supported.
-    Skip(Skip),
+    #[error("Skipped: {0}")]
+    Skip(#[from] Skip),
     /// There was an error constructing a path.
-    Path(PathError),
+    #[error("Path manipulation error: {0}")]
+    Path(#[from] PathError),
 }
 
-impl From<io::Error> for OperationResult {
-    fn from(err: io::Error) -> Self {
-        OperationResult::Io(err)
+impl OperationResult {
+    /// Returns the stable `ErrorCode` for this result.
+    #[must_use]
+    pub fn code(&self) -> ErrorCode {
+        match *self {
+            OperationResult::Io(_) => ErrorCode::Io,
+            OperationResult::Parse(_) => ErrorCode::Parse,
+            OperationResult::Skip(_) => ErrorCode::Skip,
+            OperationResult::Path(_) => ErrorCode::Path,
+        }
     }
-}
 
-impl From<Skip> for OperationResult {
-    fn from(err: Skip) -> Self {
-        OperationResult::Skip(err)
+    /// Returns the kind of result as a lowercase name: `io`, `parse`, `skip` or `path`.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            OperationResult::Io(_) => "io",
+            OperationResult::Parse(_) => "parse",
+            OperationResult::Skip(_) => "skip",
+            OperationResult::Path(_) => "path",
+        }
     }
 }
 
-impl From<ParseError> for OperationResult {
-    fn from(err: ParseError) -> Self {
-        OperationResult::Parse(err)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn codes_match_variants() {
+        let parse = OperationResult::from(ParseError::new("/a.md", "Bad"));
+        let skip = OperationResult::from(Skip::new("/a.md", SkipReason::Draft));
+        let path = OperationResult::from(PathError::new("/a.md", "Bad path."));
+        let io = OperationResult::from(io::Error::other("Bad"));
+
+        assert_eq!(parse.code(), ErrorCode::Parse);
+        assert_eq!(skip.code(), ErrorCode::Skip);
+        assert_eq!(path.code(), ErrorCode::Path);
+        assert_eq!(io.code(), ErrorCode::Io);
+        assert_eq!(parse.kind(), "parse");
+        assert_eq!(io.kind(), "io");
     }
-}
 
-impl From<PathError> for OperationResult {
-    fn from(err: PathError) -> Self {
-        OperationResult::Path(err)
+    #[test]
+    fn source_chains_to_the_underlying_error() {
+        let io = OperationResult::from(io::Error::other("Disk gone"));
+        let source = io.source().expect("An IO result has a source");
+        assert_eq!(source.to_string(), "Disk gone");
     }
-}
 
-impl fmt::Display for OperationResult {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            // Both underlying errors already impl `Display`, so we defer to
-            // their implementations.
-            OperationResult::Io(ref err) => write!(f, "IO error: {}", err),
-            OperationResult::Parse(ref err) => write!(f, "Parse error: {}", err),
-            OperationResult::Skip(ref err) => write!(f, "Skipped: {}", err),
-            OperationResult::Path(ref err) => write!(f, "Path manipulaiton error: {}", err),
-        }
+    #[test]
+    fn skip_exposes_reason() {
+        let skip = Skip::new("/a.md", SkipReason::UnsupportedExtension);
+        assert_eq!(skip.reason(), SkipReason::UnsupportedExtension);
+        assert_eq!(
+            skip.to_string(),
+            "Skipping /a.md. Not a compatible file extension."
+        );
     }
-}
 
-impl error::Error for OperationResult {
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match *self {
-            // N.B. Both of these implicitly cast `err` from their concrete
-            // types to a trait object `&Error`. This works because both error
-            // types implement `Error`.
-            OperationResult::Io(ref err) => Some(err),
-            OperationResult::Parse(ref err) => Some(err),
-            OperationResult::Skip(ref err) => Some(err),
-            OperationResult::Path(ref err) => Some(err),
-        }
+    #[test]
+    fn parse_error_preserves_source_position_and_key() {
+        let cause = io::Error::new(io::ErrorKind::InvalidData, "expected `=`");
+        let error = ParseError::new("/a.md", "Could not parse TOML front matter")
+            .with_position(3, 6)
+            .with_key("date")
+            .with_source(cause);
+
+        assert_eq!(error.line(), Some(3));
+        assert_eq!(error.column(), Some(6));
+        assert_eq!(error.key(), Some("date"));
+        assert_eq!(error.source().unwrap().to_string(), "expected `=`");
+        assert_eq!(
+            error.to_string(),
+            "Error at /a.md:3:6. Could not parse TOML front matter (key `date`): expected `=`. Skipping."
+        );
+    }
+
+    #[test]
+    fn parse_error_shows_the_line_it_occurred_on() {
+        let error = ParseError::new("/a.md", "Could not parse TOML front matter")
+            .with_position(3, 6)
+            .with_snippet("date / \"2019-01-25\"\n");
+
+        assert_eq!(error.snippet(), Some("date / \"2019-01-25\""));
+        assert_eq!(
+            error.to_string(),
+            "Error at /a.md:3:6. Could not parse TOML front matter. Skipping.\n    date / \"2019-01-25\"\n         ^"
+        );
     }
 }
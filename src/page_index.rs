@@ -1,8 +1,25 @@
-use crate::constants::*;
+use serde_json::{Map, Value};
+
+use std::collections::BTreeMap;
+use std::mem;
+
+use crate::constants::{
+    AUTHOR, AUTHORS, AUTHOR_NAME, BACK_SLASH, COVER, COVER_IMAGE, DATE, EMPTY_STRING,
+    FEATURED_IMAGE, FORWARD_SLASH, IMAGES, LINK_TITLE, PARAMS, TITLE, WORDS_PER_MINUTE,
+};
 use crate::file_location::FileLocation;
-use crate::operation_result::*;
+use crate::geojson::Location;
+use crate::links::Link;
+use crate::menu::{read_menus, MenuEntry};
+use crate::operation_result::{OperationResult, ParseError};
+use crate::settings::ContentLength;
+use crate::sitemap::SitemapConfig;
+use crate::slug::{slugify, SlugOptions};
+
+/// The number of words in the summary of a document without a `<!--more-->` divider, like Hugo's `summaryLength`.
+pub const DEFAULT_SUMMARY_LENGTH: usize = 70;
 
-#[derive(Serialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 /// Represents a summary of an individual document/post in the contents directory.
 pub struct PageIndex {
     /// Title of the document from frontmatter
@@ -13,21 +30,147 @@ pub struct PageIndex {
     pub date: String,
     /// contents of the document with markdown stripped away
     pub content: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    /// Summary of the document, either the content before `<!--more-->` or its first words
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     /// Description of the document from frontmatter
     pub description: String,
     /// Categories of the document from frontmatter
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub categories: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /// Series of the document from frontmatter
     pub series: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /// Tags of the document from frontmatter
     pub tags: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /// Keywords of the document from frontmatter
     pub keywords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Names of the document's authors from frontmatter
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    /// Terms of the site's custom taxonomies from frontmatter, keyed by taxonomy
+    pub taxonomies: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    /// Date the document was last modified from frontmatter
+    pub lastmod: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Aliases the document redirects from, from frontmatter
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Images of the document from frontmatter and, when requested by the settings, the first image in its content
+    pub images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Weight of the document from frontmatter, which orders it in lists
+    pub weight: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Menus the document is in, from frontmatter, with its name and weight in each
+    pub menus: Vec<MenuEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Where the document is about, from frontmatter
+    pub location: Option<Location>,
+    #[serde(default, rename = "type", skip_serializing_if = "String::is_empty")]
+    /// Content type of the document from frontmatter
+    pub page_type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    /// Layout the document is rendered with, from frontmatter
+    pub layout: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// Files in the document's leaf bundle, relative to the bundle, kept when requested by the settings
+    pub resources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// hrefs of the documents most related to this one by their shared terms, when requested by the settings
+    pub related: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    /// TF-IDF weights of the terms in the document's title, tags and content, when requested by the settings
+    pub weights: BTreeMap<String, f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The document's H1 to H3 headings and their anchors, when requested by the settings
+    pub headings: Vec<Heading>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// The heading of the section of the document this record is for, when the settings index each section on its own
+    pub heading: Option<Heading>,
+    #[serde(default)]
+    /// Whether the document is a section's `_index.md` or a regular page
+    pub kind: PageKind,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    /// Language of the document, for multilingual sites
+    pub lang: String,
+    #[serde(default)]
+    /// Number of words in the content
+    pub word_count: usize,
+    #[serde(default)]
+    /// Minutes it takes to read the content, at Hugo's reading speed of 212 words per minute
+    pub reading_time: usize,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    /// Whether the document is marked as a draft, which is only indexed when the settings ask for drafts
+    pub draft: bool,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    /// Custom frontmatter fields, as Hugo's `.Params` has them, when requested by the settings
+    pub params: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    /// Unrecognised frontmatter fields, kept when requested by the settings
+    pub extra: Map<String, Value>,
+    #[serde(skip)]
+    /// The date the document should be published from, from frontmatter. Not included in the index.
+    pub publish_date: Option<String>,
+    #[serde(skip)]
+    /// The date the document should no longer be published from, from frontmatter. Not included in the index.
+    pub expiry_date: Option<String>,
+    #[serde(skip)]
+    /// The slug of the document, from frontmatter. Not included in the index.
+    pub slug: Option<String>,
+    #[serde(skip)]
+    /// The url of the document, from frontmatter. Not included in the index.
+    pub url: Option<String>,
+    #[serde(skip)]
+    /// How often the document changes and how important it is, from frontmatter, for the sitemap. Not included in the
+    /// index.
+    pub sitemap: SitemapConfig,
+    #[serde(skip)]
+    /// The content of each of the document's sections, when the settings index each section on its own. Not included
+    /// in the index.
+    pub sections: Vec<PageSection>,
+    #[serde(skip)]
+    /// The absolute path of the file the document was read from. Not included in the index.
+    pub path: String,
+    #[serde(skip)]
+    /// The links in the document's content, when the settings ask for the link graph. Not included in the index.
+    pub links: Vec<Link>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+/// The kinds of document in a contents directory.
+pub enum PageKind {
+    /// A section or taxonomy landing page, described by an `_index.md` file.
+    Section,
+    /// A regular page.
+    #[default]
+    Page,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// The content under one of a document's headings, up to the next, or before its first heading.
+pub struct PageSection {
+    /// The section's heading, or `None` for the content before the first heading.
+    pub heading: Option<Heading>,
+    /// The content of the section, with its markdown stripped away.
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A heading in a document's content, which search results can link to.
+pub struct Heading {
+    /// The heading's level, from 1 for an H1.
+    pub level: usize,
+    /// The text of the heading, without its markup.
+    pub text: String,
+    /// The anchor of the heading, which Hugo gives it as its `id`.
+    pub anchor: String,
 }
 
 // TODO: Make this use a builder pattern.
@@ -52,27 +195,39 @@ impl PageIndex {
     ) -> Result<Self, OperationResult> {
         let title = title
             .ok_or_else(|| {
-                OperationResult::Parse(ParseError::new(
-                    &file_location.absolute_path,
-                    "Could not read title from front matter",
-                ))
+                OperationResult::Parse(
+                    ParseError::new(
+                        &file_location.absolute_path,
+                        "Could not read title from front matter",
+                    )
+                    .with_key(TITLE),
+                )
             })?
             .trim()
             .to_owned();
 
         let date = date
             .ok_or_else(|| {
-                OperationResult::Parse(ParseError::new(
-                    &file_location.absolute_path,
-                    "Could not read date from front matter",
-                ))
+                OperationResult::Parse(
+                    ParseError::new(
+                        &file_location.absolute_path,
+                        "Could not read date from front matter",
+                    )
+                    .with_key(DATE),
+                )
             })?
             .trim()
             .to_owned();
 
         let description = description.unwrap_or("").to_owned();
 
-        let href = build_href(slug, url, file_location);
+        let href = build_href(slug, url, file_location, SlugOptions::default());
+        let kind = if file_location.is_section() {
+            PageKind::Section
+        } else {
+            PageKind::Page
+        };
+        let word_count = content.split_whitespace().count();
 
         Ok(Self {
             title,
@@ -82,38 +237,408 @@ impl PageIndex {
             tags,
             series,
             keywords,
+            authors: Vec::new(),
             href,
             content,
+            summary: String::new(),
+            lastmod: String::new(),
+            aliases: Vec::new(),
+            images: Vec::new(),
+            weight: None,
+            menus: Vec::new(),
+            location: None,
+            page_type: String::new(),
+            layout: String::new(),
+            resources: Vec::new(),
+            related: Vec::new(),
+            weights: BTreeMap::new(),
+            headings: Vec::new(),
+            heading: None,
+            kind,
+            lang: file_location.lang.clone(),
+            word_count,
+            reading_time: reading_time(word_count),
+            draft: false,
+            params: Map::new(),
+            extra: Map::new(),
+            publish_date: None,
+            expiry_date: None,
+            slug: None,
+            url: None,
+            sitemap: SitemapConfig::default(),
+            sections: Vec::new(),
+            path: String::new(),
+            links: Vec::new(),
+            taxonomies: BTreeMap::new(),
         })
     }
+
+    /// Copies the extra fields into `params`, like Hugo's `.Params`. The fields of a `params` table are merged into the
+    /// others, taking precedence over fields of the same name, as Hugo does.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use serde_json::json;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/a.md"), Path::new("/blog/content"))?;
+    /// let mut page = PageIndex::new(Some("A"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+    /// page.extra = json!({"author": "Arran", "params": {"cover": {"image": "a.png", "width": 800}}})
+    ///     .as_object().unwrap().clone();
+    /// page.collect_params();
+    /// assert_eq!(page.params["author"], "Arran");
+    /// assert_eq!(page.params["cover"]["width"], 800);
+    /// assert!(!page.params.contains_key("params"));
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    pub fn collect_params(&mut self) {
+        let mut params = self.extra.clone();
+        if let Some(Value::Object(nested)) = params.remove(PARAMS) {
+            params.extend(nested);
+        }
+        self.params = params;
+    }
+
+    /// Moves the terms of the named taxonomies out of the extra fields into `taxonomies`. A taxonomy's terms can be
+    /// written as a list or, for a single term, a string.
+    pub fn collect_taxonomies(&mut self, taxonomies: &[String]) {
+        for taxonomy in taxonomies {
+            let terms: Vec<String> = match self.extra.remove(taxonomy) {
+                Some(Value::Array(terms)) => terms
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect(),
+                Some(Value::String(term)) => vec![term],
+                _ => continue,
+            };
+            if !terms.is_empty() {
+                self.taxonomies.insert(taxonomy.to_owned(), terms);
+            }
+        }
+    }
+
+    /// Reads the names of the document's authors from the `authors` and `author` extra fields. Each can be a name, an
+    /// object with a `name` such as `{ name = "Jane", email = "jane@example.com" }`, or a list of either. The extra
+    /// fields are left as they are, so they can still be kept or collected as a taxonomy.
+    pub fn read_authors(&mut self) {
+        let mut authors = Vec::new();
+        for key in &[AUTHORS, AUTHOR] {
+            if let Some(value) = self.extra.get(*key) {
+                collect_strings(value, AUTHOR_NAME, &mut authors);
+            }
+        }
+        self.authors = authors;
+    }
+
+    /// Reads the document's images from the `images`, `featured_image` and `cover` extra fields. Each can be a path,
+    /// an object with an `image` such as the `cover = { image = "cover.png", alt = "..." }` of some themes, or a list of
+    /// either. The extra fields are left as they are.
+    pub fn read_images(&mut self) {
+        let mut images = Vec::new();
+        for key in &[IMAGES, FEATURED_IMAGE, COVER] {
+            if let Some(value) = self.extra.get(*key) {
+                collect_strings(value, COVER_IMAGE, &mut images);
+            }
+        }
+        self.images = images;
+    }
+
+    /// Reads the menus the document is in from the `menu` or `menus` extra field. Entries are named after the
+    /// document's `linkTitle`, or its title, and weighted by its weight unless they say otherwise. The extra fields are
+    /// left as they are.
+    pub fn read_menus(&mut self) {
+        let link_title = self
+            .extra
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(LINK_TITLE))
+            .and_then(|(_, link_title)| link_title.as_str());
+        let name = link_title.unwrap_or(&self.title);
+        self.menus = read_menus(&self.extra, name, self.weight);
+    }
+
+    /// Reads where the document is about from the `location`, `lat` and `lng` extra fields. The extra fields are left as
+    /// they are.
+    pub fn read_location(&mut self) {
+        self.location = Location::from_front_matter(&self.extra);
+    }
+
+    /// Splits the page into a record for each of its sections, if it has any, or returns it as it is. Each record has the
+    /// page's fields, but the content of its section, an href to its heading's anchor and the heading itself.
+    #[must_use]
+    pub fn into_records(mut self) -> Vec<Self> {
+        if self.sections.is_empty() {
+            return vec![self];
+        }
+        let sections = mem::take(&mut self.sections);
+        // Each record is a single section, so the page's outline isn't repeated in them
+        self.headings.clear();
+        sections
+            .into_iter()
+            .map(|section| {
+                let mut record = self.clone();
+                if let Some(ref heading) = section.heading {
+                    record.href = format!("{}#{}", self.href, heading.anchor);
+                }
+                record.word_count = section.content.split_whitespace().count();
+                record.reading_time = reading_time(record.word_count);
+                record.content = section.content;
+                record.heading = section.heading;
+                record
+            })
+            .collect()
+    }
+
+    /// Reads how often the document changes and how important it is from the extra fields, for the sitemap.
+    pub fn read_sitemap(&mut self) {
+        self.sitemap = SitemapConfig::from_front_matter(&self.extra);
+    }
+
+    /// Keeps only the extra fields named in `fields`, or every extra field if `all` is set.
+    pub fn retain_extra_fields(&mut self, fields: &[String], all: bool) {
+        if !all {
+            self.extra.retain(|key, _| fields.contains(key));
+        }
+    }
+
+    /// Sets the summary to the first `words` words of the content, unless the document has a summary already. Hugo
+    /// summarises with `DEFAULT_SUMMARY_LENGTH` words unless its config says otherwise.
+    pub fn summarize(&mut self, words: usize) {
+        if self.summary.is_empty() {
+            self.summary = self
+                .content
+                .split_whitespace()
+                .take(words)
+                .collect::<Vec<&str>>()
+                .join(" ");
+        }
+    }
+
+    /// Truncates the content to at most `length` characters or words, keeping the whitespace between the words that
+    /// are kept.
+    pub fn truncate_content(&mut self, length: ContentLength) {
+        let end = match length {
+            ContentLength::Chars(chars) => self
+                .content
+                .char_indices()
+                .nth(chars)
+                .map(|(index, _)| index),
+            ContentLength::Words(words) => {
+                // Finds the start of the first word past the limit
+                let mut in_word = false;
+                let mut count = 0;
+                self.content
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        let starts_word = !in_word && !c.is_whitespace();
+                        in_word = !c.is_whitespace();
+                        if starts_word {
+                            count += 1;
+                        }
+                        starts_word && count > words
+                    })
+                    .map(|(index, _)| index)
+            }
+        };
+        if let Some(end) = end {
+            let truncated_length = self.content[..end].trim_end().len();
+            self.content.truncate(truncated_length);
+        }
+    }
+
+    /// Removes the description from the start of the content if the content begins with it, so the intro paragraph isn't stored twice.
+    /// The description only counts when it ends on a word boundary, so "Rust" isn't stripped from "Rustaceans".
+    pub fn dedupe_description(&mut self) {
+        let description = self.description.trim();
+        if description.is_empty() {
+            return;
+        }
+
+        if let Some(rest) = self.content.trim_start().strip_prefix(description) {
+            let at_boundary = rest
+                .chars()
+                .next()
+                .is_none_or(|next| !next.is_alphanumeric());
+            if at_boundary {
+                self.content = rest.trim_start().to_owned();
+            }
+        }
+    }
 }
 
-/// Tries a builds a href to the document using either slug and urls provided by frontmatter or using path info.
-fn build_href(
+/// Adds the strings in `value` to `strings`, skipping any already there. `value` can be a string, an object with a
+/// string under `key`, or a list of either.
+fn collect_strings(value: &Value, key: &str, strings: &mut Vec<String>) {
+    match value {
+        Value::String(string) => {
+            let string = string.trim();
+            if !string.is_empty() && !strings.iter().any(|existing| existing == string) {
+                strings.push(string.to_owned());
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_strings(value, key, strings);
+            }
+        }
+        Value::Object(fields) => {
+            if let Some(string @ Value::String(_)) = fields.get(key) {
+                collect_strings(string, key, strings);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Returns the minutes it takes to read `word_count` words, rounded the same way as Hugo's `.ReadingTime`.
+fn reading_time(word_count: usize) -> usize {
+    (word_count + WORDS_PER_MINUTE) / (WORDS_PER_MINUTE + 1)
+}
+
+/// Tries a builds a href to the document using either slug and urls provided by frontmatter or using path info. The
+/// slug and path are made into slugs the way Hugo does with `options`.
+pub(crate) fn build_href(
     possible_slug: Option<&str>,
     possible_url: Option<&str>,
     file_location: &FileLocation,
+    options: SlugOptions,
 ) -> String {
     if let Some(url) = possible_url {
         return url.to_string();
     }
 
-    let relative_part = match file_location.relative_directory_to_content.as_ref() {
+    // Windows separators are normalised so indexes match regardless of the OS they're generated on
+    let relative_directory = file_location
+        .relative_directory_to_content
+        .replace(BACK_SLASH, FORWARD_SLASH);
+    let relative_directory = slugify(relative_directory.trim_matches('/'), options);
+    let relative_directory = relative_directory.as_str();
+
+    // A leaf bundle's page is named after its directory rather than `index`
+    let (relative_directory, file_stem) = if file_location.is_bundle() {
+        match relative_directory.rfind(FORWARD_SLASH) {
+            Some(position) => (
+                &relative_directory[..position],
+                &relative_directory[position + 1..],
+            ),
+            None => (EMPTY_STRING, relative_directory),
+        }
+    } else {
+        (relative_directory, file_location.file_stem.as_str())
+    };
+    let file_stem = slugify(file_stem, options);
+
+    let mut relative_part = match relative_directory {
         "" => EMPTY_STRING.to_owned(),
-        _ => [FORWARD_SLASH, &file_location.relative_directory_to_content].concat(),
+        relative_directory => [FORWARD_SLASH, relative_directory].concat(),
     };
+    // Pages in languages other than the default are served from beneath the language, e.g. `/fr/post/example/`
+    if !file_location.lang.is_empty() && !file_location.default_lang {
+        relative_part = [FORWARD_SLASH, &file_location.lang, &relative_part].concat();
+    }
+
+    // A section's href is its directory, slugs only apply to regular pages
+    if file_location.is_section() {
+        return [&relative_part, FORWARD_SLASH].concat();
+    }
 
     if let Some(slug) = possible_slug {
-        return [&relative_part, FORWARD_SLASH, slug, FORWARD_SLASH].concat();
+        return [
+            &relative_part,
+            FORWARD_SLASH,
+            &slugify(slug, options),
+            FORWARD_SLASH,
+        ]
+        .concat();
+    }
+
+    [&relative_part, FORWARD_SLASH, &file_stem, FORWARD_SLASH].concat()
+}
+
+#[cfg(test)]
+/// Builds pages for tests. A page is at `/blog/content/post/NAME.md`, titled `NAME` and dated `2019-01-25`, without any
+/// description, taxonomies or content, unless it's built with them.
+pub(crate) struct TestPage {
+    path: String,
+    title: String,
+    date: String,
+    description: Option<String>,
+    categories: Vec<String>,
+    tags: Vec<String>,
+    content: String,
+}
+
+#[cfg(test)]
+impl TestPage {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            path: format!("/blog/content/post/{name}.md"),
+            title: name.to_owned(),
+            date: String::from("2019-01-25"),
+            description: None,
+            categories: Vec::new(),
+            tags: Vec::new(),
+            content: String::new(),
+        }
+    }
+
+    pub(crate) fn title(mut self, title: &str) -> Self {
+        self.title = title.to_owned();
+        self
+    }
+
+    pub(crate) fn date(mut self, date: &str) -> Self {
+        self.date = date.to_owned();
+        self
+    }
+
+    pub(crate) fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    pub(crate) fn categories(mut self, categories: &[&str]) -> Self {
+        self.categories = categories
+            .iter()
+            .map(|&category| category.to_owned())
+            .collect();
+        self
+    }
+
+    pub(crate) fn tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|&tag| tag.to_owned()).collect();
+        self
+    }
+
+    pub(crate) fn content(mut self, content: &str) -> Self {
+        self.content = content.to_owned();
+        self
     }
 
-    [
-        &relative_part,
-        FORWARD_SLASH,
-        &file_location.file_stem.to_lowercase(),
-        FORWARD_SLASH,
-    ]
-    .concat()
+    pub(crate) fn build(self) -> PageIndex {
+        let location = FileLocation::from_path(
+            std::path::Path::new(&self.path),
+            std::path::Path::new("/blog/content"),
+        )
+        .unwrap();
+        PageIndex::new(
+            Some(&self.title),
+            None,
+            Some(&self.date),
+            self.description.as_deref(),
+            self.categories,
+            Vec::new(),
+            self.tags,
+            Vec::new(),
+            self.content,
+            &location,
+            None,
+        )
+        .unwrap()
+    }
 }
 
 #[cfg(test)]
@@ -124,9 +649,13 @@ mod tests {
         FileLocation {
             extension: String::from("md"),
             relative_directory_to_content: String::from("post"),
+            content_dir: String::from("/home/blog/content"),
             absolute_path: String::from("/home/blog/content/post/example.md"),
             file_stem: String::from("example"),
             file_name: String::from("example.md"),
+            lang: String::new(),
+            default_lang: false,
+            cascade: Map::new(),
         }
     }
 
@@ -157,7 +686,7 @@ mod tests {
             url,
         );
         assert!(page_index.is_ok());
-        assert_eq!(page_index.unwrap().href, "/post/my-example-post/")
+        assert_eq!(page_index.unwrap().href, "/post/my-example-post/");
     }
 
     #[test]
@@ -190,7 +719,7 @@ mod tests {
         assert_eq!(
             page_index.unwrap().href,
             "deep/nested/post/my-example-post/"
-        )
+        );
     }
 
     #[test]
@@ -220,7 +749,114 @@ mod tests {
             url,
         );
         assert!(page_index.is_ok());
-        assert_eq!(page_index.unwrap().href, "/post/example/")
+        assert_eq!(page_index.unwrap().href, "/post/example/");
+    }
+
+    #[test]
+    fn href_normalises_windows_separators() {
+        let mut file_location = build_file_location();
+        file_location.relative_directory_to_content = String::from("post\\2021");
+
+        let page_index = PageIndex::new(
+            Some("Title"),
+            None,
+            Some("2018-01-01"),
+            None,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            "A lot of content".to_owned(),
+            &file_location,
+            None,
+        );
+        assert_eq!(page_index.unwrap().href, "/post/2021/example/");
+    }
+
+    #[test]
+    fn section_href_is_its_directory() {
+        let mut file_location = build_file_location();
+        file_location.file_stem = String::from("_index");
+        file_location.file_name = String::from("_index.md");
+
+        let section = |file_location: &FileLocation| {
+            PageIndex::new(
+                Some("Posts"),
+                Some("ignored"),
+                Some("2018-01-01"),
+                None,
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                String::new(),
+                file_location,
+                None,
+            )
+            .unwrap()
+        };
+        let page_index = section(&file_location);
+        assert_eq!(page_index.href, "/post/");
+        assert_eq!(page_index.kind, PageKind::Section);
+
+        file_location.relative_directory_to_content = String::new();
+        assert_eq!(section(&file_location).href, "/");
+    }
+
+    #[test]
+    fn bundle_href_is_named_after_its_directory() {
+        let mut file_location = build_file_location();
+        file_location.relative_directory_to_content = String::from("post/My-Post");
+        file_location.file_stem = String::from("index");
+        file_location.file_name = String::from("index.md");
+
+        let bundle = |slug: Option<&str>| {
+            PageIndex::new(
+                Some("Title"),
+                slug,
+                Some("2018-01-01"),
+                None,
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                String::new(),
+                &file_location,
+                None,
+            )
+            .unwrap()
+            .href
+        };
+        assert_eq!(bundle(None), "/post/my-post/");
+        assert_eq!(bundle(Some("renamed")), "/post/renamed/");
+    }
+
+    #[test]
+    fn href_is_prefixed_with_non_default_languages() {
+        let mut file_location = build_file_location();
+        file_location.lang = String::from("fr");
+
+        let href = |file_location: &FileLocation| {
+            PageIndex::new(
+                Some("Title"),
+                None,
+                Some("2018-01-01"),
+                None,
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                Vec::new(),
+                String::new(),
+                file_location,
+                None,
+            )
+            .unwrap()
+            .href
+        };
+        assert_eq!(href(&file_location), "/fr/post/example/");
+
+        file_location.default_lang = true;
+        assert_eq!(href(&file_location), "/post/example/");
     }
 
     #[test]
@@ -252,7 +888,105 @@ mod tests {
             url,
         );
         assert!(page_index.is_ok());
-        assert_eq!(page_index.unwrap().href, "/post/example/")
+        assert_eq!(page_index.unwrap().href, "/post/example/");
+    }
+
+    #[test]
+    fn dedupe_description_removes_leading_description_from_content() {
+        let mut page_index = TestPage::new("example")
+            .description("An example description.")
+            .content("An example description. A lot of content")
+            .build();
+
+        page_index.dedupe_description();
+        assert_eq!(page_index.content, "A lot of content");
+        assert_eq!(page_index.description, "An example description.");
+    }
+
+    #[test]
+    fn dedupe_description_leaves_content_not_starting_with_description() {
+        let mut page_index = TestPage::new("example")
+            .description("An example description.")
+            .content("A lot of content. An example description.")
+            .build();
+
+        page_index.dedupe_description();
+        assert_eq!(
+            page_index.content,
+            "A lot of content. An example description."
+        );
+    }
+
+    #[test]
+    fn dedupe_description_leaves_content_starting_with_a_longer_word() {
+        let mut page_index = TestPage::new("example")
+            .description("Rust")
+            .content("Rustaceans love the borrow checker.")
+            .build();
+
+        page_index.dedupe_description();
+        assert_eq!(page_index.content, "Rustaceans love the borrow checker.");
+    }
+
+    #[test]
+    fn summarize_takes_first_words_unless_summary_exists() {
+        let mut page_index = TestPage::new("example")
+            .content("A lot of\ncontent here")
+            .build();
+
+        page_index.summarize(3);
+        assert_eq!(page_index.summary, "A lot of");
+
+        page_index.summary = "Manual summary".to_owned();
+        page_index.summarize(3);
+        assert_eq!(page_index.summary, "Manual summary");
+    }
+
+    #[test]
+    fn truncate_content_keeps_whole_characters_or_words() {
+        let mut page_index = TestPage::new("example")
+            .content("Ünïcode words\nacross  lines here")
+            .build();
+
+        page_index.truncate_content(ContentLength::Words(3));
+        assert_eq!(page_index.content, "Ünïcode words\nacross");
+        page_index.truncate_content(ContentLength::Words(3));
+        assert_eq!(page_index.content, "Ünïcode words\nacross");
+        page_index.truncate_content(ContentLength::Chars(8));
+        assert_eq!(page_index.content, "Ünïcode");
+    }
+
+    #[test]
+    fn reading_time_rounds_like_hugo() {
+        assert_eq!(reading_time(0), 0);
+        assert_eq!(reading_time(1), 1);
+        assert_eq!(reading_time(213), 1);
+        assert_eq!(reading_time(214), 2);
+        assert_eq!(reading_time(1000), 5);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let page_index = TestPage::new("example")
+            .tags(&["Rust", "Hugo"])
+            .content("A lot of content")
+            .build();
+
+        let serialized = serde_json::to_string(&page_index).unwrap();
+        let deserialized: PageIndex = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, page_index);
+    }
+
+    #[test]
+    fn deserializes_with_defaults_for_skipped_fields() {
+        let serialized = r#"{"title":"Title","href":"/post/example/","date":"2018-01-01","content":"A lot of content"}"#;
+        let page_index: PageIndex = serde_json::from_str(serialized).unwrap();
+        assert_eq!(page_index.title, "Title");
+        assert!(page_index.description.is_empty());
+        assert!(page_index.categories.is_empty());
+        assert!(page_index.series.is_empty());
+        assert!(page_index.tags.is_empty());
+        assert!(page_index.keywords.is_empty());
     }
 
     #[test]
@@ -0,0 +1,40 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::settings::Settings;
+
+/// How the bar is drawn: a bar 30 characters wide, then how many files have been processed.
+const TEMPLATE: &str = "[{bar:30}] {pos}/{len} files";
+
+/// A progress bar on stderr of how many of the files found have been processed. It's only drawn if the settings ask
+/// for it, aren't quiet and stderr is a terminal, so it never ends up in logs or piped output.
+pub struct Progress {
+    bar: ProgressBar,
+}
+
+impl Progress {
+    /// Creates a progress bar for processing `total` files.
+    pub fn new(settings: &Settings, total: usize) -> Self {
+        // The stderr draw target hides itself when stderr isn't a terminal
+        let target = if settings.progress && !settings.quiet {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        let bar = ProgressBar::with_draw_target(Some(total as u64), target);
+        if let Ok(style) = ProgressStyle::with_template(TEMPLATE) {
+            bar.set_style(style.progress_chars("= "));
+        }
+        Self { bar }
+    }
+
+    /// Counts another file as processed. The bar is redrawn at most a few times a second, so drawing doesn't slow
+    /// down processing many small files.
+    pub fn advance(&mut self) {
+        self.bar.inc(1);
+    }
+
+    /// Clears the bar, so whatever is written next starts on an empty line.
+    pub fn finish(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
@@ -0,0 +1,212 @@
+use serde_json::Value as JsonValue;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use crate::constants;
+use crate::page_index::{PageIndex, PageKind};
+
+#[derive(Debug, Clone, PartialEq)]
+/// How pages are related, from the `related` section of the site config, like Hugo's
+/// [related content](https://gohugo.io/content-management/related/) config.
+pub struct RelatedConfig {
+    /// The front matter lists pages are related by, and how much a term they share in each is worth.
+    pub indices: Vec<RelatedIndex>,
+    /// Matches terms case insensitively.
+    pub to_lower: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A front matter list pages are related by, such as `tags`, with the weight of each term two pages share in it.
+pub struct RelatedIndex {
+    /// `tags`, `keywords`, `series`, `categories` or a custom taxonomy.
+    pub name: String,
+    /// What each shared term adds to the score of a pair of pages.
+    pub weight: u32,
+}
+
+impl Default for RelatedConfig {
+    /// Hugo's default indices, `keywords` weighted 100 and `tags` 80, along with `series` weighted 80. Hugo's `date`
+    /// index isn't a list of terms, so it isn't used.
+    fn default() -> Self {
+        let index = |name: &str, weight| RelatedIndex {
+            name: name.to_owned(),
+            weight,
+        };
+        Self {
+            indices: vec![
+                index(constants::KEYWORDS, 100),
+                index(constants::TAGS, 80),
+                index(constants::SERIES, 80),
+            ],
+            to_lower: false,
+        }
+    }
+}
+
+impl RelatedConfig {
+    /// Reads the `related` section of a site config. Indices without a name or a weight are ignored, and the defaults
+    /// are kept if there are no indices.
+    pub(crate) fn from_json(related: &JsonValue) -> Self {
+        let mut config = Self::default();
+        let get = |key: &str| {
+            related.as_object().and_then(|object| {
+                object
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .map(|(_, value)| value)
+            })
+        };
+
+        let indices: Vec<RelatedIndex> = get("indices")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|index| {
+                Some(RelatedIndex {
+                    name: index.get("name")?.as_str()?.to_owned(),
+                    weight: u32::try_from(index.get("weight")?.as_u64()?).ok()?,
+                })
+            })
+            .collect();
+        if !indices.is_empty() {
+            config.indices = indices;
+        }
+        if let Some(to_lower) = get("toLower").and_then(JsonValue::as_bool) {
+            config.to_lower = to_lower;
+        }
+        config
+    }
+}
+
+/// Sets the `related` field of each regular page to the hrefs of the `count` pages it's most related to, in the same
+/// language. Two pages' score is the sum of the weights of the terms they share. Ties go to the page walked first.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::related::{relate, RelatedConfig};
+/// use std::path::Path;
+/// # use hugo_to_json::operation_result::OperationResult;
+///
+/// let page = |name: &str, tags: &[&str]| {
+///     let location = FileLocation::from_path(&Path::new("/blog/content").join(name), Path::new("/blog/content"))?;
+///     let tags = tags.iter().map(|tag| tag.to_string()).collect();
+///     PageIndex::new(Some(name), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), tags, Vec::new(), String::new(), &location, None)
+/// };
+/// let mut pages = vec![page("a.md", &["rust", "hugo"])?, page("b.md", &["rust"])?, page("c.md", &["rust", "hugo"])?];
+///
+/// relate(&mut pages, 1, &RelatedConfig::default());
+/// assert_eq!(pages[0].related, ["/c/"]);
+/// assert_eq!(pages[1].related, ["/a/"]);
+/// # Ok::<(), OperationResult>(())
+/// ```
+pub fn relate<'a, I>(pages: I, count: usize, config: &RelatedConfig)
+where
+    I: IntoIterator<Item = &'a mut PageIndex>,
+{
+    let mut pages: Vec<&mut PageIndex> = pages.into_iter().collect();
+    let related: Vec<Vec<String>> = {
+        let pages: Vec<&PageIndex> = pages.iter().map(|page| &**page).collect();
+        most_related(&pages, count, config)
+    };
+    for (page, related) in pages.iter_mut().zip(related) {
+        page.related = related;
+    }
+}
+
+/// Finds the hrefs of the pages most related to each page.
+fn most_related(pages: &[&PageIndex], count: usize, config: &RelatedConfig) -> Vec<Vec<String>> {
+    // The positions of the pages with each term, keyed by the index and the term
+    let mut postings: HashMap<(&str, String), Vec<usize>> = HashMap::new();
+    for (position, page) in pages.iter().enumerate() {
+        if page.kind != PageKind::Page {
+            continue;
+        }
+        for index in &config.indices {
+            for term in terms(page, &index.name) {
+                postings
+                    .entry((index.name.as_str(), normalize(term, config)))
+                    .or_default()
+                    .push(position);
+            }
+        }
+    }
+
+    pages
+        .iter()
+        .enumerate()
+        .map(|(position, page)| {
+            if count == 0 || page.kind != PageKind::Page {
+                return Vec::new();
+            }
+            let mut scores: BTreeMap<usize, u32> = BTreeMap::new();
+            for index in &config.indices {
+                for term in terms(page, &index.name) {
+                    let key = (index.name.as_str(), normalize(term, config));
+                    for &other in postings.get(&key).into_iter().flatten() {
+                        if other != position && pages[other].lang == page.lang {
+                            *scores.entry(other).or_default() += index.weight;
+                        }
+                    }
+                }
+            }
+
+            let mut scores: Vec<(usize, u32)> = scores.into_iter().collect();
+            // The sort is stable, so pages with equal scores stay in the order they were walked
+            scores.sort_by_key(|&(_, score)| Reverse(score));
+            scores
+                .into_iter()
+                .take(count)
+                .map(|(other, _)| pages[other].href.clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns a page's terms in an index.
+fn terms<'a>(page: &'a PageIndex, index: &str) -> &'a [String] {
+    match index {
+        constants::TAGS => &page.tags,
+        constants::KEYWORDS => &page.keywords,
+        constants::SERIES => &page.series,
+        constants::CATEGORIES => &page.categories,
+        taxonomy => page.taxonomies.get(taxonomy).map_or(&[], Vec::as_slice),
+    }
+}
+
+fn normalize(term: &str, config: &RelatedConfig) -> String {
+    if config.to_lower {
+        term.to_lowercase()
+    } else {
+        term.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_indices_from_the_site_config() {
+        let config = RelatedConfig::from_json(&json!({
+            "indices": [{ "name": "categories", "weight": 50 }, { "name": "date" }],
+            "toLower": true,
+        }));
+        assert_eq!(
+            config.indices,
+            vec![RelatedIndex {
+                name: String::from("categories"),
+                weight: 50,
+            }]
+        );
+        assert!(config.to_lower);
+        assert_eq!(
+            RelatedConfig::from_json(&JsonValue::Null),
+            RelatedConfig::default()
+        );
+    }
+}
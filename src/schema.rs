@@ -0,0 +1,474 @@
+use serde_json::{json, Map, Value};
+
+use std::fs::{self, create_dir_all};
+use std::path::Path;
+
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::settings::{Granularity, OutputFormat, Settings};
+use crate::table::DEFAULT_COLUMNS;
+
+/// The version of [JSON Schema](https://json-schema.org/) schemas are written in.
+pub const DIALECT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Returns a JSON Schema describing the index the settings produce: an array of records, or for a Lunr index an object
+/// of the Lunr index and its records. Records only allow the fields the settings can add to them, and their `extra`
+/// only allows the configured extra fields unless every field is kept.
+///
+/// A `csv` or `tsv` index is described as an array of objects of its columns, the way most CSV readers parse it.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::schema::index_schema;
+/// use hugo_to_json::settings::Settings;
+///
+/// let settings = Settings { extra_fields: vec![String::from("author")], ..Settings::default() };
+/// let schema = index_schema(&settings);
+/// assert_eq!(schema["type"], "array");
+/// assert!(schema["items"]["required"].as_array().unwrap().contains(&"title".into()));
+/// assert!(schema["items"]["properties"]["extra"]["properties"]["author"].is_object());
+/// ```
+#[must_use]
+pub fn index_schema(settings: &Settings) -> Value {
+    let mut schema = match settings.format {
+        OutputFormat::Lunr => json!({
+            "type": "object",
+            "properties": {
+                "index": { "type": "object" },
+                "documents": { "type": "array", "items": record_schema(settings) },
+            },
+            "required": ["index", "documents"],
+            "additionalProperties": false,
+        }),
+        OutputFormat::Json if settings.envelope => json!({
+            "type": "object",
+            "properties": {
+                "generated_at": { "type": "string" },
+                "generator_version": { "type": "string" },
+                "page_count": { "type": "integer", "minimum": 0 },
+                "pages": { "type": "array", "items": record_schema(settings) },
+            },
+            "required": ["generated_at", "generator_version", "page_count", "pages"],
+            "additionalProperties": false,
+        }),
+        OutputFormat::GeoJson => geojson_schema(),
+        OutputFormat::OpenSearchSuggest => json!({
+            "type": "object",
+            "additionalProperties": {
+                "type": "array",
+                "items": { "type": ["string", "array"] },
+            },
+        }),
+        _ => json!({ "type": "array", "items": record_schema(settings) }),
+    };
+    if let Value::Object(ref mut schema) = schema {
+        schema.insert(String::from("$schema"), Value::from(DIALECT));
+        schema.insert(String::from("title"), Value::from("Hugo to JSON index"));
+    }
+    schema
+}
+
+/// Returns a JSON Schema describing a `GeoJSON` collection of pages.
+fn geojson_schema() -> Value {
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+    json!({
+        "type": "object",
+        "properties": {
+            "type": { "type": "string", "enum": ["FeatureCollection"] },
+            "features": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "type": "string", "enum": ["Feature"] },
+                        "geometry": {
+                            "type": "object",
+                            "properties": {
+                                "type": { "type": "string", "enum": ["Point"] },
+                                "coordinates": { "type": "array", "items": { "type": "number" } },
+                            },
+                            "required": ["type", "coordinates"],
+                            "additionalProperties": false,
+                        },
+                        "properties": {
+                            "type": "object",
+                            "properties": {
+                                "title": { "type": "string" },
+                                "href": { "type": "string" },
+                                "date": { "type": "string" },
+                                "place": { "type": "string" },
+                                "summary": { "type": "string" },
+                                "tags": strings,
+                            },
+                            "required": ["title", "href", "date"],
+                            "additionalProperties": false,
+                        },
+                    },
+                    "required": ["type", "geometry", "properties"],
+                    "additionalProperties": false,
+                },
+            },
+        },
+        "required": ["type", "features"],
+        "additionalProperties": false,
+    })
+}
+
+/// Returns a JSON Schema describing each record of the index the settings produce.
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn record_schema(settings: &Settings) -> Value {
+    if settings.format.delimiter().is_some() {
+        return row_schema(settings);
+    }
+
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+    let mut properties = json!({
+        "title": { "type": "string" },
+        "href": { "type": "string" },
+        "date": { "type": "string" },
+        "content": { "type": "string" },
+        "summary": { "type": "string" },
+        "description": { "type": "string" },
+        "categories": strings,
+        "series": strings,
+        "tags": strings,
+        "keywords": strings,
+        "authors": strings,
+        "taxonomies": { "type": "object", "additionalProperties": strings },
+        "lastmod": { "type": "string" },
+        "aliases": strings,
+        "images": strings,
+        "weight": { "type": "integer" },
+        "location": {
+            "type": "object",
+            "properties": {
+                "lat": { "type": "number", "minimum": -90, "maximum": 90 },
+                "lng": { "type": "number", "minimum": -180, "maximum": 180 },
+                "name": { "type": "string" },
+            },
+            "required": ["lat", "lng"],
+            "additionalProperties": false,
+        },
+        "menus": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "menu": { "type": "string" },
+                    "name": { "type": "string" },
+                    "weight": { "type": "integer" },
+                    "parent": { "type": "string" },
+                    "identifier": { "type": "string" },
+                },
+                "required": ["menu", "name"],
+                "additionalProperties": false,
+            },
+        },
+        "type": { "type": "string" },
+        "layout": { "type": "string" },
+        "kind": { "type": "string", "enum": ["section", "page"] },
+        "lang": { "type": "string" },
+        "word_count": { "type": "integer", "minimum": 0 },
+        "reading_time": { "type": "integer", "minimum": 0 },
+    });
+    let mut required = vec![
+        constants::TITLE,
+        constants::HREF,
+        constants::DATE,
+        constants::CONTENT,
+        "kind",
+        "word_count",
+        "reading_time",
+    ];
+
+    let mut fields = Map::new();
+    if settings.bundle_resources {
+        fields.insert(String::from("resources"), strings.clone());
+    }
+    if settings.headings {
+        fields.insert(
+            String::from("headings"),
+            json!({ "type": "array", "items": heading_schema() }),
+        );
+    }
+    if settings.granularity == Granularity::Heading {
+        fields.insert(String::from("heading"), heading_schema());
+    }
+    if settings.params {
+        fields.insert(String::from("params"), json!({ "type": "object" }));
+    }
+    if settings.drafts || settings.drafts_only {
+        fields.insert(String::from("draft"), json!({ "type": "boolean" }));
+    }
+    if settings.related.is_some() {
+        fields.insert(String::from("related"), strings.clone());
+    }
+    if settings.weights {
+        fields.insert(
+            String::from("weights"),
+            json!({ "type": "object", "additionalProperties": { "type": "number" } }),
+        );
+    }
+    if settings.all_fields || !settings.extra_fields.is_empty() {
+        // Extra fields are copied from front matter as they are, so they can be of any type
+        let extra: Map<String, Value> = settings
+            .extra_fields
+            .iter()
+            .map(|field| (field.clone(), json!({})))
+            .collect();
+        fields.insert(
+            String::from("extra"),
+            json!({
+                "type": "object",
+                "properties": extra,
+                "additionalProperties": settings.all_fields,
+            }),
+        );
+    }
+    if settings.format == OutputFormat::Algolia {
+        fields.insert(String::from("objectID"), json!({ "type": "string" }));
+        fields.insert(
+            String::from("part"),
+            json!({ "type": "integer", "minimum": 0 }),
+        );
+        required.extend(&["objectID", "part"]);
+    }
+
+    if let Value::Object(ref mut properties) = properties {
+        properties.extend(fields);
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Describes a heading of a page, with its level, text and anchor.
+fn heading_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "level": { "type": "integer", "minimum": 1, "maximum": 3 },
+            "text": { "type": "string" },
+            "anchor": { "type": "string" },
+        },
+        "required": ["level", "text", "anchor"],
+        "additionalProperties": false,
+    })
+}
+
+/// Describes a row of a table as an object of its columns, whose values are all strings.
+fn row_schema(settings: &Settings) -> Value {
+    let columns: Vec<&str> = if settings.columns.is_empty() {
+        DEFAULT_COLUMNS.to_vec()
+    } else {
+        settings.columns.iter().map(String::as_str).collect()
+    };
+    let properties: Map<String, Value> = columns
+        .iter()
+        .map(|&column| (column.to_owned(), json!({ "type": "string" })))
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": columns,
+        "additionalProperties": false,
+    })
+}
+
+/// Writes the schema of the index the settings produce to `path`.
+///
+/// # Errors
+/// Errors if the schema can't be written.
+pub fn write_schema(settings: &Settings, path: &Path) -> Result<(), HugotoJsonError> {
+    create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+    fs::write(path, serde_json::to_string_pretty(&index_schema(settings))?)?;
+    debug!("Wrote schema to {}", path.to_string_lossy());
+    Ok(())
+}
+
+/// Checks `instance` against `schema`, returning a description of each way it doesn't match, prefixed with the
+/// [JSON pointer](https://tools.ietf.org/html/rfc6901) of the value that doesn't.
+///
+/// Only the keywords of the schemas written by `index_schema` are checked: `type`, `enum`, `minimum`, `properties`,
+/// `required`, `additionalProperties` and `items`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::schema::problems;
+/// use serde_json::json;
+///
+/// let schema = json!({ "type": "array", "items": { "type": "object", "required": ["title"] } });
+/// assert!(problems(&schema, &json!([{ "title": "Rust" }])).is_empty());
+/// assert_eq!(problems(&schema, &json!([{ "href": "/rust/" }])), ["/0: title is required"]);
+/// ```
+#[must_use]
+pub fn problems(schema: &Value, instance: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    check(schema, instance, "", &mut problems);
+    problems
+}
+
+fn check(schema: &Value, instance: &Value, pointer: &str, problems: &mut Vec<String>) {
+    let location = if pointer.is_empty() {
+        constants::FORWARD_SLASH
+    } else {
+        pointer
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            expected => expected.as_str().into_iter().collect(),
+        };
+        if !types.iter().any(|&name| has_type(instance, name)) {
+            problems.push(format!(
+                "{}: expected {}, found {}",
+                location,
+                types.join(" or "),
+                type_name(instance)
+            ));
+            // Nothing else can be checked meaningfully against a value of the wrong type
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(instance) {
+            problems.push(format!("{location}: {instance} isn't allowed"));
+        }
+    }
+
+    if let (Some(minimum), Some(number)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        instance.as_f64(),
+    ) {
+        if number < minimum {
+            problems.push(format!("{location}: {number} is less than {minimum}"));
+        }
+    }
+
+    if let Value::Object(fields) = instance {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for required in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !fields.contains_key(required) {
+                problems.push(format!("{location}: {required} is required"));
+            }
+        }
+        for (name, value) in fields {
+            let pointer = format!("{}/{}", pointer, escape(name));
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property) => check(property, value, &pointer, problems),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        problems.push(format!("{location}: {name} isn't allowed"));
+                    }
+                    Some(additional @ Value::Object(_)) => {
+                        check(additional, value, &pointer, problems);
+                    }
+                    _ => (),
+                },
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (instance, schema.get("items")) {
+        for (position, item) in items.iter().enumerate() {
+            check(
+                item_schema,
+                item,
+                &format!("{pointer}/{position}"),
+                problems,
+            );
+        }
+    }
+}
+
+fn has_type(instance: &Value, name: &str) -> bool {
+    match name {
+        "integer" => instance.is_i64() || instance.is_u64(),
+        name => type_name(instance) == name || (name == "number" && instance.is_number()),
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Escapes a key for a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_pages_match_their_schema() {
+        let page = json!({
+            "title": "Rust",
+            "href": "/rust/",
+            "date": "2019-01-25",
+            "content": "Contents",
+            "tags": ["rust"],
+            "taxonomies": { "authors": ["ann"] },
+            "kind": "page",
+            "word_count": 1,
+            "reading_time": 1,
+            "extra": { "author": { "name": "Ann" } },
+        });
+        let settings = Settings {
+            extra_fields: vec![String::from("author")],
+            ..Settings::default()
+        };
+        assert!(problems(&index_schema(&settings), &json!([page])).is_empty());
+
+        let lunr = Settings {
+            format: OutputFormat::Lunr,
+            ..settings
+        };
+        let bundle = json!({ "index": {}, "documents": [page] });
+        assert!(problems(&index_schema(&lunr), &bundle).is_empty());
+    }
+
+    #[test]
+    fn finds_every_problem() {
+        let page = json!({
+            "title": 1,
+            "href": "/rust/",
+            "content": "",
+            "taxonomies": { "authors": "ann" },
+            "kind": "post",
+            "word_count": 0,
+            "reading_time": 0,
+            "extra": { "author": "Ann" },
+        });
+        assert_eq!(
+            problems(&index_schema(&Settings::default()), &json!([page])),
+            [
+                "/0: date is required",
+                "/0: extra isn't allowed",
+                "/0/kind: \"post\" isn't allowed",
+                "/0/taxonomies/authors: expected array, found string",
+                "/0/title: expected string, found number",
+            ]
+        );
+    }
+}
@@ -1,18 +1,1604 @@
 use structopt::StructOpt;
 
-use std::path::PathBuf;
+use chrono_tz::Tz;
+use globset::Glob;
 
-#[derive(Debug, StructOpt)]
+use crate::check::CheckRules;
+use crate::config::{Config, FieldMapping};
+use crate::date::DateLocale;
+use crate::extract::{self, ContentExtractor, ExtractOptions};
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::lunr::Boosts;
+use crate::site_config::{ContentMount, SiteConfig};
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The prefix of the environment variables that set options, e.g. `HUGOTOJSON_FORMAT` for `--format`.
+pub const ENV_PREFIX: &str = "HUGOTOJSON_";
+
+/// The output that stands for stdout, e.g. `-o -`.
+pub const STDOUT_PATH: &str = "-";
+
+#[derive(Debug, Clone, StructOpt)]
 #[structopt(
     name = "Hugo to JSON",
     about = "A tool to turn Hugo sites into a JSON representation."
 )]
 /// Represents the settings that can be provided either progamatically or as command line options.  
+#[allow(clippy::struct_excessive_bools)]
 pub struct Settings {
     /// The path to [Hugo](https://gohugo.io/)'s [contents](https://gohugo.io/content-management/organization/) directory. Defaults to `./content/`
-    #[structopt(parse(from_os_str))]
+    #[structopt(parse(from_os_str), default_value = "./content/")]
     pub scan_path: PathBuf,
-    /// The path that index will be output to. If not provided, writes to stdout.
-    #[structopt(short = "o", parse(from_os_str))]
+    /// More contents directories, such as those mounted by Hugo modules, whose pages are merged into the index as if they
+    /// were in the first. Like Hugo's mounts, a file hides any files at the same path in the directories after it.
+    #[structopt(parse(from_os_str))]
+    pub more_scan_paths: Vec<PathBuf>,
+    /// The path that index will be output to. If not provided, or `-`, writes to stdout.
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
     pub output: Option<PathBuf>,
+    /// The format of the index: `json`, `ndjson` for a JSON object on a line of its own for each page, `algolia` for
+    /// records that are split to fit Algolia's record size limit, `lunr` for a prebuilt Lunr index bundled with its
+    /// documents, `csv` or `tsv` for a table with a row for each page, `sqlite` for a `SQLite` database, which needs
+    /// `--output`, `stork` for the TOML config Stork builds an index from, `geojson` for a `GeoJSON` collection of the pages
+    /// with a location, `opensearch-suggest` for `OpenSearch` suggestions keyed by the prefixes of titles, or `tantivy` for a tantivy index in the directory given by `--output`, if built with the
+    /// `tantivy` feature.
+    #[structopt(long = "format", default_value = "json")]
+    pub format: OutputFormat,
+    /// Also writes another output from the same pages, as `PATH:KIND`, e.g. `sitemap.xml:sitemap`. The kind is an index
+    /// format, `taxonomies`, `rss`, `sitemap` or `links`, and can be left out of a `.json`, `.ndjson`, `.geojson`, `.csv`, `.tsv`,
+    /// `.db` or `.sqlite` index. Can be repeated. The first index is written in place of `-o` if it isn't given.
+    #[structopt(
+        long = "out",
+        number_of_values = 1,
+        conflicts_with_all = &["split-by-lang", "split-by-section"]
+    )]
+    pub outputs: Vec<Output>,
+    /// Pretty prints a JSON index, indenting it over many lines so changes to it can be reviewed and diffed. The index is
+    /// minified otherwise.
+    #[structopt(long = "pretty")]
+    pub pretty: bool,
+    /// The columns of a `csv` or `tsv` index, e.g. `title,href,date,tags`, which is the default. Columns are named after
+    /// the fields of a page, its custom taxonomies or its kept extra fields.
+    #[structopt(long = "fields", use_delimiter = true)]
+    pub columns: Vec<String>,
+    /// What the items of list fields, such as tags, are joined with in a `csv` or `tsv` index. Defaults to `|`.
+    #[structopt(long = "list-delimiter")]
+    pub list_delimiter: Option<String>,
+    /// Gives a `sqlite` index the full text search schema [Datasette](https://datasette.io/) and sqlite-utils detect,
+    /// with triggers that keep the search index up to date as pages are changed.
+    #[structopt(long = "sqlite-fts")]
+    pub sqlite_fts: bool,
+    /// Also writes what a search library needs alongside a `json` index: `fusejs` writes the options to create a
+    /// [Fuse.js](https://fusejs.io/) search with to `fuse-options.json`, beside the index given with `--output`, with
+    /// key weights from the config file's boosts.
+    #[structopt(long = "preset")]
+    pub preset: Option<Preset>,
+    /// Sorts the pages of the index by `date`, `title` or `href`. Pages are in the order their files are found otherwise.
+    #[structopt(long = "sort")]
+    pub sort: Option<SortKey>,
+    /// The order pages are sorted in by `--sort`: `asc` (the default) or `desc`.
+    #[structopt(long = "order", default_value = "asc")]
+    pub order: SortOrder,
+    /// Only writes this many pages to the index, after they're sorted, e.g. for a list of the newest pages.
+    #[structopt(long = "limit")]
+    pub limit: Option<usize>,
+    /// Only index files matching this glob, relative to the contents directory, e.g. `post/**`. Can be repeated.
+    #[structopt(long = "include", number_of_values = 1)]
+    pub include: Vec<Glob>,
+    /// Skip files and directories matching this glob, relative to the contents directory, e.g. `legal/**`. Can be repeated.
+    #[structopt(long = "exclude", number_of_values = 1)]
+    pub exclude: Vec<Glob>,
+    /// Removes the description from the start of a document's content when the content begins with it, so the intro isn't stored twice.
+    #[structopt(long = "dedupe-description")]
+    pub dedupe_description: bool,
+    /// Keeps the text between the tags of paired [shortcodes](https://gohugo.io/content-management/shortcodes/) such as
+    /// `{{% note %}}...{{% /note %}}`. By default shortcodes are removed from the content along with everything inside them.
+    #[structopt(long = "keep-shortcode-text")]
+    pub keep_shortcode_text: bool,
+    /// How the text of a document's content is extracted from its markdown: `strip-markdown` (the default),
+    /// `pulldown-cmark`, which handles tables, footnotes and nested lists, or `raw` to keep the markdown as it is.
+    #[structopt(long = "content-extractor", default_value = "strip-markdown")]
+    pub content_extractor: ExtractorKind,
+    /// An extractor of the library user's own, used in place of `content_extractor`.
+    #[structopt(skip)]
+    pub custom_extractor: Option<Arc<dyn ContentExtractor>>,
+    /// Leaves fenced and indented code blocks out of the content, so code doesn't dominate the index.
+    #[structopt(long = "skip-code-blocks")]
+    pub skip_code_blocks: bool,
+    /// Indexes only the text of fenced and indented code blocks as the content, for building a code search index.
+    #[structopt(long = "code-blocks-only", conflicts_with = "skip-code-blocks")]
+    pub code_blocks_only: bool,
+    /// Keeps the alt text of images in the content. Only used by the `pulldown-cmark` content extractor.
+    #[structopt(long = "keep-alt-text")]
+    pub keep_alt_text: bool,
+    /// A [chrono format string](https://docs.rs/chrono/0.4/chrono/format/strftime/index.html) used to parse front matter dates, e.g. `%d %B %Y`. Can be repeated.
+    /// When provided, dates are normalised to RFC 3339 and documents with dates that can't be parsed fail.
+    #[structopt(long = "date-format", number_of_values = 1)]
+    pub date_formats: Vec<String>,
+    /// The locale month and day names in front matter dates are written in, e.g. `fr`. Also enables date normalisation.
+    #[structopt(long = "date-locale")]
+    pub date_locale: Option<DateLocale>,
+    /// An [IANA timezone](https://en.wikipedia.org/wiki/List_of_tz_database_time_zones) such as `Europe/London` that dates are resolved to, so pages with and without explicit times are ordered consistently.
+    /// Also enables date normalisation.
+    #[structopt(long = "timezone")]
+    pub timezone: Option<Tz>,
+    /// Summarises documents without a `<!--more-->` divider with this many of their first words, like Hugo's `summaryLength`.
+    /// Defaults to the site config's `summaryLength`, then 70. `0` leaves them without a summary.
+    #[structopt(long = "summary-length")]
+    pub summary_length: Option<usize>,
+    /// Truncates each document's content to this many characters, e.g. `2000`, or words, e.g. `300words`.
+    #[structopt(long = "max-content-length")]
+    pub max_content_length: Option<ContentLength>,
+    /// Warns if the index is larger than this many bytes, e.g. `500000`, `500KB` or `2MB`.
+    #[structopt(long = "max-index-size", parse(try_from_str = parse_byte_size))]
+    pub max_index_size: Option<u64>,
+    /// Skips files larger than this many bytes, e.g. `1MB`, such as datasets committed to the contents directory by
+    /// accident, rather than reading them into memory.
+    #[structopt(long = "max-file-size", parse(try_from_str = parse_byte_size))]
+    pub max_file_size: Option<u64>,
+    /// Fails the run, rather than warning, if the index is larger than `--max-index-size`.
+    #[structopt(long = "enforce-max-index-size", requires = "max-index-size")]
+    pub enforce_max_index_size: bool,
+    /// Include documents marked as drafts, like `hugo --buildDrafts`. Their records have `"draft": true`.
+    #[structopt(long = "drafts")]
+    pub drafts: bool,
+    /// Only include documents marked as drafts, e.g. for a search of unpublished work on a staging site. Their records
+    /// have `"draft": true`.
+    #[structopt(long = "drafts-only", conflicts_with = "drafts")]
+    pub drafts_only: bool,
+    /// Include documents with a publish date in the future, like `hugo --buildFuture`.
+    #[structopt(long = "future")]
+    pub build_future: bool,
+    /// Include documents with an expiry date in the past, like `hugo --buildExpired`.
+    #[structopt(long = "expired")]
+    pub build_expired: bool,
+    /// Traverse hidden files and directories, whose names start with `.`. Paths can still be ignored by the
+    /// `.hugotojsonignore` file at the top of a contents directory.
+    #[structopt(long = "include-hidden")]
+    pub include_hidden: bool,
+    /// Follow symbolic links in the contents directory, reading the files and directories they link to. Links back to a
+    /// directory they're in are skipped rather than followed forever.
+    #[structopt(long = "follow-symlinks")]
+    pub follow_symlinks: bool,
+    /// Only read files at most this many levels below the contents directory, where files at its top are 1 level below.
+    #[structopt(long = "max-depth")]
+    pub max_depth: Option<usize>,
+    /// How log messages are written to stderr: `text`, or `json` for a JSON object on each line with the message's
+    /// level and, for files that fail, the `file` and the `kind` of failure.
+    #[structopt(long = "log-format", default_value = "text")]
+    pub log_format: LogFormat,
+    /// Only log errors, and don't show a progress bar, e.g. when the index is piped to another tool.
+    #[structopt(long = "quiet")]
+    pub quiet: bool,
+    /// Show a progress bar of the files processed on stderr, if it's a terminal.
+    #[structopt(long = "progress")]
+    pub progress: bool,
+    /// The number of files processed at once. Defaults to the number of CPUs, which can throttle shared CI runners.
+    #[structopt(long = "jobs", conflicts_with = "single-threaded")]
+    pub jobs: Option<usize>,
+    /// Process one file at a time, in the order they're walked, for deterministic debugging. The same as `--jobs 1`.
+    #[structopt(long = "single-threaded")]
+    pub single_threaded: bool,
+    /// Print totals to stderr once the index is written: pages indexed, files skipped and failed by why, bytes written
+    /// and how long it took.
+    #[structopt(long = "stats")]
+    pub stats: bool,
+    /// Include headless bundles and documents whose `_build` options keep them out of lists or from being rendered.
+    #[structopt(long = "include-unlisted")]
+    pub include_unlisted: bool,
+    /// Adds the first image in a markdown document's content to its `images`, after those from its front matter.
+    #[structopt(long = "body-image")]
+    pub body_image: bool,
+    /// Adds a `headings` field to each markdown page with the text, level and anchor of its H1 to H3 headings, so search
+    /// results can link to the section they matched in.
+    #[structopt(long = "headings")]
+    pub headings: bool,
+    /// What each record of the index is for: a `page` (the default), or each `heading` section of a markdown page, with
+    /// an href to the heading's anchor and the section's text as its content, for section level search.
+    #[structopt(long = "granularity", default_value = "page")]
+    pub granularity: Granularity,
+    /// How documents' front matter is parsed: `auto` (the default) tells from its opening fence, `+++` for TOML, `---`
+    /// for YAML or `{` for JSON, while `toml`, `yaml` or `json` parse every document's front matter as that format.
+    #[structopt(long = "front-matter", default_value = "auto")]
+    pub front_matter: FrontMatterMode,
+    /// Unrecognised front matter fields to copy into each record under `extra`, e.g. `author,cover_image`.
+    #[structopt(long = "extra-fields", use_delimiter = true)]
+    pub extra_fields: Vec<String>,
+    /// Copy every unrecognised front matter field into each record under `extra`.
+    #[structopt(long = "all-fields")]
+    pub all_fields: bool,
+    /// Copy the custom front matter fields into each record under `params`, as Hugo's `.Params` has them, with their
+    /// tables and lists kept as they are. The fields of a `params` table in the front matter are merged into them.
+    #[structopt(long = "params")]
+    pub params: bool,
+    /// List the other files in each leaf bundle, such as images and attachments, in its record under `resources`.
+    #[structopt(long = "bundle-resources")]
+    pub bundle_resources: bool,
+    /// The languages of a multilingual site, e.g. `en,fr`, starting with the default language. Pages in other languages
+    /// are read from files with a language suffix, e.g. `about.fr.md`, or a language directory, e.g. `fr/about.md`.
+    #[structopt(long = "languages", use_delimiter = true)]
+    pub languages: Vec<String>,
+    /// Write an index for each language, e.g. `fr.json`, into the output directory.
+    #[structopt(
+        long = "split-by-lang",
+        requires_all = &["output", "languages"],
+        conflicts_with = "split-by-section"
+    )]
+    pub split_by_lang: bool,
+    /// Write an index for each top level directory of the contents directory, e.g. `post.json`, into the output directory.
+    #[structopt(long = "split-by-section", requires = "output")]
+    pub split_by_section: bool,
+    /// Splits the index into files of this many pages, e.g. `index-000.json` and `index-001.json` for `-o index.json`,
+    /// with a `manifest.json` beside them listing each file's page count and date range, so clients can load them as
+    /// they're needed.
+    #[structopt(
+        long = "shard-size",
+        conflicts_with_all = &["split-by-lang", "split-by-section"]
+    )]
+    pub shard_size: Option<usize>,
+    /// An index written by an earlier run, as JSON or NDJSON, to compare the new index with. The hrefs of the records that
+    /// were added, removed and updated, with the fields that changed, are written to `changes.json` beside the index.
+    /// Can be the index that's about to be replaced, as it's read first.
+    #[structopt(
+        long = "diff",
+        parse(from_os_str),
+        conflicts_with_all = &["watch", "split-by-lang", "split-by-section"]
+    )]
+    pub diff: Option<PathBuf>,
+    /// Re-reads the index after writing it and fails if it isn't valid.
+    #[structopt(long = "validate")]
+    pub validate: bool,
+    /// Normalises each page's content for search: lowercases it, removes stop words and stems the remaining words with
+    /// the Snowball stemmer for the page's language.
+    #[structopt(long = "normalize-content")]
+    pub normalize_content: bool,
+    /// The language content is normalised for, e.g. `en` or `fr`, when a page's front matter doesn't set `language`
+    /// and it isn't in a language of a multilingual site. Defaults to the site's default content language, then `en`.
+    #[structopt(long = "language", requires = "normalize-content")]
+    pub language: Option<String>,
+    /// Adds a `related` field to each page with the hrefs of this many of the pages most related to it by shared tags,
+    /// keywords and series, weighted by the `related` section of the site config like Hugo's related content.
+    #[structopt(long = "related")]
+    pub related: Option<usize>,
+    /// Adds a `weights` field to each page with the TF-IDF weight of each term in its title, tags and content, after
+    /// stop words are removed, so a client can rank pages without their content.
+    #[structopt(long = "weights")]
+    pub weights: bool,
+    /// Stems the terms in `weights`, so e.g. `search` and `searching` are weighted as one term.
+    #[structopt(long = "stem-weights", requires = "weights")]
+    pub stem_weights: bool,
+    /// Also writes a taxonomy index to this file, mapping each tag, category, series and custom taxonomy term to the
+    /// hrefs of the pages that have it.
+    #[structopt(long = "taxonomies", parse(from_os_str))]
+    pub taxonomy_index: Option<PathBuf>,
+    /// Also writes a feed of the newest pages to this file, as Atom if it ends in `.atom` and otherwise as RSS 2.0.
+    /// Links are absolute if the site config has a `baseURL`.
+    #[structopt(long = "rss", parse(from_os_str))]
+    pub rss: Option<PathBuf>,
+    /// The number of pages in the feed. Defaults to the site config's `rssLimit`, then 20.
+    #[structopt(long = "rss-limit", requires = "rss")]
+    pub rss_limit: Option<usize>,
+    /// Also writes a sitemap of every page to this file, with its last modified date and the change frequency and
+    /// priority from its front matter or the site config. Locations are absolute if the site config has a `baseURL`.
+    #[structopt(long = "sitemap", parse(from_os_str))]
+    pub sitemap: Option<PathBuf>,
+    /// Also writes the site's link graph to this file: every link in each page's content, with the href of the page
+    /// it's on, where it goes, whether it goes to the site or elsewhere, and its text.
+    #[structopt(long = "links", parse(from_os_str))]
+    pub links: Option<PathBuf>,
+    /// Checks that each internal link and `ref` shortcode in each page's content goes to a page in the index or a
+    /// file in the static or contents directory. Broken links are reported like files that fail to process.
+    #[structopt(long = "check-links")]
+    pub check_links: bool,
+    /// The site's static directory, which checked links can go to files in. Defaults to the `static` directory beside
+    /// the contents directory.
+    #[structopt(long = "static-dir", parse(from_os_str), requires = "check-links")]
+    pub static_dir: Option<PathBuf>,
+    /// Also writes a [JSON Schema](https://json-schema.org/) describing the index to this file, including the configured
+    /// extra fields. With `--validate`, the index is checked against the schema too.
+    #[structopt(long = "emit-schema", parse(from_os_str))]
+    pub emit_schema: Option<PathBuf>,
+    /// Also writes the SHA-256 checksum of each index written to a file beside it, e.g. `index.json.sha256`, in the
+    /// format `sha256sum --check` reads. Needs the index written to a file.
+    #[structopt(long = "checksum")]
+    pub checksum: bool,
+    /// Leaves an index that's the same as the one already written untouched, so its modification time is kept and
+    /// caches and syncs don't see a change. Needs the index written to a file.
+    #[structopt(long = "skip-unchanged")]
+    pub skip_unchanged: bool,
+    /// Wraps a `json` index in an object of metadata about it, its `generated_at` time, `generator_version` and
+    /// `page_count`, with the pages under `pages`.
+    #[structopt(long = "envelope")]
+    pub envelope: bool,
+    /// Signs each index written to a file with a key, as `ALGORITHM:PATH`, e.g. `ed25519:signing-key.pem`, and writes
+    /// the signature beside it, e.g. `index.json.sig`, so clients can check it wasn't changed after it was built. The key
+    /// is a PKCS#8 PEM file, such as `openssl genpkey -algorithm ed25519` writes. Needs the index written to a file.
+    #[structopt(long = "sign")]
+    pub sign: Option<Signer>,
+    /// Leaves the existing index as it was if the run fails because files failed to process, rather than replacing it
+    /// with an index of the files that didn't. The same goes for each index written by `--split-by-section` or
+    /// `--split-by-lang`, and the taxonomy index, feed, sitemap and link graph. An index is never replaced until it's
+    /// completely written and checked.
+    #[structopt(long = "no-clobber-on-error")]
+    pub no_clobber_on_error: bool,
+    /// Which files failing to process make the run exit with an error: `parse`, `io`, `any` (the default) or `none`.
+    /// Failures are always logged and counted, whichever is chosen.
+    #[structopt(long = "fail-on", default_value = "any")]
+    pub fail_on: FailOn,
+    /// Fails the run if pages have the same href or title as another page, rather than only warning about them.
+    #[structopt(long = "fail-on-duplicates")]
+    pub fail_on_duplicates: bool,
+    /// Writes each file that failed to process, with its path, the kind of failure and a message, to this file as JSON.
+    #[structopt(long = "error-report", parse(from_os_str), conflicts_with = "watch")]
+    pub error_report: Option<PathBuf>,
+    /// Writes the index compressed with `gzip` or `brotli`, adding `.gz` or `.br` to the output file name, for static
+    /// hosts that serve precompressed assets.
+    #[structopt(long = "compress", requires = "output")]
+    pub compress: Option<Compression>,
+    /// Keeps the pages built from each file in this file, so later runs only re-process the files that have changed.
+    #[structopt(long = "cache-file", parse(from_os_str))]
+    pub cache_file: Option<PathBuf>,
+    /// Reads a single document from stdin and prints its record as a JSON object instead of indexing the contents
+    /// directory, for previewing or checking one file from an editor or a pre-commit hook. Requires `--path`.
+    #[structopt(
+        long = "stdin",
+        requires = "stdin-path",
+        conflicts_with_all = &["watch", "upload", "split-by-section", "split-by-lang"]
+    )]
+    pub stdin: bool,
+    /// The path of the document read from stdin, e.g. `content/post/foo.md`, inside the contents directory or relative
+    /// to it. Its href, section and cascaded front matter are worked out from it.
+    #[structopt(long = "path", parse(from_os_str), requires = "stdin")]
+    pub stdin_path: Option<PathBuf>,
+    /// Keep running, re-processing changed files and rewriting the index whenever the contents directory changes.
+    #[structopt(long = "watch")]
+    pub watch: bool,
+    /// Upload the documents to a search engine, `meilisearch` or `typesense`, instead of writing an index.
+    #[structopt(
+        long = "upload",
+        requires = "endpoint",
+        conflicts_with_all = &["watch", "split-by-section", "split-by-lang"]
+    )]
+    pub upload: Option<UploadTarget>,
+    /// POSTs the index to this URL instead of writing it, e.g. to a serverless function that reindexes the site. An
+    /// `--output` starting `http://` or `https://` is posted to as well. Failed requests are retried like uploads, and
+    /// `--api-key` is sent as a bearer token.
+    #[structopt(
+        long = "post-url",
+        conflicts_with_all = &["watch", "upload", "split-by-section", "split-by-lang"]
+    )]
+    pub post_url: Option<String>,
+    /// A header to send with the index posted to `--post-url`, as `NAME: VALUE`, e.g. `"X-Api-Key: secret"`. Can be
+    /// repeated.
+    #[structopt(long = "header", number_of_values = 1)]
+    pub headers: Vec<Header>,
+    /// The URL of the search engine to upload to, e.g. `http://localhost:7700`.
+    #[structopt(long = "endpoint", requires = "upload")]
+    pub endpoint: Option<String>,
+    /// The API key to upload with.
+    #[structopt(long = "api-key")]
+    pub api_key: Option<String>,
+    /// The `MeiliSearch` index or Typesense collection to upload to. Defaults to `pages`.
+    #[structopt(long = "index-name")]
+    pub index_name: Option<String>,
+    /// The number of documents to upload in each request. Defaults to 1000.
+    #[structopt(long = "batch-size")]
+    pub batch_size: Option<usize>,
+    /// How many times to retry an upload that fails because of the network or a server error.
+    #[structopt(long = "retries", default_value = "3")]
+    pub retries: u32,
+    /// The path to a `hugotojson.toml` config file. Defaults to `hugotojson.toml`, or `.hugotojson.toml`, in the current
+    /// directory, if it exists.
+    #[structopt(long = "config", parse(from_os_str))]
+    pub config: Option<PathBuf>,
+    /// The profile in the config file whose options are used, e.g. `search`. Options given on the command line or by
+    /// environment variables take precedence.
+    #[structopt(long = "profile")]
+    pub profile: Option<String>,
+    /// The path to the Hugo site config, e.g. `config.toml`, or its config directory, e.g. `config`, whose `permalinks`,
+    /// `taxonomies` and `timeZone` are applied.
+    #[structopt(long = "site-config", parse(from_os_str))]
+    pub site_config: Option<PathBuf>,
+    /// Prefix hrefs with the site config's `baseURL`.
+    #[structopt(long = "absolute-hrefs", requires = "site-config")]
+    pub absolute_hrefs: bool,
+    /// Build hrefs like `/post/example.html` rather than `/post/example/`, as the site config's `uglyURLs` does.
+    #[structopt(long = "ugly-urls")]
+    pub ugly_urls: bool,
+    /// The Hugo site config. Read from the site config file by `load_config`.
+    #[structopt(skip)]
+    pub site: SiteConfig,
+    /// How front matter keys map to index fields. Read from the config file by `load_config`.
+    #[structopt(skip)]
+    pub fields: FieldMapping,
+    /// How much matches in each field of a Lunr index count. Read from the config file by `load_config`.
+    #[structopt(skip)]
+    pub boosts: Boosts,
+    /// The rules the `check` subcommand checks pages against. Read from the config file by `load_config`.
+    #[structopt(skip)]
+    pub check_rules: CheckRules,
+    /// The environment variables with the prefix that don't name an option, which `run` warns about once logging is
+    /// set up. Found by `from_env_and_args`.
+    #[structopt(skip)]
+    pub ignored_env_vars: Vec<String>,
+    /// A subcommand to run instead of producing an index.
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Default for Settings {
+    /// Returns the settings of a command line without any options, so the defaults match the CLI's, e.g. `./content/`
+    /// for `scan_path` and 3 `retries`.
+    fn default() -> Self {
+        Self::from_iter(["hugo_to_json"])
+    }
+}
+
+impl Settings {
+    /// Parses the command line along with the options set by environment variables, such as `HUGOTOJSON_FORMAT=csv`
+    /// for `--format csv` or `HUGOTOJSON_PRETTY=true` for `--pretty`, and by the profile chosen with `--profile`.
+    /// Options on the command line take precedence over the environment, which takes precedence over the profile. Like
+    /// `from_args`, prints the error and exits if they're invalid.
+    ///
+    /// # Errors
+    /// Errors if a profile was chosen but the config file can't be read or doesn't have it.
+    pub fn from_env_and_args() -> Result<Self, HugotoJsonError> {
+        let (args, ignored_env_vars) = args_with_env(env::vars_os(), env::args_os());
+        let mut settings = Self::from_iter(args_with_profile(args)?);
+        settings.ignored_env_vars = ignored_env_vars;
+        Ok(settings)
+    }
+
+    /// Returns every contents directory, starting with `scan_path`.
+    pub fn scan_paths(&self) -> Vec<&Path> {
+        let mut scan_paths = vec![self.scan_path.as_path()];
+        scan_paths.extend(self.more_scan_paths.iter().map(PathBuf::as_path));
+        scan_paths
+    }
+
+    /// Returns the number of files processed at once, or 0 for one per CPU.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::Settings;
+    ///
+    /// assert_eq!(Settings::default().threads(), 0);
+    /// assert_eq!(Settings { jobs: Some(2), ..Settings::default() }.threads(), 2);
+    /// assert_eq!(Settings { single_threaded: true, ..Settings::default() }.threads(), 1);
+    /// ```
+    #[must_use]
+    pub fn threads(&self) -> usize {
+        if self.single_threaded {
+            1
+        } else {
+            self.jobs.unwrap_or(0)
+        }
+    }
+
+    /// Returns the extractor of documents' text: the custom extractor if there is one, otherwise the built-in one the
+    /// settings choose.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::{ExtractorKind, Settings};
+    ///
+    /// let settings = Settings { content_extractor: ExtractorKind::Raw, ..Settings::default() };
+    /// assert_eq!(settings.extractor().extract("*Raw*"), "*Raw*");
+    /// ```
+    #[must_use]
+    pub fn extractor(&self) -> Arc<dyn ContentExtractor> {
+        if let Some(ref extractor) = self.custom_extractor {
+            return Arc::clone(extractor);
+        }
+        match self.content_extractor {
+            ExtractorKind::StripMarkdown => Arc::new(extract::StripMarkdown {
+                skip_code_blocks: self.skip_code_blocks,
+            }),
+            ExtractorKind::PulldownCmark => Arc::new(extract::PulldownCmark(ExtractOptions {
+                skip_code_blocks: self.skip_code_blocks,
+                keep_alt_text: self.keep_alt_text,
+            })),
+            ExtractorKind::Raw => Arc::new(extract::RawMarkdown {
+                skip_code_blocks: self.skip_code_blocks,
+            }),
+        }
+    }
+
+    /// Returns the site's static directory: the one in the settings, or the `static` directory beside the contents
+    /// directory.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::Settings;
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// let settings = Settings { scan_path: PathBuf::from("/blog/content"), ..Settings::default() };
+    /// assert_eq!(settings.static_dir(), Path::new("/blog/static"));
+    /// ```
+    #[must_use]
+    pub fn static_dir(&self) -> PathBuf {
+        self.static_dir.clone().unwrap_or_else(|| {
+            self.scan_path
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join("static")
+        })
+    }
+
+    /// Returns true if the settings need the links in each page's content, to write the link graph or check them.
+    #[must_use]
+    pub fn keeps_links(&self) -> bool {
+        self.links.is_some() || self.check_links
+    }
+
+    /// Returns the first contents directory `path` is in, if it's in any of them.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::Settings;
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// let settings = Settings {
+    ///     scan_path: PathBuf::from("/blog/content"),
+    ///     more_scan_paths: vec![PathBuf::from("/blog/themes/docs/content")],
+    ///     ..Settings::default()
+    /// };
+    /// let path = Path::new("/blog/themes/docs/content/guide.md");
+    /// assert_eq!(settings.scan_path_of(path), Some(Path::new("/blog/themes/docs/content")));
+    /// assert_eq!(settings.scan_path_of(Path::new("/blog/README.md")), None);
+    /// ```
+    #[must_use]
+    pub fn scan_path_of(&self, path: &Path) -> Option<&Path> {
+        self.scan_paths()
+            .into_iter()
+            .find(|scan_path| path.starts_with(scan_path))
+    }
+
+    /// Returns the module mount of the site config that mounted the contents directory `scan_path`, if the contents
+    /// directories come from mounts.
+    #[must_use]
+    pub fn content_mount(&self, scan_path: &Path) -> Option<&ContentMount> {
+        self.scan_paths()
+            .into_iter()
+            .position(|path| path == scan_path)
+            .and_then(|position| self.site.content_mounts.get(position))
+    }
+
+    /// Moves the taxonomy index, feed, sitemap and link graph given with `--out` to their own settings, and the first
+    /// index to `output` and `format` if there's no `output`, leaving the other indexes in `outputs`. An `output` of `-`
+    /// is cleared, so the index is written to stdout, and an `output` that's an HTTP URL is moved to `post_url`.
+    pub fn apply_outputs(&mut self) {
+        let mut indexes = Vec::new();
+        for output in self.outputs.drain(..) {
+            let path = Some(output.path.clone());
+            match output.kind {
+                OutputKind::Index(_) => indexes.push(output),
+                OutputKind::Taxonomies => self.taxonomy_index = path,
+                OutputKind::Rss => self.rss = path,
+                OutputKind::Sitemap => self.sitemap = path,
+                OutputKind::Links => self.links = path,
+            }
+        }
+        if self.output.is_none() && !indexes.is_empty() {
+            let first = indexes.remove(0);
+            if let OutputKind::Index(format) = first.kind {
+                self.format = format;
+            }
+            self.output = Some(first.path);
+        }
+        self.outputs = indexes;
+        if self.output.as_deref() == Some(Path::new(STDOUT_PATH)) {
+            self.output = None;
+        }
+        if let Some(url) = self.output.as_ref().and_then(|output| output.to_str()) {
+            if url.starts_with("http://") || url.starts_with("https://") {
+                self.post_url = Some(url.to_owned());
+                self.output = None;
+            }
+        }
+    }
+
+    /// Applies the options in the config file, if there is one, and reads the Hugo site config, if one was given.
+    /// Directories mounted into the content by the site config's module mounts replace the contents directories.
+    ///
+    /// # Errors
+    /// Errors if a config file was given but doesn't exist, or if the config file or site config is invalid.
+    pub fn load_config(&mut self) -> Result<(), HugotoJsonError> {
+        let path = match self.config {
+            Some(ref path) => path.clone(),
+            None => Config::default_path(),
+        };
+        if self.config.is_some() || path.exists() {
+            let config = Config::load(&path)?;
+            self.fields = config.fields;
+            self.boosts = config.lunr.boosts;
+            self.check_rules = config.check;
+        }
+
+        if let Some(ref path) = self.site_config {
+            self.site = SiteConfig::load(path)?;
+            // The timezone on the command line takes precedence
+            if self.timezone.is_none() {
+                self.timezone = self.site.time_zone;
+            }
+            let mut sources = self
+                .site
+                .content_mounts
+                .iter()
+                .map(|mount| mount.source.clone());
+            if let Some(scan_path) = sources.next() {
+                self.scan_path = scan_path;
+                self.more_scan_paths = sources.collect();
+                info!("Using the content mounted by {}", path.to_string_lossy());
+            }
+        }
+        // Either the command line or the site config can ask for ugly URLs
+        if self.ugly_urls {
+            self.site.ugly_urls = true;
+        }
+        Ok(())
+    }
+}
+
+/// Puts the options set by environment variables before the command line's arguments, leaving out those also given on
+/// the command line. The variable's name after the prefix is the option's long name, so `HUGOTOJSON_DATE_FORMAT` sets
+/// `--date-format`. A flag is set by `true` and left unset by `false`. Also returns the names of the variables that
+/// don't name an option, which are left out.
+fn args_with_env<V, A>(vars: V, args: A) -> (Vec<OsString>, Vec<String>)
+where
+    V: IntoIterator<Item = (OsString, OsString)>,
+    A: IntoIterator<Item = OsString>,
+{
+    let mut args = args.into_iter();
+    let program = args.next().unwrap_or_default();
+    let args: Vec<OsString> = args.collect();
+    let known = command_line_options();
+
+    let mut options = Vec::new();
+    let mut ignored = Vec::new();
+    for (name, value) in vars {
+        let (Ok(name), Ok(value)) = (name.into_string(), value.into_string()) else {
+            continue;
+        };
+        let Some(long) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let long = long.to_lowercase().replace('_', "-");
+        if !known.iter().any(|option| option.long == long) {
+            ignored.push(name);
+        } else if value != "false" && !is_given(&args, &long, &known) {
+            options.push((long, value));
+        }
+    }
+    options.sort();
+    ignored.sort();
+
+    let mut env_args = vec![program];
+    for (long, value) in options {
+        let value = Some(value).filter(|value| value != "true");
+        env_args.push(option_arg(&long, value.as_deref()));
+    }
+    env_args.extend(args);
+    (env_args, ignored)
+}
+
+/// Puts the options of the profile chosen by `--profile`, if there is one, before the arguments, leaving out those
+/// already given. The profile is read from the config file given by `--config`, or the one in the current directory.
+///
+/// # Errors
+/// Errors if the config file can't be read or doesn't have the profile.
+fn args_with_profile(args: Vec<OsString>) -> Result<Vec<OsString>, HugotoJsonError> {
+    let Some(name) = option_value(&args[1..], "profile") else {
+        return Ok(args);
+    };
+    let path = option_value(&args[1..], "config").map_or_else(Config::default_path, PathBuf::from);
+    let config = Config::load(&path)?;
+    let profile = config
+        .profiles
+        .get(&name)
+        .ok_or_else(|| HugotoJsonError::Config {
+            path: path.to_string_lossy().into_owned(),
+            reason: format!("There's no profile named `{name}`"),
+        })?;
+
+    let known = command_line_options();
+    let mut profile_args = vec![args[0].clone()];
+    for (long, value) in profile.options() {
+        if !is_given(&args[1..], &long, &known) {
+            profile_args.push(option_arg(&long, value.as_deref()));
+        }
+    }
+    profile_args.extend_from_slice(&args[1..]);
+    Ok(profile_args)
+}
+
+/// An option or flag of the command line, as `Settings::clap()` describes it.
+struct CommandLineOption {
+    long: &'static str,
+    short: Option<char>,
+    takes_value: bool,
+}
+
+/// Returns every option and flag of the command line that has a long name.
+fn command_line_options() -> Vec<CommandLineOption> {
+    let app = Settings::clap();
+    let flags = app.p.flags.iter().filter_map(|flag| {
+        Some(CommandLineOption {
+            long: flag.s.long?,
+            short: flag.s.short,
+            takes_value: false,
+        })
+    });
+    let options = app.p.opts.iter().filter_map(|option| {
+        Some(CommandLineOption {
+            long: option.s.long?,
+            short: option.s.short,
+            takes_value: true,
+        })
+    });
+    flags.chain(options).collect()
+}
+
+/// Returns true if the option with the long name `long`, or its short name, is among the arguments.
+fn is_given(args: &[OsString], long: &str, known: &[CommandLineOption]) -> bool {
+    let short = known
+        .iter()
+        .find(|option| option.long == long)
+        .and_then(|option| option.short);
+    args.iter()
+        .filter_map(|arg| arg.to_str())
+        .take_while(|&arg| arg != "--")
+        .any(|arg| {
+            let name = arg.split('=').next().unwrap_or_default();
+            name.strip_prefix("--") == Some(long)
+                || short.is_some_and(|short| is_short_given(arg, short, known))
+        })
+}
+
+/// Returns true if the argument gives the option with the short name `short`, either alone as in `-o`, after other
+/// short flags as in `-qo`, or with its value attached as in `-oindex.json`.
+fn is_short_given(arg: &str, short: char, known: &[CommandLineOption]) -> bool {
+    let Some(cluster) = arg
+        .strip_prefix('-')
+        .filter(|cluster| !cluster.starts_with('-'))
+    else {
+        return false;
+    };
+    for name in cluster.chars() {
+        if name == short {
+            return true;
+        }
+        // Whatever follows an option that takes a value is that value
+        let is_flag = known
+            .iter()
+            .any(|option| option.short == Some(name) && !option.takes_value);
+        if !is_flag {
+            return false;
+        }
+    }
+    false
+}
+
+/// Returns the value of the option with the long name `long` among the arguments, given either as `--long value` or
+/// `--long=value`.
+fn option_value(args: &[OsString], long: &str) -> Option<String> {
+    let flag = ["--", long].concat();
+    let prefix = [&flag, "="].concat();
+    let mut args = args
+        .iter()
+        .filter_map(|arg| arg.to_str())
+        .take_while(|&arg| arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next().map(String::from);
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_owned());
+        }
+    }
+    None
+}
+
+/// Returns the argument that sets an option, or a flag if it has no value.
+fn option_arg(long: &str, value: Option<&str>) -> OsString {
+    match value {
+        Some(value) => ["--", long, "=", value].concat().into(),
+        None => ["--", long].concat().into(),
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+/// Represents the subcommands that can be run instead of producing an index.
+pub enum Command {
+    /// Generates a synthetic Hugo content tree for reproducing bugs and benchmarking.
+    #[structopt(name = "gen-fixture")]
+    GenFixture(FixtureSettings),
+    /// Checks every page's front matter against rules instead of writing an index, reporting each rule broken with the
+    /// file and line, and fails if any are. The rules are those in the `[check]` table of the config file along with
+    /// these options.
+    #[structopt(name = "check")]
+    Check(CheckSettings),
+}
+
+#[derive(Debug, Clone, Default, StructOpt)]
+/// Represents the options of the `check` subcommand, which add to the rules in the config file.
+pub struct CheckSettings {
+    /// Front matter fields every page must have, e.g. `description,tags`.
+    #[structopt(long = "required", use_delimiter = true)]
+    pub required: Vec<String>,
+    /// The only tags pages may have, e.g. `rust,hugo`.
+    #[structopt(long = "allowed-tags", use_delimiter = true)]
+    pub allowed_tags: Vec<String>,
+    /// The fewest characters a description may have.
+    #[structopt(long = "min-description")]
+    pub min_description_length: Option<usize>,
+    /// The most characters a description may have.
+    #[structopt(long = "max-description")]
+    pub max_description_length: Option<usize>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+/// Represents the settings for generating a synthetic Hugo content tree.
+pub struct FixtureSettings {
+    /// The directory the content tree will be written to. It will be created if it doesn't exist.
+    #[structopt(parse(from_os_str))]
+    pub out_dir: PathBuf,
+    /// The number of documents to generate.
+    #[structopt(long = "pages", default_value = "100")]
+    pub pages: usize,
+    /// The front matter formats to cycle through, comma separated. One or more of `toml`, `yaml` and `json`.
+    #[structopt(
+        long = "formats",
+        use_delimiter = true,
+        default_value = "toml,yaml,json"
+    )]
+    pub formats: Vec<FrontMatterFormat>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The formats an index can be written in.
+pub enum OutputFormat {
+    /// An array of `PageIndex` records.
+    #[default]
+    Json,
+    /// Newline delimited JSON, a `PageIndex` record on each line.
+    Ndjson,
+    /// An array of `AlgoliaRecord`s, splitting pages that are too large into several records.
+    Algolia,
+    /// A prebuilt Lunr index of the titles, tags and contents, with the `PageIndex` records it was built from.
+    Lunr,
+    /// A table of comma separated values, with a header row of the columns and a row for each page.
+    Csv,
+    /// A table of tab separated values, with a header row of the columns and a row for each page.
+    Tsv,
+    /// A `SQLite` database with tables of the pages, their tags and categories, and a full text index of their content.
+    Sqlite,
+    /// The input config of a [Stork](https://stork-search.net/) index, with the contents of each page inline.
+    Stork,
+    /// A [GeoJSON](https://geojson.org/) `FeatureCollection` of the pages that have a location, as points.
+    GeoJson,
+    /// The [OpenSearch suggestions](https://github.com/dewitt/opensearch) for each prefix of the titles, a JSON object
+    /// of `[query, [titles], [descriptions], [urls]]` arrays keyed by the lowercased prefix.
+    OpenSearchSuggest,
+    /// A [tantivy](https://github.com/quickwit-oss/tantivy) index of the title, href, date, tags and content, in a
+    /// directory of its own.
+    #[cfg(feature = "tantivy")]
+    Tantivy,
+}
+
+impl OutputFormat {
+    /// Returns the delimiter of the fields of a table format, or `None` if the format isn't a table.
+    #[must_use]
+    pub fn delimiter(self) -> Option<u8> {
+        match self {
+            OutputFormat::Csv => Some(b','),
+            OutputFormat::Tsv => Some(b'\t'),
+            OutputFormat::Json
+            | OutputFormat::Ndjson
+            | OutputFormat::Algolia
+            | OutputFormat::Lunr
+            | OutputFormat::Sqlite
+            | OutputFormat::Stork
+            | OutputFormat::GeoJson
+            | OutputFormat::OpenSearchSuggest => None,
+            #[cfg(feature = "tantivy")]
+            OutputFormat::Tantivy => None,
+        }
+    }
+
+    /// Returns the media type of an index in the format, sent as its `Content-Type` when it's posted.
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Json | OutputFormat::Algolia | OutputFormat::Lunr => "application/json",
+            OutputFormat::Ndjson => "application/x-ndjson",
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Tsv => "text/tab-separated-values",
+            OutputFormat::Stork => "application/toml",
+            OutputFormat::GeoJson => "application/geo+json",
+            OutputFormat::OpenSearchSuggest => "application/json",
+            OutputFormat::Sqlite => "application/vnd.sqlite3",
+            #[cfg(feature = "tantivy")]
+            OutputFormat::Tantivy => "application/octet-stream",
+        }
+    }
+
+    /// Returns the extension of a file holding an index in the format, or `None` for a format written to a directory.
+    #[must_use]
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Json
+            | OutputFormat::Algolia
+            | OutputFormat::Lunr
+            | OutputFormat::OpenSearchSuggest => Some("json"),
+            OutputFormat::Ndjson => Some("ndjson"),
+            OutputFormat::Csv => Some("csv"),
+            OutputFormat::Tsv => Some("tsv"),
+            OutputFormat::Stork => Some("toml"),
+            OutputFormat::GeoJson => Some("geojson"),
+            OutputFormat::Sqlite => Some("db"),
+            #[cfg(feature = "tantivy")]
+            OutputFormat::Tantivy => None,
+        }
+    }
+
+    /// Returns true for the formats written to a database at a path of their own rather than to a stream.
+    #[must_use]
+    pub fn is_database(self) -> bool {
+        match self {
+            OutputFormat::Sqlite => true,
+            #[cfg(feature = "tantivy")]
+            OutputFormat::Tantivy => true,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "algolia" => Ok(OutputFormat::Algolia),
+            "lunr" => Ok(OutputFormat::Lunr),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            "stork" => Ok(OutputFormat::Stork),
+            "geojson" => Ok(OutputFormat::GeoJson),
+            "opensearch-suggest" => Ok(OutputFormat::OpenSearchSuggest),
+            #[cfg(feature = "tantivy")]
+            "tantivy" => Ok(OutputFormat::Tantivy),
+            #[cfg(not(feature = "tantivy"))]
+            "tantivy" => Err(String::from(
+                "The tantivy format needs hugo_to_json to be built with the `tantivy` feature",
+            )),
+            other => Err(format!("Unknown output format `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An output written with `--out`, parsed from `PATH:KIND`.
+pub struct Output {
+    /// Where the output is written.
+    pub path: PathBuf,
+    /// What's written.
+    pub kind: OutputKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What an output written with `--out` is: an index, or one of the files written alongside it.
+pub enum OutputKind {
+    /// An index in the given format.
+    Index(OutputFormat),
+    /// A taxonomy index, like `--taxonomies`.
+    Taxonomies,
+    /// A feed, like `--rss`.
+    Rss,
+    /// A sitemap, like `--sitemap`.
+    Sitemap,
+    /// The link graph, like `--links`.
+    Links,
+}
+
+impl FromStr for OutputKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "taxonomies" => Ok(OutputKind::Taxonomies),
+            "rss" => Ok(OutputKind::Rss),
+            "sitemap" => Ok(OutputKind::Sitemap),
+            "links" => Ok(OutputKind::Links),
+            other => other
+                .parse()
+                .map(OutputKind::Index)
+                .map_err(|_| format!("Unknown output kind `{other}`")),
+        }
+    }
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    /// Parses `PATH:KIND`, or a path to an index whose format is told by its extension.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::{Output, OutputFormat, OutputKind};
+    /// use std::path::PathBuf;
+    ///
+    /// let output: Output = "static/feed.xml:rss".parse().unwrap();
+    /// assert_eq!(output, Output { path: PathBuf::from("static/feed.xml"), kind: OutputKind::Rss });
+    /// let output: Output = "static/index.csv".parse().unwrap();
+    /// assert_eq!(output.kind, OutputKind::Index(OutputFormat::Csv));
+    /// assert!("static/feed.xml".parse::<Output>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((path, kind)) = s.rsplit_once(':') {
+            if let Ok(kind) = kind.parse() {
+                return Ok(Self {
+                    path: PathBuf::from(path),
+                    kind,
+                });
+            }
+        }
+        let path = PathBuf::from(s);
+        let kind = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => OutputKind::Index(OutputFormat::Json),
+            Some("ndjson") => OutputKind::Index(OutputFormat::Ndjson),
+            Some("geojson") => OutputKind::Index(OutputFormat::GeoJson),
+            Some("csv") => OutputKind::Index(OutputFormat::Csv),
+            Some("tsv") => OutputKind::Index(OutputFormat::Tsv),
+            Some("db" | "sqlite") => OutputKind::Index(OutputFormat::Sqlite),
+            _ => {
+                return Err(format!(
+                    "Can't tell what to write to `{s}`, give its kind, e.g. `{s}:json`"
+                ))
+            }
+        };
+        Ok(Self { path, kind })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The fields the pages of an index can be sorted by.
+pub enum SortKey {
+    /// The date the page was published.
+    Date,
+    /// The title of the page, ignoring case.
+    Title,
+    /// The href of the page.
+    Href,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "date" => Ok(SortKey::Date),
+            "title" => Ok(SortKey::Title),
+            "href" => Ok(SortKey::Href),
+            other => Err(format!("Unknown sort field `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The orders pages can be sorted in.
+pub enum SortOrder {
+    /// Oldest, or alphabetically first, first.
+    #[default]
+    Asc,
+    /// Newest, or alphabetically last, first.
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            other => Err(format!("Unknown sort order `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The formats log messages can be written in.
+pub enum LogFormat {
+    /// Plain text, one message per line.
+    #[default]
+    Text,
+    /// A JSON object per line, for log aggregators.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("Unknown log format `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// What each record of the index is for.
+pub enum Granularity {
+    /// A record for each page.
+    #[default]
+    Page,
+    /// A record for each section of a page under an H1 to H3 heading, and for its content before the first heading.
+    Heading,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "page" => Ok(Granularity::Page),
+            "heading" => Ok(Granularity::Heading),
+            other => Err(format!("Unknown granularity `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How the format of documents' front matter is decided.
+pub enum FrontMatterMode {
+    /// Tells the format from the fence each document's front matter opens with.
+    #[default]
+    Auto,
+    /// Parses every document's front matter as this format.
+    Force(FrontMatterFormat),
+}
+
+impl FromStr for FrontMatterMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "auto" => Ok(FrontMatterMode::Auto),
+            format => format.parse().map(FrontMatterMode::Force),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How much of a document's content is kept.
+pub enum ContentLength {
+    /// At most this many characters.
+    Chars(usize),
+    /// At most this many words.
+    Words(usize),
+}
+
+impl FromStr for ContentLength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let number_length = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(number_length);
+        let length = number
+            .parse()
+            .map_err(|_| format!("Invalid content length `{s}`"))?;
+        match unit.trim() {
+            "" | "c" | "chars" => Ok(ContentLength::Chars(length)),
+            "w" | "words" => Ok(ContentLength::Words(length)),
+            other => Err(format!("Unknown content length unit `{other}`")),
+        }
+    }
+}
+
+/// Parses a size in bytes, with an optional decimal unit: `B`, `KB`, `MB` or `GB`.
+fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let s = s.trim().to_uppercase();
+    let number_length = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(number_length);
+    let size: u64 = number.parse().map_err(|_| format!("Invalid size `{s}`"))?;
+    let multiplier = match unit.trim() {
+        "" | "B" => 1,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        other => return Err(format!("Unknown size unit `{other}`")),
+    };
+    size.checked_mul(multiplier)
+        .ok_or_else(|| format!("Size `{s}` is too large"))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The built-in ways the text of a document's content can be extracted from its markdown.
+pub enum ExtractorKind {
+    /// The [strip_markdown](https://docs.rs/strip_markdown) crate.
+    #[default]
+    StripMarkdown,
+    /// `extract::extract_text`, built on [pulldown-cmark](https://docs.rs/pulldown-cmark) events.
+    PulldownCmark,
+    /// The markdown itself.
+    Raw,
+}
+
+impl FromStr for ExtractorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "strip-markdown" => Ok(ExtractorKind::StripMarkdown),
+            "pulldown-cmark" => Ok(ExtractorKind::PulldownCmark),
+            "raw" => Ok(ExtractorKind::Raw),
+            other => Err(format!("Unknown content extractor `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The compressions an index can be written with.
+pub enum Compression {
+    /// [gzip](https://www.gzip.org/), supported by every static host that serves precompressed files.
+    Gzip,
+    /// [Brotli](https://github.com/google/brotli), which makes smaller files.
+    Brotli,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "brotli" | "br" => Ok(Compression::Brotli),
+            other => Err(format!("Unknown compression `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A header sent with an HTTP request, parsed from `NAME: VALUE`.
+pub struct Header {
+    /// The name of the header, e.g. `Authorization`.
+    pub name: String,
+    /// The value of the header.
+    pub value: String,
+}
+
+impl FromStr for Header {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        match (parts.next().map(str::trim), parts.next().map(str::trim)) {
+            (Some(name), Some(value)) if !name.is_empty() => Ok(Header {
+                name: name.to_owned(),
+                value: value.to_owned(),
+            }),
+            _ => Err(format!("Expected a header as `NAME: VALUE`, not `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The key an index is signed with, parsed from `ALGORITHM:PATH`.
+pub enum Signer {
+    /// An [Ed25519](https://ed25519.cr.yp.to/) key, read from a PKCS#8 PEM file.
+    Ed25519(PathBuf),
+}
+
+impl FromStr for Signer {
+    type Err = String;
+
+    /// Parses `ALGORITHM:PATH`, where the only algorithm is `ed25519`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::Signer;
+    /// use std::path::PathBuf;
+    ///
+    /// let signer: Signer = "ed25519:keys/signing.pem".parse().unwrap();
+    /// assert_eq!(signer, Signer::Ed25519(PathBuf::from("keys/signing.pem")));
+    /// assert!("rsa:keys/signing.pem".parse::<Signer>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((algorithm, path))
+                if algorithm.eq_ignore_ascii_case("ed25519") && !path.is_empty() =>
+            {
+                Ok(Signer::Ed25519(PathBuf::from(path)))
+            }
+            _ => Err(format!("Expected a key as `ed25519:PATH`, not `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Which files failing to process make a run fail.
+pub enum FailOn {
+    /// Files whose front matter can't be parsed.
+    Parse,
+    /// Files that can't be read.
+    Io,
+    /// Any file that fails to process, or any broken link. This is the default.
+    #[default]
+    Any,
+    /// None; failures are reported but the run succeeds.
+    None,
+}
+
+impl FailOn {
+    /// Returns true if a failure of `kind`, such as `parse` or `io`, makes the run fail.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::settings::FailOn;
+    ///
+    /// assert!(FailOn::Parse.fails_on("parse"));
+    /// assert!(!FailOn::Parse.fails_on("io"));
+    /// assert!(!FailOn::None.fails_on("parse"));
+    /// ```
+    #[must_use]
+    pub fn fails_on(self, kind: &str) -> bool {
+        match self {
+            FailOn::Parse => kind == "parse",
+            FailOn::Io => kind == "io",
+            FailOn::Any => true,
+            FailOn::None => false,
+        }
+    }
+}
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "parse" => Ok(FailOn::Parse),
+            "io" => Ok(FailOn::Io),
+            "any" => Ok(FailOn::Any),
+            "none" => Ok(FailOn::None),
+            other => Err(format!("Unknown failure kind `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The search engines documents can be uploaded to.
+pub enum UploadTarget {
+    /// [MeiliSearch](https://www.meilisearch.com/).
+    MeiliSearch,
+    /// [Typesense](https://typesense.org/).
+    Typesense,
+}
+
+impl FromStr for UploadTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "meilisearch" => Ok(UploadTarget::MeiliSearch),
+            "typesense" => Ok(UploadTarget::Typesense),
+            other => Err(format!("Unknown search engine `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The search libraries a preset can set an index up for.
+pub enum Preset {
+    /// [Fuse.js](https://fusejs.io/), which searches the array of pages with the options in `fuse-options.json`.
+    FuseJs,
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "fusejs" | "fuse" => Ok(Preset::FuseJs),
+            other => Err(format!("Unknown preset `{other}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The front matter formats supported by Hugo.
+pub enum FrontMatterFormat {
+    /// Front matter fenced by `+++`.
+    Toml,
+    /// Front matter fenced by `---`.
+    Yaml,
+    /// Front matter as a JSON object at the start of the document.
+    Json,
+}
+
+impl FromStr for FrontMatterFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "toml" => Ok(FrontMatterFormat::Toml),
+            "yaml" | "yml" => Ok(FrontMatterFormat::Yaml),
+            "json" => Ok(FrontMatterFormat::Json),
+            other => Err(format!("Unknown front matter format `{other}`")),
+        }
+    }
+}
+
+impl fmt::Display for FrontMatterFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format = match *self {
+            FrontMatterFormat::Toml => "toml",
+            FrontMatterFormat::Yaml => "yaml",
+            FrontMatterFormat::Json => "json",
+        };
+        write!(f, "{format}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os_strings(strings: &[&str]) -> Vec<OsString> {
+        strings.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn default_matches_the_command_line_defaults() {
+        let settings = Settings::default();
+        assert_eq!(settings.scan_path, PathBuf::from("./content/"));
+        assert_eq!(settings.retries, 3);
+        assert_eq!(settings.format, OutputFormat::Json);
+        assert_eq!(settings.order, SortOrder::Asc);
+        assert_eq!(settings.log_format, LogFormat::Text);
+        assert_eq!(settings.granularity, Granularity::Page);
+        assert_eq!(settings.front_matter, FrontMatterMode::Auto);
+        assert_eq!(settings.fail_on, FailOn::Any);
+        assert_eq!(settings.content_extractor, ExtractorKind::StripMarkdown);
+        assert!(settings.command.is_none());
+    }
+
+    #[test]
+    fn environment_variables_are_options_the_command_line_takes_precedence_over() {
+        let vars = vec![
+            ("HUGOTOJSON_FORMAT", "csv"),
+            ("HUGOTOJSON_DATE_FORMAT", "%d/%m/%Y"),
+            ("HUGOTOJSON_PRETTY", "true"),
+            ("HUGOTOJSON_VALIDATE", "false"),
+            ("HUGOTOJSON_OUTPUT", "env.json"),
+            ("HUGOTOJSON_LIMIT", "10"),
+            ("HUGOTOJSON_NO_SUCH_OPTION", "true"),
+            ("HUGO_TO_JSON_LOG", "debug"),
+            ("PATH", "/bin"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (OsString::from(name), OsString::from(value)));
+        let args = os_strings(&["hugo_to_json", "content", "-o", "index.json", "--limit=5"]);
+        let (env_args, ignored) = args_with_env(vars, args);
+        assert_eq!(ignored, ["HUGOTOJSON_NO_SUCH_OPTION"]);
+        assert_eq!(
+            env_args,
+            os_strings(&[
+                "hugo_to_json",
+                "--date-format=%d/%m/%Y",
+                "--format=csv",
+                "--pretty",
+                "content",
+                "-o",
+                "index.json",
+                "--limit=5",
+            ])
+        );
+    }
+
+    #[test]
+    fn outputs_are_moved_to_their_settings() {
+        let mut settings = Settings {
+            outputs: vec![
+                "index.json".parse().unwrap(),
+                "sitemap.xml:sitemap".parse().unwrap(),
+                "search.json:lunr".parse().unwrap(),
+            ],
+            ..Settings::default()
+        };
+        settings.apply_outputs();
+        assert_eq!(settings.output, Some(PathBuf::from("index.json")));
+        assert_eq!(settings.format, OutputFormat::Json);
+        assert_eq!(settings.sitemap, Some(PathBuf::from("sitemap.xml")));
+        assert_eq!(
+            settings.outputs,
+            vec![Output {
+                path: PathBuf::from("search.json"),
+                kind: OutputKind::Index(OutputFormat::Lunr),
+            }]
+        );
+    }
+
+    #[test]
+    fn urls_are_posted_to_and_dashes_are_stdout() {
+        let mut settings = Settings {
+            output: Some(PathBuf::from("https://example.com/reindex")),
+            ..Settings::default()
+        };
+        settings.apply_outputs();
+        assert_eq!(settings.output, None);
+        assert_eq!(
+            settings.post_url.as_deref(),
+            Some("https://example.com/reindex")
+        );
+
+        let mut settings = Settings {
+            output: Some(PathBuf::from("-")),
+            ..Settings::default()
+        };
+        settings.apply_outputs();
+        assert_eq!(settings.output, None);
+        assert_eq!(
+            "Authorization: Bearer key".parse::<Header>(),
+            Ok(Header {
+                name: String::from("Authorization"),
+                value: String::from("Bearer key"),
+            })
+        );
+        assert!("Authorization".parse::<Header>().is_err());
+    }
+
+    #[test]
+    fn option_values_are_found_in_either_form() {
+        let args = os_strings(&[
+            "--profile",
+            "search",
+            "--config=site.toml",
+            "--",
+            "--pretty",
+        ]);
+        assert_eq!(option_value(&args, "profile").as_deref(), Some("search"));
+        assert_eq!(option_value(&args, "config").as_deref(), Some("site.toml"));
+        assert!(!is_given(&args, "pretty", &command_line_options()));
+    }
+
+    #[test]
+    fn short_options_are_given_alone_clustered_or_with_their_value_attached() {
+        let known = command_line_options();
+        for arg in ["-o", "-oindex.json", "-o=index.json"] {
+            assert!(is_given(&os_strings(&[arg]), "output", &known), "{}", arg);
+        }
+        for arg in ["-", "--", "--other", "-x"] {
+            assert!(!is_given(&os_strings(&[arg]), "output", &known), "{}", arg);
+        }
+
+        let known = [
+            CommandLineOption {
+                long: "quiet",
+                short: Some('q'),
+                takes_value: false,
+            },
+            CommandLineOption {
+                long: "format",
+                short: Some('f'),
+                takes_value: true,
+            },
+        ];
+        assert!(is_given(&os_strings(&["-qf", "csv"]), "format", &known));
+        assert!(is_given(&os_strings(&["-fq"]), "format", &known));
+        // `q` is the value of `-f` here, not the flag
+        assert!(!is_given(&os_strings(&["-fq"]), "quiet", &known));
+    }
 }
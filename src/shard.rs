@@ -0,0 +1,224 @@
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::compress;
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+use crate::sort;
+use crate::traverse::date_normalizer;
+
+/// The name of the manifest, which is written beside the shards.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Lists the shards an index was split into, so a client can fetch only the ones it needs.
+pub struct Manifest {
+    /// The number of pages across all the shards.
+    pub pages: usize,
+    /// The shards, in the order their pages were written.
+    pub shards: Vec<Shard>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A file holding some of the pages of an index.
+pub struct Shard {
+    /// The name of the file, e.g. `index-000.json` or `index-000.json.gz` if it's compressed, in the manifest's
+    /// directory.
+    pub file: String,
+    /// The number of pages in the shard.
+    pub pages: usize,
+    /// The date of the shard's oldest page, as written in its front matter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest: Option<String>,
+    /// The date of the shard's newest page, as written in its front matter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest: Option<String>,
+}
+
+impl Shard {
+    /// Creates the listing of a shard of `pages` written to `file`. Pages whose date can't be read aren't in its date
+    /// range.
+    fn new(settings: &Settings, file: String, pages: &[&PageIndex]) -> Self {
+        let normalizer = date_normalizer(settings);
+        let mut dated: Vec<_> = pages
+            .iter()
+            .filter_map(|page| {
+                normalizer
+                    .to_instant(&page.date)
+                    .map(|instant| (instant, page))
+            })
+            .collect();
+        dated.sort_by_key(|&(instant, _)| instant);
+        Self {
+            file,
+            pages: pages.len(),
+            oldest: dated.first().map(|(_, page)| page.date.clone()),
+            newest: dated.last().map(|(_, page)| page.date.clone()),
+        }
+    }
+}
+
+/// Returns the path of the shard at `position`, e.g. `index-001.json` for the second shard of `index.json`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::shard::shard_path;
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(shard_path(Path::new("static/index.json"), 1), PathBuf::from("static/index-001.json"));
+/// assert_eq!(shard_path(Path::new("static/index"), 12), PathBuf::from("static/index-012"));
+/// ```
+pub fn shard_path(output: &Path, position: usize) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let name = match output.extension() {
+        Some(extension) => format!("{}-{:03}.{}", stem, position, extension.to_string_lossy()),
+        None => format!("{stem}-{position:03}"),
+    };
+    output.with_file_name(name)
+}
+
+/// Writes the pages, sorted and limited by the settings, to shards of `shard_size` pages each beside the settings'
+/// output, named after it, along with a manifest listing them. Returns the total size of the shards in bytes.
+///
+/// # Errors
+/// Errors if there's no output to write the shards beside, the format is a database, or a shard or the manifest can't
+/// be written.
+pub(crate) fn write_shards<'a, I>(
+    settings: &Settings,
+    shard_size: usize,
+    pages: I,
+) -> Result<u64, HugotoJsonError>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let output = match settings.output {
+        Some(ref output) if !settings.format.is_database() => output,
+        _ => {
+            return Err(HugotoJsonError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--shard-size needs an index written to a path given with --output, which isn't a database",
+            )))
+        }
+    };
+    let pages = sort::sort_and_limit(settings, pages);
+    let mut manifest = Manifest {
+        pages: pages.len(),
+        shards: Vec::new(),
+    };
+    let mut size = 0;
+    for (position, shard) in pages.chunks(shard_size.max(1)).enumerate() {
+        let path = shard_path(output, position);
+        let shard_settings = Settings {
+            output: Some(path.clone()),
+            shard_size: None,
+            // The pages have already been sorted and limited as a whole
+            sort: None,
+            limit: None,
+            ..settings.clone()
+        };
+        size += crate::write_index(&shard_settings, shard.iter().copied())?;
+        let file = compress::compressed_path(&path, settings.compress)
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        manifest.shards.push(Shard::new(settings, file, shard));
+    }
+
+    let path = output.with_file_name(MANIFEST_FILE_NAME);
+    create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+    fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+    debug!(
+        "Wrote {} shards listed in {}",
+        manifest.shards.len(),
+        path.to_string_lossy()
+    );
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+    use crate::settings::{Compression, SortKey};
+
+    #[test]
+    fn shards_are_listed_with_their_date_ranges() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            output: Some(directory.path().join("index.json")),
+            sort: Some(SortKey::Date),
+            ..Settings::default()
+        };
+        let pages = vec![
+            TestPage::new("c").date("2019-03-01").build(),
+            TestPage::new("a").date("2019-01-25T10:00:00+01:00").build(),
+            TestPage::new("b").date("2019-02-14").build(),
+        ];
+        write_shards(&settings, 2, &pages).unwrap();
+
+        let manifest: Manifest = serde_json::from_str(
+            &fs::read_to_string(directory.path().join(MANIFEST_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest.pages, 3);
+        assert_eq!(
+            manifest.shards,
+            vec![
+                Shard {
+                    file: String::from("index-000.json"),
+                    pages: 2,
+                    oldest: Some(String::from("2019-01-25T10:00:00+01:00")),
+                    newest: Some(String::from("2019-02-14")),
+                },
+                Shard {
+                    file: String::from("index-001.json"),
+                    pages: 1,
+                    oldest: Some(String::from("2019-03-01")),
+                    newest: Some(String::from("2019-03-01")),
+                },
+            ]
+        );
+        let second: Vec<PageIndex> = serde_json::from_str(
+            &fs::read_to_string(directory.path().join("index-001.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(second, vec![pages[0].clone()]);
+    }
+
+    #[test]
+    fn compressed_shards_are_listed_by_the_files_written() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            output: Some(directory.path().join("index.json")),
+            compress: Some(Compression::Gzip),
+            ..Settings::default()
+        };
+        let pages = vec![
+            TestPage::new("a").build(),
+            TestPage::new("b").date("2019-02-14").build(),
+        ];
+        write_shards(&settings, 1, &pages).unwrap();
+
+        let manifest: Manifest = serde_json::from_str(
+            &fs::read_to_string(directory.path().join(MANIFEST_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        let files: Vec<_> = manifest
+            .shards
+            .iter()
+            .map(|shard| &shard.file[..])
+            .collect();
+        assert_eq!(files, ["index-000.json.gz", "index-001.json.gz"]);
+        for file in files {
+            let shard: Vec<PageIndex> = serde_json::from_str(
+                &compress::read_to_string(&directory.path().join(file), settings.compress).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(shard.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::{Signer as _, SigningKey};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::settings::Signer;
+
+/// The extension added to the name of an index for its signature file, e.g. `index.json.sig`.
+pub const EXTENSION: &str = "sig";
+
+/// Returns the path of the signature file of the index at `path`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::sign::signature_path;
+/// use std::path::{Path, PathBuf};
+///
+/// assert_eq!(signature_path(Path::new("public/index.json")), PathBuf::from("public/index.json.sig"));
+/// ```
+#[must_use]
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// Signs the file at `path` with the signer's key. Returns the signature and the public key it can be checked with,
+/// both base64 encoded. The signature is over the bytes of the file as they are, compressed or not.
+///
+/// # Errors
+/// Errors if the key can't be read or isn't an Ed25519 key in a PKCS#8 PEM file, or the file can't be read.
+pub fn sign(signer: &Signer, path: &Path) -> Result<(String, String), HugotoJsonError> {
+    let Signer::Ed25519(key_file) = signer;
+    let key = read_key(key_file)?;
+    let signature = key.sign(&fs::read(path)?);
+    Ok((
+        BASE64.encode(signature.to_bytes()),
+        BASE64.encode(key.verifying_key().to_bytes()),
+    ))
+}
+
+fn read_key(key_file: &Path) -> Result<SigningKey, HugotoJsonError> {
+    let pem = fs::read_to_string(key_file)?;
+    SigningKey::from_pkcs8_pem(&pem).map_err(|err| {
+        HugotoJsonError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} isn't an Ed25519 private key in a PKCS#8 PEM file: {}",
+                key_file.to_string_lossy(),
+                err
+            ),
+        ))
+    })
+}
+
+/// Writes the signature of an index beside it, base64 encoded on a line of its own. An unchanged signature is left
+/// untouched, like the index.
+///
+/// # Errors
+/// Errors if the signature can't be written.
+pub(crate) fn write_signature(
+    destination: &Path,
+    signature: &str,
+    public_key: &str,
+) -> Result<(), HugotoJsonError> {
+    let line = format!("{signature}\n");
+    let path = signature_path(destination);
+    if fs::read_to_string(&path).ok().as_ref() != Some(&line) {
+        fs::write(&path, line)?;
+    }
+    info!(
+        "Signed {} with the key whose public key is {}",
+        destination.to_string_lossy(),
+        public_key
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+    use std::convert::TryInto;
+
+    #[test]
+    fn signatures_are_checked_with_the_public_key() {
+        let directory = tempfile::tempdir().unwrap();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let key_file = directory.path().join("signing.pem");
+        fs::write(&key_file, key.to_pkcs8_pem(LineEnding::LF).unwrap()).unwrap();
+        let index = directory.path().join("index.json");
+        fs::write(&index, "[]").unwrap();
+
+        let (signature, public_key) = sign(&Signer::Ed25519(key_file), &index).unwrap();
+        let public_key: [u8; 32] = BASE64.decode(public_key).unwrap().try_into().unwrap();
+        let signature: [u8; 64] = BASE64.decode(signature).unwrap().try_into().unwrap();
+        let verifying_key = VerifyingKey::from_bytes(&public_key).unwrap();
+        assert_eq!(verifying_key, key.verifying_key());
+        assert!(verifying_key
+            .verify(b"[]", &Signature::from_bytes(&signature))
+            .is_ok());
+        assert!(verifying_key
+            .verify(b"[{}]", &Signature::from_bytes(&signature))
+            .is_err());
+
+        let not_a_key = directory.path().join("not-a-key.pem");
+        fs::write(&not_a_key, "Not a key").unwrap();
+        assert!(sign(&Signer::Ed25519(not_a_key), &index).is_err());
+    }
+}
@@ -0,0 +1,629 @@
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
+use serde_json::Value as JsonValue;
+use yaml_rust::YamlLoader;
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::constants;
+use crate::file_location::FileLocation;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::{PageIndex, PageKind};
+use crate::related::RelatedConfig;
+use crate::sitemap::SitemapConfig;
+use crate::slug::{slugify, SlugOptions};
+use crate::traverse::{toml_to_json, yaml_to_json};
+
+/// The component of a mount's target that mounts it into the content, rather than assets, static files or layouts.
+const CONTENT_MOUNT: &str = "content";
+
+/// The extensions of the files a config directory is read from.
+const CONFIG_EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// The names of the files in a config directory that are the root of the config, rather than a section of it.
+const ROOT_CONFIG_NAMES: [&str; 2] = ["hugo", "config"];
+
+/// The name of Hugo's config directory, which is in the project directory.
+const CONFIG_DIR: &str = "config";
+
+/// The config directory's environment that's read, with the settings common to every environment.
+const DEFAULT_ENVIRONMENT: &str = "_default";
+
+/// The taxonomies that already have their own fields in a `PageIndex`.
+const BUILT_IN_TAXONOMIES: [&str; 3] = [constants::TAGS, constants::CATEGORIES, constants::SERIES];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// The parts of a [Hugo site config](https://gohugo.io/getting-started/configuration/) that affect the index.
+pub struct SiteConfig {
+    /// The site's `baseURL`, used to make absolute hrefs.
+    pub base_url: Option<String>,
+    /// The site's `title`, which titles its feed.
+    pub title: Option<String>,
+    /// The site's `permalinks`, URL patterns such as `/:year/:month/:slug/` keyed by section.
+    pub permalinks: BTreeMap<String, String>,
+    /// The front matter keys of the site's custom `taxonomies`, such as `authors`.
+    pub taxonomies: Vec<String>,
+    /// The site's `timeZone`, which dates without an offset are taken to be in.
+    pub time_zone: Option<Tz>,
+    /// The site's `related` content config, which weights the terms pages are related by.
+    pub related: RelatedConfig,
+    /// The site's `defaultContentLanguage`, or its `languageCode` if it hasn't one, which content is normalised for.
+    pub default_language: Option<String>,
+    /// The site's `sitemap` section, with the change frequency and priority of pages that don't set their own.
+    pub sitemap: SitemapConfig,
+    /// The site's `rssLimit`, the number of pages in its feed, if it's positive.
+    pub rss_limit: Option<usize>,
+    /// The site's `summaryLength`, the number of words in the summaries of documents without a `<!--more-->` divider.
+    pub summary_length: Option<usize>,
+    /// The directories mounted into the site's content by the `mounts` of its `module` section.
+    pub content_mounts: Vec<ContentMount>,
+    /// How paths are made into slugs, from the site's `removePathAccents` and `disablePathToLower`.
+    pub slugs: SlugOptions,
+    /// The site's `uglyURLs`, which serves pages as `/post/example.html` rather than `/post/example/`.
+    pub ugly_urls: bool,
+    /// The site's `canonifyURLs`, which makes URLs absolute with its `baseURL`.
+    pub canonify_urls: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A directory mounted into a site's content by a [Hugo module mount](https://gohugo.io/hugo-modules/configuration/#module-configuration-mounts).
+pub struct ContentMount {
+    /// The mounted directory. Relative sources are resolved against the directory of the site config, or the directory
+    /// its config directory is in.
+    pub source: PathBuf,
+    /// Where the directory is mounted in the content, relative to it, e.g. `docs` for a target of `content/docs`.
+    /// Empty for a directory mounted at the top of the content.
+    pub target: String,
+    /// The language of the mounted content, if the mount gives one.
+    pub lang: Option<String>,
+}
+
+impl ContentMount {
+    /// Reads a mount from the site config, returning `None` if it doesn't mount a directory into the content. Relative
+    /// sources are resolved against `project_dir`.
+    fn from_json(mount: &JsonValue, project_dir: &Path) -> Option<Self> {
+        let source = mount.get("source").and_then(JsonValue::as_str)?;
+        let mut target = Path::new(mount.get("target").and_then(JsonValue::as_str)?).components();
+        if target.next()?.as_os_str() != CONTENT_MOUNT {
+            return None;
+        }
+        Some(Self {
+            source: project_dir.join(source),
+            target: target
+                .filter_map(|component| component.as_os_str().to_str())
+                .collect::<Vec<_>>()
+                .join(constants::FORWARD_SLASH),
+            lang: mount
+                .get("lang")
+                .and_then(JsonValue::as_str)
+                .filter(|lang| !lang.is_empty())
+                .map(String::from),
+        })
+    }
+}
+
+impl SiteConfig {
+    /// Reads a Hugo site config file written in TOML, YAML or JSON, chosen by its extension, or a
+    /// [config directory](https://gohugo.io/getting-started/configuration/#configuration-directory) of them.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use hugo_to_json::site_config::SiteConfig;
+    /// use std::path::Path;
+    /// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+    ///
+    /// let site_config = SiteConfig::load(Path::new("config.toml"))?;
+    /// println!("{:?}", site_config.base_url);
+    /// # Ok::<(), HugotoJsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Errors if the file can't be read, can't be parsed or has an unknown `timeZone`.
+    pub fn load(path: &Path) -> Result<Self, HugotoJsonError> {
+        if path.is_dir() {
+            return Self::load_dir(path);
+        }
+        let config = read_config_file(path)?;
+        let project_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        Self::from_json(&config, project_dir).map_err(|reason| config_error(path, reason))
+    }
+
+    /// Reads a config directory, such as `config` or `config/_default`, whose `_default` directory is read if it has
+    /// one. Its `hugo` or `config` file is the root of the config, and each other file is a section of it named after
+    /// the file, e.g. `taxonomies.toml` for the `taxonomies`. Relative paths are resolved against the directory the
+    /// `config` directory is in.
+    ///
+    /// # Errors
+    /// Errors if the directory or a file in it can't be read, a file can't be parsed, or it has an unknown `timeZone`.
+    fn load_dir(path: &Path) -> Result<Self, HugotoJsonError> {
+        let dir = match path.join(DEFAULT_ENVIRONMENT) {
+            default if default.is_dir() => default,
+            _ => path.to_path_buf(),
+        };
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let file = entry?.path();
+            let is_config = file.extension().is_some_and(|extension| {
+                CONFIG_EXTENSIONS.contains(&extension.to_string_lossy().to_lowercase().as_str())
+            });
+            if file.is_file() && is_config {
+                files.push(file);
+            }
+        }
+        files.sort();
+
+        let stem = |file: &Path| {
+            file.file_stem()
+                .map(|stem| stem.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        };
+        // The root files are read first, so the sections in their own files take precedence
+        let (roots, sections): (Vec<PathBuf>, Vec<PathBuf>) = files
+            .into_iter()
+            .partition(|file| ROOT_CONFIG_NAMES.contains(&stem(file).as_str()));
+        let mut config = serde_json::Map::new();
+        for file in &roots {
+            if let JsonValue::Object(root) = read_config_file(file)? {
+                config.extend(root);
+            }
+        }
+        for file in &sections {
+            // Hugo matches keys case insensitively, so a section replaces the root's whatever its case
+            let name = stem(file);
+            config.retain(|key, _| !key.eq_ignore_ascii_case(&name));
+            config.insert(name, read_config_file(file)?);
+        }
+
+        let project_dir = dir
+            .ancestors()
+            .find(|ancestor| ancestor.file_name().is_some_and(|name| name == CONFIG_DIR))
+            .and_then(Path::parent)
+            .or_else(|| dir.parent())
+            .unwrap_or_else(|| Path::new(""));
+        Self::from_json(&JsonValue::Object(config), project_dir)
+            .map_err(|reason| config_error(path, reason))
+    }
+
+    /// Reads the site config from its JSON representation. Keys are matched case insensitively, like Hugo does.
+    /// Relative paths in the config are resolved against `project_dir`.
+    fn from_json(config: &JsonValue, project_dir: &Path) -> Result<Self, String> {
+        let get = |key: &str| {
+            config.as_object().and_then(|object| {
+                object
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .map(|(_, value)| value)
+            })
+        };
+
+        let mut permalinks = BTreeMap::new();
+        if let Some(JsonValue::Object(patterns)) = get("permalinks") {
+            for (section, pattern) in patterns {
+                match pattern {
+                    JsonValue::String(pattern) => {
+                        permalinks.insert(section.to_owned(), pattern.to_owned());
+                    }
+                    // Newer versions of Hugo group the patterns by page kind, only regular pages are indexed
+                    JsonValue::Object(patterns) if section == "page" => {
+                        for (section, pattern) in patterns {
+                            if let Some(pattern) = pattern.as_str() {
+                                permalinks.insert(section.to_owned(), pattern.to_owned());
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let taxonomies = match get("taxonomies") {
+            Some(JsonValue::Object(taxonomies)) => taxonomies
+                .values()
+                .filter_map(JsonValue::as_str)
+                .filter(|taxonomy| !BUILT_IN_TAXONOMIES.contains(taxonomy))
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let time_zone = match get("timeZone").and_then(JsonValue::as_str) {
+            Some(time_zone) => Some(
+                time_zone
+                    .parse::<Tz>()
+                    .map_err(|err| format!("Unknown timeZone `{time_zone}`: {err}"))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            base_url: get("baseURL")
+                .and_then(JsonValue::as_str)
+                .filter(|base_url| !base_url.is_empty())
+                .map(String::from),
+            title: get("title")
+                .and_then(JsonValue::as_str)
+                .filter(|title| !title.is_empty())
+                .map(String::from),
+            permalinks,
+            taxonomies,
+            time_zone,
+            related: get("related")
+                .map(RelatedConfig::from_json)
+                .unwrap_or_default(),
+            default_language: get("defaultContentLanguage")
+                .or_else(|| get("languageCode"))
+                .and_then(JsonValue::as_str)
+                .filter(|language| !language.is_empty())
+                .map(String::from),
+            sitemap: get("sitemap")
+                .map(SitemapConfig::from_json)
+                .unwrap_or_default(),
+            rss_limit: get("rssLimit")
+                .and_then(JsonValue::as_u64)
+                .filter(|&limit| limit > 0)
+                .and_then(|limit| usize::try_from(limit).ok()),
+            summary_length: get("summaryLength")
+                .and_then(JsonValue::as_u64)
+                .and_then(|length| usize::try_from(length).ok()),
+            content_mounts: get("module")
+                .and_then(|module| module.get("mounts"))
+                .and_then(JsonValue::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|mount| ContentMount::from_json(mount, project_dir))
+                .collect(),
+            slugs: SlugOptions {
+                remove_accents: get("removePathAccents")
+                    .and_then(JsonValue::as_bool)
+                    .unwrap_or_default(),
+                keep_case: get("disablePathToLower")
+                    .and_then(JsonValue::as_bool)
+                    .unwrap_or_default(),
+            },
+            ugly_urls: get("uglyURLs")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or_default(),
+            canonify_urls: get("canonifyURLs")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Builds a page's href from the permalink pattern of its section, if there is one. Pages with a `url` in their
+    /// front matter, and section pages, keep their href. Returns `None` if the page's href isn't changed.
+    #[must_use]
+    pub fn permalink(
+        &self,
+        page_index: &PageIndex,
+        file_location: &FileLocation,
+        date: Option<NaiveDate>,
+    ) -> Option<String> {
+        if page_index.url.is_some() || page_index.kind == PageKind::Section {
+            return None;
+        }
+
+        let directory = file_location.relative_directory_to_content.as_str();
+        // A leaf bundle's directory is its name rather than one of its sections
+        let (sections, file_name) = if file_location.is_bundle() {
+            match directory.rfind(constants::FORWARD_SLASH) {
+                Some(position) => (&directory[..position], &directory[position + 1..]),
+                None => (constants::EMPTY_STRING, directory),
+            }
+        } else {
+            (directory, file_location.file_stem.as_str())
+        };
+        let section = sections
+            .split(constants::FORWARD_SLASH)
+            .next()
+            .unwrap_or_default();
+        let pattern = self.permalinks.get(section)?;
+
+        let title = slugify(&page_index.title, self.slugs);
+        let slug = page_index
+            .slug
+            .as_ref()
+            .map(|slug| slugify(slug, self.slugs));
+        let mut href = String::new();
+        let mut rest = pattern.as_str();
+        while let Some(position) = rest.find(':') {
+            href.push_str(&rest[..position]);
+            rest = &rest[position + 1..];
+            let token_length = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let token = &rest[..token_length];
+            let value = match token {
+                "year" => date?.year().to_string(),
+                "month" => format!("{:02}", date?.month()),
+                "monthname" => date?.format("%B").to_string().to_lowercase(),
+                "day" => format!("{:02}", date?.day()),
+                "weekday" => date?.weekday().num_days_from_sunday().to_string(),
+                "weekdayname" => date?.format("%A").to_string().to_lowercase(),
+                "yearday" => date?.ordinal().to_string(),
+                "section" => slugify(section, self.slugs),
+                "sections" => slugify(sections, self.slugs),
+                "title" => title.clone(),
+                "slug" => slug.clone().unwrap_or_else(|| title.clone()),
+                "filename" => slugify(file_name, self.slugs),
+                "slugorfilename" => slug
+                    .clone()
+                    .unwrap_or_else(|| slugify(file_name, self.slugs)),
+                // Unknown tokens are kept as they are
+                _ => [":", token].concat(),
+            };
+            href.push_str(&value);
+            rest = &rest[token_length..];
+        }
+        href.push_str(rest);
+
+        if !href.starts_with(constants::FORWARD_SLASH) {
+            href.insert_str(0, constants::FORWARD_SLASH);
+        }
+        if !file_location.lang.is_empty() && !file_location.default_lang {
+            href.insert_str(0, &[constants::FORWARD_SLASH, &file_location.lang].concat());
+        }
+        Some(href)
+    }
+
+    /// Makes a page's href ugly, e.g. `/post/example.html` rather than `/post/example/`, if the site has `uglyURLs`.
+    /// Pages with a `url` in their front matter, and section pages, keep their href. Returns `None` if the page's href
+    /// isn't changed.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::site_config::SiteConfig;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/example.md"), Path::new("/blog/content"))?;
+    /// let page = PageIndex::new(Some("Example"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+    /// let site_config = SiteConfig { ugly_urls: true, ..SiteConfig::default() };
+    /// assert_eq!(site_config.ugly_href(&page).as_deref(), Some("/post/example.html"));
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    #[must_use]
+    pub fn ugly_href(&self, page_index: &PageIndex) -> Option<String> {
+        if !self.ugly_urls || page_index.url.is_some() || page_index.kind == PageKind::Section {
+            return None;
+        }
+        let path = page_index.href.strip_suffix(constants::FORWARD_SLASH)?;
+        if path.is_empty() {
+            return None;
+        }
+        Some([path, ".html"].concat())
+    }
+
+    /// Prefixes a site relative href with the base URL. Other hrefs, and every href if there's no base URL, are
+    /// returned as they are.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::site_config::SiteConfig;
+    ///
+    /// let site_config = SiteConfig { base_url: Some(String::from("https://example.com/")), ..SiteConfig::default() };
+    /// assert_eq!(site_config.absolute_href("/post/example/"), "https://example.com/post/example/");
+    /// ```
+    #[must_use]
+    pub fn absolute_href(&self, href: &str) -> String {
+        match self.base_url {
+            Some(ref base_url) if href.starts_with(constants::FORWARD_SLASH) => {
+                [base_url.trim_end_matches('/'), href].concat()
+            }
+            _ => href.to_owned(),
+        }
+    }
+}
+
+fn config_error(path: &Path, reason: String) -> HugotoJsonError {
+    HugotoJsonError::Config {
+        path: path.to_string_lossy().into_owned(),
+        reason,
+    }
+}
+
+/// Reads a config file written in TOML, YAML or JSON, chosen by its extension, as JSON.
+fn read_config_file(path: &Path) -> Result<JsonValue, HugotoJsonError> {
+    let contents = fs::read_to_string(path)?;
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    Ok(match extension.as_deref() {
+        Some("yaml" | "yml") => YamlLoader::load_from_str(&contents)
+            .map_err(|err| config_error(path, err.to_string()))?
+            .first()
+            .map_or(JsonValue::Null, yaml_to_json),
+        Some("json") => {
+            serde_json::from_str(&contents).map_err(|err| config_error(path, err.to_string()))?
+        }
+        _ => toml_to_json(
+            &contents
+                .parse::<toml::Value>()
+                .map_err(|err| config_error(path, err.to_string()))?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn page(path: &str, title: &str, slug: Option<&str>) -> (PageIndex, FileLocation) {
+        let location =
+            FileLocation::from_path(Path::new(path), Path::new("/blog/content")).unwrap();
+        let mut page_index = PageIndex::new(
+            Some(title),
+            slug,
+            Some("2019-01-25"),
+            None,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            String::new(),
+            &location,
+            None,
+        )
+        .unwrap();
+        page_index.slug = slug.map(String::from);
+        (page_index, location)
+    }
+
+    #[test]
+    fn loads_toml_and_yaml_site_configs() {
+        let mut toml = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        write!(
+            toml,
+            "baseurl = \"https://example.com/\"\ntitle = \"Example\"\nrssLimit = 5\nsummaryLength = 30\ntimeZone = \"Europe/London\"\n[permalinks]\npost = \"/:year/:slug/\"\n[taxonomies]\ntag = \"tags\"\nauthor = \"authors\"\n"
+        )
+        .unwrap();
+        let site_config = SiteConfig::load(toml.path()).unwrap();
+        assert_eq!(
+            site_config.base_url.as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(site_config.permalinks["post"], "/:year/:slug/");
+        assert_eq!(site_config.taxonomies, vec!["authors"]);
+        assert_eq!(site_config.time_zone, Some(chrono_tz::Europe::London));
+        assert_eq!(site_config.default_language, None);
+        assert_eq!(site_config.title.as_deref(), Some("Example"));
+        assert_eq!(site_config.rss_limit, Some(5));
+        assert_eq!(site_config.summary_length, Some(30));
+
+        let mut yaml = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        write!(
+            yaml,
+            "baseURL: https://example.org/\nlanguageCode: fr-FR\npermalinks:\n  page:\n    talks: /:section/:title/\n"
+        )
+        .unwrap();
+        let site_config = SiteConfig::load(yaml.path()).unwrap();
+        assert_eq!(
+            site_config.base_url.as_deref(),
+            Some("https://example.org/")
+        );
+        assert_eq!(site_config.permalinks["talks"], "/:section/:title/");
+        assert_eq!(site_config.default_language.as_deref(), Some("fr-FR"));
+    }
+
+    #[test]
+    fn loads_config_directories() {
+        let project = tempfile::tempdir().unwrap();
+        let default = project.path().join("config").join("_default");
+        fs::create_dir_all(&default).unwrap();
+        fs::write(
+            default.join("hugo.toml"),
+            "baseURL = \"https://example.com/\"\n[Taxonomies]\ntag = \"tags\"\n[[module.mounts]]\nsource = \"docs\"\ntarget = \"content\"\n",
+        )
+        .unwrap();
+        fs::write(
+            default.join("taxonomies.yaml"),
+            "tag: tags\nmood: moods\nauthor: authors\n",
+        )
+        .unwrap();
+        fs::write(default.join("README.md"), "Not config").unwrap();
+
+        let site_config = SiteConfig::load(&project.path().join("config")).unwrap();
+        assert_eq!(
+            site_config.base_url.as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(site_config.taxonomies, vec!["authors", "moods"]);
+        assert_eq!(
+            site_config.content_mounts[0].source,
+            project.path().join("docs")
+        );
+        assert_eq!(SiteConfig::load(&default).unwrap(), site_config);
+    }
+
+    #[test]
+    fn reads_content_mounts() {
+        let config = serde_json::json!({
+            "module": { "mounts": [
+                { "source": "content", "target": "content" },
+                { "source": "/docs/en", "target": "content/docs", "lang": "en" },
+                { "source": "static", "target": "static" },
+            ] },
+        });
+        let site_config = SiteConfig::from_json(&config, Path::new("/blog")).unwrap();
+        assert_eq!(
+            site_config.content_mounts,
+            [
+                ContentMount {
+                    source: PathBuf::from("/blog/content"),
+                    target: String::new(),
+                    lang: None,
+                },
+                ContentMount {
+                    source: PathBuf::from("/docs/en"),
+                    target: String::from("docs"),
+                    lang: Some(String::from("en")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn permalinks_expand_tokens() {
+        let mut site_config = SiteConfig::default();
+        site_config.permalinks.insert(
+            String::from("post"),
+            String::from("/:year/:month/:day/:slug/"),
+        );
+        site_config.permalinks.insert(
+            String::from("talks"),
+            String::from(":sections/:title/:filename"),
+        );
+        let date = NaiveDate::from_ymd_opt(2019, 1, 25);
+
+        let (page_index, location) = page(
+            "/blog/content/post/example.md",
+            "An Example",
+            Some("my-slug"),
+        );
+        assert_eq!(
+            site_config
+                .permalink(&page_index, &location, date)
+                .as_deref(),
+            Some("/2019/01/25/my-slug/")
+        );
+        let (page_index, location) = page("/blog/content/post/example.md", "An Example!", None);
+        assert_eq!(
+            site_config
+                .permalink(&page_index, &location, date)
+                .as_deref(),
+            Some("/2019/01/25/an-example/")
+        );
+        let (page_index, location) = page("/blog/content/talks/2019/rust/index.md", "Rust", None);
+        assert_eq!(
+            site_config
+                .permalink(&page_index, &location, date)
+                .as_deref(),
+            Some("/talks/2019/rust/rust")
+        );
+        let (page_index, location) = page("/blog/content/about.md", "About", None);
+        assert_eq!(site_config.permalink(&page_index, &location, date), None);
+    }
+
+    #[test]
+    fn ugly_hrefs_are_only_for_regular_pages() {
+        let site_config = SiteConfig {
+            ugly_urls: true,
+            ..SiteConfig::default()
+        };
+        let (page_index, _) = page("/blog/content/post/rust/index.md", "Rust", None);
+        assert_eq!(
+            site_config.ugly_href(&page_index).as_deref(),
+            Some("/post/rust.html")
+        );
+        let (page_index, _) = page("/blog/content/post/_index.md", "Posts", None);
+        assert_eq!(site_config.ugly_href(&page_index), None);
+        let (mut page_index, _) = page("/blog/content/post/example.md", "Example", None);
+        page_index.url = Some(String::from("/example/"));
+        assert_eq!(site_config.ugly_href(&page_index), None);
+    }
+}
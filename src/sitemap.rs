@@ -0,0 +1,223 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Map, Value as JsonValue};
+
+use std::fs::{self, create_dir_all};
+use std::path::Path;
+
+use crate::constants;
+use crate::date::DateNormalizer;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+use crate::site_config::SiteConfig;
+use crate::xml::element;
+
+/// The most URLs the [sitemap protocol](https://www.sitemaps.org/protocol.html) allows in one sitemap.
+const MAX_URLS: usize = 50_000;
+
+/// The change frequencies the sitemap protocol allows.
+const CHANGE_FREQUENCIES: [&str; 7] = [
+    "always", "hourly", "daily", "weekly", "monthly", "yearly", "never",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+/// How often a page changes and how important it is relative to the site's other pages, for search engines reading
+/// the sitemap. Like Hugo, pages set these in a `sitemap` section of their front matter and the site config sets the
+/// defaults in its own `sitemap` section.
+pub struct SitemapConfig {
+    /// `always`, `hourly`, `daily`, `weekly`, `monthly`, `yearly` or `never`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changefreq: Option<String>,
+    /// From 0 to 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+}
+
+impl SitemapConfig {
+    /// Reads a `sitemap` section. Change frequencies the sitemap protocol doesn't allow, and priorities outside 0 to 1,
+    /// such as Hugo's default of -1, are ignored.
+    pub(crate) fn from_json(sitemap: &JsonValue) -> Self {
+        Self::read(|key| sitemap.get(key))
+    }
+
+    /// Reads the `sitemap` section of a page's front matter, falling back to a top level `changefreq` and `priority`.
+    pub(crate) fn from_front_matter(front_matter: &Map<String, JsonValue>) -> Self {
+        front_matter
+            .get(constants::SITEMAP)
+            .map(Self::from_json)
+            .unwrap_or_default()
+            .or(&Self::read(|key| front_matter.get(key)))
+    }
+
+    fn read<'a, F>(get: F) -> Self
+    where
+        F: Fn(&str) -> Option<&'a JsonValue>,
+    {
+        Self {
+            changefreq: get(constants::CHANGEFREQ)
+                .and_then(JsonValue::as_str)
+                .map(str::to_lowercase)
+                .filter(|changefreq| CHANGE_FREQUENCIES.contains(&changefreq.as_str())),
+            priority: get(constants::PRIORITY)
+                .and_then(JsonValue::as_f64)
+                .filter(|priority| (0.0..=1.0).contains(priority)),
+        }
+    }
+
+    /// Fills in whatever isn't set from `defaults`.
+    #[must_use]
+    pub fn or(self, defaults: &Self) -> Self {
+        Self {
+            changefreq: self.changefreq.or_else(|| defaults.changefreq.clone()),
+            priority: self.priority.or(defaults.priority),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A page as it appears in a sitemap.
+pub struct SitemapUrl {
+    /// The absolute URL of the page, if the site config has a `baseURL`, otherwise its href.
+    pub loc: String,
+    /// When the page was last modified, or its date if it doesn't say.
+    pub lastmod: Option<DateTime<Utc>>,
+    /// How often the page changes.
+    pub changefreq: Option<String>,
+    /// How important the page is.
+    pub priority: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+/// A [sitemap](https://www.sitemaps.org/protocol.html) of every page of a site, in the order they were walked.
+pub struct Sitemap {
+    site: SiteConfig,
+    normalizer: DateNormalizer,
+    urls: Vec<SitemapUrl>,
+}
+
+impl Sitemap {
+    /// Creates an empty sitemap. Locations are made absolute with the site config's `baseURL`, pages without their own
+    /// change frequency and priority take the site config's, and page dates are read with `normalizer`.
+    #[must_use]
+    pub fn new(site: &SiteConfig, normalizer: DateNormalizer) -> Self {
+        Self {
+            site: site.clone(),
+            normalizer,
+            urls: Vec::new(),
+        }
+    }
+
+    /// Adds a page to the sitemap.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::date::DateNormalizer;
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::site_config::SiteConfig;
+    /// use hugo_to_json::sitemap::Sitemap;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+    /// let mut page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)?;
+    /// page.lastmod = String::from("2019-02-14T10:00:00Z");
+    /// page.sitemap.changefreq = Some(String::from("weekly"));
+    ///
+    /// let site = SiteConfig { base_url: Some(String::from("https://example.com/")), ..SiteConfig::default() };
+    /// let mut sitemap = Sitemap::new(&site, DateNormalizer::new(Vec::new(), None));
+    /// sitemap.add(&page);
+    ///
+    /// let url = &sitemap.urls()[0];
+    /// assert_eq!(url.loc, "https://example.com/post/rust/");
+    /// assert_eq!(url.lastmod.unwrap().to_rfc3339(), "2019-02-14T10:00:00+00:00");
+    /// assert_eq!(url.changefreq.as_deref(), Some("weekly"));
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    pub fn add(&mut self, page: &PageIndex) {
+        let lastmod = if page.lastmod.is_empty() {
+            &page.date
+        } else {
+            &page.lastmod
+        };
+        let config = page.sitemap.clone().or(&self.site.sitemap);
+        self.urls.push(SitemapUrl {
+            loc: self.site.absolute_href(&page.href),
+            lastmod: self.normalizer.to_instant(lastmod),
+            changefreq: config.changefreq,
+            priority: config.priority,
+        });
+    }
+
+    /// Returns the pages in the sitemap.
+    #[must_use]
+    pub fn urls(&self) -> &[SitemapUrl] {
+        &self.urls
+    }
+
+    /// Renders the sitemap as XML.
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+        for url in &self.urls {
+            xml.push_str("  <url>\n");
+            element(&mut xml, 4, "loc", &url.loc);
+            if let Some(lastmod) = url.lastmod {
+                let lastmod = lastmod.to_rfc3339_opts(SecondsFormat::Secs, true);
+                element(&mut xml, 4, "lastmod", &lastmod);
+            }
+            if let Some(ref changefreq) = url.changefreq {
+                element(&mut xml, 4, "changefreq", changefreq);
+            }
+            if let Some(priority) = url.priority {
+                element(&mut xml, 4, "priority", &priority.to_string());
+            }
+            xml.push_str("  </url>\n");
+        }
+        xml.push_str("</urlset>\n");
+        xml
+    }
+
+    /// Writes the sitemap to `path`. Warns if search engines won't accept it because its locations aren't absolute or
+    /// it has too many of them.
+    ///
+    /// # Errors
+    /// Errors if the sitemap can't be written.
+    pub fn write(&self, path: &Path) -> Result<(), HugotoJsonError> {
+        if self.site.base_url.is_none() {
+            warn!("The sitemap's locations aren't absolute as the site config has no baseURL");
+        }
+        if self.urls.len() > MAX_URLS {
+            warn!(
+                "The sitemap has {} URLs, more than the {} search engines accept",
+                self.urls.len(),
+                MAX_URLS
+            );
+        }
+        create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+        fs::write(path, self.to_xml())?;
+        debug!("Wrote sitemap to {}", path.to_string_lossy());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_the_sitemap_section_before_top_level_keys() {
+        let front_matter = json!({
+            "sitemap": { "changefreq": "Daily", "priority": -1 },
+            "changefreq": "weekly",
+            "priority": 0.8,
+        });
+        let config = SitemapConfig::from_front_matter(front_matter.as_object().unwrap());
+        assert_eq!(config.changefreq.as_deref(), Some("daily"));
+        assert_eq!(config.priority, Some(0.8));
+
+        let config = SitemapConfig::from_json(&json!({ "changefreq": "fortnightly" }));
+        assert_eq!(config, SitemapConfig::default());
+    }
+}
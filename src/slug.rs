@@ -0,0 +1,97 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How paths are made into slugs, from the site config. The defaults match Hugo's.
+pub struct SlugOptions {
+    /// Removes accents from letters, e.g. `über` becomes `uber`, from the site's `removePathAccents`.
+    pub remove_accents: bool,
+    /// Keeps the case of letters rather than lowercasing them, from the site's `disablePathToLower`.
+    pub keep_case: bool,
+}
+
+/// Makes a path, or part of one, into a slug the way Hugo does. Letters, digits and `.`, `/`, `\`, `_`, `#`, `+`, `~`,
+/// `-` and `@` are kept, along with escapes such as `%20`, spaces between them become hyphens and everything else is
+/// removed. Unless the options say otherwise, letters are lowercased and accents are kept.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::slug::{slugify, SlugOptions};
+///
+/// assert_eq!(slugify("Über uns", SlugOptions::default()), "über-uns");
+/// assert_eq!(slugify(" What's new? (2019) ", SlugOptions::default()), "whats-new-2019");
+/// let options = SlugOptions { remove_accents: true, keep_case: true };
+/// assert_eq!(slugify("Über uns/Café", options), "Uber-uns/Cafe");
+/// ```
+#[must_use]
+pub fn slugify(text: &str, options: SlugOptions) -> String {
+    let source: Vec<char> = if options.remove_accents {
+        remove_accents(text).collect()
+    } else {
+        text.chars().collect()
+    };
+    let mut slug = String::with_capacity(text.len());
+    let mut hyphen = false;
+    for (position, &c) in source.iter().enumerate() {
+        let is_escape = c == '%'
+            && source
+                .get(position + 1..position + 3)
+                .is_some_and(|digits| digits.iter().all(char::is_ascii_hexdigit));
+        if c.is_alphanumeric() || is_combining_mark(c) || "./\\_#+~-@".contains(c) || is_escape {
+            if hyphen {
+                slug.push('-');
+                hyphen = false;
+            }
+            slug.push(c);
+        } else if c.is_whitespace() && !slug.is_empty() {
+            hyphen = true;
+        }
+    }
+    if options.keep_case {
+        slug
+    } else {
+        slug.to_lowercase()
+    }
+}
+
+/// Returns `text` without its accents. Letters are decomposed into a base letter and the combining marks that accent
+/// it, and recomposed without the marks.
+fn remove_accents(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.nfd().filter(|&c| !is_combining_mark(c)).nfc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_match_hugo() {
+        let options = SlugOptions::default();
+        assert_eq!(slugify("Hello, World!", options), "hello-world");
+        assert_eq!(slugify("C++ & C#", options), "c++-c#");
+        assert_eq!(slugify("100%  sure", options), "100-sure");
+        assert_eq!(slugify("50%25 off", options), "50%25-off");
+        assert_eq!(slugify("post/My Dir", options), "post/my-dir");
+        // Combining accents are kept, unless accents are removed
+        assert_eq!(slugify("cafe\u{301}", options), "cafe\u{301}");
+        let options = SlugOptions {
+            remove_accents: true,
+            keep_case: false,
+        };
+        assert_eq!(slugify("cafe\u{301} Ñandú", options), "cafe-nandu");
+    }
+
+    #[test]
+    fn marks_of_other_scripts_are_kept() {
+        let options = SlugOptions::default();
+        assert_eq!(slugify("नमस्ते दुनिया", options), "नमस्ते-दुनिया");
+        assert_eq!(slugify("สวัสดี ชาวโลก", options), "สวัสดี-ชาวโลก");
+        assert_eq!(slugify("مَرْحَبًا بالعالم", options), "مَرْحَبًا-بالعالم");
+        let options = SlugOptions {
+            remove_accents: true,
+            keep_case: false,
+        };
+        assert_eq!(slugify("مَرْحَبًا", options), "مرحبا");
+        assert_eq!(slugify("Ærøskøbing Łódź", options), "ærøskøbing-łodz");
+    }
+}
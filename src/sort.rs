@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+
+use std::cmp::Ordering;
+
+use crate::date::DateNormalizer;
+use crate::page_index::PageIndex;
+use crate::settings::{Settings, SortKey, SortOrder};
+use crate::traverse::date_normalizer;
+
+/// Sorts pages by the key and in the order in the settings, then keeps only as many as the settings' limit. Pages are
+/// left in the order they're given if the settings don't sort them.
+///
+/// Pages are sorted by when they were published, so dates written differently are compared correctly, and pages whose
+/// date can't be read come last whichever the order. Titles are compared ignoring case. Pages that are otherwise equal
+/// are sorted by href.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::settings::{Settings, SortKey, SortOrder};
+/// use hugo_to_json::sort::sort_and_limit;
+/// use std::path::Path;
+/// # use hugo_to_json::operation_result::OperationResult;
+///
+/// let page = |name: &str, date: &str| {
+///     let location = FileLocation::from_path(&Path::new("/blog/content").join(name), Path::new("/blog/content"))?;
+///     PageIndex::new(Some(name), None, Some(date), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::new(), &location, None)
+/// };
+/// let pages = vec![page("a.md", "2019-01-25")?, page("b.md", "2019-03-01T10:00:00+01:00")?, page("c.md", "2019-02-14")?];
+///
+/// let settings = Settings { sort: Some(SortKey::Date), order: SortOrder::Desc, limit: Some(2), ..Settings::default() };
+/// let hrefs: Vec<&str> = sort_and_limit(&settings, &pages).iter().map(|page| page.href.as_str()).collect();
+/// assert_eq!(hrefs, ["/b/", "/c/"]);
+/// # Ok::<(), OperationResult>(())
+/// ```
+pub fn sort_and_limit<'a, I>(settings: &Settings, pages: I) -> Vec<&'a PageIndex>
+where
+    I: IntoIterator<Item = &'a PageIndex>,
+{
+    let mut pages: Vec<&PageIndex> = pages.into_iter().collect();
+    if let Some(key) = settings.sort {
+        let normalizer = date_normalizer(settings);
+        let mut keyed: Vec<(SortValue, &PageIndex)> = pages
+            .into_iter()
+            .map(|page| (SortValue::of(page, key, &normalizer), page))
+            .collect();
+        keyed.sort_by(|(a_value, a), (b_value, b)| {
+            a_value
+                .compare(b_value, settings.order)
+                .then_with(|| a.href.cmp(&b.href))
+        });
+        pages = keyed.into_iter().map(|(_, page)| page).collect();
+    }
+    if let Some(limit) = settings.limit {
+        pages.truncate(limit);
+    }
+    pages
+}
+
+/// The value of the field a page is sorted by.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Date(DateTime<Utc>),
+    Text(String),
+    /// A date that can't be read, which sorts after everything else.
+    Missing,
+}
+
+impl SortValue {
+    fn of(page: &PageIndex, key: SortKey, normalizer: &DateNormalizer) -> Self {
+        match key {
+            SortKey::Date => normalizer
+                .to_instant(&page.date)
+                .map_or(SortValue::Missing, SortValue::Date),
+            SortKey::Title => SortValue::Text(page.title.to_lowercase()),
+            SortKey::Href => SortValue::Text(page.href.clone()),
+        }
+    }
+
+    fn compare(&self, other: &Self, order: SortOrder) -> Ordering {
+        match (self, other, order) {
+            (SortValue::Missing, _, _) | (_, SortValue::Missing, _) | (_, _, SortOrder::Asc) => {
+                self.cmp(other)
+            }
+            (_, _, SortOrder::Desc) => other.cmp(self),
+        }
+    }
+}
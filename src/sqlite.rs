@@ -0,0 +1,317 @@
+use rusqlite::{params, Connection};
+
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::Path;
+
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::index_writer::IndexWriter;
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+
+/// The tables of the database. Tags and categories are normalized into tables of their own, joined to the pages they're
+/// on.
+const TABLES: &str = "
+CREATE TABLE pages (
+    id INTEGER PRIMARY KEY,
+    href TEXT NOT NULL,
+    title TEXT NOT NULL,
+    date TEXT NOT NULL,
+    description TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    content TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    lang TEXT NOT NULL,
+    word_count INTEGER NOT NULL,
+    reading_time INTEGER NOT NULL,
+    record TEXT NOT NULL
+);
+CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+CREATE TABLE page_tags (
+    page_id INTEGER NOT NULL REFERENCES pages (id),
+    tag_id INTEGER NOT NULL REFERENCES tags (id),
+    PRIMARY KEY (page_id, tag_id)
+);
+CREATE TABLE categories (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+CREATE TABLE page_categories (
+    page_id INTEGER NOT NULL REFERENCES pages (id),
+    category_id INTEGER NOT NULL REFERENCES categories (id),
+    PRIMARY KEY (page_id, category_id)
+);
+";
+
+/// A full text index of the pages' title, description and content, filled in as each page is written.
+const FTS_TABLE: &str = "
+CREATE VIRTUAL TABLE pages_fts USING fts5 (
+    title, description, content, content = 'pages', content_rowid = 'id'
+);
+";
+
+/// The same full text index, in the form sqlite-utils' `enable-fts --create-triggers` writes, which is how Datasette
+/// finds the index of a table. The triggers fill it in and keep it up to date as pages are inserted, updated or deleted.
+const DATASETTE_FTS_TABLE: &str = "
+CREATE VIRTUAL TABLE [pages_fts] USING FTS5 ([title], [description], [content], content=[pages]);
+CREATE TRIGGER [pages_ai] AFTER INSERT ON [pages] BEGIN
+    INSERT INTO [pages_fts] (rowid, [title], [description], [content])
+    VALUES (new.rowid, new.[title], new.[description], new.[content]);
+END;
+CREATE TRIGGER [pages_ad] AFTER DELETE ON [pages] BEGIN
+    INSERT INTO [pages_fts] ([pages_fts], rowid, [title], [description], [content])
+    VALUES ('delete', old.rowid, old.[title], old.[description], old.[content]);
+END;
+CREATE TRIGGER [pages_au] AFTER UPDATE ON [pages] BEGIN
+    INSERT INTO [pages_fts] ([pages_fts], rowid, [title], [description], [content])
+    VALUES ('delete', old.rowid, old.[title], old.[description], old.[content]);
+    INSERT INTO [pages_fts] (rowid, [title], [description], [content])
+    VALUES (new.rowid, new.[title], new.[description], new.[content]);
+END;
+";
+
+/// Writes pages to a [SQLite](https://sqlite.org/) database, with a table of pages, tables of their tags and categories
+/// and an FTS5 full text index of their content, which with the settings' `sqlite_fts` is kept up to date by triggers
+/// the way Datasette expects. Each page's whole record is kept as JSON in the `record` column of `pages`. The database
+/// is written in a single transaction, committed by `finalize`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::sqlite::SqliteWriter;
+/// use hugo_to_json::{IndexWriter, Settings};
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let tags = vec![String::from("rust")];
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), tags, Vec::new(), String::from("Fearless concurrency"), &location, None)?;
+///
+/// let directory = tempfile::tempdir()?;
+/// let path = directory.path().join("index.db");
+/// let mut writer = SqliteWriter::create(&path, &Settings::default())?;
+/// writer.write_page(&page)?;
+/// writer.finalize()?;
+///
+/// let connection = rusqlite::Connection::open(&path)?;
+/// let href: String = connection.query_row(
+///     "SELECT href FROM pages JOIN pages_fts ON pages.id = pages_fts.rowid WHERE pages_fts MATCH 'concurrency'",
+///     [],
+///     |row| row.get(0),
+/// )?;
+/// assert_eq!(href, "/post/rust/");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct SqliteWriter {
+    connection: Connection,
+    /// Whether triggers fill in the full text index, rather than the writer.
+    fts_triggers: bool,
+}
+
+impl SqliteWriter {
+    /// Creates a database at `path`, replacing any file already there, and starts the transaction the pages are
+    /// written in.
+    ///
+    /// # Errors
+    /// Errors if the file can't be replaced or the database can't be created.
+    pub fn create(path: &Path, settings: &Settings) -> Result<Self, HugotoJsonError> {
+        create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+        match fs::remove_file(path) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+            result => result?,
+        }
+        let connection = Connection::open(path)?;
+        connection.execute_batch(TABLES)?;
+        connection.execute_batch(if settings.sqlite_fts {
+            DATASETTE_FTS_TABLE
+        } else {
+            FTS_TABLE
+        })?;
+        connection.execute_batch("BEGIN")?;
+        Ok(Self {
+            connection,
+            fts_triggers: settings.sqlite_fts,
+        })
+    }
+
+    /// Adds each of `names` to the `table` of names if it isn't there already, and joins the page to it.
+    fn join_names(
+        &self,
+        page_id: i64,
+        names: &[String],
+        table: &str,
+        join_table: &str,
+        column: &str,
+    ) -> Result<(), HugotoJsonError> {
+        let mut insert_name = self
+            .connection
+            .prepare_cached(&format!("INSERT OR IGNORE INTO {table} (name) VALUES (?1)"))?;
+        let mut join = self.connection.prepare_cached(&format!(
+            "INSERT OR IGNORE INTO {join_table} (page_id, {column}) SELECT ?1, id FROM {table} WHERE name = ?2"
+        ))?;
+        for name in names {
+            insert_name.execute(params![name])?;
+            join.execute(params![page_id, name])?;
+        }
+        Ok(())
+    }
+}
+
+impl IndexWriter for SqliteWriter {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        let record = serde_json::to_value(page)?;
+        self.connection
+            .prepare_cached(
+                "INSERT INTO pages (href, title, date, description, summary, content, kind, lang, word_count,
+                    reading_time, record)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?
+            .execute(params![
+                page.href,
+                page.title,
+                page.date,
+                page.description,
+                page.summary,
+                page.content,
+                record["kind"].as_str().unwrap_or_default(),
+                page.lang,
+                page.word_count,
+                page.reading_time,
+                record.to_string(),
+            ])?;
+        let page_id = self.connection.last_insert_rowid();
+        if !self.fts_triggers {
+            self.connection
+                .prepare_cached(
+                    "INSERT INTO pages_fts (rowid, title, description, content) VALUES (?1, ?2, ?3, ?4)",
+                )?
+                .execute(params![page_id, page.title, page.description, page.content])?;
+        }
+        self.join_names(page_id, &page.tags, "tags", "page_tags", "tag_id")?;
+        self.join_names(
+            page_id,
+            &page.categories,
+            "categories",
+            "page_categories",
+            "category_id",
+        )?;
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        self.connection.execute_batch("COMMIT")?;
+        Ok(())
+    }
+}
+
+/// Reads the records of the pages in a database written by `SqliteWriter` back as a JSON array, in the order they were
+/// written, so the database can be validated like a JSON index.
+///
+/// # Errors
+/// Errors if the database can't be read.
+pub fn records_json(path: &Path) -> Result<String, HugotoJsonError> {
+    let connection = Connection::open(path)?;
+    let mut statement = connection.prepare("SELECT record FROM pages ORDER BY id")?;
+    let records = statement
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(format!("[{}]", records.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    #[test]
+    fn tags_and_categories_are_normalized() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("index.db");
+        // An existing database is replaced
+        for _ in 0..2 {
+            let mut writer = SqliteWriter::create(&path, &Settings::default()).unwrap();
+            writer
+                .write_page(
+                    &TestPage::new("rust")
+                        .tags(&["rust", "hugo"])
+                        .categories(&["code"])
+                        .content("All about rust")
+                        .build(),
+                )
+                .unwrap();
+            writer
+                .write_page(
+                    &TestPage::new("go")
+                        .tags(&["hugo"])
+                        .content("All about go")
+                        .build(),
+                )
+                .unwrap();
+            writer.finalize().unwrap();
+        }
+
+        let connection = Connection::open(&path).unwrap();
+        let count = |sql: &str| -> i64 { connection.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(count("SELECT count(*) FROM pages"), 2);
+        assert_eq!(count("SELECT count(*) FROM tags"), 2);
+        assert_eq!(count("SELECT count(*) FROM page_tags"), 3);
+        assert_eq!(count("SELECT count(*) FROM categories"), 1);
+        assert_eq!(
+            count(
+                "SELECT count(*) FROM page_tags JOIN tags ON tags.id = tag_id WHERE tags.name = 'hugo'"
+            ),
+            2
+        );
+        assert_eq!(
+            count("SELECT rowid FROM pages_fts WHERE pages_fts MATCH 'go'"),
+            2
+        );
+
+        let records: serde_json::Value =
+            serde_json::from_str(&records_json(&path).unwrap()).unwrap();
+        assert_eq!(records[0]["href"], "/post/rust/");
+        assert_eq!(records[1]["tags"][0], "hugo");
+    }
+
+    #[test]
+    fn datasette_fts_is_kept_up_to_date_by_triggers() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("index.db");
+        let settings = Settings {
+            sqlite_fts: true,
+            ..Settings::default()
+        };
+        let mut writer = SqliteWriter::create(&path, &settings).unwrap();
+        writer
+            .write_page(&TestPage::new("rust").content("All about rust").build())
+            .unwrap();
+        writer.finalize().unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let matches = |term: &str| -> i64 {
+            connection
+                .query_row(
+                    "SELECT count(*) FROM pages_fts WHERE pages_fts MATCH ?1",
+                    params![term],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(matches("rust"), 1);
+        connection
+            .execute("UPDATE pages SET content = 'All about ferris'", [])
+            .unwrap();
+        assert_eq!(matches("ferris"), 1);
+        connection.execute("DELETE FROM pages", []).unwrap();
+        assert_eq!(matches("rust"), 0);
+
+        // This is how Datasette finds the full text index of a table
+        let fts_table: String = connection
+            .query_row(
+                "SELECT name FROM sqlite_master WHERE rootpage = 0
+                    AND sql LIKE '%VIRTUAL TABLE%USING FTS%content=[pages]%'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(fts_table, "pages_fts");
+    }
+}
@@ -0,0 +1,123 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+
+use crate::operation_result::{OperationResult, SkipReason};
+use crate::page_index::PageIndex;
+
+/// Totals of what happened while writing an index, printed when the settings ask for them: how many pages were indexed,
+/// how many files were skipped and failed and why, how many bytes were written and how long it all took.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::operation_result::{OperationResult, Skip, SkipReason};
+/// use hugo_to_json::stats::Stats;
+///
+/// let stats = Stats::new();
+/// stats.record(&Err(OperationResult::from(Skip::new("/blog/content/wip.md", SkipReason::Draft))));
+/// stats.add_bytes(1024);
+/// assert_eq!(stats.pages(), 0);
+/// assert!(stats.to_string().contains("Files skipped: 1 (draft 1)"));
+/// ```
+#[derive(Debug)]
+pub struct Stats {
+    started: Instant,
+    pages: Cell<usize>,
+    skipped: RefCell<BTreeMap<SkipReason, usize>>,
+    failed: RefCell<BTreeMap<&'static str, usize>>,
+    bytes: Cell<u64>,
+}
+
+impl Stats {
+    /// Starts counting, and timing, from now.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            pages: Cell::new(0),
+            skipped: RefCell::default(),
+            failed: RefCell::default(),
+            bytes: Cell::new(0),
+        }
+    }
+
+    /// Counts the result of processing a file.
+    pub fn record(&self, result: &Result<PageIndex, OperationResult>) {
+        match result {
+            Ok(_) => self.pages.set(self.pages.get() + 1),
+            Err(OperationResult::Skip(skip)) => {
+                *self.skipped.borrow_mut().entry(skip.reason()).or_insert(0) += 1;
+            }
+            Err(error) => *self.failed.borrow_mut().entry(error.kind()).or_insert(0) += 1,
+        }
+    }
+
+    /// Counts bytes written to an index.
+    pub fn add_bytes(&self, bytes: u64) {
+        self.bytes.set(self.bytes.get() + bytes);
+    }
+
+    /// The number of pages indexed so far.
+    pub fn pages(&self) -> usize {
+        self.pages.get()
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Pages indexed: {}", self.pages.get())?;
+        let skipped = self.skipped.borrow();
+        writeln!(
+            f,
+            "Files skipped: {}{}",
+            skipped.values().sum::<usize>(),
+            breakdown(skipped.iter().map(|(reason, count)| (name(*reason), count)))
+        )?;
+        let failed = self.failed.borrow();
+        writeln!(
+            f,
+            "Files failed: {}{}",
+            failed.values().sum::<usize>(),
+            breakdown(failed.iter().map(|(kind, count)| (*kind, count)))
+        )?;
+        writeln!(f, "Bytes written: {}", self.bytes.get())?;
+        write!(f, "Wall time: {:.2}s", self.started.elapsed().as_secs_f64())
+    }
+}
+
+/// Describes counts of each kind, e.g. ` (draft 2, expired 1)`, or nothing if there aren't any.
+fn breakdown<'a, I>(counts: I) -> String
+where
+    I: Iterator<Item = (&'a str, &'a usize)>,
+{
+    let counts: Vec<String> = counts
+        .map(|(kind, count)| format!("{kind} {count}"))
+        .collect();
+    if counts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", counts.join(", "))
+    }
+}
+
+fn name(reason: SkipReason) -> &'static str {
+    match reason {
+        SkipReason::Draft => "draft",
+        SkipReason::NotDraft => "published",
+        SkipReason::FutureDated => "future",
+        SkipReason::Expired => "expired",
+        SkipReason::Headless => "headless",
+        SkipReason::Unlisted => "unlisted",
+        SkipReason::Excluded => "excluded",
+        SkipReason::UnsupportedExtension => "unsupported extension",
+        SkipReason::Directory => "directory",
+        SkipReason::TooLarge => "too large",
+    }
+}
@@ -0,0 +1,130 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::constants;
+use crate::error_report::ErrorRecord;
+use crate::file_location::FileLocation;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::operation_result::OperationResult;
+use crate::settings::Settings;
+use crate::traverse::{log_result, place, process_document, Cascades};
+
+/// Reads a single document from stdin and prints its record to stdout as a JSON object, without reading the rest of
+/// the contents directory. The document is processed as if it were the file at the settings' `stdin_path`, so its
+/// href, section and cascaded front matter are worked out from there. Nothing is printed for a draft or another
+/// document that wouldn't be published.
+///
+/// Related pages and term weights need every page, so they aren't added.
+///
+/// # Errors
+/// Errors if stdin can't be read, there's no path or it isn't the path of a file, or the document fails to process
+/// and the settings fail on that kind of failure.
+pub fn convert_stdin(settings: &Settings) -> Result<(), HugotoJsonError> {
+    // The command line requires a path, without one the location can't be worked out and fails as a path error
+    let path = settings.stdin_path.clone().unwrap_or_default();
+    let file_location = match stdin_location(&path, settings) {
+        Ok(file_location) => file_location,
+        Err(error) => {
+            error!("{error}");
+            return Err(HugotoJsonError::Meta {
+                total: 1,
+                parse: 0,
+                io: 0,
+                path: 1,
+            });
+        }
+    };
+
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents)?;
+    let result = process_document(&contents, &file_location, settings);
+    log_result(&file_location, &result);
+    let errors = match result {
+        Ok(page_index) => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            if settings.pretty {
+                serde_json::to_writer_pretty(&mut stdout, &page_index)?;
+            } else {
+                serde_json::to_writer(&mut stdout, &page_index)?;
+            }
+            stdout.flush()?;
+            Vec::new()
+        }
+        Err(OperationResult::Skip(_)) => Vec::new(),
+        Err(ref error) => vec![ErrorRecord::new(&file_location, error)],
+    };
+    crate::report_errors(settings, &errors)
+}
+
+/// Returns the location of a document at `path`, which is either inside a contents directory or relative to the first, in
+/// the language its path gives it and with the front matter cascaded to it from the sections it's in.
+///
+/// # Errors
+/// Returns a `PathError` if `path` doesn't have a file name and extension.
+fn stdin_location(path: &Path, settings: &Settings) -> Result<FileLocation, OperationResult> {
+    let path = absolute(path);
+    let scan_paths = settings.scan_paths();
+    let content_dirs: Vec<PathBuf> = scan_paths.iter().map(|dir| absolute(dir)).collect();
+    // Paths outside the contents directories are taken to be relative to the first
+    let (position, path) =
+        if let Some(position) = content_dirs.iter().position(|dir| path.starts_with(dir)) {
+            (position, path)
+        } else {
+            let relative = path
+                .strip_prefix(env::current_dir().unwrap_or_default())
+                .unwrap_or(&path);
+            (0, content_dirs[0].join(relative))
+        };
+    let (scan_path, content_dir) = (scan_paths[position], &content_dirs[position]);
+
+    let mut file_location = place(
+        FileLocation::from_path(&path, content_dir)?,
+        scan_path,
+        settings,
+    );
+    let sections: Vec<FileLocation> = path
+        .ancestors()
+        .skip(1)
+        .take_while(|directory| directory.starts_with(content_dir))
+        .flat_map(|directory| section_files(directory, scan_path, content_dir, settings))
+        .collect();
+    Cascades::collect(&sections, settings.front_matter).apply(&mut file_location);
+    Ok(file_location)
+}
+
+/// Returns the section files in `directory`, in every language, which may cascade front matter to the files below.
+/// `content_dir` is the absolute path of the contents directory `scan_path`.
+fn section_files(
+    directory: &Path,
+    scan_path: &Path,
+    content_dir: &Path,
+    settings: &Settings,
+) -> Vec<FileLocation> {
+    let prefix = format!("{}.", constants::SECTION_FILE_STEM);
+    fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .as_ref()
+                .is_some_and(|name| name.starts_with(&prefix))
+        })
+        .filter_map(|path| FileLocation::from_path(&path, content_dir).ok())
+        .map(|file_location| place(file_location, scan_path, settings))
+        .collect()
+}
+
+/// Makes `path` absolute, relative to the current directory, without any `.` components.
+fn absolute(path: &Path) -> PathBuf {
+    env::current_dir()
+        .map_or_else(|_| path.to_owned(), |directory| directory.join(path))
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
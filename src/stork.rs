@@ -0,0 +1,136 @@
+use std::io::{self, Write};
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::index_writer::IndexWriter;
+use crate::page_index::PageIndex;
+
+/// How Stork is told the contents of a file are plain text, as the content of the index is.
+pub const PLAIN_TEXT: &str = "PlainText";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A file in a [Stork](https://stork-search.net/) input config, with its contents given inline.
+pub struct StorkFile {
+    /// Where search results link to.
+    pub url: String,
+    /// The title shown in search results.
+    pub title: String,
+    /// The text that's searched.
+    pub contents: String,
+    /// The kind of text the contents are, always `PlainText`.
+    pub filetype: String,
+}
+
+impl StorkFile {
+    /// Creates the file of a page.
+    #[must_use]
+    pub fn new(page: &PageIndex) -> Self {
+        Self {
+            url: page.href.clone(),
+            title: page.title.clone(),
+            contents: page.content.clone(),
+            filetype: PLAIN_TEXT.to_owned(),
+        }
+    }
+}
+
+/// Writes pages as the TOML config Stork builds an index from, with an `[[input.files]]` table for each page, so the
+/// config is generated from the site rather than kept by hand. Settings of Stork's own, such as its output, can be
+/// passed to `stork build` alongside it.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::stork::StorkWriter;
+/// use hugo_to_json::IndexWriter;
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::from("Contents"), &location, None)?;
+///
+/// let mut stork = StorkWriter::new(Vec::new());
+/// stork.write_page(&page)?;
+/// stork.finalize()?;
+/// assert_eq!(
+///     String::from_utf8(stork.into_inner())?,
+///     "[[input.files]]\nurl = \"/post/rust/\"\ntitle = \"Rust\"\ncontents = \"Contents\"\nfiletype = \"PlainText\"\n"
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct StorkWriter<W: Write> {
+    writer: W,
+    files: usize,
+}
+
+impl<W: Write> StorkWriter<W> {
+    /// Creates a writer of a config to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer, files: 0 }
+    }
+
+    /// Returns the writer the config was written to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> IndexWriter for StorkWriter<W> {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        let file = toml::to_string(&StorkFile::new(page))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if self.files > 0 {
+            self.writer.write_all(b"\n")?;
+        }
+        write!(self.writer, "[[input.files]]\n{file}")?;
+        self.files += 1;
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        if self.files == 0 {
+            // Stork needs a list of files, even if it's empty
+            self.writer.write_all(b"[input]\nfiles = []\n")?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+
+    #[derive(Deserialize)]
+    struct Config {
+        input: Input,
+    }
+
+    #[derive(Deserialize)]
+    struct Input {
+        files: Vec<StorkFile>,
+    }
+
+    fn config(pages: &[PageIndex]) -> Vec<StorkFile> {
+        let mut writer = StorkWriter::new(Vec::new());
+        for page in pages {
+            writer.write_page(page).unwrap();
+        }
+        writer.finalize().unwrap();
+        let config: Config =
+            toml::from_str(&String::from_utf8(writer.into_inner()).unwrap()).unwrap();
+        config.input.files
+    }
+
+    #[test]
+    fn configs_read_back_as_stork_reads_them() {
+        assert!(config(&[]).is_empty());
+
+        let page = TestPage::new("quotes")
+            .title("\"Quoted\" title")
+            .content("Several\nlines with ''' and \"\"\"")
+            .build();
+        let files = config(&[page.clone(), page.clone()]);
+        assert_eq!(files, vec![StorkFile::new(&page), StorkFile::new(&page)]);
+    }
+}
@@ -0,0 +1,140 @@
+use serde_json::Value as JsonValue;
+
+use std::io::{self, Write};
+
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+
+/// The columns of a table when the settings don't name any.
+pub const DEFAULT_COLUMNS: [&str; 4] = [
+    constants::TITLE,
+    constants::HREF,
+    constants::DATE,
+    constants::TAGS,
+];
+
+/// What the items of list fields are joined with when the settings don't say.
+pub const DEFAULT_LIST_DELIMITER: &str = "|";
+
+/// Writes pages as a table of comma or tab separated values, with a header row of the columns and a row for each page,
+/// for auditing front matter in a spreadsheet.
+pub struct TableWriter<W: Write> {
+    writer: csv::Writer<W>,
+    columns: Vec<String>,
+    list_delimiter: String,
+}
+
+impl<W: Write> TableWriter<W> {
+    /// Creates a writer of the columns and delimiters in the settings and writes the header row.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::settings::{OutputFormat, Settings};
+    /// use hugo_to_json::table::TableWriter;
+    /// use std::path::Path;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+    /// let tags = vec![String::from("rust"), String::from("hugo")];
+    /// let page = PageIndex::new(Some("Rust, again"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), tags, Vec::new(), String::new(), &location, None)?;
+    ///
+    /// let settings = Settings { format: OutputFormat::Csv, ..Settings::default() };
+    /// let mut table = TableWriter::new(Vec::new(), &settings)?;
+    /// table.write_page(&page)?;
+    /// let csv = String::from_utf8(table.into_inner()?)?;
+    /// assert_eq!(csv, "title,href,date,tags\n\"Rust, again\",/post/rust/,2019-01-25,rust|hugo\n");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Errors if the header row can't be written.
+    pub fn new(writer: W, settings: &Settings) -> Result<Self, HugotoJsonError> {
+        let columns = if settings.columns.is_empty() {
+            DEFAULT_COLUMNS
+                .iter()
+                .map(|&column| column.to_owned())
+                .collect()
+        } else {
+            settings.columns.clone()
+        };
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(settings.format.delimiter().unwrap_or(b','))
+            .from_writer(writer);
+        writer.write_record(&columns).map_err(io::Error::from)?;
+        Ok(Self {
+            writer,
+            columns,
+            list_delimiter: settings
+                .list_delimiter
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LIST_DELIMITER.to_owned()),
+        })
+    }
+
+    /// Writes a row of the page's value for each column.
+    ///
+    /// # Errors
+    /// Errors if the page can't be serialized or the row can't be written.
+    pub fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        let record = serde_json::to_value(page)?;
+        let row: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| cell(&record, column, &self.list_delimiter))
+            .collect();
+        self.writer.write_record(&row).map_err(io::Error::from)?;
+        Ok(())
+    }
+
+    /// Flushes the rows written so far to the writer.
+    ///
+    /// # Errors
+    /// Errors if the rows can't be flushed.
+    pub fn flush(&mut self) -> Result<(), HugotoJsonError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Flushes the table and returns the writer it was written to.
+    ///
+    /// # Errors
+    /// Errors if the table can't be flushed.
+    pub fn into_inner(self) -> Result<W, HugotoJsonError> {
+        self.writer
+            .into_inner()
+            .map_err(|err| HugotoJsonError::Io(err.into_error()))
+    }
+}
+
+/// Looks up a field of a serialized page: one of its own fields, a custom taxonomy or a kept extra field.
+pub(crate) fn lookup<'a>(record: &'a JsonValue, field: &str) -> Option<&'a JsonValue> {
+    record
+        .get(field)
+        .or_else(|| {
+            record
+                .get("taxonomies")
+                .and_then(|taxonomies| taxonomies.get(field))
+        })
+        .or_else(|| record.get("extra").and_then(|extra| extra.get(field)))
+}
+
+/// Returns a page's value for a column. Lists are joined with the list delimiter, objects are written as JSON and
+/// missing fields are empty.
+fn cell(record: &JsonValue, column: &str, list_delimiter: &str) -> String {
+    match lookup(record, column) {
+        None | Some(JsonValue::Null) => String::new(),
+        Some(JsonValue::String(text)) => text.clone(),
+        Some(JsonValue::Array(items)) => items
+            .iter()
+            .map(|item| match item {
+                JsonValue::String(text) => text.clone(),
+                item => item.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(list_delimiter),
+        Some(value) => value.to_string(),
+    }
+}
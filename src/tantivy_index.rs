@@ -0,0 +1,168 @@
+use tantivy::schema::{Field, Schema, STORED, STRING, TEXT};
+use tantivy::{Index, TantivyDocument};
+
+use std::fs::{self, create_dir_all};
+use std::path::Path;
+
+use crate::constants::{CONTENT, DATE, HREF, TAGS, TITLE};
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::index_writer::IndexWriter;
+use crate::page_index::PageIndex;
+
+/// How much memory the index is built in before it's written out, in bytes.
+const MEMORY_BUDGET: usize = 50_000_000;
+
+/// The file tantivy keeps in the directory of every index.
+const META_FILE_NAME: &str = "meta.json";
+
+/// The fields of each document in the index.
+struct Fields {
+    title: Field,
+    href: Field,
+    date: Field,
+    tags: Field,
+    content: Field,
+}
+
+/// Returns the schema of the index: the title and content are tokenized for search, and the href, date and each tag
+/// are kept whole, so they can be filtered on. Every field is stored to be shown in results.
+#[must_use]
+pub fn schema() -> Schema {
+    let mut schema = Schema::builder();
+    schema.add_text_field(TITLE, TEXT | STORED);
+    schema.add_text_field(HREF, STRING | STORED);
+    schema.add_text_field(DATE, STRING | STORED);
+    schema.add_text_field(TAGS, STRING | STORED);
+    schema.add_text_field(CONTENT, TEXT | STORED);
+    schema.build()
+}
+
+/// Builds a [tantivy](https://github.com/quickwit-oss/tantivy) index on disk, which a search server can open without
+/// ingesting the pages itself. The pages are committed by `finalize`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::tantivy_index::TantivyWriter;
+/// use hugo_to_json::IndexWriter;
+/// use std::path::Path;
+///
+/// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+/// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), String::from("Fearless concurrency"), &location, None)?;
+///
+/// let directory = tempfile::tempdir()?;
+/// let mut writer = TantivyWriter::create(directory.path())?;
+/// writer.write_page(&page)?;
+/// writer.finalize()?;
+///
+/// let index = tantivy::Index::open_in_dir(directory.path())?;
+/// assert_eq!(index.reader()?.searcher().num_docs(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TantivyWriter {
+    writer: tantivy::IndexWriter,
+    fields: Fields,
+}
+
+impl TantivyWriter {
+    /// Creates an index in the directory at `path`, replacing any index already there.
+    ///
+    /// # Errors
+    /// Errors if the directory can't be created or already holds files other than an index.
+    pub fn create(path: &Path) -> Result<Self, HugotoJsonError> {
+        if path.join(META_FILE_NAME).exists() {
+            fs::remove_dir_all(path)?;
+        }
+        create_dir_all(path)?;
+        let schema = schema();
+        let field = |name| schema.get_field(name);
+        let fields = Fields {
+            title: field(TITLE)?,
+            href: field(HREF)?,
+            date: field(DATE)?,
+            tags: field(TAGS)?,
+            content: field(CONTENT)?,
+        };
+        let index = Index::create_in_dir(path, schema.clone())?;
+        let writer = index.writer_with_num_threads(1, MEMORY_BUDGET)?;
+        Ok(Self { writer, fields })
+    }
+}
+
+impl IndexWriter for TantivyWriter {
+    fn write_page(&mut self, page: &PageIndex) -> Result<(), HugotoJsonError> {
+        let mut document = TantivyDocument::default();
+        document.add_text(self.fields.title, &page.title);
+        document.add_text(self.fields.href, &page.href);
+        document.add_text(self.fields.date, &page.date);
+        for tag in &page.tags {
+            document.add_text(self.fields.tags, tag);
+        }
+        document.add_text(self.fields.content, &page.content);
+        self.writer.add_document(document)?;
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<(), HugotoJsonError> {
+        self.writer.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+    use tantivy::collector::TopDocs;
+    use tantivy::query::QueryParser;
+    use tantivy::schema::Value;
+
+    #[test]
+    fn pages_can_be_searched() {
+        let directory = tempfile::tempdir().unwrap();
+        // An existing index is replaced
+        for _ in 0..2 {
+            let mut writer = TantivyWriter::create(directory.path()).unwrap();
+            writer
+                .write_page(
+                    &TestPage::new("rust")
+                        .tags(&["rust", "code"])
+                        .content("All about rust")
+                        .build(),
+                )
+                .unwrap();
+            writer
+                .write_page(
+                    &TestPage::new("hugo")
+                        .tags(&["web"])
+                        .content("All about hugo")
+                        .build(),
+                )
+                .unwrap();
+            writer.finalize().unwrap();
+        }
+
+        let index = Index::open_in_dir(directory.path()).unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        let schema = index.schema();
+        let field = |name| schema.get_field(name).unwrap();
+        let parser = QueryParser::for_index(&index, vec![field(TITLE), field(CONTENT)]);
+        let hrefs = |query: &str| -> Vec<String> {
+            let query = parser.parse_query(query).unwrap();
+            searcher
+                .search(&query, &TopDocs::with_limit(10))
+                .unwrap()
+                .into_iter()
+                .map(|(_, address)| {
+                    let document: TantivyDocument = searcher.doc(address).unwrap();
+                    let href = document.get_first(field(HREF)).unwrap();
+                    href.as_str().unwrap().to_owned()
+                })
+                .collect()
+        };
+        assert_eq!(hrefs("hugo"), vec!["/post/hugo/"]);
+        assert_eq!(hrefs("tags:code"), vec!["/post/rust/"]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::constants;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(transparent)]
+/// Maps each term of each taxonomy to the hrefs of the pages that have it, e.g. `tags` → `rust` → `["/post/a/"]`, for
+/// tag clouds and other widgets that would otherwise aggregate the index themselves.
+pub struct TaxonomyIndex {
+    taxonomies: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl TaxonomyIndex {
+    /// Creates an index with no pages in it. Tags, categories and series are always present, even without terms.
+    #[must_use]
+    pub fn new() -> Self {
+        let taxonomies = [constants::TAGS, constants::CATEGORIES, constants::SERIES]
+            .iter()
+            .map(|taxonomy| ((*taxonomy).to_owned(), BTreeMap::new()))
+            .collect();
+        Self { taxonomies }
+    }
+
+    /// Creates an index of `pages`.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::file_location::FileLocation;
+    /// use hugo_to_json::page_index::PageIndex;
+    /// use hugo_to_json::taxonomy_index::TaxonomyIndex;
+    /// use std::path::Path;
+    /// # use hugo_to_json::operation_result::OperationResult;
+    ///
+    /// let location = FileLocation::from_path(Path::new("/blog/content/post/rust.md"), Path::new("/blog/content"))?;
+    /// let tags = vec![String::from("rust")];
+    /// let page = PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), tags, Vec::new(), String::new(), &location, None)?;
+    ///
+    /// let index = TaxonomyIndex::from_pages(&[page]);
+    /// assert_eq!(index.hrefs("tags", "rust"), ["/post/rust/"]);
+    /// assert!(index.hrefs("categories", "rust").is_empty());
+    /// # Ok::<(), OperationResult>(())
+    /// ```
+    pub fn from_pages<'a, I>(pages: I) -> Self
+    where
+        I: IntoIterator<Item = &'a PageIndex>,
+    {
+        let mut index = Self::new();
+        for page in pages {
+            index.add(page);
+        }
+        index
+    }
+
+    /// Adds the terms of a page, including those of the site's custom taxonomies.
+    pub fn add(&mut self, page: &PageIndex) {
+        let taxonomies = [
+            (constants::TAGS, &page.tags),
+            (constants::CATEGORIES, &page.categories),
+            (constants::SERIES, &page.series),
+        ];
+        let custom = page
+            .taxonomies
+            .iter()
+            .map(|(taxonomy, terms)| (taxonomy.as_str(), terms));
+        for (taxonomy, terms) in taxonomies.iter().copied().chain(custom) {
+            let index = self.taxonomies.entry(taxonomy.to_owned()).or_default();
+            for term in terms {
+                let hrefs = index.entry(term.clone()).or_default();
+                if !hrefs.contains(&page.href) {
+                    hrefs.push(page.href.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns the hrefs of the pages with `term` in `taxonomy`, in the order the pages were added.
+    pub fn hrefs(&self, taxonomy: &str, term: &str) -> &[String] {
+        self.taxonomies
+            .get(taxonomy)
+            .and_then(|terms| terms.get(term))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Writes the index to `path` as a JSON object.
+    ///
+    /// # Errors
+    /// Errors if the index can't be serialized or written.
+    pub fn write(&self, path: &Path) -> Result<(), HugotoJsonError> {
+        create_dir_all(path.with_file_name(constants::EMPTY_STRING))?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        debug!("Wrote taxonomy index to {}", path.to_string_lossy());
+        Ok(())
+    }
+}
+
+impl Default for TaxonomyIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::file_location::FileLocation;
+use crate::links::Link;
+use crate::page_index::{PageIndex, PageSection};
+use crate::settings::{FailOn, OutputFormat, Settings, SortOrder};
+use crate::sitemap::SitemapConfig;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// What a page was built from: the file's modification time and size, a hash of its contents and a hash of everything
+/// else about its location that affects the page.
+pub struct Stamp {
+    /// Seconds and nanoseconds since the Unix epoch, and the size in bytes.
+    modified: Option<(u64, u32, u64)>,
+    content_hash: String,
+    context_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    stamp: Stamp,
+    page: PageIndex,
+    /// The page's sitemap config, which isn't part of the page when it's serialized.
+    #[serde(default)]
+    sitemap: SitemapConfig,
+    /// The page's sections, which aren't part of the page when it's serialized either.
+    #[serde(default)]
+    sections: Vec<PageSection>,
+    /// The path of the file the page was built from, which isn't part of the page when it's serialized either.
+    #[serde(default)]
+    path: String,
+    /// The links in the page's content, which aren't part of the page when it's serialized either.
+    #[serde(default)]
+    links: Vec<Link>,
+}
+
+impl CacheEntry {
+    fn page(&self) -> PageIndex {
+        PageIndex {
+            path: self.path.clone(),
+            sitemap: self.sitemap.clone(),
+            sections: self.sections.clone(),
+            links: self.links.clone(),
+            ..self.page.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+/// The pages built by a previous run, keyed by the path of the file each was built from, so unchanged files don't
+/// need to be processed again.
+pub struct Cache {
+    /// Identifies the version and settings the pages were built with. The cache is discarded if either changes.
+    fingerprint: String,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl Cache {
+    /// Reads the cache file in the settings. Returns an empty cache if there isn't one, it can't be read, or it was
+    /// built by another version or with other settings.
+    pub fn load(settings: &Settings) -> Self {
+        let fingerprint = fingerprint(settings);
+        let Some(ref path) = settings.cache_file else {
+            return Self::empty(fingerprint);
+        };
+
+        let cache: Result<Self, String> =
+            File::open(path)
+                .map_err(|err| err.to_string())
+                .and_then(|file| {
+                    serde_json::from_reader(BufReader::new(file)).map_err(|err| err.to_string())
+                });
+        match cache {
+            Ok(cache) if cache.fingerprint == fingerprint => {
+                debug!("Loaded {} cached pages", cache.entries.len());
+                cache
+            }
+            Ok(_) => {
+                info!("The version or settings have changed since the cache was written, rebuilding it");
+                Self::empty(fingerprint)
+            }
+            Err(error) => {
+                if path.exists() {
+                    warn!("Ignoring cache {}: {}", path.to_string_lossy(), error);
+                }
+                Self::empty(fingerprint)
+            }
+        }
+    }
+
+    fn empty(fingerprint: String) -> Self {
+        Self {
+            fingerprint,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Looks up the page built from a content file, returning it if the file hasn't changed since, along with the
+    /// file's current stamp. The file is only read if its modification time or size has changed.
+    pub fn lookup(
+        &self,
+        file_location: &FileLocation,
+        settings: &Settings,
+    ) -> (Option<PageIndex>, Option<Stamp>) {
+        if !file_location.is_content() {
+            return (None, None);
+        }
+
+        let path = Path::new(&file_location.absolute_path);
+        let entry = self.entries.get(&file_location.absolute_path);
+        let modified = modified_time(path);
+        let context = context_hash(file_location, settings);
+        if let Some(entry) = entry {
+            if modified.is_some()
+                && entry.stamp.modified == modified
+                && entry.stamp.context_hash == context
+            {
+                return (Some(entry.page()), Some(entry.stamp.clone()));
+            }
+        }
+
+        let content_hash = match fs::read(path) {
+            Ok(contents) => hash(&contents),
+            // The error is reported when the file is processed
+            Err(_) => return (None, None),
+        };
+        let stamp = Stamp {
+            modified,
+            content_hash,
+            context_hash: context,
+        };
+        let page = entry
+            .filter(|entry| {
+                entry.stamp.content_hash == stamp.content_hash
+                    && entry.stamp.context_hash == stamp.context_hash
+            })
+            .map(CacheEntry::page);
+        (page, Some(stamp))
+    }
+
+    /// Replaces the cached pages with `pages`, keyed by the path of the file each was built from. Pages with an expiry
+    /// date are left out, as whether they're published has to be checked against the time of each run.
+    pub fn replace<I>(&mut self, pages: I)
+    where
+        I: IntoIterator<Item = (String, Stamp, PageIndex)>,
+    {
+        self.entries = pages
+            .into_iter()
+            .filter(|(_, _, page)| page.expiry_date.is_none())
+            .map(|(path, stamp, page)| {
+                let sitemap = page.sitemap.clone();
+                let sections = page.sections.clone();
+                let links = page.links.clone();
+                (
+                    path,
+                    CacheEntry {
+                        stamp,
+                        sitemap,
+                        sections,
+                        path: page.path.clone(),
+                        links,
+                        page,
+                    },
+                )
+            })
+            .collect();
+    }
+
+    /// Writes the cache to the cache file in the settings. Failing to write it only loses the time it would save, so
+    /// errors are logged rather than returned.
+    pub fn save(&self, settings: &Settings) {
+        if let Some(ref path) = settings.cache_file {
+            let written = File::create(path)
+                .map_err(|err| err.to_string())
+                .and_then(|file| {
+                    serde_json::to_writer(BufWriter::new(file), self).map_err(|err| err.to_string())
+                });
+            match written {
+                Ok(()) => debug!("Cached {} pages", self.entries.len()),
+                Err(error) => warn!(
+                    "Failed to write cache {}: {}",
+                    path.to_string_lossy(),
+                    error
+                ),
+            }
+        }
+    }
+}
+
+/// Identifies the version of the tool and the settings that affect how pages are built.
+fn fingerprint(settings: &Settings) -> String {
+    // Settings that only affect how the index is written or sent don't invalidate the cache
+    let page_settings = Settings {
+        output: None,
+        cache_file: None,
+        // A profile only chooses the other settings
+        profile: None,
+        command: None,
+        format: OutputFormat::default(),
+        outputs: Vec::new(),
+        pretty: false,
+        columns: Vec::new(),
+        list_delimiter: None,
+        sqlite_fts: false,
+        preset: None,
+        sort: None,
+        order: SortOrder::default(),
+        limit: None,
+        split_by_lang: false,
+        split_by_section: false,
+        shard_size: None,
+        validate: false,
+        diff: None,
+        error_report: None,
+        quiet: false,
+        taxonomy_index: None,
+        rss: None,
+        rss_limit: None,
+        sitemap: None,
+        // Links are only kept if they're written or checked, but where they're written and how doesn't matter
+        links: Some(PathBuf::new()).filter(|_| settings.keeps_links()),
+        check_links: false,
+        static_dir: None,
+        emit_schema: None,
+        // Related pages and weights are found after the pages are built
+        related: None,
+        weights: false,
+        stem_weights: false,
+        fail_on: FailOn::default(),
+        fail_on_duplicates: false,
+        no_clobber_on_error: false,
+        checksum: false,
+        skip_unchanged: false,
+        envelope: false,
+        sign: None,
+        watch: false,
+        jobs: None,
+        single_threaded: false,
+        compress: None,
+        max_index_size: None,
+        enforce_max_index_size: false,
+        upload: None,
+        post_url: None,
+        headers: Vec::new(),
+        endpoint: None,
+        api_key: None,
+        index_name: None,
+        batch_size: None,
+        retries: 0,
+        ..settings.clone()
+    };
+    format!(
+        "{}-{}",
+        env!("CARGO_PKG_VERSION"),
+        hash(format!("{page_settings:?}").as_bytes())
+    )
+}
+
+/// Hashes what affects a page other than its own file: the front matter cascaded to it and, if they're listed, the
+/// resources of its bundle.
+fn context_hash(file_location: &FileLocation, settings: &Settings) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&file_location.cascade)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    if settings.bundle_resources {
+        file_location.bundle_resources().hash(&mut hasher);
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+fn hash(bytes: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Returns a file's modification time and size, which are read without reading the file.
+fn modified_time(path: &Path) -> Option<(u64, u32, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let since_epoch = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos(),
+        metadata.len(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn unchanged_files_are_found_and_changed_files_are_not() {
+        let dir = tempfile::Builder::new().prefix("cache").tempdir().unwrap();
+        let path = dir.path().join("page.md");
+        fs::write(&path, "+++\ntitle = \"Page\"\n+++\n").unwrap();
+        let location = FileLocation::from_path(&path, dir.path()).unwrap();
+        let settings = Settings {
+            cache_file: Some(dir.path().join("cache.json")),
+            ..Settings::default()
+        };
+        let page = PageIndex::new(
+            Some("Page"),
+            None,
+            Some("2019-01-25"),
+            None,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            String::new(),
+            &location,
+            None,
+        )
+        .unwrap();
+
+        let mut cache = Cache::load(&settings);
+        let (cached, stamp) = cache.lookup(&location, &settings);
+        assert_eq!(cached, None);
+        cache.replace(vec![(
+            location.absolute_path.clone(),
+            stamp.unwrap(),
+            page.clone(),
+        )]);
+        cache.save(&settings);
+
+        let cache = Cache::load(&settings);
+        assert_eq!(cache.lookup(&location, &settings).0, Some(page));
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "Changed").unwrap();
+        assert_eq!(cache.lookup(&location, &settings).0, None);
+
+        let other_settings = Settings {
+            summary_length: Some(10),
+            ..settings
+        };
+        assert!(Cache::load(&other_settings).entries.is_empty());
+    }
+}
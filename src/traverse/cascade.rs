@@ -0,0 +1,325 @@
+use globset::{GlobBuilder, GlobMatcher};
+use serde_json::{Map, Value as JsonValue};
+use toml::Value as TomlValue;
+use yaml_rust::{Yaml, YamlLoader};
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::constants;
+use crate::file_location::FileLocation;
+use crate::settings::{FrontMatterFormat, FrontMatterMode};
+use crate::traverse::extra::{json_to_yaml, toml_to_json, yaml_to_json};
+use crate::traverse::traverser::{front_matter_format, split_front_matter};
+
+/// The key in a cascade entry that limits which pages it applies to.
+const TARGET: &str = "_target";
+
+#[derive(Debug, Default)]
+/// The front matter defaults declared with `cascade` in the `_index.md` files of a contents directory, keyed by the
+/// directory and language of the section file that declared them.
+pub struct Cascades {
+    cascades: BTreeMap<(String, String), Vec<Cascade>>,
+}
+
+#[derive(Debug)]
+/// One entry of a section's `cascade`, applied to the pages its target matches.
+struct Cascade {
+    values: Map<String, JsonValue>,
+    path: Option<GlobMatcher>,
+    kind: Option<String>,
+    lang: Option<String>,
+}
+
+impl Cascades {
+    /// Reads the `cascade` of every section file in `file_locations`. Section files that can't be read or parsed are
+    /// left to report their error when they are processed. Their front matter's format is decided by `mode`.
+    pub fn collect<'a, I>(file_locations: I, mode: FrontMatterMode) -> Self
+    where
+        I: IntoIterator<Item = &'a FileLocation>,
+    {
+        let mut cascades = BTreeMap::new();
+        for file_location in file_locations {
+            if !file_location.is_section() || !file_location.is_content() {
+                continue;
+            }
+            let entries = fs::read_to_string(&file_location.absolute_path)
+                .ok()
+                .and_then(|contents| front_matter(&contents, mode))
+                .map(|front_matter| read_cascade(&front_matter))
+                .unwrap_or_default();
+            if !entries.is_empty() {
+                cascades.insert(
+                    (
+                        file_location.relative_directory_to_content.clone(),
+                        file_location.lang.clone(),
+                    ),
+                    entries,
+                );
+            }
+        }
+        Self { cascades }
+    }
+
+    /// Sets the values cascaded to a file from the sections above it. A nearer section's values win over those of
+    /// the sections above it. A section file doesn't cascade to itself.
+    pub fn apply(&self, file_location: &mut FileLocation) {
+        let mut values = Map::new();
+        if self.cascades.is_empty() {
+            file_location.cascade = values;
+            return;
+        }
+
+        let directory = file_location.relative_directory_to_content.as_str();
+        let mut ancestors: Vec<&str> = directory
+            .match_indices(constants::FORWARD_SLASH)
+            .map(|(position, _)| &directory[..position])
+            .collect();
+        if !directory.is_empty() {
+            ancestors.insert(0, constants::EMPTY_STRING);
+            if !file_location.is_section() {
+                ancestors.push(directory);
+            }
+        } else if !file_location.is_section() {
+            ancestors.push(constants::EMPTY_STRING);
+        }
+
+        let path = content_path(file_location);
+        for ancestor in ancestors.into_iter().rev() {
+            let key = (ancestor.to_owned(), file_location.lang.clone());
+            for cascade in self.cascades.get(&key).into_iter().flatten() {
+                if !cascade.targets(file_location, &path) {
+                    continue;
+                }
+                for (key, value) in &cascade.values {
+                    if !values.contains_key(key) {
+                        values.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        file_location.cascade = values;
+    }
+}
+
+impl Cascade {
+    fn new(mut values: Map<String, JsonValue>) -> Self {
+        let target = values.remove(TARGET);
+        let target_value = |key: &str| {
+            target
+                .as_ref()
+                .and_then(|target| target.get(key))
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+        };
+        let path = target_value("path").and_then(|path| {
+            GlobBuilder::new(&path)
+                .literal_separator(true)
+                .build()
+                .map(|glob| glob.compile_matcher())
+                .map_err(|err| warn!("Ignoring cascade target path `{path}`: {err}"))
+                .ok()
+        });
+
+        Self {
+            values,
+            path,
+            kind: target_value("kind"),
+            lang: target_value("lang"),
+        }
+    }
+
+    /// Returns true if the cascade applies to the file, whose content path is `path`.
+    fn targets(&self, file_location: &FileLocation, path: &str) -> bool {
+        let kind = if file_location.is_section() {
+            "section"
+        } else {
+            "page"
+        };
+        // Each part of the target that's given has to match
+        self.path.iter().all(|glob| glob.is_match(path))
+            && self.kind.iter().all(|target| target == kind)
+            && self.lang.iter().all(|target| *target == file_location.lang)
+    }
+}
+
+/// The path Hugo matches cascade targets against, e.g. `/post/example` for `post/example.md` and `/post` for
+/// `post/_index.md`.
+fn content_path(file_location: &FileLocation) -> String {
+    let directory = &file_location.relative_directory_to_content;
+    if file_location.is_section() || file_location.is_bundle() {
+        [constants::FORWARD_SLASH, directory].concat()
+    } else if directory.is_empty() {
+        [constants::FORWARD_SLASH, &file_location.file_stem].concat()
+    } else {
+        [
+            constants::FORWARD_SLASH,
+            directory,
+            constants::FORWARD_SLASH,
+            &file_location.file_stem,
+        ]
+        .concat()
+    }
+}
+
+/// Reads the front matter of a content document into JSON, whatever format it's written in, unless the mode forces one.
+fn front_matter(contents: &str, mode: FrontMatterMode) -> Option<JsonValue> {
+    match front_matter_format(contents, mode)? {
+        FrontMatterFormat::Toml => {
+            let (source, _) = split_front_matter(contents, constants::TOML_FENCE)?;
+            source
+                .parse::<TomlValue>()
+                .ok()
+                .map(|value| toml_to_json(&value))
+        }
+        FrontMatterFormat::Yaml => {
+            let (source, _) = split_front_matter(contents, constants::YAML_FENCE)?;
+            YamlLoader::load_from_str(source)
+                .ok()?
+                .first()
+                .map(yaml_to_json)
+        }
+        FrontMatterFormat::Json => serde_json::Deserializer::from_str(contents.trim())
+            .into_iter::<JsonValue>()
+            .next()?
+            .ok(),
+    }
+}
+
+/// Reads the entries of a section's `cascade`, which is either a table of values or an array of them.
+fn read_cascade(front_matter: &JsonValue) -> Vec<Cascade> {
+    match front_matter.get(constants::CASCADE) {
+        Some(JsonValue::Object(values)) => vec![Cascade::new(values.clone())],
+        Some(JsonValue::Array(entries)) => entries
+            .iter()
+            .filter_map(JsonValue::as_object)
+            .map(|values| Cascade::new(values.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Adds the cascaded values to TOML front matter, keeping the values the front matter sets itself.
+pub fn apply_to_toml(front_matter: &mut TomlValue, cascade: &Map<String, JsonValue>) {
+    if let TomlValue::Table(ref mut table) = front_matter {
+        for (key, value) in cascade {
+            if !table.contains_key(key) {
+                // Nulls have no TOML representation, and an unset key means the same thing
+                if let Ok(value) = TomlValue::try_from(value) {
+                    table.insert(key.clone(), value);
+                }
+            }
+        }
+    }
+}
+
+/// Adds the cascaded values to YAML front matter, keeping the values the front matter sets itself.
+pub fn apply_to_yaml(front_matter: &mut Yaml, cascade: &Map<String, JsonValue>) {
+    if let Yaml::Hash(ref mut hash) = front_matter {
+        for (key, value) in cascade {
+            hash.entry(Yaml::String(key.clone()))
+                .or_insert_with(|| json_to_yaml(value));
+        }
+    }
+}
+
+/// Adds the cascaded values to JSON front matter, keeping the values the front matter sets itself.
+pub fn apply_to_json(front_matter: &mut JsonValue, cascade: &Map<String, JsonValue>) {
+    if let JsonValue::Object(ref mut object) = front_matter {
+        for (key, value) in cascade {
+            object.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn location(path: &str) -> FileLocation {
+        FileLocation::from_path(Path::new(path), Path::new("/blog/content")).unwrap()
+    }
+
+    fn cascades(sections: &[(&str, JsonValue)]) -> Cascades {
+        Cascades {
+            cascades: sections
+                .iter()
+                .map(|(directory, front_matter)| {
+                    (
+                        (directory.to_string(), String::new()),
+                        read_cascade(front_matter),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn nearer_sections_win() {
+        let cascades = cascades(&[
+            (
+                "",
+                json!({ "cascade": { "tags": ["site"], "banner": "site.png" } }),
+            ),
+            ("post", json!({ "cascade": { "tags": ["post"] } })),
+        ]);
+
+        let mut page = location("/blog/content/post/rust/example.md");
+        cascades.apply(&mut page);
+        assert_eq!(
+            JsonValue::Object(page.cascade),
+            json!({ "tags": ["post"], "banner": "site.png" })
+        );
+
+        let mut section = location("/blog/content/post/_index.md");
+        cascades.apply(&mut section);
+        assert_eq!(
+            JsonValue::Object(section.cascade),
+            json!({ "tags": ["site"], "banner": "site.png" })
+        );
+    }
+
+    #[test]
+    fn targets_limit_the_pages_a_cascade_applies_to() {
+        let cascades = cascades(&[(
+            "",
+            json!({ "cascade": [
+                { "draft": true, "_target": { "path": "/drafts/**" } },
+                { "banner": "section.png", "_target": { "kind": "section" } },
+            ] }),
+        )]);
+
+        let mut draft = location("/blog/content/drafts/wip/idea.md");
+        cascades.apply(&mut draft);
+        assert_eq!(JsonValue::Object(draft.cascade), json!({ "draft": true }));
+
+        let mut page = location("/blog/content/post/example.md");
+        cascades.apply(&mut page);
+        assert!(page.cascade.is_empty());
+
+        let mut section = location("/blog/content/post/_index.md");
+        cascades.apply(&mut section);
+        assert_eq!(
+            JsonValue::Object(section.cascade),
+            json!({ "banner": "section.png" })
+        );
+    }
+
+    #[test]
+    fn front_matter_keeps_its_own_values() {
+        let cascade = json!({ "draft": true, "tags": ["cascaded"] });
+        let cascade = cascade.as_object().unwrap();
+
+        let mut toml = "draft = false".parse::<TomlValue>().unwrap();
+        apply_to_toml(&mut toml, cascade);
+        assert_eq!(toml.get("draft").and_then(TomlValue::as_bool), Some(false));
+        assert!(toml.get("tags").is_some());
+
+        let mut yaml = YamlLoader::load_from_str("draft: false").unwrap().remove(0);
+        apply_to_yaml(&mut yaml, cascade);
+        assert_eq!(yaml["draft"].as_bool(), Some(false));
+        assert_eq!(yaml["tags"][0].as_str(), Some("cascaded"));
+    }
+}
@@ -0,0 +1,168 @@
+use serde_json::{Map, Number, Value as JsonValue};
+use toml::Value as TomlValue;
+use yaml_rust::Yaml;
+
+use crate::config::FieldMapping;
+use crate::constants;
+
+/// The front matter keys that are read into `PageIndex` fields, everything else is an extra.
+const KNOWN_KEYS: [&str; 18] = [
+    constants::DRAFT,
+    constants::TITLE,
+    constants::SLUG,
+    constants::URL,
+    constants::DATE,
+    constants::PUBLISH_DATE,
+    constants::EXPIRY_DATE,
+    constants::LASTMOD,
+    constants::DESCRIPTION,
+    constants::TAGS,
+    constants::CATEGORIES,
+    constants::SERIES,
+    constants::KEYWORDS,
+    constants::ALIASES,
+    constants::WEIGHT,
+    constants::TYPE,
+    constants::LAYOUT,
+    constants::CASCADE,
+];
+
+fn is_extra(key: &str, fields: &FieldMapping) -> bool {
+    !KNOWN_KEYS.contains(&key) && !fields.is_mapped(key)
+}
+
+/// Collects the keys that aren't read into fields of TOML front matter.
+pub fn from_toml(front_matter: &TomlValue, fields: &FieldMapping) -> Map<String, JsonValue> {
+    front_matter
+        .as_table()
+        .map(|table| {
+            table
+                .iter()
+                .filter(|(key, _)| is_extra(key, fields))
+                .map(|(key, value)| (key.to_owned(), toml_to_json(value)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collects the keys that aren't read into fields of YAML front matter.
+pub fn from_yaml(front_matter: &Yaml, fields: &FieldMapping) -> Map<String, JsonValue> {
+    front_matter
+        .as_hash()
+        .map(|hash| {
+            hash.iter()
+                .filter_map(|(key, value)| yaml_key(key).map(|key| (key, value)))
+                .filter(|(key, _)| is_extra(key, fields))
+                .map(|(key, value)| (key, yaml_to_json(value)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collects the keys that aren't read into fields of JSON front matter.
+pub fn from_json(front_matter: &JsonValue, fields: &FieldMapping) -> Map<String, JsonValue> {
+    front_matter
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .filter(|(key, _)| is_extra(key, fields))
+                .map(|(key, value)| (key.to_owned(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn toml_to_json(value: &TomlValue) -> JsonValue {
+    match value {
+        TomlValue::String(s) => JsonValue::String(s.to_owned()),
+        TomlValue::Integer(i) => JsonValue::from(*i),
+        TomlValue::Float(f) => Number::from_f64(*f).map_or(JsonValue::Null, JsonValue::Number),
+        TomlValue::Boolean(b) => JsonValue::Bool(*b),
+        // Datetimes are written as they appear in the front matter
+        TomlValue::Datetime(datetime) => JsonValue::String(datetime.to_string()),
+        TomlValue::Array(array) => JsonValue::Array(array.iter().map(toml_to_json).collect()),
+        TomlValue::Table(table) => JsonValue::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_owned(), toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn yaml_key(key: &Yaml) -> Option<String> {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => Some(s.to_owned()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) fn yaml_to_json(value: &Yaml) -> JsonValue {
+    match value {
+        Yaml::String(s) => JsonValue::String(s.to_owned()),
+        Yaml::Integer(i) => JsonValue::from(*i),
+        Yaml::Real(_) => value
+            .as_f64()
+            .and_then(Number::from_f64)
+            .map_or(JsonValue::Null, JsonValue::Number),
+        Yaml::Boolean(b) => JsonValue::Bool(*b),
+        Yaml::Array(array) => JsonValue::Array(array.iter().map(yaml_to_json).collect()),
+        Yaml::Hash(hash) => JsonValue::Object(
+            hash.iter()
+                .filter_map(|(key, value)| yaml_key(key).map(|key| (key, yaml_to_json(value))))
+                .collect(),
+        ),
+        Yaml::Null | Yaml::Alias(_) | Yaml::BadValue => JsonValue::Null,
+    }
+}
+
+pub(crate) fn json_to_yaml(value: &JsonValue) -> Yaml {
+    match value {
+        JsonValue::String(s) => Yaml::String(s.to_owned()),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        JsonValue::Bool(b) => Yaml::Boolean(*b),
+        JsonValue::Array(array) => Yaml::Array(array.iter().map(json_to_yaml).collect()),
+        JsonValue::Object(object) => Yaml::Hash(
+            object
+                .iter()
+                .map(|(key, value)| (Yaml::String(key.to_owned()), json_to_yaml(value)))
+                .collect(),
+        ),
+        JsonValue::Null => Yaml::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn toml_extras_exclude_known_keys() {
+        let front_matter: TomlValue = "title = \"Title\"\nauthor = \"Arran\"\nrating = 4.5\npublished = 2019-01-25T07:52:40Z\n[cover]\nimage = \"a.png\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            JsonValue::Object(from_toml(&front_matter, &FieldMapping::default())),
+            json!({"author": "Arran", "rating": 4.5, "published": "2019-01-25T07:52:40Z", "cover": {"image": "a.png"}})
+        );
+    }
+
+    #[test]
+    fn yaml_extras_exclude_known_keys() {
+        let front_matter =
+            &YamlLoader::load_from_str("title: Title\nauthor: Arran\nprojects:\n  - a\n  - 2\n")
+                .unwrap()[0];
+        assert_eq!(
+            JsonValue::Object(from_yaml(front_matter, &FieldMapping::default())),
+            json!({"author": "Arran", "projects": ["a", 2]})
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use serde_json::{Map, Value as JsonValue};
+use toml::Value as TomlValue;
+use yaml_rust::Yaml;
+
+use crate::config::FieldMapping;
+use crate::traverse::extra;
+
+/// Parsed front matter, whichever format it was written in, so the fields of a page can be read from any of them the
+/// same way.
+pub(crate) trait FrontMatter {
+    /// Returns the string at `key`.
+    fn string(&self, key: &str) -> Option<&str>;
+
+    /// Returns the integer at `key`.
+    fn integer(&self, key: &str) -> Option<i64>;
+
+    /// Returns the boolean at `key`.
+    fn boolean(&self, key: &str) -> Option<bool>;
+
+    /// Returns the trimmed strings in the list at `key`, leaving out anything else in it. A missing key is an empty list.
+    fn strings(&self, key: &str) -> Vec<String>;
+
+    /// Collects the keys that aren't read into fields.
+    fn extra(&self, fields: &FieldMapping) -> Map<String, JsonValue>;
+}
+
+impl FrontMatter for TomlValue {
+    fn string(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(TomlValue::as_str)
+    }
+
+    fn integer(&self, key: &str) -> Option<i64> {
+        self.get(key).and_then(TomlValue::as_integer)
+    }
+
+    fn boolean(&self, key: &str) -> Option<bool> {
+        self.get(key).and_then(TomlValue::as_bool)
+    }
+
+    fn strings(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .and_then(TomlValue::as_array)
+            .map(|values| trimmed(values.iter().filter_map(TomlValue::as_str)))
+            .unwrap_or_default()
+    }
+
+    fn extra(&self, fields: &FieldMapping) -> Map<String, JsonValue> {
+        extra::from_toml(self, fields)
+    }
+}
+
+impl FrontMatter for Yaml {
+    fn string(&self, key: &str) -> Option<&str> {
+        self[key].as_str()
+    }
+
+    fn integer(&self, key: &str) -> Option<i64> {
+        self[key].as_i64()
+    }
+
+    fn boolean(&self, key: &str) -> Option<bool> {
+        self[key].as_bool()
+    }
+
+    fn strings(&self, key: &str) -> Vec<String> {
+        self[key]
+            .as_vec()
+            .map(|values| trimmed(values.iter().filter_map(Yaml::as_str)))
+            .unwrap_or_default()
+    }
+
+    fn extra(&self, fields: &FieldMapping) -> Map<String, JsonValue> {
+        extra::from_yaml(self, fields)
+    }
+}
+
+impl FrontMatter for JsonValue {
+    fn string(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(JsonValue::as_str)
+    }
+
+    fn integer(&self, key: &str) -> Option<i64> {
+        self.get(key).and_then(JsonValue::as_i64)
+    }
+
+    fn boolean(&self, key: &str) -> Option<bool> {
+        self.get(key).and_then(JsonValue::as_bool)
+    }
+
+    fn strings(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .and_then(JsonValue::as_array)
+            .map(|values| trimmed(values.iter().filter_map(JsonValue::as_str)))
+            .unwrap_or_default()
+    }
+
+    fn extra(&self, fields: &FieldMapping) -> Map<String, JsonValue> {
+        extra::from_json(self, fields)
+    }
+}
+
+fn trimmed<'a, I: Iterator<Item = &'a str>>(values: I) -> Vec<String> {
+    values.map(|value| value.trim().to_owned()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn formats_are_read_alike() {
+        let toml: TomlValue = "title = \"Post\"\nweight = 3\ndraft = true\ntags = [\" rust \", 1]"
+            .parse()
+            .unwrap();
+        let yaml =
+            YamlLoader::load_from_str("title: Post\nweight: 3\ndraft: true\ntags: [' rust ', 1]")
+                .unwrap()
+                .remove(0);
+        let json: JsonValue = serde_json::from_str(
+            r#"{"title": "Post", "weight": 3, "draft": true, "tags": [" rust ", 1]}"#,
+        )
+        .unwrap();
+        let front_matters: [&dyn FrontMatter; 3] = [&toml, &yaml, &json];
+        for front_matter in &front_matters {
+            assert_eq!(front_matter.string("title"), Some("Post"));
+            assert_eq!(front_matter.integer("weight"), Some(3));
+            assert_eq!(front_matter.boolean("draft"), Some(true));
+            assert_eq!(front_matter.strings("tags"), ["rust"]);
+            assert_eq!(front_matter.strings("categories"), Vec::<String>::new());
+            assert_eq!(front_matter.string("description"), None);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use std::path::Path;
+
+use crate::hugo_to_json_error::HugotoJsonError;
+
+/// The name of the file at the top of a contents directory that lists the paths in it not to traverse.
+pub const IGNORE_FILE_NAME: &str = ".hugotojsonignore";
+
+/// The patterns of an ignore file, written in [gitignore](https://git-scm.com/docs/gitignore) syntax and matched
+/// against paths relative to the contents directory the file is in.
+#[derive(Debug, Clone)]
+pub struct IgnoreFile {
+    gitignore: Gitignore,
+}
+
+impl Default for IgnoreFile {
+    fn default() -> Self {
+        Self {
+            gitignore: Gitignore::empty(),
+        }
+    }
+}
+
+impl IgnoreFile {
+    /// Reads the ignore file at the top of `directory`, which ignores nothing if there isn't one.
+    ///
+    /// # Errors
+    /// Errors if the ignore file can't be read or has a pattern that isn't a valid glob.
+    pub fn load(directory: &Path) -> Result<Self, HugotoJsonError> {
+        let path = directory.join(IGNORE_FILE_NAME);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let mut builder = GitignoreBuilder::new("");
+        if let Some(error) = builder.add(path) {
+            return Err(error.into());
+        }
+        Ok(Self {
+            gitignore: builder.build()?,
+        })
+    }
+
+    /// Parses the patterns of an ignore file. Blank lines and lines starting with `#` are skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// use hugo_to_json::IgnoreFile;
+    /// use std::path::Path;
+    ///
+    /// let ignore_file = IgnoreFile::parse("# Work in progress\ndrafts/\n*.tmp.md\n!keep.tmp.md\n")?;
+    /// assert!(ignore_file.is_ignored(Path::new("drafts"), true));
+    /// assert!(ignore_file.is_ignored(Path::new("post/notes.tmp.md"), false));
+    /// assert!(!ignore_file.is_ignored(Path::new("keep.tmp.md"), false));
+    /// # Ok::<(), hugo_to_json::hugo_to_json_error::HugotoJsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Errors if a pattern isn't a valid glob.
+    pub fn parse(contents: &str) -> Result<Self, HugotoJsonError> {
+        let mut builder = GitignoreBuilder::new("");
+        for line in contents.lines() {
+            builder.add_line(None, line)?;
+        }
+        Ok(Self {
+            gitignore: builder.build()?,
+        })
+    }
+
+    /// Returns true if the path, relative to the contents directory, is ignored, or is in a directory that is. Later
+    /// patterns take precedence over earlier ones, so a negated pattern re-includes what an earlier one ignored.
+    #[must_use]
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        self.gitignore
+            .matched_path_or_any_parents(relative, is_dir)
+            .is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_follow_gitignore_rules() {
+        let ignore_file = IgnoreFile::parse(
+            "/legal\nprivate/\npost/*.draft.md\n\\#notes.md\n!/post/keep.draft.md\n",
+        )
+        .unwrap();
+        // Patterns starting with a slash only match at the top
+        assert!(ignore_file.is_ignored(Path::new("legal"), true));
+        assert!(!ignore_file.is_ignored(Path::new("post/legal"), true));
+        // Patterns ending with a slash only match directories
+        assert!(ignore_file.is_ignored(Path::new("post/private"), true));
+        assert!(!ignore_file.is_ignored(Path::new("private"), false));
+        // Wildcards don't match slashes
+        assert!(ignore_file.is_ignored(Path::new("post/a.draft.md"), false));
+        assert!(!ignore_file.is_ignored(Path::new("post/2019/a.draft.md"), false));
+        assert!(ignore_file.is_ignored(Path::new("#notes.md"), false));
+        assert!(!ignore_file.is_ignored(Path::new("post/keep.draft.md"), false));
+    }
+
+    #[test]
+    fn escapes_keep_trailing_spaces_and_leading_exclamation_marks() {
+        let ignore_file = IgnoreFile::parse("trailing\\ \n\\!important.md\n").unwrap();
+        assert!(ignore_file.is_ignored(Path::new("trailing "), false));
+        assert!(!ignore_file.is_ignored(Path::new("trailing"), false));
+        assert!(ignore_file.is_ignored(Path::new("!important.md"), false));
+        assert!(!ignore_file.is_ignored(Path::new("important.md"), false));
+    }
+
+    #[test]
+    fn children_of_ignored_directories_are_ignored_unless_re_included() {
+        let ignore_file = IgnoreFile::parse("drafts/*\n!drafts/keep.md\n").unwrap();
+        assert!(ignore_file.is_ignored(Path::new("drafts/notes.md"), false));
+        assert!(!ignore_file.is_ignored(Path::new("drafts/keep.md"), false));
+
+        let ignore_file = IgnoreFile::parse("archive/\n").unwrap();
+        assert!(ignore_file.is_ignored(Path::new("archive/2019/post.md"), false));
+    }
+}
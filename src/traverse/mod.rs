@@ -1,5 +1,24 @@
+mod cache;
+mod cascade;
+mod extra;
+mod front_matter;
+mod ignore_file;
+mod path_filter;
+mod shortcodes;
 mod traverse_results;
 mod traverser;
 
+pub use self::cache::Cache;
+pub use self::cascade::Cascades;
+pub(crate) use self::extra::{toml_to_json, yaml_to_json};
+pub use self::ignore_file::{IgnoreFile, IGNORE_FILE_NAME};
+pub use self::path_filter::PathFilter;
 pub use self::traverse_results::TraverseResults;
-pub use self::traverser::Traverser;
+#[cfg(feature = "async")]
+pub(crate) use self::traverser::{check_extension, check_size};
+pub(crate) use self::traverser::{date_normalizer, place};
+pub use self::traverser::{
+    is_hidden_path, log_result, process_document, process_file, process_md_contents,
+    process_md_json_front_matter, process_md_toml_front_matter, process_md_yaml_front_matter,
+    Traverser,
+};
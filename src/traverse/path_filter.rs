@@ -0,0 +1,142 @@
+use globset::{GlobSet, GlobSetBuilder};
+
+use std::path::{Path, PathBuf};
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::settings::Settings;
+use crate::traverse::IgnoreFile;
+
+/// Decides which paths in the contents directories are traversed, using the include and exclude globs in the settings
+/// and the ignore file of each contents directory. Globs are matched against paths relative to the contents directory
+/// they're in.
+pub struct PathFilter {
+    roots: Vec<(PathBuf, IgnoreFile)>,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(settings: &Settings) -> Result<Self, HugotoJsonError> {
+        let include = if settings.include.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for glob in &settings.include {
+                builder.add(glob.clone());
+            }
+            Some(builder.build()?)
+        };
+
+        let mut builder = GlobSetBuilder::new();
+        for glob in &settings.exclude {
+            builder.add(glob.clone());
+        }
+
+        let mut roots = Vec::new();
+        for root in settings.scan_paths() {
+            roots.push((root.to_path_buf(), IgnoreFile::load(root)?));
+        }
+
+        Ok(Self {
+            roots,
+            include,
+            exclude: builder.build()?,
+        })
+    }
+
+    /// Returns true if the entry at `path` should be traversed.
+    /// Excluded and ignored directories are pruned along with everything beneath them, include globs only apply to files.
+    pub fn allows(&self, path: &Path, is_dir: bool) -> bool {
+        let (relative, ignore_file) = match self
+            .roots
+            .iter()
+            .find_map(|(root, ignore_file)| Some((path.strip_prefix(root).ok()?, ignore_file)))
+        {
+            Some((relative, ignore_file)) if !relative.as_os_str().is_empty() => {
+                (relative, ignore_file)
+            }
+            // The contents directories themselves are always traversed
+            _ => return true,
+        };
+
+        if self.exclude.is_match(relative) || ignore_file.is_ignored(relative, is_dir) {
+            return false;
+        }
+        is_dir
+            || self
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(relative))
+    }
+
+    /// Like `allows` but also checks every directory between the contents directory and `path`, for paths that
+    /// weren't reached by walking.
+    pub fn allows_nested(&self, path: &Path, is_dir: bool) -> bool {
+        path.ancestors()
+            .skip(1)
+            .take_while(|ancestor| {
+                self.roots
+                    .iter()
+                    .any(|(root, _)| ancestor.starts_with(root))
+            })
+            .all(|ancestor| self.allows(ancestor, true))
+            && self.allows(path, is_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use globset::Glob;
+
+    fn build_filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        let settings = Settings {
+            scan_path: PathBuf::from("/home/blog/content"),
+            include: include.iter().map(|g| Glob::new(g).unwrap()).collect(),
+            exclude: exclude.iter().map(|g| Glob::new(g).unwrap()).collect(),
+            ..Settings::default()
+        };
+        PathFilter::new(&settings).unwrap()
+    }
+
+    #[test]
+    fn everything_is_allowed_without_globs() {
+        let filter = build_filter(&[], &[]);
+        assert!(filter.allows(Path::new("/home/blog/content/post/a.md"), false));
+        assert!(filter.allows(Path::new("/home/blog/content/legal"), true));
+    }
+
+    #[test]
+    fn include_only_applies_to_files() {
+        let filter = build_filter(&["post/**"], &[]);
+        assert!(filter.allows(Path::new("/home/blog/content/post/a.md"), false));
+        assert!(filter.allows(Path::new("/home/blog/content/talks"), true));
+        assert!(!filter.allows(Path::new("/home/blog/content/talks/a.md"), false));
+    }
+
+    #[test]
+    fn exclude_prunes_directories() {
+        let filter = build_filter(&[], &["legal"]);
+        assert!(!filter.allows(Path::new("/home/blog/content/legal"), true));
+        assert!(filter.allows(Path::new("/home/blog/content/legal/a.md"), false));
+        assert!(!filter.allows_nested(Path::new("/home/blog/content/legal/a.md"), false));
+        assert!(filter.allows_nested(Path::new("/home/blog/content/post/a.md"), false));
+    }
+
+    #[test]
+    fn globs_match_paths_in_every_contents_directory() {
+        let settings = Settings {
+            scan_path: PathBuf::from("/home/blog/content"),
+            more_scan_paths: vec![PathBuf::from("/home/blog/themes/docs/content")],
+            exclude: vec![Glob::new("legal/**").unwrap()],
+            ..Settings::default()
+        };
+        let filter = PathFilter::new(&settings).unwrap();
+        assert!(!filter.allows(Path::new("/home/blog/content/legal/a.md"), false));
+        assert!(!filter.allows(
+            Path::new("/home/blog/themes/docs/content/legal/a.md"),
+            false
+        ));
+        assert!(filter.allows(Path::new("/home/blog/themes/docs/content/post/a.md"), false));
+    }
+}
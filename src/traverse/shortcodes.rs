@@ -0,0 +1,150 @@
+/// The delimiters of a shortcode whose inner text is passed through as it is, and one whose inner text is markdown.
+const DELIMITERS: [(&str, &str); 2] = [("{{<", ">}}"), ("{{%", "%}}")];
+
+#[derive(Debug, PartialEq)]
+/// A piece of a document body, either text or a shortcode tag.
+enum Token<'a> {
+    Text(&'a str),
+    Open { name: &'a str, self_closing: bool },
+    Close { name: &'a str },
+}
+
+/// Removes [Hugo shortcodes](https://gohugo.io/content-management/shortcodes/) from a document body, such as
+/// `{{< figure src="a.png" >}}` and `{{% note %}}...{{% /note %}}`. Paired shortcodes are removed along with
+/// everything between their tags, unless `keep_inner_text` is set, in which case only the tags are removed.
+pub fn strip_shortcodes(body: &str, keep_inner_text: bool) -> String {
+    if !body.contains("{{") {
+        return body.to_owned();
+    }
+    render(&tokenize(body), keep_inner_text)
+}
+
+fn render(tokens: &[Token], keep_inner_text: bool) -> String {
+    let mut output = String::new();
+    let mut position = 0;
+    while position < tokens.len() {
+        match tokens[position] {
+            Token::Text(text) => output.push_str(text),
+            Token::Open {
+                name,
+                self_closing: false,
+            } => {
+                if let Some(close) = matching_close(tokens, position, name) {
+                    if keep_inner_text {
+                        output.push_str(&render(&tokens[position + 1..close], keep_inner_text));
+                    }
+                    position = close;
+                }
+            }
+            // Unpaired and stray closing tags are dropped on their own
+            _ => (),
+        }
+        position += 1;
+    }
+    output
+}
+
+/// Finds the position of the tag closing the shortcode opened at `open`, allowing for shortcodes of the same name
+/// nested inside it.
+fn matching_close(tokens: &[Token], open: usize, name: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(open + 1) {
+        match *token {
+            Token::Open {
+                name: nested,
+                self_closing: false,
+            } if nested == name => depth += 1,
+            Token::Close { name: closed } if closed == name => {
+                if depth == 0 {
+                    return Some(position);
+                }
+                depth -= 1;
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn tokenize(body: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+    while let Some((start, (open, close))) = next_delimiter(rest) {
+        let inner_start = start + open.len();
+        let Some(inner_length) = rest[inner_start..].find(close) else {
+            // An unterminated shortcode is left as text
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        tokens.push(tag(&rest[inner_start..inner_start + inner_length]));
+        rest = &rest[inner_start + inner_length + close.len()..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// Finds the earliest shortcode opening delimiter in `text`, returning its position and the delimiters.
+fn next_delimiter(text: &str) -> Option<(usize, (&'static str, &'static str))> {
+    DELIMITERS
+        .iter()
+        .filter_map(|&(open, close)| text.find(open).map(|start| (start, (open, close))))
+        .min_by_key(|&(start, _)| start)
+}
+
+/// Reads the name of a shortcode tag from what's between its delimiters, e.g. ` figure src="a.png" `.
+fn tag(inner: &str) -> Token<'_> {
+    let inner = inner.trim();
+    let self_closing = inner.ends_with('/');
+    let inner = inner.trim_end_matches('/').trim();
+    match inner.strip_prefix('/') {
+        Some(closing) => Token::Close {
+            name: closing.split_whitespace().next().unwrap_or_default(),
+        },
+        None => Token::Open {
+            name: inner.split_whitespace().next().unwrap_or_default(),
+            self_closing,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_unpaired_shortcodes() {
+        assert_eq!(
+            strip_shortcodes(
+                "Before {{< figure src=\"a.png\" >}} after {{< br />}}",
+                false
+            ),
+            "Before  after "
+        );
+    }
+
+    #[test]
+    fn removes_paired_shortcodes_and_their_inner_text() {
+        let body = "A {{% note %}}Nested {{< b >}}bold{{< /b >}}{{% /note %}} B";
+        assert_eq!(strip_shortcodes(body, false), "A  B");
+        assert_eq!(strip_shortcodes(body, true), "A Nested bold B");
+    }
+
+    #[test]
+    fn nested_shortcodes_with_the_same_name_pair_up() {
+        let body = "{{< div >}}one {{< div >}}two{{< /div >}} three{{< /div >}} four";
+        assert_eq!(strip_shortcodes(body, true), "one two three four");
+        assert_eq!(strip_shortcodes(body, false), " four");
+    }
+
+    #[test]
+    fn leaves_text_that_isnt_a_shortcode() {
+        assert_eq!(
+            strip_shortcodes("Templates use {{ .Title }} and {{< unterminated", false),
+            "Templates use {{ .Title }} and {{< unterminated"
+        );
+    }
+}
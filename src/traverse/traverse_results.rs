@@ -15,4 +15,4 @@ impl TraverseResults {
             errors,
         }
     }
-}
\ No newline at end of file
+}
@@ -1,331 +1,1034 @@
-use strip_markdown::strip_markdown;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use toml::Value;
 use walkdir::{DirEntry, WalkDir};
 use yaml_rust::YamlLoader;
 
-use num_cpus;
-use std::fs;
-use std::path::PathBuf;
-use std::sync::mpsc::channel;
-use threadpool::ThreadPool;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::panic;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::thread;
 
 use crate::constants;
-use crate::file_location::*;
-use crate::hugo_to_json_error::*;
-use crate::operation_result::*;
-use crate::page_index::PageIndex;
+use crate::date::DateNormalizer;
+use crate::extract::{
+    asciidoc_text, extract_code_blocks, extract_headings, extract_links, first_image, rst_text,
+    split_sections,
+};
+use crate::file_location::FileLocation;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::links::Link;
+use crate::normalize::{normalize_text, DEFAULT_LANGUAGE};
+use crate::operation_result::{OperationResult, ParseError, Skip, SkipReason};
+use crate::page_index::{build_href, PageIndex, PageSection, DEFAULT_SUMMARY_LENGTH};
+use crate::progress::Progress;
+use crate::settings::{FrontMatterFormat, FrontMatterMode, Granularity, Settings};
+use crate::slug::SlugOptions;
+use crate::traverse::front_matter::FrontMatter;
+use crate::traverse::shortcodes::strip_shortcodes;
+use crate::traverse::{cascade, Cache, Cascades, PathFilter};
+
+use serde_json::Value as JsonValue;
+
+/// How many files each thread may be processing or have processed ahead of the results handed on, which bounds how many
+/// results are held in memory however many files there are.
+const FILES_IN_FLIGHT_PER_THREAD: usize = 64;
+
+/// The largest buffer a thread keeps to read documents into, in bytes.
+const MAX_KEPT_BUFFER_CAPACITY: usize = 1 << 20;
+
+thread_local! {
+    /// The buffer each thread reads documents into, reused so reading each file doesn't allocate.
+    static DOCUMENT_BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// The result of processing a file, alongside the location of that file.
+pub type LocatedResult = (FileLocation, Result<PageIndex, OperationResult>);
 
 pub struct Traverser {
-    contents_directory_path: PathBuf,
+    settings: Settings,
 }
 
 impl Traverser {
-    pub fn new(contents_directory_path: PathBuf) -> Self {
-        Self {
-            contents_directory_path,
-        }
+    pub fn new(settings: Settings) -> Self {
+        Self { settings }
     }
 
-    /// Uses multiple threads to traverse
+    /// Collects the files in the contents directory then processes them in parallel.
+    /// Results are in the order the files were walked, which is sorted by file name.
     pub fn traverse_files(
         &self,
     ) -> Result<Vec<Result<PageIndex, OperationResult>>, HugotoJsonError> {
-        let mut index = Vec::new();
+        let index = self
+            .traverse_locations()?
+            .into_iter()
+            .map(|(_, result)| result)
+            .filter(|result| match result {
+                Err(OperationResult::Skip(_)) => false, // Skips don't need to be handled
+                _ => true,
+            })
+            .collect();
 
-        // TODO: Attempt to use Raynon for a speed increase.
+        Ok(index)
+    }
 
-        let thread_count = num_cpus::get();
-        let pool = ThreadPool::new(thread_count);
-        let (tx, rx) = channel();
+    /// Like `traverse_files` but pairs each result with the location of the file it came from, including skipped files.
+    pub fn traverse_locations(&self) -> Result<Vec<LocatedResult>, HugotoJsonError> {
+        let mut results = Vec::new();
+        self.traverse_streaming(|located_result| {
+            results.push(located_result);
+            Ok(())
+        })?;
+        Ok(results)
+    }
 
-        // This errors early if the path doesn't exist
-        fs::metadata(&self.contents_directory_path)?;
+    /// Processes the files in the contents directory in parallel, handing each result to `on_result` as soon as it and
+    /// every result walked before it are ready. Only a bounded number of results waiting on an earlier file, or on
+    /// `on_result`, are held in memory.
+    /// Stops early if `on_result` returns an error.
+    pub fn traverse_streaming<F>(&self, mut on_result: F) -> Result<(), HugotoJsonError>
+    where
+        F: FnMut(LocatedResult) -> Result<(), HugotoJsonError>,
+    {
+        let file_locations = self.collect_locations()?;
+        let mut progress = Progress::new(&self.settings, file_locations.len());
+        let settings = self.settings.clone();
+        let caching = settings.cache_file.is_some();
+        let cache = if caching {
+            Cache::load(&settings)
+        } else {
+            Cache::default()
+        };
+        // Files are processed a window at a time and the channel is bounded, so workers wait for slow writers rather
+        // than piling up results
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(settings.threads())
+            .build()
+            .map_err(|error| io::Error::other(error.to_string()))?;
+        let window = pool.current_num_threads() * FILES_IN_FLIGHT_PER_THREAD;
+        let (sender, receiver) = sync_channel(window);
+
+        let worker = thread::spawn(move || {
+            let hung_up = AtomicBool::new(false);
+            let mut file_locations = file_locations.into_iter().enumerate();
+            while !hung_up.load(Ordering::Relaxed) {
+                let locations: Vec<_> = file_locations.by_ref().take(window).collect();
+                if locations.is_empty() {
+                    break;
+                }
+                pool.install(|| {
+                    locations.into_par_iter().for_each_with(
+                        sender.clone(),
+                        |sender, (position, file_location)| {
+                            let (cached, stamp) = if caching {
+                                cache.lookup(&file_location, &settings)
+                            } else {
+                                (None, None)
+                            };
+                            let result = if let Some(page_index) = cached {
+                                debug!("Reusing cached {file_location}");
+                                Ok(page_index)
+                            } else {
+                                debug!("Processing {file_location}");
+                                process_file(&file_location, &settings)
+                            };
+                            // The receiver only hangs up once it has failed, so the rest of the files don't need to be
+                            // processed
+                            if sender
+                                .send((position, ((file_location, result), stamp)))
+                                .is_err()
+                            {
+                                hung_up.store(true, Ordering::Relaxed);
+                            }
+                        },
+                    );
+                });
+            }
+            drop(sender);
+            cache
+        });
+
+        // Results arrive in whatever order they finish in, so hold them until it's their turn
+        let mut pending = BTreeMap::new();
+        let mut next_position = 0;
+        let mut outcome = Ok(());
+        let mut cached_pages = Vec::new();
+        'receiving: for (position, located_result) in &receiver {
+            progress.advance();
+            pending.insert(position, located_result);
+            while let Some((located_result, stamp)) = pending.remove(&next_position) {
+                next_position += 1;
+                log_result(&located_result.0, &located_result.1);
+                if let (Some(stamp), Ok(ref page_index)) = (stamp, &located_result.1) {
+                    cached_pages.push((
+                        located_result.0.absolute_path.clone(),
+                        stamp,
+                        page_index.clone(),
+                    ));
+                }
+                if let Err(error) = on_result(located_result) {
+                    outcome = Err(error);
+                    break 'receiving;
+                }
+            }
+        }
+        drop(receiver);
+        progress.finish();
+
+        let mut cache = match worker.join() {
+            Ok(cache) => cache,
+            Err(panic) => panic::resume_unwind(panic),
+        };
+        if caching && outcome.is_ok() {
+            cache.replace(cached_pages);
+            cache.save(&self.settings);
+        }
+        outcome
+    }
 
-        for entry in WalkDir::new(&self.contents_directory_path)
-            .into_iter()
-            .filter_entry(|e| !is_hidden(e))
-        {
-            match entry {
-                Ok(ref file) => {
-                    let file_location = FileLocation::new(file, &self.contents_directory_path);
-                    // TODO: What should be done in this case?
-                    if file_location.is_err() {
-                        continue;
-                    }
+    /// Walks each contents directory in file name order, collecting the location of every file that isn't hidden along
+    /// with the front matter cascaded to it.
+    pub(crate) fn collect_locations(&self) -> Result<Vec<FileLocation>, HugotoJsonError> {
+        let path_filter = PathFilter::new(&self.settings)?;
+        let mut file_locations = Vec::new();
+        let mut site_paths = HashSet::new();
+        for scan_path in self.settings.scan_paths() {
+            // This errors early if the path doesn't exist
+            fs::metadata(scan_path)?;
+            for file_location in self.walk(scan_path, &path_filter) {
+                let site_path = (
+                    file_location.lang.clone(),
+                    file_location.relative_directory_to_content.clone(),
+                    file_location.file_name.clone(),
+                );
+                // Like Hugo's mounts, a file in an earlier contents directory hides those at the same path in later ones
+                if site_paths.insert(site_path) {
+                    file_locations.push(file_location);
+                } else {
+                    debug!(
+                        "Skipping {file_location}, a file in an earlier contents directory has its path"
+                    );
+                }
+            }
+        }
 
-                    let thread_tx = tx.clone();
-                    let file_location = file_location.unwrap();
+        // Cascades are read from every section first, so they're ready for each file however the files are processed
+        let cascades = Cascades::collect(&file_locations, self.settings.front_matter);
+        for file_location in &mut file_locations {
+            cascades.apply(file_location);
+        }
 
-                    pool.execute(move || {
-                        debug!("Processing {}", &file_location);
-                        let process_result = process_file(&file_location);
-                        thread_tx.send(process_result).expect("Channel exists");
-                    });
-                }
+        Ok(file_locations)
+    }
+
+    /// Walks a contents directory in file name order, collecting the location of every file that isn't hidden. Symbolic
+    /// links are followed if the settings ask, and only as deep as the settings' maximum depth.
+    fn walk(&self, scan_path: &Path, path_filter: &PathFilter) -> Vec<FileLocation> {
+        let mut walk_dir = WalkDir::new(scan_path)
+            .follow_links(self.settings.follow_symlinks)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        if let Some(max_depth) = self.settings.max_depth {
+            walk_dir = walk_dir.max_depth(max_depth);
+        }
+        walk_dir
+            .into_iter()
+            .filter_entry(|e| {
+                (self.settings.include_hidden || !is_hidden(e))
+                    && path_filter.allows(e.path(), e.file_type().is_dir())
+            })
+            .filter_map(|entry| match entry {
+                // TODO: What should be done when a location can't be built?
+                Ok(ref file) => FileLocation::new(file, scan_path)
+                    .map(|file_location| place(file_location, scan_path, &self.settings))
+                    .ok(),
                 Err(error) => {
-                    if let Some(io_error) = error.into_io_error() {
-                        error!("Failed {}", io_error)
+                    let file = error.path().map(Path::to_path_buf).unwrap_or_default();
+                    if let Some(ancestor) = error.loop_ancestor() {
+                        warn!(
+                            file:% = file.display(), kind = "skip";
+                            "Skipping {}, it links back to {}",
+                            file.to_string_lossy(),
+                            ancestor.to_string_lossy()
+                        );
+                    } else if let Some(io_error) = error.into_io_error() {
+                        error!(file:% = file.display(), kind = "io"; "Failed {io_error}");
                     } else {
-                        error!("Error reading unknown file")
+                        error!("Error reading unknown file");
                     }
+                    None
                 }
-            }
-        }
+            })
+            .collect()
+    }
+}
 
-        // This sender must be dropped as otherwise the iterator blocks as it's possible for the channel to still send messages
-        drop(tx);
+/// Places a file from the contents directory `scan_path` in the site: at the target of the module mount the directory
+/// comes from, if it does, and in its language.
+pub(crate) fn place(
+    file_location: FileLocation,
+    scan_path: &Path,
+    settings: &Settings,
+) -> FileLocation {
+    match settings.content_mount(scan_path) {
+        Some(mount) => file_location.mounted_at(mount, &settings.languages),
+        None => file_location.with_languages(&settings.languages),
+    }
+}
 
-        for result in rx {
-            match result {
-                Err(OperationResult::Skip(ref err)) => warn!("{}", err), // Skips don't need to be handled
-                Err(OperationResult::Path(ref err)) => {
-                    error!("{}", err);
-                    index.push(result);
-                }
-                Err(OperationResult::Parse(ref err)) => {
-                    error!("{}", err);
-                    index.push(result);
-                }
-                Err(OperationResult::Io(ref err)) => {
-                    error!("{}", err);
-                    index.push(result);
-                }
-                Ok(_) => index.push(result),
-            }
+/// Logs skips as warnings and failures as errors, with the file and the kind of result so they can be picked out of
+/// structured logs.
+pub fn log_result(file_location: &FileLocation, result: &Result<PageIndex, OperationResult>) {
+    let file = file_location.absolute_path.as_str();
+    match result {
+        Err(OperationResult::Skip(ref err)) => warn!(file = file, kind = "skip"; "{err}"),
+        Err(ref err) => {
+            error!(file = file, kind = err.kind(), code = err.code().as_str(); "{err}");
         }
-
-        pool.join();
-        Ok(index)
+        Ok(_) => (),
     }
 }
 
-fn process_file(file_location: &FileLocation) -> Result<PageIndex, OperationResult> {
+/// Processes a single file into a `PageIndex`, applying the post processing options in `settings`. Files larger than
+/// the settings' maximum file size are skipped without being read.
+pub fn process_file(
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<PageIndex, OperationResult> {
+    check_extension(file_location)?;
+    let mut file = File::open(&file_location.absolute_path)?;
+    let size = file.metadata()?.len();
+    check_size(file_location, size, settings)?;
+    DOCUMENT_BUFFER.with(|buffer| {
+        let mut contents = buffer.borrow_mut();
+        contents.clear();
+        contents.reserve(usize::try_from(size).unwrap_or_default());
+        file.read_to_string(&mut contents)?;
+        let result = process_document(&contents, file_location, settings);
+        // An unusually large document shouldn't hold on to its memory
+        if contents.capacity() > MAX_KEPT_BUFFER_CAPACITY {
+            *contents = String::new();
+        }
+        result
+    })
+}
+
+/// Skips the file unless it has the extension of a kind of document that can be processed.
+pub(crate) fn check_extension(file_location: &FileLocation) -> Result<(), OperationResult> {
     match file_location.extension.as_ref() {
-        constants::MARKDOWN_EXTENSION => process_md_file(&file_location),
+        // Markdown, AsciiDoc and reStructuredText all have front matter in the same formats before their body
+        constants::MARKDOWN_EXTENSION
+        | constants::ASCIIDOC_EXTENSION
+        | constants::RST_EXTENSION => Ok(()),
         // TODO: .html files
-        _ => Err(OperationResult::Path(PathError::new(
+        // TODO: Handle None
+        _ => Err(OperationResult::Skip(Skip::new(
             &file_location.absolute_path,
-            "Not a compatible file extension.",
+            SkipReason::UnsupportedExtension,
         ))),
-        // TODO: Handle None
     }
 }
 
-fn process_md_file(file_location: &FileLocation) -> Result<PageIndex, OperationResult> {
-    let contents = fs::read_to_string(file_location.absolute_path.to_string())?;
-    let first_line = contents.lines().find(|&l| !l.trim().is_empty());
-
-    match first_line.unwrap_or_default().chars().next() {
-        Some('+') => process_md_toml_front_matter(&contents, &file_location),
-        Some('-') => process_md_yaml_front_matter(&contents, &file_location),
-        // TODO: JSON frontmatter '{' => process_json_frontmatter()
-        _ => Err(OperationResult::Parse(ParseError::new(
+/// Skips the file if it's `size` bytes, which is larger than the settings' maximum file size.
+pub(crate) fn check_size(
+    file_location: &FileLocation,
+    size: u64,
+    settings: &Settings,
+) -> Result<(), OperationResult> {
+    if settings
+        .max_file_size
+        .is_some_and(|max_file_size| size > max_file_size)
+    {
+        return Err(OperationResult::Skip(Skip::new(
             &file_location.absolute_path,
-            "Could not determine file front matter type.",
-        ))),
+            SkipReason::TooLarge,
+        )));
     }
+    Ok(())
 }
 
-fn process_md_toml_front_matter(
+/// Processes the contents of a document that lives at `file_location` into a `PageIndex`, applying the post processing
+/// options in `settings`. The document doesn't need to have been read from `file_location`.
+///
+/// # Errors
+/// Returns a `ParseError` if the front matter can't be parsed, or a `Skip` if the document wouldn't be published.
+#[allow(clippy::too_many_lines)]
+pub fn process_document(
     contents: &str,
     file_location: &FileLocation,
+    settings: &Settings,
 ) -> Result<PageIndex, OperationResult> {
-    let split_content: Vec<&str> = contents.trim().split(constants::TOML_FENCE).collect();
+    let mut page_index = process_md_contents(contents, file_location, settings)?;
 
-    let length = split_content.len();
-    if length <= 1 {
-        return Err(OperationResult::Parse(ParseError::new(
-            &file_location.absolute_path,
-            "Could not split on TOML fence.",
-        )));
+    check_publication(&page_index, file_location, settings, Utc::now())?;
+    if !settings.include_unlisted {
+        check_listing(&page_index, file_location)?;
+    }
+    page_index.path.clone_from(&file_location.absolute_path);
+    page_index.read_authors();
+    page_index.read_images();
+    page_index.read_menus();
+    page_index.read_location();
+    if file_location.extension == constants::MARKDOWN_EXTENSION {
+        let body = document_body(contents, settings.front_matter);
+        if settings.body_image {
+            if let Some(image) =
+                first_image(body).filter(|image| !page_index.images.contains(image))
+            {
+                page_index.images.push(image);
+            }
+        }
+        if settings.headings {
+            page_index.headings = extract_headings(body);
+        }
+        if settings.keeps_links() {
+            // Links are found in the body, but their lines are counted from the top of the file
+            let body_line = contents[..contents.len() - body.len()]
+                .matches('\n')
+                .count();
+            page_index.links = extract_links(body)
+                .into_iter()
+                .map(|link| Link {
+                    line: link.line + body_line,
+                    ..link
+                })
+                .collect();
+        }
+        if settings.granularity == Granularity::Heading {
+            page_index.sections = split_sections(body)
+                .into_iter()
+                .map(|(heading, markdown)| PageSection {
+                    heading,
+                    content: strip_body(markdown, file_location, settings).0,
+                })
+                .filter(|section| section.heading.is_some() || !section.content.is_empty())
+                .collect();
+        }
+    }
+    // Params are collected before taxonomy terms are moved out of the extra fields, as Hugo keeps them in `.Params`
+    if settings.params {
+        page_index.collect_params();
+    }
+    page_index.collect_taxonomies(&settings.site.taxonomies);
+    page_index.read_sitemap();
+    // The front matter language is read before the extra fields that aren't wanted are dropped
+    let language = content_language(&page_index, settings).to_owned();
+    page_index.retain_extra_fields(&settings.extra_fields, settings.all_fields);
+
+    if settings.bundle_resources {
+        page_index.resources = file_location.bundle_resources();
     }
 
-    let front_matter = split_content[length - 2]
-        .trim()
-        .parse::<Value>()
-        .map_err(|_| {
+    if settings.dedupe_description {
+        page_index.dedupe_description();
+    }
+
+    page_index.summarize(
+        settings
+            .summary_length
+            .or(settings.site.summary_length)
+            .unwrap_or(DEFAULT_SUMMARY_LENGTH),
+    );
+
+    // The summary is taken first so it stays readable
+    if settings.normalize_content {
+        page_index.content = normalize_text(&page_index.content, &language);
+        for section in &mut page_index.sections {
+            section.content = normalize_text(&section.content, &language);
+        }
+    }
+
+    // The summary is taken first so it isn't cut short too
+    if let Some(max_content_length) = settings.max_content_length {
+        page_index.truncate_content(max_content_length);
+    }
+
+    if let Some(href) = settings.site.permalink(
+        &page_index,
+        file_location,
+        date_normalizer(settings).to_local_date(&page_index.date),
+    ) {
+        page_index.href = href;
+    } else if settings.site.slugs != SlugOptions::default() {
+        // Pages are created with Hugo's default slugs, which the site config can change
+        page_index.href = build_href(
+            page_index.slug.as_deref(),
+            page_index.url.as_deref(),
+            file_location,
+            settings.site.slugs,
+        );
+    }
+
+    if let Some(href) = settings.site.ugly_href(&page_index) {
+        page_index.href = href;
+    }
+
+    if settings.absolute_hrefs || settings.site.canonify_urls {
+        page_index.href = settings.site.absolute_href(&page_index.href);
+    }
+
+    if !settings.date_formats.is_empty()
+        || settings.date_locale.is_some()
+        || settings.timezone.is_some()
+    {
+        let normalizer = date_normalizer(settings);
+        page_index.date = normalizer.normalize(&page_index.date).ok_or_else(|| {
             ParseError::new(
                 &file_location.absolute_path,
-                "Could not parse TOML front matter.",
+                &format!(
+                    "Could not parse date `{}` with the configured formats",
+                    page_index.date
+                ),
             )
+            .with_key(constants::DATE)
         })?;
-    let is_draft = front_matter
-        .get(constants::DRAFT)
-        .and_then(Value::as_bool)
-        .unwrap_or(false);
+    }
 
-    // TODO: Add a flag to allow indexing drafts
-    if is_draft {
-        return Err(OperationResult::Skip(Skip::new(
+    Ok(page_index)
+}
+
+/// Returns the language a page's content is in: the `language` in its front matter, the language of a multilingual
+/// site it's in, the `--language` setting or the site's default content language, in that order.
+fn content_language<'a>(page_index: &'a PageIndex, settings: &'a Settings) -> &'a str {
+    page_index
+        .extra
+        .get(constants::LANGUAGE)
+        .and_then(JsonValue::as_str)
+        .or_else(|| Some(page_index.lang.as_str()).filter(|lang| !lang.is_empty()))
+        .or(settings.language.as_deref())
+        .or(settings.site.default_language.as_deref())
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// Creates a `DateNormalizer` with the date formats, locale and timezone in the settings.
+pub(crate) fn date_normalizer(settings: &Settings) -> DateNormalizer {
+    let normalizer = DateNormalizer::new(settings.date_formats.clone(), settings.date_locale);
+    match settings.timezone {
+        Some(timezone) => normalizer.with_timezone(timezone),
+        None => normalizer,
+    }
+}
+
+/// Skips drafts unless the settings ask for them, and documents that aren't drafts if the settings only ask for drafts.
+fn check_draft(
+    is_draft: bool,
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<(), OperationResult> {
+    let reason = if is_draft && !settings.drafts && !settings.drafts_only {
+        SkipReason::Draft
+    } else if !is_draft && settings.drafts_only {
+        SkipReason::NotDraft
+    } else {
+        return Ok(());
+    };
+    Err(OperationResult::Skip(Skip::new(
+        &file_location.absolute_path,
+        reason,
+    )))
+}
+
+/// Skips documents that Hugo wouldn't publish at `now`, because their publish date is in the future or their expiry
+/// date has passed, unless the settings ask for them. Like Hugo, the publish date falls back to the date. Like an
+/// unparseable date, a publish or expiry date that can't be parsed is warned about and ignored, unless date formats
+/// are configured.
+///
+/// # Errors
+/// Returns a `ParseError` if the publish or expiry date can't be parsed with the configured date formats.
+fn check_publication(
+    page_index: &PageIndex,
+    file_location: &FileLocation,
+    settings: &Settings,
+    now: DateTime<Utc>,
+) -> Result<(), OperationResult> {
+    let normalizer = date_normalizer(settings);
+    let to_instant = |date: &str, key: &str| match normalizer.to_instant(date) {
+        Some(instant) => Ok(Some(instant)),
+        None if settings.date_formats.is_empty() => {
+            warn!(
+                file = file_location.absolute_path.as_str(), kind = "parse";
+                "Could not parse {key} `{date}`, so it's ignored"
+            );
+            Ok(None)
+        }
+        None => Err(ParseError::new(
             &file_location.absolute_path,
-            "Is draft.",
-        )));
+            &format!("Could not parse date `{date}` with the configured formats"),
+        )
+        .with_key(key)),
+    };
+
+    if !settings.build_future {
+        let publish_date = match page_index.publish_date {
+            Some(ref publish_date) => to_instant(publish_date, constants::PUBLISH_DATE)?,
+            // Dates that can't be parsed are left for date normalisation to report, if it's enabled
+            None => normalizer.to_instant(&page_index.date),
+        };
+        if publish_date.is_some_and(|publish_date| publish_date > now) {
+            return Err(OperationResult::Skip(Skip::new(
+                &file_location.absolute_path,
+                SkipReason::FutureDated,
+            )));
+        }
     }
 
-    let title = front_matter.get(constants::TITLE).and_then(Value::as_str);
-    let slug = front_matter.get(constants::SLUG).and_then(Value::as_str);
-    let date = front_matter.get(constants::DATE).and_then(Value::as_str);
-    let description = front_matter
-        .get(constants::DESCRIPTION)
-        .and_then(Value::as_str);
-    let url = front_matter.get(constants::URL).and_then(Value::as_str);
-
-    let categories: Vec<String> = front_matter
-        .get(constants::CATEGORIES)
-        .and_then(Value::as_array)
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let series: Vec<String> = front_matter
-        .get(constants::SERIES)
-        .and_then(Value::as_array)
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let tags: Vec<String> = front_matter
-        .get(constants::TAGS)
-        .and_then(Value::as_array)
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let keywords: Vec<String> = front_matter
-        .get(constants::KEYWORDS)
-        .and_then(Value::as_array)
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let content = strip_markdown(split_content[length - 1].trim());
-
-    PageIndex::new(
-        title,
-        slug,
-        date,
-        description,
-        categories,
-        series,
-        tags,
-        keywords,
-        content,
-        &file_location,
-        url,
-    )
+    if !settings.build_expired {
+        if let Some(ref expiry_date) = page_index.expiry_date {
+            if to_instant(expiry_date, constants::EXPIRY_DATE)?
+                .is_some_and(|expiry_date| expiry_date <= now)
+            {
+                return Err(OperationResult::Skip(Skip::new(
+                    &file_location.absolute_path,
+                    SkipReason::Expired,
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Skips documents Hugo doesn't list or render: the `index.md` of a headless bundle, and documents whose `_build` front
+/// matter sets `list` or `render` to `false` or `never`. Documents only listed in their own section, with a `list` of
+/// `local`, aren't in site wide lists so are skipped too.
+fn check_listing(
+    page_index: &PageIndex,
+    file_location: &FileLocation,
+) -> Result<(), OperationResult> {
+    let skip = |reason| {
+        Err(OperationResult::Skip(Skip::new(
+            &file_location.absolute_path,
+            reason,
+        )))
+    };
+
+    let headless = page_index.extra.get(constants::HEADLESS) == Some(&JsonValue::Bool(true));
+    if headless && file_location.is_bundle() {
+        return skip(SkipReason::Headless);
+    }
+
+    let build_options = page_index.extra.get(constants::BUILD_OPTIONS);
+    let option = |key| build_options.and_then(|options| options.get(key));
+    let listed = match option(constants::BUILD_LIST) {
+        Some(JsonValue::Bool(list)) => *list,
+        Some(JsonValue::String(list)) => list == "always",
+        _ => true,
+    };
+    let rendered = match option(constants::BUILD_RENDER) {
+        Some(JsonValue::Bool(render)) => *render,
+        Some(JsonValue::String(render)) => render != "never",
+        _ => true,
+    };
+    if !listed || !rendered {
+        return skip(SkipReason::Unlisted);
+    }
+    Ok(())
 }
 
-fn process_md_yaml_front_matter(
+/// Creates a `PageIndex` from the contents of a markdown document, detecting whether its front matter is TOML, YAML or JSON
+/// unless the settings say which it is.
+///
+/// # Errors
+/// Returns a `ParseError` if the front matter type can't be determined or parsed, or a `Skip` if the document is left out for being, or not
+/// being, a draft.
+pub fn process_md_contents(
     contents: &str,
     file_location: &FileLocation,
+    settings: &Settings,
 ) -> Result<PageIndex, OperationResult> {
-    let split_content: Vec<&str> = contents.trim().split(constants::YAML_FENCE).collect();
-    let length = split_content.len();
-    if length <= 1 {
-        return Err(OperationResult::Parse(ParseError::new(
+    match front_matter_format(contents, settings.front_matter) {
+        Some(FrontMatterFormat::Toml) => {
+            process_md_toml_front_matter(contents, file_location, settings)
+        }
+        Some(FrontMatterFormat::Yaml) => {
+            process_md_yaml_front_matter(contents, file_location, settings)
+        }
+        Some(FrontMatterFormat::Json) => {
+            process_md_json_front_matter(contents, file_location, settings)
+        }
+        _ => Err(OperationResult::Parse(ParseError::new(
             &file_location.absolute_path,
-            "Could not split on YAML fence.",
-        )));
+            "Could not determine file front matter type",
+        ))),
     }
+}
 
-    let front_matter = split_content[1].trim();
-    let front_matter = YamlLoader::load_from_str(front_matter).map_err(|_| {
-        ParseError::new(
+/// Works out the format of a document's front matter. A forced format is taken as it is, otherwise it's told from the
+/// document's first non-empty line: a `+++` or `---` fence on its own, or the `{` a JSON object opens with. Documents
+/// that open with anything else, such as a `- item` list, have no front matter that can be read.
+pub(crate) fn front_matter_format(
+    contents: &str,
+    mode: FrontMatterMode,
+) -> Option<FrontMatterFormat> {
+    if let FrontMatterMode::Force(format) = mode {
+        return Some(format);
+    }
+    let first_line = contents.lines().find(|&l| !l.trim().is_empty())?.trim();
+    if first_line == constants::TOML_FENCE {
+        Some(FrontMatterFormat::Toml)
+    } else if first_line == constants::YAML_FENCE {
+        Some(FrontMatterFormat::Yaml)
+    } else if first_line.starts_with('{') {
+        Some(FrontMatterFormat::Json)
+    } else {
+        None
+    }
+}
+
+/// Creates a `PageIndex` from the contents of a markdown document with TOML front matter.
+///
+/// # Errors
+/// Returns a `ParseError` if the front matter can't be parsed, or a `Skip` if the document is left out for being, or not
+/// being, a draft.
+#[allow(clippy::too_many_lines)]
+pub fn process_md_toml_front_matter(
+    contents: &str,
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<PageIndex, OperationResult> {
+    let (front_matter_source, body) = split_front_matter(contents, constants::TOML_FENCE)
+        .ok_or_else(|| {
+            OperationResult::Parse(ParseError::new(
+                &file_location.absolute_path,
+                "Could not split on TOML fence",
+            ))
+        })?;
+
+    let front_matter_source = front_matter_source.trim();
+    let mut front_matter = front_matter_source.parse::<Value>().map_err(|err| {
+        let error = ParseError::new(
             &file_location.absolute_path,
-            "Could not parse YAML front matter.",
+            "Could not parse TOML front matter",
+        );
+        // toml reports 0-based positions relative to the front matter block
+        let error = match err.line_col() {
+            Some((line, column)) => at_position(
+                error,
+                contents,
+                lines_before(contents, front_matter_source) + line + 1,
+                column + 1,
+            ),
+            None => error,
+        };
+        error.with_source(err)
+    })?;
+    cascade::apply_to_toml(&mut front_matter, &file_location.cascade);
+    read_page_index(&front_matter, body, file_location, settings)
+}
+
+/// Creates a `PageIndex` from the contents of a markdown document with YAML front matter.
+///
+/// # Errors
+/// Returns a `ParseError` if the front matter can't be parsed, or a `Skip` if the document is left out for being, or not
+/// being, a draft.
+#[allow(clippy::too_many_lines)]
+pub fn process_md_yaml_front_matter(
+    contents: &str,
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<PageIndex, OperationResult> {
+    let (front_matter_source, body) = split_front_matter(contents, constants::YAML_FENCE)
+        .ok_or_else(|| {
+            OperationResult::Parse(ParseError::new(
+                &file_location.absolute_path,
+                "Could not split on YAML fence",
+            ))
+        })?;
+
+    let front_matter_source = front_matter_source.trim();
+    let front_matter = YamlLoader::load_from_str(front_matter_source).map_err(|err| {
+        // yaml-rust reports a 1-based line and 0-based column relative to the front matter block
+        let marker = err.marker();
+        at_position(
+            ParseError::new(
+                &file_location.absolute_path,
+                "Could not parse YAML front matter",
+            ),
+            contents,
+            lines_before(contents, front_matter_source) + marker.line(),
+            marker.col() + 1,
         )
+        .with_source(err)
     })?;
-    let front_matter = front_matter.first().ok_or_else(|| {
+    let mut front_matter = front_matter.first().cloned().ok_or_else(|| {
         ParseError::new(
             &file_location.absolute_path,
-            "Could not parse YAML front matter.",
+            "Could not parse YAML front matter",
         )
     })?;
+    cascade::apply_to_yaml(&mut front_matter, &file_location.cascade);
+    read_page_index(&front_matter, body, file_location, settings)
+}
 
-    let is_draft = front_matter[constants::DRAFT].as_bool().unwrap_or(false);
+/// Creates a `PageIndex` from the contents of a markdown document with JSON front matter.
+///
+/// # Errors
+/// Returns a `ParseError` if the front matter can't be parsed, or a `Skip` if the document is left out for being, or not
+/// being, a draft.
+#[allow(clippy::too_many_lines)]
+pub fn process_md_json_front_matter(
+    contents: &str,
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<PageIndex, OperationResult> {
+    let front_matter_source = contents.trim();
+    // The front matter is the first JSON object in the document, everything after it is content
+    let mut stream =
+        serde_json::Deserializer::from_str(front_matter_source).into_iter::<JsonValue>();
+    let mut front_matter = match stream.next() {
+        Some(Ok(front_matter)) => front_matter,
+        Some(Err(err)) => {
+            return Err(OperationResult::Parse(
+                at_position(
+                    ParseError::new(
+                        &file_location.absolute_path,
+                        "Could not parse JSON front matter",
+                    ),
+                    contents,
+                    lines_before(contents, front_matter_source) + err.line(),
+                    err.column(),
+                )
+                .with_source(err),
+            ))
+        }
+        None => {
+            return Err(OperationResult::Parse(ParseError::new(
+                &file_location.absolute_path,
+                "Could not parse JSON front matter",
+            )))
+        }
+    };
+    let body = &front_matter_source[stream.byte_offset()..];
+    cascade::apply_to_json(&mut front_matter, &file_location.cascade);
+    read_page_index(&front_matter, body, file_location, settings)
+}
 
-    // TODO: Add a flag to allow indexing drafts
-    if is_draft {
-        return Err(OperationResult::Skip(Skip::new(
-            &file_location.absolute_path,
-            "Is draft.",
-        )));
-    }
+/// Creates a `PageIndex` from a document's front matter, whichever format it's in, and its body.
+///
+/// # Errors
+/// Returns a `Skip` if the document is left out for being, or not being, a draft.
+fn read_page_index<F: FrontMatter>(
+    front_matter: &F,
+    body: &str,
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> Result<PageIndex, OperationResult> {
+    let fields = &settings.fields;
+    let string = |field| front_matter.string(fields.key(field));
+    let strings = |field| front_matter.strings(fields.key(field));
+
+    let is_draft = front_matter
+        .boolean(fields.key(constants::DRAFT))
+        .unwrap_or(false);
+    check_draft(is_draft, file_location, settings)?;
 
-    let title = front_matter[constants::TITLE].as_str();
-    let slug = front_matter[constants::SLUG].as_str();
-    let description = front_matter[constants::DESCRIPTION].as_str();
-    let date = front_matter[constants::DATE].as_str();
-    let url = front_matter[constants::URL].as_str();
-
-    let series: Vec<String> = front_matter[constants::SERIES]
-        .as_vec()
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let categories: Vec<String> = front_matter[constants::CATEGORIES]
-        .as_vec()
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let tags: Vec<String> = front_matter[constants::TAGS]
-        .as_vec()
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let keywords: Vec<String> = front_matter[constants::KEYWORDS]
-        .as_vec()
-        .unwrap_or(&Vec::new())
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.trim().to_owned()))
-        .collect();
-
-    let content = strip_markdown(split_content[length - 1].trim());
-
-    PageIndex::new(
-        title,
+    let (content, summary) = strip_body(body, file_location, settings);
+    let slug = string(constants::SLUG);
+    let url = string(constants::URL);
+    let mut page_index = PageIndex::new(
+        string(constants::TITLE),
         slug,
-        date,
-        description,
-        categories,
-        series,
-        tags,
-        keywords,
+        string(constants::DATE),
+        string(constants::DESCRIPTION),
+        strings(constants::CATEGORIES),
+        strings(constants::SERIES),
+        strings(constants::TAGS),
+        strings(constants::KEYWORDS),
         content,
-        &file_location,
+        file_location,
         url,
-    )
+    )?;
+    page_index.draft = is_draft;
+    page_index.publish_date = string(constants::PUBLISH_DATE).map(str::to_owned);
+    page_index.expiry_date = string(constants::EXPIRY_DATE).map(str::to_owned);
+    page_index.slug = slug.map(str::to_owned);
+    page_index.url = url.map(str::to_owned);
+    string(constants::LASTMOD)
+        .unwrap_or_default()
+        .trim()
+        .clone_into(&mut page_index.lastmod);
+    page_index.aliases = strings(constants::ALIASES);
+    page_index.weight = front_matter.integer(fields.key(constants::WEIGHT));
+    string(constants::TYPE)
+        .unwrap_or_default()
+        .trim()
+        .clone_into(&mut page_index.page_type);
+    string(constants::LAYOUT)
+        .unwrap_or_default()
+        .trim()
+        .clone_into(&mut page_index.layout);
+    page_index.extra = front_matter.extra(fields);
+    page_index.summary = summary.unwrap_or_default();
+    Ok(page_index)
+}
+
+/// Strips the shortcodes and markup from a document's body, returning its content and, if the body has a summary
+/// divider, the summary before it. Markdown goes through the configured extractor; `AsciiDoc` and reStructuredText are
+/// reduced to their plain text.
+fn strip_body(
+    body: &str,
+    file_location: &FileLocation,
+    settings: &Settings,
+) -> (String, Option<String>) {
+    let body = strip_shortcodes(body, settings.keep_shortcode_text);
+    let extractor = settings.extractor();
+    let extract = |markdown: &str| {
+        if file_location.extension == constants::ASCIIDOC_EXTENSION {
+            asciidoc_text(markdown)
+        } else if file_location.extension == constants::RST_EXTENSION {
+            rst_text(markdown)
+        } else if settings.code_blocks_only {
+            extract_code_blocks(markdown)
+        } else {
+            extractor.extract(markdown)
+        }
+    };
+    match body.find(constants::SUMMARY_DIVIDER) {
+        Some(index) => {
+            let summary = body[..index].trim();
+            let rest = body[index + constants::SUMMARY_DIVIDER.len()..].trim();
+            let content = extract(&[summary, rest].join("\n\n"));
+            (content, Some(extract(summary)))
+        }
+        None => (extract(body.trim()), None),
+    }
+}
+
+/// Splits a document into its front matter and its body. The front matter is between the `fence` on the document's
+/// first non-empty line and the next line that's only the same fence. Everything after that line is the body, so
+/// fences further on, such as `---` horizontal rules or `+++` in code blocks, are kept as content. Front matter that's
+/// never closed runs to the end of the document.
+pub(crate) fn split_front_matter<'a>(contents: &'a str, fence: &str) -> Option<(&'a str, &'a str)> {
+    let opened = contents.trim_start().strip_prefix(fence)?;
+    let start = opened.find('\n')? + 1;
+    if !opened[..start].trim().is_empty() {
+        return None;
+    }
+    let mut end = start;
+    for line in opened[start..].split_inclusive('\n') {
+        if line.trim_end() == fence {
+            return Some((&opened[start..end], &opened[end + line.len()..]));
+        }
+        end += line.len();
+    }
+    Some((&opened[start..], ""))
+}
+
+/// Returns the body of a document, everything after its front matter. Documents whose front matter can't be found are
+/// all body.
+fn document_body(contents: &str, mode: FrontMatterMode) -> &str {
+    match front_matter_format(contents, mode) {
+        Some(FrontMatterFormat::Toml) => {
+            split_front_matter(contents, constants::TOML_FENCE).map_or(contents, |(_, body)| body)
+        }
+        Some(FrontMatterFormat::Yaml) => {
+            split_front_matter(contents, constants::YAML_FENCE).map_or(contents, |(_, body)| body)
+        }
+        Some(FrontMatterFormat::Json) => {
+            let source = contents.trim_start();
+            let mut stream = serde_json::Deserializer::from_str(source).into_iter::<JsonValue>();
+            match stream.next() {
+                Some(Ok(_)) => &source[stream.byte_offset()..],
+                _ => contents,
+            }
+        }
+        None => contents,
+    }
+}
+
+/// Attaches the 1-based `line` and `column` of `contents` where a parse error occurred to it, along with the line itself.
+fn at_position(error: ParseError, contents: &str, line: usize, column: usize) -> ParseError {
+    let error = error.with_position(line, column);
+    match contents.lines().nth(line.saturating_sub(1)) {
+        Some(snippet) => error.with_snippet(snippet),
+        None => error,
+    }
+}
+
+/// Counts the lines in `contents` before `section` begins, so positions relative to a front matter block can be
+/// reported relative to the file.
+fn lines_before(contents: &str, section: &str) -> usize {
+    contents
+        .find(section)
+        .map_or(0, |index| contents[..index].matches('\n').count())
 }
 
 fn is_hidden(entry: &DirEntry) -> bool {
     entry
         .file_name()
         .to_str()
-        .map_or(false, |s| s.starts_with('.'))
+        .is_some_and(|s| s.starts_with('.'))
+}
+
+/// Returns true if any component of `path` below `root` is hidden.
+pub fn is_hidden_path(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root).is_ok_and(|relative| {
+        relative
+            .components()
+            .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+    })
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::path::Path;
 
     fn build_file_location() -> FileLocation {
-        FileLocation {
-            extension: String::from("md"),
-            relative_directory_to_content: String::from("post"),
-            absolute_path: String::from("/home/blog/content/post/example.md"),
-            file_name: String::from("example.md"),
-            file_stem: String::from("example"),
+        FileLocation::from_path(
+            Path::new("/home/blog/content/post/example.md"),
+            Path::new("/home/blog/content"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn results_are_in_walk_order_across_windows() {
+        let content_dir = tempfile::Builder::new()
+            .prefix("not-a-dotfile-windows")
+            .tempdir()
+            .unwrap();
+        let files = rayon::current_num_threads() * FILES_IN_FLIGHT_PER_THREAD * 2 + 1;
+        for number in 0..files {
+            fs::write(
+                content_dir.path().join(format!("{number:05}.md")),
+                "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+            )
+            .unwrap();
         }
+        let settings = Settings {
+            scan_path: content_dir.path().to_owned(),
+            ..Settings::default()
+        };
+
+        let mut names = Vec::new();
+        Traverser::new(settings)
+            .traverse_streaming(|(file_location, _)| {
+                names.push(file_location.file_stem);
+                Ok(())
+            })
+            .unwrap();
+        let expected: Vec<String> = (0..files).map(|number| format!("{number:05}")).collect();
+        assert_eq!(names, expected);
+
+        // Stopping early doesn't wait for every file to be processed
+        let mut handed_on = 0;
+        let result = Traverser::new(Settings {
+            scan_path: content_dir.path().to_owned(),
+            ..Settings::default()
+        })
+        .traverse_streaming(|_| {
+            handed_on += 1;
+            Err(HugotoJsonError::Unknown)
+        });
+        assert!(result.is_err());
+        assert_eq!(handed_on, 1);
     }
 
     #[test]
@@ -346,7 +1049,8 @@ tags:
 The state of images on the web is pretty rough. What should be an easy goal, showing a user a picture, is...
 "#,
         );
-        let page_index = process_md_yaml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_yaml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_ok());
         let page_index = page_index.unwrap();
         assert_eq!(page_index.title, "Responsive Blog Images");
@@ -364,6 +1068,44 @@ The state of images on the web is pretty rough. What should be an easy goal, sho
         assert!(page_index.categories.is_empty());
     }
 
+    #[test]
+    fn fences_in_the_body_are_content() {
+        let yaml =
+            "---\ntitle: Rules\ndate: 2019-01-25\n---\nBefore the rule\n\n---\n\nAfter the rule\n";
+        assert_eq!(
+            split_front_matter(yaml, constants::YAML_FENCE),
+            Some((
+                "title: Rules\ndate: 2019-01-25\n",
+                "Before the rule\n\n---\n\nAfter the rule\n"
+            ))
+        );
+        let page_index =
+            process_md_yaml_front_matter(yaml, &build_file_location(), &Settings::default())
+                .unwrap();
+        assert_eq!(page_index.title, "Rules");
+
+        let toml =
+            "\n+++\ntitle = \"Code\"\ndate = \"2019-01-25\"\n+++\n```\n+++\nx = 1\n+++\n```\n";
+        assert_eq!(
+            split_front_matter(toml, constants::TOML_FENCE),
+            Some((
+                "title = \"Code\"\ndate = \"2019-01-25\"\n",
+                "```\n+++\nx = 1\n+++\n```\n"
+            ))
+        );
+        let page_index =
+            process_md_toml_front_matter(toml, &build_file_location(), &Settings::default())
+                .unwrap();
+        assert_eq!(page_index.title, "Code");
+
+        // The fences must be on lines of their own
+        assert_eq!(split_front_matter("----\ntitle: A\n----\n", "---"), None);
+        assert_eq!(
+            split_front_matter("---\ntitle: A\n", "---"),
+            Some(("title: A\n", ""))
+        );
+    }
+
     #[test]
     fn page_index_from_yaml_returns_skip_err_when_draft() {
         let contents = String::from(
@@ -382,15 +1124,45 @@ tags:
 The state of images on the web is pretty rough. What should be an easy goal, showing a user a picture, is...
 "#,
         );
-        let page_index = process_md_yaml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_yaml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_err());
         // Pattern match the error type
         match page_index.unwrap_err() {
-            OperationResult::Skip(_) => (), // The case where the result is a Skip result succeeds
+            OperationResult::Skip(skip) => assert_eq!(skip.reason(), SkipReason::Draft),
             _ => panic!("This should fail"), // All other cases fail
         }
     }
 
+    #[test]
+    fn page_index_from_yaml_includes_drafts_when_asked() {
+        let draft = "---\ndraft: true\ntitle: Draft\ndate: 2019-01-20\n---\nUnpublished\n";
+        let published = "---\ntitle: Published\ndate: 2019-01-20\n---\nPublished\n";
+        let drafts = Settings {
+            drafts: true,
+            ..Settings::default()
+        };
+        let drafts_only = Settings {
+            drafts_only: true,
+            ..Settings::default()
+        };
+
+        let page_index =
+            process_md_yaml_front_matter(draft, &build_file_location(), &drafts).unwrap();
+        assert!(page_index.draft);
+        let page_index =
+            process_md_yaml_front_matter(published, &build_file_location(), &drafts).unwrap();
+        assert!(!page_index.draft);
+
+        let page_index =
+            process_md_yaml_front_matter(draft, &build_file_location(), &drafts_only).unwrap();
+        assert!(page_index.draft);
+        match process_md_yaml_front_matter(published, &build_file_location(), &drafts_only) {
+            Err(OperationResult::Skip(skip)) => assert_eq!(skip.reason(), SkipReason::NotDraft),
+            _ => panic!("Only drafts should be included"),
+        }
+    }
+
     #[test]
     fn page_index_from_yaml_returns_ok_if_fence_not_closed() {
         let contents = String::from(
@@ -405,7 +1177,8 @@ tags:
   - Images
 "#,
         );
-        let page_index = process_md_yaml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_yaml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_ok());
     }
 
@@ -424,7 +1197,8 @@ tags
 "#,
         );
 
-        let page_index = process_md_yaml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_yaml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_err());
         // Pattern match error
         match page_index.unwrap_err() {
@@ -451,7 +1225,8 @@ Design is iterative
 "#,
         );
 
-        let page_index = process_md_toml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_toml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_ok());
         let page_index = page_index.unwrap();
         assert_eq!(page_index.title, "Evaluating Software Design");
@@ -485,11 +1260,12 @@ Design is iterative
 "#,
         );
 
-        let page_index = process_md_toml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_toml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_err());
         // Pattern match error
         match page_index.unwrap_err() {
-            OperationResult::Skip(_) => (), // The case where the result is a Skip result succeeds
+            OperationResult::Skip(skip) => assert_eq!(skip.reason(), SkipReason::Draft),
             _ => panic!("This should fail"), // All other cases fail
         }
     }
@@ -509,7 +1285,8 @@ Design is iterative
 "#,
         );
 
-        let page_index = process_md_toml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_toml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_err());
         // Pattern match error
         match page_index.unwrap_err() {
@@ -533,7 +1310,8 @@ Design is iterative
 "#,
         );
 
-        let page_index = process_md_toml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_toml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_err());
         // Pattern match error
         match page_index.unwrap_err() {
@@ -558,7 +1336,8 @@ Design is iterative
 "#,
         );
 
-        let page_index = process_md_toml_front_matter(&contents, &build_file_location());
+        let page_index =
+            process_md_toml_front_matter(&contents, &build_file_location(), &Settings::default());
         assert!(page_index.is_err());
         // Pattern match error
         match page_index.unwrap_err() {
@@ -566,4 +1345,389 @@ Design is iterative
             _ => panic!("This should fail"), // All other cases fail
         }
     }
+
+    #[test]
+    fn page_index_from_toml_parse_err_preserves_source_and_position() {
+        let contents = String::from(
+            r#"
++++
+draft = false
+date: "2016-04-17"
+title = """Evaluating Software Design"""
++++
+
+Design is iterative
+"#,
+        );
+
+        match process_md_toml_front_matter(&contents, &build_file_location(), &Settings::default())
+            .unwrap_err()
+        {
+            OperationResult::Parse(err) => {
+                assert!(std::error::Error::source(&err).is_some());
+                assert_eq!(err.line(), Some(4));
+                assert_eq!(err.snippet(), Some("date: \"2016-04-17\""));
+            }
+            _ => panic!("This should fail"),
+        }
+    }
+
+    #[test]
+    fn page_index_from_yaml_parse_err_preserves_source_and_position() {
+        let contents = String::from(
+            r#"
+---
+title: Responsive Blog Images
+date: "2019-01-20T23:11:28Z"
+tags
+  - :Hugo
+---
+"#,
+        );
+
+        match process_md_yaml_front_matter(&contents, &build_file_location(), &Settings::default())
+            .unwrap_err()
+        {
+            OperationResult::Parse(err) => {
+                assert!(std::error::Error::source(&err).is_some());
+                assert!(err.line().is_some());
+            }
+            _ => panic!("This should fail"),
+        }
+    }
+
+    #[test]
+    fn page_index_from_json() {
+        let contents = String::from(
+            r#"
+{
+    "draft": false,
+    "title": "Replacing Sed/Awk With Amber",
+    "date": "2019-01-25T07:52:40Z",
+    "slug": "replacing-awk-sed-with-amber",
+    "tags": ["Rust", "Amber"]
+}
+
+Contents here
+"#,
+        );
+
+        let page_index =
+            process_md_json_front_matter(&contents, &build_file_location(), &Settings::default());
+        assert!(page_index.is_ok());
+        let page_index = page_index.unwrap();
+        assert_eq!(page_index.title, "Replacing Sed/Awk With Amber");
+        assert_eq!(page_index.content, "Contents here");
+        assert_eq!(page_index.date, "2019-01-25T07:52:40Z");
+        assert_eq!(page_index.href, "/post/replacing-awk-sed-with-amber/");
+        assert_eq!(page_index.tags, vec!["Rust", "Amber"]);
+    }
+
+    #[test]
+    fn page_index_from_json_returns_skip_err_when_draft() {
+        let contents = String::from(
+            r#"{ "draft": true, "title": "Draft", "date": "2019-01-25" }
+Contents here
+"#,
+        );
+
+        match process_md_contents(&contents, &build_file_location(), &Settings::default())
+            .unwrap_err()
+        {
+            OperationResult::Skip(skip) => assert_eq!(skip.reason(), SkipReason::Draft),
+            _ => panic!("This should fail"),
+        }
+    }
+
+    #[test]
+    fn page_index_from_json_returns_parse_err_for_malformed_json() {
+        let contents = String::from(
+            r#"{
+    "title": "Malformed",
+    "date" "2019-01-25"
+}
+Contents here
+"#,
+        );
+
+        match process_md_json_front_matter(&contents, &build_file_location(), &Settings::default())
+            .unwrap_err()
+        {
+            OperationResult::Parse(err) => assert_eq!(err.line(), Some(3)),
+            _ => panic!("This should fail"),
+        }
+    }
+
+    fn skip_reason(contents: &str, settings: &Settings) -> Option<SkipReason> {
+        let now: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let page_index =
+            process_md_contents(contents, &build_file_location(), &Settings::default()).unwrap();
+        match check_publication(&page_index, &build_file_location(), settings, now) {
+            Ok(()) => None,
+            Err(OperationResult::Skip(skip)) => Some(skip.reason()),
+            Err(err) => panic!("Unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn future_and_expired_documents_are_skipped() {
+        let settings = Settings::default();
+        let future = "+++\ntitle = \"Future\"\ndate = \"2019-01-25\"\npublishDate = \"2021-01-01\"\n+++\nContents";
+        let future_by_date =
+            "+++\ntitle = \"Future\"\ndate = \"2021-01-25T07:52:40Z\"\n+++\nContents";
+        let expired = "+++\ntitle = \"Expired\"\ndate = \"2019-01-25\"\nexpiryDate = \"2019-12-31\"\n+++\nContents";
+        let current = "+++\ntitle = \"Current\"\ndate = \"2019-01-25\"\nexpiryDate = \"2021-01-01\"\n+++\nContents";
+
+        assert_eq!(
+            skip_reason(future, &settings),
+            Some(SkipReason::FutureDated)
+        );
+        assert_eq!(
+            skip_reason(future_by_date, &settings),
+            Some(SkipReason::FutureDated)
+        );
+        assert_eq!(skip_reason(expired, &settings), Some(SkipReason::Expired));
+        assert_eq!(skip_reason(current, &settings), None);
+
+        let settings = Settings {
+            build_future: true,
+            build_expired: true,
+            ..Settings::default()
+        };
+        assert_eq!(skip_reason(future, &settings), None);
+        assert_eq!(skip_reason(expired, &settings), None);
+    }
+
+    #[test]
+    fn documents_without_a_divider_are_summarised_like_hugo() {
+        let contents = format!(
+            "+++\ntitle = \"Long\"\ndate = \"2019-01-25\"\n+++\n{}",
+            "word ".repeat(100)
+        );
+        let summary_words = |settings: &Settings| {
+            process_document(&contents, &build_file_location(), settings)
+                .unwrap()
+                .summary
+                .split_whitespace()
+                .count()
+        };
+        assert_eq!(summary_words(&Settings::default()), DEFAULT_SUMMARY_LENGTH);
+
+        let mut settings = Settings::default();
+        settings.site.summary_length = Some(30);
+        assert_eq!(summary_words(&settings), 30);
+        settings.summary_length = Some(0);
+        assert_eq!(summary_words(&settings), 0);
+    }
+
+    #[test]
+    fn unparseable_publish_and_expiry_dates_are_ignored_unless_formats_are_configured() {
+        let now: DateTime<Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+        let contents = "+++\ntitle = \"Unparseable\"\ndate = \"2019-01-25\"\npublishDate = \"Jan 2, 2019\"\nexpiryDate = \"Whenever\"\n+++\nContents";
+        let page_index =
+            process_md_contents(contents, &build_file_location(), &Settings::default()).unwrap();
+        assert!(check_publication(
+            &page_index,
+            &build_file_location(),
+            &Settings::default(),
+            now
+        )
+        .is_ok());
+
+        let settings = Settings {
+            date_formats: vec![String::from("%Y-%m-%d")],
+            ..Settings::default()
+        };
+        match check_publication(&page_index, &build_file_location(), &settings, now) {
+            Err(OperationResult::Parse(err)) => assert_eq!(err.key(), Some("publishDate")),
+            _ => panic!("This should fail"),
+        }
+    }
+
+    #[test]
+    fn headless_and_unlisted_documents_are_skipped() {
+        let bundle = FileLocation::from_path(
+            Path::new("/blog/content/headless/index.md"),
+            Path::new("/blog/content"),
+        )
+        .unwrap();
+        let reason = |contents: &str, file_location: &FileLocation, settings: &Settings| {
+            match process_document(contents, file_location, settings) {
+                Ok(_) => None,
+                Err(OperationResult::Skip(skip)) => Some(skip.reason()),
+                Err(err) => panic!("Unexpected error {}", err),
+            }
+        };
+        let settings = Settings::default();
+        let headless =
+            "+++\ntitle = \"Headless\"\ndate = \"2019-01-25\"\nheadless = true\n+++\nContents";
+        let unlisted = "+++\ntitle = \"Unlisted\"\ndate = \"2019-01-25\"\n[_build]\nlist = \"never\"\n+++\nContents";
+        let unrendered =
+            "---\ntitle: Unrendered\ndate: 2019-01-25\n_build:\n  render: false\n---\nContents";
+
+        assert_eq!(
+            reason(headless, &bundle, &settings),
+            Some(SkipReason::Headless)
+        );
+        // Only bundles can be headless
+        assert_eq!(reason(headless, &build_file_location(), &settings), None);
+        assert_eq!(
+            reason(unlisted, &build_file_location(), &settings),
+            Some(SkipReason::Unlisted)
+        );
+        assert_eq!(
+            reason(unrendered, &build_file_location(), &settings),
+            Some(SkipReason::Unlisted)
+        );
+
+        let settings = Settings {
+            include_unlisted: true,
+            ..Settings::default()
+        };
+        assert_eq!(reason(headless, &bundle, &settings), None);
+        assert_eq!(reason(unlisted, &build_file_location(), &settings), None);
+    }
+
+    #[test]
+    fn front_matter_format_is_told_from_the_whole_fence() {
+        let auto = FrontMatterMode::Auto;
+        assert_eq!(
+            front_matter_format("\n+++\ntitle = \"A\"\n+++\n", auto),
+            Some(FrontMatterFormat::Toml)
+        );
+        assert_eq!(
+            front_matter_format("---  \ntitle: A\n---\n", auto),
+            Some(FrontMatterFormat::Yaml)
+        );
+        assert_eq!(
+            front_matter_format("{\"title\": \"A\"}\n", auto),
+            Some(FrontMatterFormat::Json)
+        );
+        // Lists and rules that only start like a fence aren't front matter
+        assert_eq!(front_matter_format("- item\n- item\n", auto), None);
+        assert_eq!(front_matter_format("-----\nContents\n", auto), None);
+        assert_eq!(
+            front_matter_format("- item\n", FrontMatterMode::Force(FrontMatterFormat::Yaml)),
+            Some(FrontMatterFormat::Yaml)
+        );
+
+        let contents = "---\ntitle: Rust\ndate: 2019-01-25\n---\nContents";
+        let settings = Settings {
+            front_matter: FrontMatterMode::Force(FrontMatterFormat::Toml),
+            ..Settings::default()
+        };
+        assert!(
+            process_md_contents(contents, &build_file_location(), &Settings::default()).is_ok()
+        );
+        match process_md_contents(contents, &build_file_location(), &settings) {
+            Err(OperationResult::Parse(_)) => (),
+            _ => panic!("YAML front matter should fail to parse as TOML"),
+        }
+    }
+
+    #[test]
+    fn summary_is_content_before_divider() {
+        let contents = "+++\ntitle = \"Summary\"\ndate = \"2019-01-25\"\n+++\nThe summary.\n<!--more-->\nThe rest.";
+
+        let page_index =
+            process_md_contents(contents, &build_file_location(), &Settings::default()).unwrap();
+        assert_eq!(page_index.summary, "The summary.");
+        assert!(!page_index.content.contains("more"));
+        assert!(page_index.content.contains("The rest."));
+    }
+
+    #[test]
+    fn lastmod_and_aliases_are_read() {
+        let toml = "+++\ntitle = \"Moved\"\ndate = \"2019-01-25\"\nlastmod = \"2019-03-01\"\naliases = [\"/old/\", \"/older/\"]\n+++\nContents";
+        let yaml = "---\ntitle: Moved\ndate: 2019-01-25\nlastmod: 2019-03-01\naliases:\n  - /old/\n  - /older/\n---\nContents";
+
+        for contents in &[toml, yaml] {
+            let page_index =
+                process_md_contents(contents, &build_file_location(), &Settings::default())
+                    .unwrap();
+            assert_eq!(page_index.lastmod, "2019-03-01");
+            assert_eq!(page_index.aliases, vec!["/old/", "/older/"]);
+        }
+    }
+
+    #[test]
+    fn authors_are_read_from_each_form() {
+        let authors = |contents: &str| {
+            process_document(contents, &build_file_location(), &Settings::default())
+                .unwrap()
+                .authors
+        };
+        let front_matter = "title: Post\ndate: 2019-01-25";
+        assert_eq!(
+            authors(&format!("---\n{front_matter}\nauthor: Jane\n---\n")),
+            ["Jane"]
+        );
+        assert_eq!(
+            authors(&format!(
+                "---\n{front_matter}\nauthors: [Jane, Sam]\nauthor: Sam\n---\n"
+            )),
+            ["Jane", "Sam"]
+        );
+        assert_eq!(
+            authors("+++\ntitle = \"Post\"\ndate = \"2019-01-25\"\n[author]\nname = \"Jane\"\nemail = \"jane@example.com\"\n+++\n"),
+            ["Jane"]
+        );
+        assert_eq!(
+            authors("{\"title\": \"Post\", \"date\": \"2019-01-25\", \"authors\": [{\"name\": \"Jane\"}, \"Sam\"]}\n"),
+            ["Jane", "Sam"]
+        );
+        assert!(authors(&format!("---\n{front_matter}\n---\n")).is_empty());
+    }
+
+    #[test]
+    fn images_are_read_from_front_matter_and_body() {
+        let yaml = "---\ntitle: Post\ndate: 2019-01-25\nimages: [/a.png, /b.png]\nfeatured_image: /a.png\ncover:\n  image: /c.png\n  alt: Cover\n---\n![Diagram](/d.png)\n";
+        let json = "{\"title\": \"Post\", \"date\": \"2019-01-25\", \"cover\": \"/c.png\"}\n![Diagram](/d.png)\n";
+        let settings = Settings {
+            body_image: true,
+            ..Settings::default()
+        };
+        let images = |contents: &str, settings: &Settings| {
+            process_document(contents, &build_file_location(), settings)
+                .unwrap()
+                .images
+        };
+
+        assert_eq!(
+            images(yaml, &Settings::default()),
+            ["/a.png", "/b.png", "/c.png"]
+        );
+        assert_eq!(
+            images(yaml, &settings),
+            ["/a.png", "/b.png", "/c.png", "/d.png"]
+        );
+        assert_eq!(images(json, &settings), ["/c.png", "/d.png"]);
+    }
+
+    #[test]
+    fn weight_type_and_layout_are_read() {
+        let toml = "+++\ntitle = \"Guide\"\ndate = \"2019-01-25\"\nweight = -10\ntype = \"docs\"\nlayout = \"wide\"\n+++\nContents";
+        let yaml = "---\ntitle: Guide\ndate: 2019-01-25\nweight: -10\ntype: docs\nlayout: wide\n---\nContents";
+        let json = "{\"title\": \"Guide\", \"date\": \"2019-01-25\", \"weight\": -10, \"type\": \"docs\", \"layout\": \"wide\"}\nContents";
+
+        for contents in &[toml, yaml, json] {
+            let page_index =
+                process_md_contents(contents, &build_file_location(), &Settings::default())
+                    .unwrap();
+            assert_eq!(page_index.weight, Some(-10));
+            assert_eq!(page_index.page_type, "docs");
+            assert_eq!(page_index.layout, "wide");
+            assert!(page_index.extra.is_empty());
+        }
+
+        let page_index = process_md_contents(
+            "+++\ntitle = \"Guide\"\ndate = \"2019-01-25\"\n+++\nContents",
+            &build_file_location(),
+            &Settings::default(),
+        )
+        .unwrap();
+        let record = serde_json::to_value(&page_index).unwrap();
+        assert!(record.get("weight").is_none());
+        assert!(record.get("type").is_none());
+    }
 }
@@ -0,0 +1,451 @@
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+use crate::settings::{Header, Settings, UploadTarget};
+use crate::sort;
+use crate::stats::Stats;
+
+/// The index or collection documents are uploaded to when no other is given.
+pub const DEFAULT_INDEX_NAME: &str = "pages";
+/// The number of documents sent in each request when no other is given.
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+/// How long to wait before the first retry. The wait doubles with each further retry, up to `MAX_RETRY_DOUBLINGS` times.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// The most times the wait before a retry is doubled, so it's never more than 32 seconds.
+const MAX_RETRY_DOUBLINGS: u32 = 6;
+
+#[derive(Serialize, Debug)]
+/// A page as it's uploaded, with an `id` the search engine accepts as a primary key.
+struct Document<'a> {
+    id: String,
+    #[serde(flatten)]
+    page: &'a PageIndex,
+}
+
+/// A batch of documents, or a whole index, ready to be sent.
+struct Batch {
+    url: String,
+    content_type: &'static str,
+    headers: Vec<Header>,
+    body: String,
+}
+
+/// Traverses the contents directory and uploads every page to the search engine in the settings.
+///
+/// # Errors
+/// Errors if the contents directory can't be read, a batch can't be uploaded, or any file failed to process.
+pub fn upload_index(settings: &Settings) -> Result<(), HugotoJsonError> {
+    info!("Scanning {:?}", settings.scan_paths());
+    for scan_path in settings.scan_paths() {
+        fs::metadata(scan_path)?;
+    }
+    let (pages, mut errors) = crate::collect_pages(settings, &Stats::new())?;
+    let records: Vec<PageIndex> = pages
+        .iter()
+        .cloned()
+        .flat_map(PageIndex::into_records)
+        .collect();
+    upload(settings, &records)?;
+    errors.extend(crate::write_page_outputs(settings, &pages)?);
+    crate::report_errors(settings, &errors)
+}
+
+/// Uploads pages to [MeiliSearch](https://www.meilisearch.com/) or [Typesense](https://typesense.org/) in batches,
+/// retrying batches that fail because of the network or a server error. Returns the number of documents uploaded.
+///
+/// Documents replace any existing documents with the same `id`, which is made from the page's href. `MeiliSearch` indexes
+/// the documents in the background after accepting them. A Typesense collection must already exist with a schema for
+/// the documents.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::settings::{Settings, UploadTarget};
+/// use hugo_to_json::upload::upload;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let settings = Settings {
+///     upload: Some(UploadTarget::MeiliSearch),
+///     endpoint: Some(String::from("http://localhost:7700")),
+///     api_key: Some(String::from("masterKey")),
+///     ..Settings::default()
+/// };
+/// let index = hugo_to_json::convert(settings.clone())?;
+/// upload(&settings, &index.pages)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors if no upload target or endpoint is set, or if a batch is rejected or still fails after every retry.
+pub fn upload(settings: &Settings, pages: &[PageIndex]) -> Result<usize, HugotoJsonError> {
+    let upload_error = |reason: String| HugotoJsonError::Upload { reason };
+    let target = settings
+        .upload
+        .ok_or_else(|| upload_error(String::from("No search engine to upload to was given")))?;
+    let endpoint = settings
+        .endpoint
+        .as_ref()
+        .ok_or_else(|| upload_error(String::from("No endpoint to upload to was given")))?
+        .trim_end_matches('/');
+    let index_name = settings
+        .index_name
+        .as_ref()
+        .map_or(DEFAULT_INDEX_NAME, String::as_str);
+    let batch_size = settings.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+    let headers: Vec<Header> = settings
+        .api_key
+        .iter()
+        .map(|api_key| match target {
+            UploadTarget::MeiliSearch => bearer(api_key),
+            UploadTarget::Typesense => Header {
+                name: String::from("X-TYPESENSE-API-KEY"),
+                value: api_key.clone(),
+            },
+        })
+        .collect();
+
+    for pages in pages.chunks(batch_size) {
+        let documents = pages.iter().map(|page| Document {
+            id: document_id(&page.href),
+            page,
+        });
+        let batch = match target {
+            UploadTarget::MeiliSearch => Batch {
+                url: format!("{endpoint}/indexes/{index_name}/documents?primaryKey=id"),
+                content_type: "application/json",
+                headers: headers.clone(),
+                body: serde_json::to_string(&documents.collect::<Vec<_>>())?,
+            },
+            UploadTarget::Typesense => Batch {
+                url: format!("{endpoint}/collections/{index_name}/documents/import?action=upsert"),
+                content_type: "text/plain",
+                headers: headers.clone(),
+                body: documents
+                    .map(|document| serde_json::to_string(&document))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join("\n"),
+            },
+        };
+
+        let response = send(&batch, settings.retries)?;
+        if target == UploadTarget::Typesense {
+            check_import_results(&response)?;
+        }
+        debug!("Uploaded a batch of {} documents", pages.len());
+    }
+
+    info!("Uploaded {} documents to {}", pages.len(), endpoint);
+    Ok(pages.len())
+}
+
+/// Traverses the contents directory and POSTs the index, in the settings' format, to the settings' `post_url`.
+///
+/// # Errors
+/// Errors if the contents directory can't be read, the format is a database, the index can't be posted, or any file
+/// failed to process.
+pub fn post_index(settings: &Settings) -> Result<(), HugotoJsonError> {
+    if settings.format.is_database() {
+        return Err(HugotoJsonError::Upload {
+            reason: String::from("A sqlite or tantivy index can't be posted"),
+        });
+    }
+    info!("Scanning {:?}", settings.scan_paths());
+    for scan_path in settings.scan_paths() {
+        fs::metadata(scan_path)?;
+    }
+    let (pages, mut errors) = crate::collect_pages(settings, &Stats::new())?;
+    let index = crate::serialize_pages(settings, sort::sort_and_limit(settings, &pages))?;
+    post(settings, index)?;
+    errors.extend(crate::write_page_outputs(settings, &pages)?);
+    crate::report_errors(settings, &errors)
+}
+
+/// POSTs a serialized index to the settings' `post_url`, with the settings' headers and the API key as a bearer token,
+/// retrying like an upload. Returns the body of the response.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::settings::Settings;
+/// use hugo_to_json::upload::post;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let settings = Settings {
+///     post_url: Some(String::from("https://example.com/reindex")),
+///     headers: vec!["X-Api-Key: secret".parse().unwrap()],
+///     ..Settings::default()
+/// };
+/// post(&settings, String::from("[]"))?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors if no URL is set, or if the index is rejected or still fails to post after every retry.
+pub fn post(settings: &Settings, index: String) -> Result<String, HugotoJsonError> {
+    let url = settings
+        .post_url
+        .as_ref()
+        .ok_or_else(|| HugotoJsonError::Upload {
+            reason: String::from("No URL to post the index to was given"),
+        })?;
+    let mut headers = settings.headers.clone();
+    headers.extend(settings.api_key.iter().map(|api_key| bearer(api_key)));
+    let batch = Batch {
+        url: url.clone(),
+        content_type: settings.format.content_type(),
+        headers,
+        body: index,
+    };
+    let response = send(&batch, settings.retries)?;
+    info!("Posted the index to {url}");
+    Ok(response)
+}
+
+/// Returns the header that authorizes a request with `api_key` as a bearer token.
+fn bearer(api_key: &str) -> Header {
+    Header {
+        name: String::from("Authorization"),
+        value: format!("Bearer {api_key}"),
+    }
+}
+
+/// Sends a batch, retrying network errors, rate limiting and server errors up to `retries` times. Returns the body of
+/// the response.
+fn send(batch: &Batch, retries: u32) -> Result<String, HugotoJsonError> {
+    let mut attempt = 0;
+    loop {
+        let mut request = ureq::post(&batch.url).set("Content-Type", batch.content_type);
+        for header in &batch.headers {
+            request = request.set(&header.name, &header.value);
+        }
+
+        match request.send_string(&batch.body) {
+            Ok(response) => return Ok(response.into_string()?),
+            Err(ureq::Error::Status(status, response))
+                if !is_retryable(status) || attempt >= retries =>
+            {
+                return Err(HugotoJsonError::Upload {
+                    reason: format!(
+                        "{} responded with {}: {}",
+                        batch.url,
+                        status,
+                        response.into_string().unwrap_or_default()
+                    ),
+                });
+            }
+            Err(error) if attempt >= retries => {
+                return Err(HugotoJsonError::Upload {
+                    reason: error.to_string(),
+                });
+            }
+            Err(error) => warn!("Retrying upload to {}: {}", batch.url, error),
+        }
+
+        thread::sleep(retry_delay(attempt));
+        attempt += 1;
+    }
+}
+
+/// Returns how long to wait before retrying a request that failed on `attempt`, counting from 0.
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_DELAY * 2_u32.pow(attempt.min(MAX_RETRY_DOUBLINGS))
+}
+
+/// Returns true if a request that failed with `status` may succeed if it's sent again.
+fn is_retryable(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// Typesense reports whether each document was imported on its own line of the response, even when the request
+/// succeeds, so the lines are checked for failures.
+fn check_import_results(response: &str) -> Result<(), HugotoJsonError> {
+    let failures: Vec<String> = response
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|result| result["success"] != Value::Bool(true))
+        .map(|result| {
+            result["error"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_owned()
+        })
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(HugotoJsonError::Upload {
+            reason: format!(
+                "{} documents failed to import: {}",
+                failures.len(),
+                failures.join("; ")
+            ),
+        })
+    }
+}
+
+/// Makes a document id from a href. Search engines only accept some characters in ids, so every other character is
+/// written as `_` followed by its bytes in hex. `_` is escaped too, so different hrefs always have different ids.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::upload::document_id;
+///
+/// assert_eq!(document_id("/post/my-post/"), "_2fpost_2fmy-post_2f");
+/// ```
+#[must_use]
+pub fn document_id(href: &str) -> String {
+    href.bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || byte == b'-' {
+                char::from(byte).to_string()
+            } else {
+                format!("_{byte:02x}")
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::TestPage;
+    use crate::settings::OutputFormat;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Serves one canned response per request and returns the requests received.
+    fn serve(responses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    let mut request = String::new();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if let Some(length) = line.to_lowercase().strip_prefix("content-length:") {
+                            content_length = length.trim().parse().unwrap();
+                        }
+                        request.push_str(&line);
+                        if line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    request.push_str(&String::from_utf8(body).unwrap());
+                    reader.into_inner().write_all(response.as_bytes()).unwrap();
+                    request
+                })
+                .collect()
+        });
+        (endpoint, server)
+    }
+
+    fn pages(count: usize) -> Vec<PageIndex> {
+        (0..count)
+            .map(|number| {
+                TestPage::new(&number.to_string())
+                    .title("Title")
+                    .content("Content")
+                    .build()
+            })
+            .collect()
+    }
+
+    fn settings(target: UploadTarget, endpoint: String) -> Settings {
+        Settings {
+            upload: Some(target),
+            endpoint: Some(endpoint),
+            api_key: Some(String::from("key")),
+            batch_size: Some(2),
+            retries: 1,
+            ..Settings::default()
+        }
+    }
+
+    const ACCEPTED: &str =
+        "HTTP/1.1 202 Accepted\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+    const UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const BAD_REQUEST: &str =
+        "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    #[test]
+    fn retry_delays_double_up_to_a_limit() {
+        assert_eq!(retry_delay(0), Duration::from_millis(500));
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(6), Duration::from_secs(32));
+        assert_eq!(retry_delay(u32::MAX), Duration::from_secs(32));
+    }
+
+    #[test]
+    fn uploads_to_meilisearch_in_batches() {
+        let (endpoint, server) = serve(vec![ACCEPTED, ACCEPTED]);
+        let uploaded = upload(&settings(UploadTarget::MeiliSearch, endpoint), &pages(3)).unwrap();
+        assert_eq!(uploaded, 3);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /indexes/pages/documents?primaryKey=id "));
+        assert!(requests[0].contains("Authorization: Bearer key\r\n"));
+        assert!(requests[0].contains(r#"[{"id":"_2fpost_2f0_2f","title":"Title""#));
+        assert!(requests[1].contains(r#"[{"id":"_2fpost_2f2_2f""#));
+    }
+
+    #[test]
+    fn retries_server_errors_but_not_rejections() {
+        let (endpoint, server) = serve(vec![UNAVAILABLE, ACCEPTED, BAD_REQUEST]);
+        let settings = settings(UploadTarget::MeiliSearch, endpoint);
+        assert!(upload(&settings, &pages(1)).is_ok());
+        match upload(&settings, &pages(1)).unwrap_err() {
+            HugotoJsonError::Upload { reason } => assert!(reason.contains("400")),
+            err => panic!("Unexpected error {}", err),
+        }
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn reports_typesense_import_failures() {
+        const IMPORTED: &str = "HTTP/1.1 200 OK\r\nContent-Length: 54\r\nConnection: close\r\n\r\n{\"success\":true}\n{\"success\":false,\"error\":\"Bad field\"}";
+        let (endpoint, server) = serve(vec![IMPORTED]);
+        match upload(&settings(UploadTarget::Typesense, endpoint), &pages(2)).unwrap_err() {
+            HugotoJsonError::Upload { reason } => assert!(reason.contains("Bad field")),
+            err => panic!("Unexpected error {}", err),
+        }
+
+        let request = &server.join().unwrap()[0];
+        assert!(request.starts_with("POST /collections/pages/documents/import?action=upsert "));
+        assert!(request.contains("X-TYPESENSE-API-KEY: key\r\n"));
+        assert!(request.contains("\n{\"id\":\"_2fpost_2f1_2f\""));
+    }
+
+    #[test]
+    fn posts_the_index_with_its_headers() {
+        let (endpoint, server) = serve(vec![UNAVAILABLE, ACCEPTED]);
+        let settings = Settings {
+            post_url: Some(format!("{endpoint}/reindex")),
+            headers: vec!["X-Site: blog".parse().unwrap()],
+            api_key: Some(String::from("key")),
+            format: OutputFormat::Ndjson,
+            retries: 1,
+            ..Settings::default()
+        };
+        assert_eq!(post(&settings, String::from("{}\n")).unwrap(), "{}");
+
+        let request = &server.join().unwrap()[1];
+        assert!(request.starts_with("POST /reindex "));
+        assert!(request.contains("Content-Type: application/x-ndjson\r\n"));
+        assert!(request.contains("X-Site: blog\r\n"));
+        assert!(request.contains("Authorization: Bearer key\r\n"));
+        assert!(request.ends_with("\r\n\r\n{}\n"));
+    }
+}
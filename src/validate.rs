@@ -0,0 +1,455 @@
+use serde_json::Value;
+
+use crate::constants::{DATE, HREF, PAGES, TITLE};
+use crate::geojson::FeatureCollection;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::opensearch::{Suggestion, Suggestions};
+use crate::page_index::PageIndex;
+use crate::schema;
+
+/// Re-parses a serialized index and checks every record has the shape of a `PageIndex` with a title, href and date.
+/// For a Lunr index the records are its `documents`, and for an index in an envelope its `pages`.
+///
+/// Returns the number of records checked.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::validate_index;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let index = r#"[{"title":"Title","href":"/post/example/","date":"2019-01-25","content":"Contents"}]"#;
+/// assert_eq!(validate_index(index)?, 1);
+/// assert!(validate_index(r#"[{"title":"Title"}]"#).is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every problem found if the index isn't a JSON array of valid records.
+pub fn validate_index(serialized_index: &str) -> Result<usize, HugotoJsonError> {
+    let index: Value =
+        serde_json::from_str(serialized_index).map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The index is not valid JSON: {}", err)],
+        })?;
+
+    let records = index
+        .get("documents")
+        .or_else(|| index.get(PAGES))
+        .unwrap_or(&index)
+        .as_array()
+        .ok_or_else(|| HugotoJsonError::Validation {
+            problems: vec![String::from("The index is not a JSON array")],
+        })?;
+
+    let problems: Vec<String> = records
+        .iter()
+        .enumerate()
+        .flat_map(|(position, record)| validate_record(record, position))
+        .collect();
+
+    if problems.is_empty() {
+        Ok(records.len())
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+/// Joins the lines of an index written as newline delimited JSON into a JSON array, so it can be validated like one.
+/// Blank lines are left out.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::{lines_to_array, validate_index};
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let index = "{\"title\":\"Title\",\"href\":\"/a/\",\"date\":\"2019\",\"content\":\"\"}\n\n{}\n";
+/// assert_eq!(lines_to_array(index), "[{\"title\":\"Title\",\"href\":\"/a/\",\"date\":\"2019\",\"content\":\"\"},{}]");
+/// assert!(validate_index(&lines_to_array(index)).is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+#[must_use]
+pub fn lines_to_array(serialized_index: &str) -> String {
+    let lines: Vec<&str> = serialized_index
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    format!("[{}]", lines.join(","))
+}
+
+/// Re-parses an index written as a table of values separated by `delimiter` and checks every row has a value for each
+/// column, and a title, href and date if those are among the columns.
+///
+/// Returns the number of rows checked, not counting the header.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::validate_table;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// assert_eq!(validate_table("title,href\nTitle,/post/example/\n", b',')?, 1);
+/// assert!(validate_table("title,href\n,/post/example/\n", b',').is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every problem found if the table can't be read or has invalid rows.
+pub fn validate_table(serialized_index: &str, delimiter: u8) -> Result<usize, HugotoJsonError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(serialized_index.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The table's header can't be read: {}", err)],
+        })?
+        .clone();
+    let required: Vec<(usize, &str)> = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| [TITLE, HREF, DATE].contains(column))
+        .collect();
+
+    let mut problems = Vec::new();
+    let mut rows = 0;
+    for (position, row) in reader.records().enumerate() {
+        rows += 1;
+        let row = match row {
+            Ok(row) => row,
+            Err(err) => {
+                problems.push(format!("Row {position}: {err}"));
+                continue;
+            }
+        };
+        for &(column, name) in &required {
+            if row.get(column).unwrap_or_default().trim().is_empty() {
+                problems.push(format!("Row {position}: {name} is empty"));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(rows)
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+/// Re-parses an index written as a Stork config and checks every file has a URL, a title and contents.
+///
+/// Returns the number of files checked.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::validate_stork;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let config = "[[input.files]]\nurl = \"/a/\"\ntitle = \"Title\"\ncontents = \"\"\nfiletype = \"PlainText\"\n";
+/// assert_eq!(validate_stork(config)?, 1);
+/// assert!(validate_stork("[[input.files]]\nurl = \"/a/\"\n").is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every problem found if the config can't be read or has invalid files.
+pub fn validate_stork(serialized_index: &str) -> Result<usize, HugotoJsonError> {
+    let config: toml::Value =
+        toml::from_str(serialized_index).map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The config is not valid TOML: {}", err)],
+        })?;
+    let files = config
+        .get("input")
+        .and_then(|input| input.get("files"))
+        .and_then(toml::Value::as_array)
+        .ok_or_else(|| HugotoJsonError::Validation {
+            problems: vec![String::from("The config has no list of input files")],
+        })?;
+
+    let problems: Vec<String> = files
+        .iter()
+        .enumerate()
+        .flat_map(|(position, file)| {
+            let mut problems = Vec::new();
+            let field = |name: &str| file.get(name).and_then(toml::Value::as_str);
+            for name in &["url", "title"] {
+                if field(name).is_none_or(|value| value.trim().is_empty()) {
+                    problems.push(format!("File {position}: {name} is empty"));
+                }
+            }
+            if field("contents").is_none() {
+                problems.push(format!("File {position}: contents is missing"));
+            }
+            problems
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(files.len())
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+/// Re-parses an index written as a `GeoJSON` collection and checks every feature is a point at coordinates in range,
+/// with a title, an href and a date.
+///
+/// Returns the number of features checked.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::validate_geojson;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let point = |coordinates: &str| format!(
+///     r#"{{"type":"FeatureCollection","features":[{{"type":"Feature","geometry":{{"type":"Point","coordinates":{}}},"properties":{{"title":"Lisbon","href":"/lisbon/","date":"2019-01-25"}}}}]}}"#,
+///     coordinates
+/// );
+/// assert_eq!(validate_geojson(&point("[-9.14,38.72]"))?, 1);
+/// assert!(validate_geojson(&point("[38.72,-190]")).is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every problem found if the index isn't a collection of valid features.
+pub fn validate_geojson(serialized_index: &str) -> Result<usize, HugotoJsonError> {
+    let collection: FeatureCollection =
+        serde_json::from_str(serialized_index).map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The index is not a GeoJSON collection: {}", err)],
+        })?;
+
+    let problems: Vec<String> = collection
+        .features
+        .iter()
+        .enumerate()
+        .flat_map(|(position, feature)| {
+            let mut problems = Vec::new();
+            let [lng, lat] = feature.geometry.coordinates;
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+                problems.push(format!(
+                    "Feature {position}: coordinates [{lng}, {lat}] are out of range"
+                ));
+            }
+            let properties = &feature.properties;
+            for (name, value) in &[
+                (TITLE, &properties.title),
+                (HREF, &properties.href),
+                (DATE, &properties.date),
+            ] {
+                if value.trim().is_empty() {
+                    problems.push(format!("Feature {position}: {name} is empty"));
+                }
+            }
+            problems
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(collection.features.len())
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+/// Re-parses an index written as `OpenSearch` suggestions and checks each prefix's suggestion is for that prefix, with a
+/// description and a URL for each title.
+///
+/// Returns the number of prefixes checked.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::validate_suggestions;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// assert_eq!(validate_suggestions(r#"{"r": ["r", ["Rust"], [""], ["/rust/"]]}"#)?, 1);
+/// assert!(validate_suggestions(r#"{"r": ["r", ["Rust"], [], []]}"#).is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every problem found if the index isn't suggestions keyed by their queries.
+pub fn validate_suggestions(serialized_index: &str) -> Result<usize, HugotoJsonError> {
+    let suggestions: Suggestions =
+        serde_json::from_str(serialized_index).map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The index is not OpenSearch suggestions: {}", err)],
+        })?;
+
+    let problems: Vec<String> = suggestions
+        .iter()
+        .flat_map(|(prefix, Suggestion(query, titles, descriptions, urls))| {
+            let mut problems = Vec::new();
+            if query != prefix {
+                problems.push(format!("Prefix `{prefix}`: the query is `{query}`"));
+            }
+            if descriptions.len() != titles.len() || urls.len() != titles.len() {
+                problems.push(format!(
+                    "Prefix `{}`: {} titles, {} descriptions and {} URLs",
+                    prefix,
+                    titles.len(),
+                    descriptions.len(),
+                    urls.len()
+                ));
+            }
+            problems
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(suggestions.len())
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+/// Re-parses a serialized index and checks it against a JSON Schema, such as the one `schema::index_schema` describes
+/// the index with.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::schema::index_schema;
+/// use hugo_to_json::settings::Settings;
+/// use hugo_to_json::validate::validate_against_schema;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let schema = index_schema(&Settings::default());
+/// let index = r#"[{"title":"Title","href":"/post/example/","date":"2019-01-25","content":"","kind":"page","word_count":0,"reading_time":0}]"#;
+/// validate_against_schema(index, &schema)?;
+/// assert!(validate_against_schema(r#"[{"title":"Title","author":"Ann"}]"#, &schema).is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every way the index doesn't match the schema.
+pub fn validate_against_schema(
+    serialized_index: &str,
+    schema: &Value,
+) -> Result<(), HugotoJsonError> {
+    let index: Value =
+        serde_json::from_str(serialized_index).map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The index is not valid JSON: {}", err)],
+        })?;
+    let problems = schema::problems(schema, &index);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+/// Re-parses a serialized index and checks no record is larger than `max_size` bytes once serialized on its own, such
+/// as the `algolia::MAX_RECORD_SIZE` Algolia accepts.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::validate::validate_record_sizes;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let index = r#"[{"title":"Title"}, {"title":"A longer title"}]"#;
+/// validate_record_sizes(index, 30)?;
+/// assert!(validate_record_sizes(index, 20).is_err());
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Returns a `Validation` error listing every record that's too large, or if the index isn't a JSON array.
+pub fn validate_record_sizes(
+    serialized_index: &str,
+    max_size: usize,
+) -> Result<(), HugotoJsonError> {
+    let index: Vec<Value> =
+        serde_json::from_str(serialized_index).map_err(|err| HugotoJsonError::Validation {
+            problems: vec![format!("The index is not a JSON array: {}", err)],
+        })?;
+    let problems: Vec<String> = index
+        .iter()
+        .map(|record| serde_json::to_string(record).map_or(0, |record| record.len()))
+        .enumerate()
+        .filter(|(_, size)| *size > max_size)
+        .map(|(position, size)| {
+            format!("Record {position}: {size} bytes is over the limit of {max_size} bytes")
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(HugotoJsonError::Validation { problems })
+    }
+}
+
+fn validate_record(record: &Value, position: usize) -> Vec<String> {
+    let page_index: PageIndex = match serde_json::from_value(record.clone()) {
+        Ok(page_index) => page_index,
+        Err(err) => return vec![format!("Record {}: {}", position, err)],
+    };
+
+    let mut problems = Vec::new();
+    if page_index.title.trim().is_empty() {
+        problems.push(format!("Record {position}: title is empty"));
+    }
+    if page_index.href.trim().is_empty() {
+        problems.push(format!("Record {position}: href is empty"));
+    }
+    if page_index.date.trim().is_empty() {
+        problems.push(format!("Record {position}: date is empty"));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(serialized_index: &str) -> Vec<String> {
+        match validate_index(serialized_index) {
+            Err(HugotoJsonError::Validation { problems }) => problems,
+            _ => panic!("This should fail validation"),
+        }
+    }
+
+    #[test]
+    fn valid_index_passes() {
+        let index = r#"[{"title":"Title","href":"/post/example/","date":"2019-01-25","content":"Contents","tags":["Rust"]}]"#;
+        assert_eq!(validate_index(index).unwrap(), 1);
+        assert_eq!(validate_index("[]").unwrap(), 0);
+    }
+
+    #[test]
+    fn lunr_documents_are_validated() {
+        let index = r#"{"index":{},"documents":[{"title":"Title","href":"/post/example/","date":"2019-01-25","content":"Contents"}]}"#;
+        assert_eq!(validate_index(index).unwrap(), 1);
+        assert_eq!(
+            problems(r#"{"index":{},"documents":[{"title":"Title"}]}"#).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn non_array_fails() {
+        assert_eq!(problems("{}"), vec!["The index is not a JSON array"]);
+    }
+
+    #[test]
+    fn records_missing_fields_or_with_wrong_types_fail() {
+        let index = r#"[{"title":"Title","href":"/a/","date":"2019","content":""},{"title":"Title","href":"/b/"},{"title":"Title","href":"/c/","date":"2019","content":"","tags":"Rust"}]"#;
+        let problems = problems(index);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("Record 1"));
+        assert!(problems[1].starts_with("Record 2"));
+    }
+
+    #[test]
+    fn tables_with_short_rows_fail() {
+        let table = "title\thref\tdate\nTitle\t/a/\t2019\nTitle\t/b/\n";
+        let Err(HugotoJsonError::Validation { problems }) = validate_table(table, b'\t') else {
+            panic!("This should fail validation")
+        };
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Row 1"));
+    }
+
+    #[test]
+    fn records_with_empty_required_fields_fail() {
+        let index = r#"[{"title":" ","href":"","date":"2019","content":""}]"#;
+        assert_eq!(
+            problems(index),
+            vec!["Record 0: title is empty", "Record 0: href is empty"]
+        );
+    }
+}
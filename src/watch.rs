@@ -0,0 +1,387 @@
+use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
+use walkdir::WalkDir;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use crate::constants;
+use crate::file_location::FileLocation;
+use crate::hugo_to_json_error::HugotoJsonError;
+use crate::page_index::PageIndex;
+use crate::settings::Settings;
+use crate::traverse::{
+    is_hidden_path, log_result, place, process_file, Cascades, PathFilter, Traverser,
+};
+
+/// How long to wait for a burst of file system events to settle before re-processing.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// An index that can be updated one file at a time as the contents directory changes.
+/// Pages are keyed by their path, which keeps them in the same order a full traversal produces.
+pub struct WatchedIndex {
+    settings: Settings,
+    path_filter: PathFilter,
+    cascades: Cascades,
+    pages: BTreeMap<PathBuf, PageIndex>,
+}
+
+impl WatchedIndex {
+    /// Builds the index by traversing the whole contents directory.
+    /// The scan paths are canonicalized so they match the paths reported by the file system watcher.
+    ///
+    /// # Errors
+    /// Errors if the contents directory doesn't exist or can't be read.
+    pub fn new(settings: &Settings) -> Result<Self, HugotoJsonError> {
+        let mut settings = settings.clone();
+        settings.scan_path = fs::canonicalize(&settings.scan_path)?;
+        settings.more_scan_paths = settings
+            .more_scan_paths
+            .iter()
+            .map(fs::canonicalize)
+            .collect::<Result<_, _>>()?;
+        let mut watched_index = Self {
+            path_filter: PathFilter::new(&settings)?,
+            settings,
+            cascades: Cascades::default(),
+            pages: BTreeMap::new(),
+        };
+        watched_index.rebuild()?;
+        Ok(watched_index)
+    }
+
+    /// Discards every page and traverses the whole contents directory again.
+    ///
+    /// # Errors
+    /// Errors if the contents directory doesn't exist or can't be read.
+    pub fn rebuild(&mut self) -> Result<(), HugotoJsonError> {
+        let traverser = Traverser::new(self.settings.clone());
+        let located_results = traverser.traverse_locations()?;
+        self.cascades = Cascades::collect(
+            located_results.iter().map(|(location, _)| location),
+            self.settings.front_matter,
+        );
+        self.pages = located_results
+            .into_iter()
+            .filter_map(|(file_location, result)| {
+                result
+                    .ok()
+                    .map(|page_index| (PathBuf::from(file_location.absolute_path), page_index))
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Re-processes the file or directory at `path`. Files that no longer exist or no longer produce a page are removed.
+    /// A change to a section's `_index.md` may change what cascades to the pages beneath it, so rebuilds the index.
+    pub fn update(&mut self, path: &Path) {
+        let scan_path = match self.settings.scan_path_of(path) {
+            Some(scan_path) => scan_path.to_path_buf(),
+            None => return,
+        };
+        if (!self.settings.include_hidden && is_hidden_path(path, &scan_path))
+            || !self.path_filter.allows_nested(path, path.is_dir())
+            || self.is_hidden_by_earlier_scan_path(path, &scan_path)
+        {
+            return;
+        }
+
+        if is_section_file(path) {
+            if let Err(error) = self.rebuild() {
+                error!("{error}");
+            }
+            return;
+        }
+
+        if path.is_dir() {
+            let files: Vec<PathBuf> = WalkDir::new(path)
+                .follow_links(self.settings.follow_symlinks)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .map(walkdir::DirEntry::into_path)
+                .collect();
+            for file in files {
+                self.update(&file);
+            }
+            return;
+        }
+
+        if !path.is_file() {
+            self.remove(path);
+            return;
+        }
+
+        debug!("Re-processing {}", path.to_string_lossy());
+        let result = match FileLocation::from_path(path, &scan_path) {
+            Ok(file_location) => {
+                let mut file_location = place(file_location, &scan_path, &self.settings);
+                self.cascades.apply(&mut file_location);
+                let result = process_file(&file_location, &self.settings);
+                log_result(&file_location, &result);
+                result
+            }
+            Err(error) => {
+                error!(file:% = path.display(), kind = error.kind(); "{error}");
+                Err(error)
+            }
+        };
+        match result {
+            Ok(page_index) => {
+                self.pages.insert(path.to_path_buf(), page_index);
+            }
+            Err(_) => {
+                self.pages.remove(path);
+            }
+        }
+    }
+
+    /// Returns true if a file in a contents directory before `scan_path` has the same path as `path`, and so hides it.
+    fn is_hidden_by_earlier_scan_path(&self, path: &Path, scan_path: &Path) -> bool {
+        // Paths are compared where they're mounted in the content
+        let target = |scan_path| {
+            self.settings.content_mount(scan_path).map_or_else(
+                || Path::new(constants::EMPTY_STRING),
+                |mount| Path::new(&mount.target),
+            )
+        };
+        let mounted = target(scan_path).join(path.strip_prefix(scan_path).unwrap_or(path));
+        self.settings
+            .scan_paths()
+            .into_iter()
+            .take_while(|&earlier| earlier != scan_path)
+            .any(|earlier| {
+                mounted
+                    .strip_prefix(target(earlier))
+                    .is_ok_and(|relative| earlier.join(relative).exists())
+            })
+    }
+
+    /// Removes the page at `path`, or every page beneath it if it was a directory. Removing a section's `_index.md`
+    /// rebuilds the index, as its cascade no longer applies.
+    pub fn remove(&mut self, path: &Path) {
+        if is_section_file(path) {
+            if let Err(error) = self.rebuild() {
+                error!("{error}");
+            }
+            return;
+        }
+        self.pages
+            .retain(|page_path, _| !page_path.starts_with(path));
+    }
+
+    /// Returns the pages in the index in order.
+    #[must_use]
+    pub fn pages(&self) -> Vec<&PageIndex> {
+        self.pages.values().collect()
+    }
+
+    /// Writes the index to the output location in the settings, along with its other indexes and the taxonomy index,
+    /// feed and sitemap if the settings ask for them.
+    ///
+    /// # Errors
+    /// Errors if the index can't be serialized, written or fails validation.
+    pub fn write(&self) -> Result<(), HugotoJsonError> {
+        if crate::finishes_pages(&self.settings) {
+            // Any change can change which pages are related and how terms are weighted, so they're worked out again
+            let mut pages: Vec<PageIndex> = self.pages.values().cloned().collect();
+            crate::finish_pages(&self.settings, &mut pages);
+            crate::write_every_index(&self.settings, &pages)?;
+        } else {
+            crate::write_every_index(&self.settings, self.pages.values())?;
+        }
+        // Broken links are logged as they're found, and don't stop the index being kept up to date
+        crate::write_page_outputs(&self.settings, self.pages.values()).map(|_| ())
+    }
+
+    /// Applies a file system event, returning true if the index may have changed.
+    ///
+    /// # Errors
+    /// Errors if a rescan is requested and the contents directory can't be read.
+    fn apply(&mut self, event: DebouncedEvent) -> Result<bool, HugotoJsonError> {
+        match event {
+            DebouncedEvent::Create(ref path)
+            | DebouncedEvent::Write(ref path)
+            | DebouncedEvent::Chmod(ref path) => self.update(path),
+            DebouncedEvent::Remove(ref path) => self.remove(path),
+            DebouncedEvent::Rename(ref from, ref to) => {
+                self.remove(from);
+                self.update(to);
+            }
+            DebouncedEvent::Rescan => self.rebuild()?,
+            DebouncedEvent::Error(error, path) => {
+                error!(
+                    "Watch error {}: {}",
+                    path.map_or_else(String::new, |p| p.to_string_lossy().into_owned()),
+                    error
+                );
+                return Ok(false);
+            }
+            // Notices are followed by a debounced event for the same path
+            DebouncedEvent::NoticeWrite(_) | DebouncedEvent::NoticeRemove(_) => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// Returns true if `path` is a section's `_index` file, which may have a `cascade`.
+fn is_section_file(path: &Path) -> bool {
+    match path.file_stem().and_then(|stem| stem.to_str()) {
+        // Translations of a section file have a language suffix, e.g. `_index.fr`
+        Some(stem) => {
+            stem == constants::SECTION_FILE_STEM
+                || stem.starts_with(&[constants::SECTION_FILE_STEM, "."].concat())
+        }
+        None => false,
+    }
+}
+
+/// Writes an index then keeps watching the contents directory, re-processing only the files that change and rewriting
+/// the index after each change. Runs until the watcher stops.
+///
+/// # Examples
+/// ```no_run
+/// use hugo_to_json::settings::Settings;
+/// use hugo_to_json::watch::watch;
+/// use std::path::PathBuf;
+/// # use hugo_to_json::hugo_to_json_error::HugotoJsonError;
+///
+/// let settings = Settings {
+///     scan_path: PathBuf::from("./content"),
+///     output: Some(PathBuf::from("./public/index.json")),
+///     watch: true,
+///     ..Settings::default()
+/// };
+/// watch(&settings)?;
+/// # Ok::<(), HugotoJsonError>(())
+/// ```
+///
+/// # Errors
+/// Errors if the contents directory can't be read or watched, or if the index can't be written.
+pub fn watch(settings: &Settings) -> Result<(), HugotoJsonError> {
+    let mut watched_index = WatchedIndex::new(settings)?;
+    watched_index.write()?;
+
+    let (sender, receiver) = channel();
+    let mut watcher = watcher(sender, DEBOUNCE)?;
+    for scan_path in watched_index.settings.scan_paths() {
+        watcher.watch(scan_path, RecursiveMode::Recursive)?;
+        info!("Watching {} for changes", scan_path.to_string_lossy());
+    }
+
+    for event in receiver {
+        if watched_index.apply(event)? {
+            if let Err(error) = watched_index.write() {
+                // A bad write shouldn't end the session, the next change may fix it
+                error!("{error}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    fn write_page(path: &Path, title: &str) {
+        let mut file = File::create(path).unwrap();
+        write!(
+            file,
+            "+++\ntitle = \"{title}\"\ndate = \"2019-01-01\"\n+++\nContent"
+        )
+        .unwrap();
+    }
+
+    fn titles(watched_index: &WatchedIndex) -> Vec<&str> {
+        watched_index
+            .pages()
+            .iter()
+            .map(|page| page.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn updates_only_changed_files() {
+        let dir = tempfile::Builder::new().prefix("watch").tempdir().unwrap();
+        write_page(&dir.path().join("a.md"), "A");
+        write_page(&dir.path().join("b.md"), "B");
+        let settings = Settings {
+            scan_path: dir.path().to_path_buf(),
+            ..Settings::default()
+        };
+        let mut watched_index = WatchedIndex::new(&settings).unwrap();
+        assert_eq!(titles(&watched_index), vec!["A", "B"]);
+
+        let root = watched_index.settings.scan_path.clone();
+        write_page(&root.join("b.md"), "B2");
+        fs::create_dir(root.join("post")).unwrap();
+        write_page(&root.join("post").join("c.md"), "C");
+        write_page(&root.join(".hidden.md"), "Hidden");
+        watched_index.update(&root.join("b.md"));
+        watched_index.update(&root.join("post"));
+        watched_index.update(&root.join(".hidden.md"));
+        assert_eq!(titles(&watched_index), vec!["A", "B2", "C"]);
+    }
+
+    #[test]
+    fn removes_deleted_files_and_directories() {
+        let dir = tempfile::Builder::new().prefix("watch").tempdir().unwrap();
+        fs::create_dir(dir.path().join("post")).unwrap();
+        write_page(&dir.path().join("a.md"), "A");
+        write_page(&dir.path().join("post").join("b.md"), "B");
+        write_page(&dir.path().join("post").join("c.md"), "C");
+        let settings = Settings {
+            scan_path: dir.path().to_path_buf(),
+            ..Settings::default()
+        };
+        let mut watched_index = WatchedIndex::new(&settings).unwrap();
+        assert_eq!(titles(&watched_index), vec!["A", "B", "C"]);
+
+        let root = watched_index.settings.scan_path.clone();
+        fs::remove_file(root.join("a.md")).unwrap();
+        watched_index.update(&root.join("a.md"));
+        assert_eq!(titles(&watched_index), vec!["B", "C"]);
+
+        fs::remove_dir_all(root.join("post")).unwrap();
+        watched_index.remove(&root.join("post"));
+        assert!(watched_index.pages().is_empty());
+    }
+
+    #[test]
+    fn section_changes_update_cascaded_pages() {
+        let dir = tempfile::Builder::new().prefix("watch").tempdir().unwrap();
+        fs::create_dir(dir.path().join("post")).unwrap();
+        write_page(&dir.path().join("post").join("a.md"), "A");
+        let settings = Settings {
+            scan_path: dir.path().to_path_buf(),
+            ..Settings::default()
+        };
+        let mut watched_index = WatchedIndex::new(&settings).unwrap();
+        assert_eq!(titles(&watched_index), vec!["A"]);
+
+        let root = watched_index.settings.scan_path.clone();
+        let section = root.join("post").join("_index.md");
+        let mut file = File::create(&section).unwrap();
+        write!(
+            file,
+            "+++\ntitle = \"Posts\"\ndate = \"2019-01-01\"\n[cascade]\ndraft = true\n+++\n"
+        )
+        .unwrap();
+        watched_index.update(&section);
+        assert_eq!(titles(&watched_index), vec!["Posts"]);
+
+        write_page(&root.join("post").join("b.md"), "B");
+        watched_index.update(&root.join("post").join("b.md"));
+        assert_eq!(titles(&watched_index), vec!["Posts"]);
+
+        fs::remove_file(&section).unwrap();
+        watched_index.remove(&section);
+        assert_eq!(titles(&watched_index), vec!["A", "B"]);
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::lunr::{stem, tokenize, trim_and_filter};
+use crate::page_index::PageIndex;
+
+/// The number of decimal places weights are rounded to, which keeps the index small.
+const PRECISION: f64 = 10_000.0;
+
+/// Sets the `weights` field of each page to the [TF-IDF](https://en.wikipedia.org/wiki/Tf%E2%80%93idf) weight of each
+/// term in its title, tags and content, so a client can rank pages for a query without their content. Terms are
+/// lowercased and stop words are removed, as Lunr does, and terms are stemmed if `stem` is set.
+///
+/// A term's weight is its frequency in the page, as a fraction of the page's terms, times the smoothed inverse of the
+/// number of pages it appears in: `ln((1 + pages) / (1 + pages with the term)) + 1`.
+///
+/// # Examples
+/// ```
+/// use hugo_to_json::file_location::FileLocation;
+/// use hugo_to_json::page_index::PageIndex;
+/// use hugo_to_json::weights::weigh;
+/// use std::path::Path;
+/// # use hugo_to_json::operation_result::OperationResult;
+///
+/// let page = |name: &str, content: &str| {
+///     let location = FileLocation::from_path(&Path::new("/blog/content").join(name), Path::new("/blog/content"))?;
+///     PageIndex::new(Some("Rust"), None, Some("2019-01-25"), None, Vec::new(), Vec::new(), Vec::new(), Vec::new(), content.to_owned(), &location, None)
+/// };
+/// let mut pages = vec![page("a.md", "The borrow checker")?, page("b.md", "Searching the index")?];
+///
+/// weigh(&mut pages, true);
+/// assert!(pages[0].weights["borrow"] > pages[0].weights["rust"]);
+/// assert!(!pages[0].weights.contains_key("the"));
+/// assert!(pages[1].weights.contains_key("search"));
+/// # Ok::<(), OperationResult>(())
+/// ```
+#[allow(clippy::cast_precision_loss)]
+pub fn weigh<'a, I>(pages: I, stem_terms: bool)
+where
+    I: IntoIterator<Item = &'a mut PageIndex>,
+{
+    let mut pages: Vec<&mut PageIndex> = pages.into_iter().collect();
+    let frequencies: Vec<BTreeMap<String, usize>> = pages
+        .iter()
+        .map(|page| term_frequencies(page, stem_terms))
+        .collect();
+
+    let mut document_frequencies: HashMap<&str, usize> = HashMap::new();
+    for terms in &frequencies {
+        for term in terms.keys() {
+            *document_frequencies.entry(term).or_default() += 1;
+        }
+    }
+
+    let page_count = pages.len() as f64;
+    for (page, terms) in pages.iter_mut().zip(&frequencies) {
+        let term_count = terms.values().sum::<usize>() as f64;
+        page.weights = terms
+            .iter()
+            .map(|(term, &count)| {
+                let pages_with_term = document_frequencies[term.as_str()] as f64;
+                let idf = ((1.0 + page_count) / (1.0 + pages_with_term)).ln() + 1.0;
+                let weight = count as f64 / term_count * idf;
+                (term.clone(), (weight * PRECISION).round() / PRECISION)
+            })
+            .collect();
+    }
+}
+
+/// Counts each term in a page's title, tags and content.
+fn term_frequencies(page: &PageIndex, stem_terms: bool) -> BTreeMap<String, usize> {
+    let mut frequencies = BTreeMap::new();
+    for text in [&page.title, &page.content]
+        .iter()
+        .copied()
+        .chain(&page.tags)
+    {
+        let tokens = tokenize(text);
+        for term in trim_and_filter(&tokens) {
+            let term = if stem_terms {
+                stem(term)
+            } else {
+                term.to_owned()
+            };
+            *frequencies.entry(term).or_default() += 1;
+        }
+    }
+    frequencies
+}
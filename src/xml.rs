@@ -0,0 +1,34 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Escapes the characters that can't appear as themselves in XML text or attributes.
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(&['&', '<', '>', '"', '\''][..]) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Writes an element with escaped text on its own line, indented by `indent` spaces.
+pub fn element(xml: &mut String, indent: usize, name: &str, text: &str) {
+    writeln!(
+        xml,
+        "{:indent$}<{name}>{}</{name}>",
+        "",
+        escape(text),
+        indent = indent,
+        name = name
+    )
+    .unwrap();
+}
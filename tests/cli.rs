@@ -7,7 +7,7 @@ use std::fs::*;
 use std::io::prelude::*;
 use std::io::{BufReader, Write};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 #[test]
 fn input_folder_doesnt_exist() -> Result<(), Box<dyn std::error::Error>> {
@@ -111,6 +111,44 @@ Contents here
     Ok(())
 }
 
+#[test]
+fn include_hidden_and_ignore_file_control_what_is_skipped() -> Result<(), Box<dyn std::error::Error>>
+{
+    let input_dir = Builder::new().prefix("not-a-dotfile-ignore").tempdir()?;
+    create_dir_all(input_dir.path().join(".well-known"))?;
+    create_dir_all(input_dir.path().join("drafts"))?;
+    for file_name in &[".well-known/about.md", "drafts/wip.md", "visible.md"] {
+        write(
+            input_dir.path().join(file_name),
+            "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+        )?;
+    }
+    write(
+        input_dir.path().join(".hugotojsonignore"),
+        "# Not ready\ndrafts/\n",
+    )?;
+
+    let hrefs = |args: &[&str]| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd.arg(input_dir.path()).args(args).output()?;
+        assert!(output.status.success());
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        Ok(v.as_array()
+            .unwrap()
+            .iter()
+            .map(|page| page["href"].as_str().unwrap().to_owned())
+            .collect())
+    };
+    assert_eq!(hrefs(&[])?, ["/visible/"]);
+    assert_eq!(
+        hrefs(&["--include-hidden"])?,
+        ["/.well-known/about/", "/visible/"]
+    );
+
+    input_dir.close()?;
+    Ok(())
+}
+
 #[test]
 fn skips_everything_except_md_files() -> Result<(), Box<dyn std::error::Error>> {
     let input_dir = tempdir()?;
@@ -175,7 +213,7 @@ Contents here
     cmd.arg(file.path()).arg("-o").arg(output_file_path);
 
     cmd.assert().success().stderr(predicate::str::contains(
-        "Skipping ".to_owned() + &file.path().to_string_lossy().into_owned() + ". Is draft",
+        "Skipping ".to_owned() + &file.path().to_string_lossy() + ". Is draft",
     ));
 
     let output_file = File::open(output_file_path)?;
@@ -189,6 +227,229 @@ Contents here
     Ok(())
 }
 
+#[test]
+fn skips_headless_and_unlisted_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-unlisted").tempdir()?;
+    create_dir_all(input_dir.path().join("headless"))?;
+    for (file_name, front_matter) in &[
+        ("headless/index.md", "headless = true"),
+        ("hidden.md", "[_build]\nlist = false\nrender = false"),
+        ("visible.md", ""),
+    ] {
+        write(
+            input_dir.path().join(file_name),
+            format!(
+                "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n{}\n+++\nContents",
+                front_matter
+            ),
+        )?;
+    }
+
+    let hrefs = |include_unlisted: bool| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        cmd.arg(input_dir.path());
+        if include_unlisted {
+            cmd.arg("--include-unlisted");
+        }
+        let output = cmd.output()?;
+        assert!(output.status.success());
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        Ok(v.as_array()
+            .unwrap()
+            .iter()
+            .map(|page| page["href"].as_str().unwrap().to_owned())
+            .collect())
+    };
+    assert_eq!(hrefs(false)?, ["/visible/"]);
+    assert_eq!(hrefs(true)?, ["/headless/", "/hidden/", "/visible/"]);
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn front_matter_forces_the_parser() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-front-matter")
+        .tempdir()?;
+    write(
+        input_dir.path().join("toml.md"),
+        "+++\ntitle = \"TOML\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("yaml.md"),
+        "---\ntitle: YAML\ndate: 2019-01-25\n---\nContents",
+    )?;
+    write(input_dir.path().join("list.md"), "- item\n- item\n")?;
+
+    let titles = |format: &str| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let output = Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+            .arg(input_dir.path())
+            .args(["--front-matter", format, "--fail-on", "none"])
+            .output()?;
+        assert!(output.status.success());
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        let mut titles: Vec<String> = v
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|page| page["title"].as_str().unwrap().to_owned())
+            .collect();
+        titles.sort();
+        Ok(titles)
+    };
+    assert_eq!(titles("auto")?, ["TOML", "YAML"]);
+    assert_eq!(titles("yaml")?, ["YAML"]);
+    assert_eq!(titles("toml")?, ["TOML"]);
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(input_dir.path())
+        .args(["--front-matter", "xml"])
+        .assert()
+        .failure();
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn granularity_heading_writes_a_record_for_each_section() -> Result<(), Box<dyn std::error::Error>>
+{
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-granularity")
+        .tempdir()?;
+    write(
+        input_dir.path().join("guide.md"),
+        "+++\ntitle = \"Guide\"\ndate = \"2019-01-25\"\ntags = [\"docs\"]\n+++\nIntro\n\n## Install\n\nRun the installer\n\n## Use it\n\nRun it\n",
+    )?;
+
+    let output = Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(input_dir.path())
+        .args(["--granularity", "heading"])
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let records = v.as_array().unwrap();
+    let hrefs: Vec<&str> = records
+        .iter()
+        .map(|record| record["href"].as_str().unwrap())
+        .collect();
+    assert_eq!(hrefs, ["/guide/", "/guide/#install", "/guide/#use-it"]);
+    assert_eq!(records[1]["content"], "Run the installer");
+    assert_eq!(records[1]["heading"]["text"], "Install");
+    assert_eq!(records[1]["title"], "Guide");
+    assert_eq!(records[1]["tags"][0], "docs");
+    assert!(records[0].get("heading").is_none());
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn stats_prints_totals() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-stats").tempdir()?;
+    for (file_name, front_matter) in &[
+        ("draft.md", "draft = true"),
+        ("malformed.md", "title = "),
+        ("page.md", ""),
+    ] {
+        write(
+            input_dir.path().join(file_name),
+            format!(
+                "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n{}\n+++\nContents",
+                front_matter
+            ),
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--stats")
+        .arg("--progress")
+        .arg("--fail-on")
+        .arg("none")
+        .output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("Pages indexed: 1\n"));
+    assert!(stderr.contains("Files skipped: 1 (draft 1)\n"));
+    assert!(stderr.contains("Files failed: 1 (parse 1)\n"));
+    assert!(stderr.contains(&format!("Bytes written: {}\n", output.stdout.len())));
+    assert!(stderr.contains("Wall time: "));
+    // The progress bar is only drawn on a terminal
+    assert!(!stderr.contains("1/3 files"));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn max_file_size_skips_large_files() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-max-file-size")
+        .tempdir()?;
+    write(
+        input_dir.path().join("page.md"),
+        "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("dataset.md"),
+        format!(
+            "+++\ntitle = \"Dataset\"\ndate = \"2019-01-25\"\n+++\n{}",
+            "1,2,3\n".repeat(1000)
+        ),
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--max-file-size")
+        .arg("1KB")
+        .arg("--stats")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v.as_array().unwrap().len(), 1);
+    assert_eq!(v[0]["title"], "Page");
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("Files skipped: 1 (too large 1)\n"));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn log_format_json_writes_structured_records() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-json-log").tempdir()?;
+    let malformed = input_dir.path().join("malformed.md");
+    write(&malformed, "+++\ntitle = \n+++\nContents")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--log-format")
+        .arg("json")
+        .output()?;
+    assert!(!output.status.success());
+    let records = String::from_utf8(output.stderr)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<Value>, _>>()?;
+    let failure = records
+        .iter()
+        .find(|record| record["kind"] == "parse")
+        .unwrap();
+    assert_eq!(failure["level"], "ERROR");
+    assert_eq!(failure["file"], malformed.to_string_lossy().as_ref());
+    assert_eq!(failure["code"], "E002");
+    // The error the tool fails with is logged last
+    assert_eq!(records.last().unwrap()["code"], "E005");
+
+    input_dir.close()?;
+    Ok(())
+}
+
 #[test]
 fn malformed_toml_produces_warning_and_exit_error() -> Result<(), Box<dyn std::error::Error>> {
     let mut file = Builder::new()
@@ -300,7 +561,7 @@ tags:
     let mut buf_reader = BufReader::new(output_file);
     let mut read_back = String::new();
     buf_reader.read_to_string(&mut read_back)?;
-    let expected = r#"[{"title":"What You Can Achieve In a Year","href":"/what-you-can-achieve-in-a-year/","date":"2019-02-15T20:01:39Z","content":"Jon Edmiston\n","tags":["Blog","RSS","Blogging"]}]"#;
+    let expected = r#"[{"title":"What You Can Achieve In a Year","href":"/what-you-can-achieve-in-a-year/","date":"2019-02-15T20:01:39Z","content":"Jon Edmiston\n","summary":"Jon Edmiston","tags":["Blog","RSS","Blogging"],"kind":"page","word_count":2,"reading_time":1}]"#;
     assert_eq!(read_back, expected);
 
     remove_file(output_file_path)?;
@@ -339,7 +600,7 @@ Contents here
     let mut buf_reader = BufReader::new(output_file);
     let mut read_back = String::new();
     buf_reader.read_to_string(&mut read_back)?;
-    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/replacing-awk-sed-with-amber/","date":"2019-01-25T07:52:40Z","content":"Contents here","tags":["Rust","Bash","Awk","Sed","Amber","Code Search","Replace","Unix"]}]"#;
+    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/replacing-awk-sed-with-amber/","date":"2019-01-25T07:52:40Z","content":"Contents here","summary":"Contents here","tags":["Rust","Bash","Awk","Sed","Amber","Code Search","Replace","Unix"],"kind":"page","word_count":2,"reading_time":1}]"#;
     assert_eq!(read_back, expected);
 
     remove_file(output_file_path)?;
@@ -347,6 +608,50 @@ Contents here
     Ok(())
 }
 
+#[test]
+fn pretty_prints_the_index() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-pretty")
+        .suffix(".md")
+        .tempfile()?;
+
+    let contents = r#"+++
+title = "Replacing Sed/Awk With Amber"
+date = "2019-01-25T07:52:40Z"
+slug = "replacing-awk-sed-with-amber"
+tags = ['Rust']
++++
+Contents here
+"#;
+    writeln!(file, "{}", contents)?;
+
+    let expected = r#"[
+  {
+    "title": "Replacing Sed/Awk With Amber",
+    "href": "/replacing-awk-sed-with-amber/",
+    "date": "2019-01-25T07:52:40Z",
+    "content": "Contents here",
+    "summary": "Contents here",
+    "tags": [
+      "Rust"
+    ],
+    "kind": "page",
+    "word_count": 2,
+    "reading_time": 1
+  }
+]"#;
+    // The index is pretty printed the same whether it's streamed or not
+    for args in &[&["--pretty"][..], &["--pretty", "--sort", "date"][..]] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd.arg(file.path()).args(*args).output()?;
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout)?, expected);
+    }
+
+    file.close()?;
+    Ok(())
+}
+
 #[test]
 fn correctly_produces_json_for_toml_with_url_property() -> Result<(), Box<dyn std::error::Error>> {
     let mut file = Builder::new()
@@ -378,7 +683,7 @@ Contents here
     let mut buf_reader = BufReader::new(output_file);
     let mut read_back = String::new();
     buf_reader.read_to_string(&mut read_back)?;
-    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/en/post/2019/replacing-awk-sed-with-amber","date":"2019-01-25T07:52:40Z","content":"Contents here","tags":["Rust","Bash","Awk","Sed","Amber","Code Search","Replace","Unix"]}]"#;
+    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/en/post/2019/replacing-awk-sed-with-amber","date":"2019-01-25T07:52:40Z","content":"Contents here","summary":"Contents here","tags":["Rust","Bash","Awk","Sed","Amber","Code Search","Replace","Unix"],"kind":"page","word_count":2,"reading_time":1}]"#;
     assert_eq!(read_back, expected);
 
     remove_file(output_file_path)?;
@@ -425,8 +730,14 @@ tags:
     let mut read_back = String::new();
     buf_reader.read_to_string(&mut read_back)?;
     let expected = format!(
-        r#"[{{"title":"What You Can Achieve In a Year","href":"/{0}/what-you-can-achieve-in-a-year/","date":"2019-02-15T20:01:39Z","content":"Jon Edmiston\n","tags":["Blog","RSS","Blogging"]}}]"#,
-        nested_inside_content_dir.path().file_stem().unwrap().to_str().unwrap()
+        r#"[{{"title":"What You Can Achieve In a Year","href":"/{0}/what-you-can-achieve-in-a-year/","date":"2019-02-15T20:01:39Z","content":"Jon Edmiston\n","summary":"Jon Edmiston","tags":["Blog","RSS","Blogging"],"kind":"page","word_count":2,"reading_time":1}}]"#,
+        nested_inside_content_dir
+            .path()
+            .file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_lowercase()
     );
     assert_eq!(read_back, expected);
 
@@ -473,9 +784,20 @@ tags:
     let mut read_back = String::new();
     buf_reader.read_to_string(&mut read_back)?;
     let expected = format!(
-        r#"[{{"title":"What You Can Achieve In a Year","href":"/{0}/{1}/","date":"2019-02-15T20:01:39Z","content":"Jon Edmiston\n","tags":["Blog","RSS","Blogging"]}}]"#,
-        nested_inside_content_dir.path().file_stem().unwrap().to_str().unwrap(),
-        file.path().file_stem().unwrap().to_str().unwrap().to_lowercase()
+        r#"[{{"title":"What You Can Achieve In a Year","href":"/{0}/{1}/","date":"2019-02-15T20:01:39Z","content":"Jon Edmiston\n","summary":"Jon Edmiston","tags":["Blog","RSS","Blogging"],"kind":"page","word_count":2,"reading_time":1}}]"#,
+        nested_inside_content_dir
+            .path()
+            .file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_lowercase(),
+        file.path()
+            .file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_lowercase()
     );
     assert_eq!(read_back, expected);
 
@@ -533,3 +855,2776 @@ Contents here
     input_dir.close()?;
     Ok(())
 }
+
+#[test]
+fn dedupe_description_removes_description_from_content() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-dedupe-description")
+        .suffix(".md")
+        .tempfile()?;
+    let output_file_path = "./dedupe_description_removes_description_from_content.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    let contents = r#"+++
+draft = false
+title = "Replacing Sed/Awk With Amber"
+date = "2019-01-25T07:52:40Z"
+slug = "replacing-awk-sed-with-amber"
+description = "Amber is a code search and replace tool."
++++
+Amber is a code search and replace tool. Contents here
+"#;
+
+    writeln!(file, "{}", contents)?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--dedupe-description");
+
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let mut buf_reader = BufReader::new(output_file);
+    let mut read_back = String::new();
+    buf_reader.read_to_string(&mut read_back)?;
+    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/replacing-awk-sed-with-amber/","date":"2019-01-25T07:52:40Z","content":"Contents here","summary":"Contents here","description":"Amber is a code search and replace tool.","kind":"page","word_count":10,"reading_time":1}]"#;
+    assert_eq!(read_back, expected);
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn unsupported_extensions_are_skipped_without_error() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-unsupported-extensions")
+        .tempdir()?;
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-unsupported-extensions")
+        .suffix(".txt")
+        .tempfile_in(input_dir.path())?;
+    writeln!(file, "Contents here")?;
+
+    let output_file_path = "./unsupported_extensions_are_skipped_without_error.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    cmd.arg(input_dir.path()).arg("-o").arg(output_file_path);
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Not a compatible file extension."));
+
+    let output_file = File::open(output_file_path)?;
+    let mut buf_reader = BufReader::new(output_file);
+    let mut read_back = String::new();
+    buf_reader.read_to_string(&mut read_back)?;
+    assert_eq!(read_back, "[]");
+
+    remove_file(output_file_path)?;
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn gen_fixture_produces_an_indexable_site() -> Result<(), Box<dyn std::error::Error>> {
+    let fixture_dir = Builder::new()
+        .prefix("not-a-dotfile-gen-fixture")
+        .tempdir()?;
+    let content_dir = fixture_dir.path().join("content");
+    let output_file_path = "./gen_fixture_produces_an_indexable_site.json";
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("gen-fixture")
+        .arg(&content_dir)
+        .arg("--pages")
+        .arg("50")
+        .arg("--formats")
+        .arg("toml,yaml,json");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(&content_dir).arg("-o").arg(output_file_path);
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let buf_reader = BufReader::new(output_file);
+    let v: Value = serde_json::from_reader(buf_reader)?;
+    // Every eleventh page is a draft
+    assert_eq!(v.as_array().unwrap().len(), 45);
+
+    remove_file(output_file_path)?;
+    fixture_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn validate_succeeds_for_a_valid_index() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-validate")
+        .suffix(".md")
+        .tempfile()?;
+    let output_file_path = "./validate_succeeds_for_a_valid_index.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    let contents = r#"+++
+draft = false
+title = "Replacing Sed/Awk With Amber"
+date = "2019-01-25T07:52:40Z"
+slug = "replacing-awk-sed-with-amber"
++++
+Contents here
+"#;
+    writeln!(file, "{}", contents)?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--validate");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Validated 1 records"));
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn emit_schema_describes_the_index() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-emit-schema")
+        .suffix(".md")
+        .tempfile()?;
+    let output_dir = Builder::new().prefix("not-a-dotfile-schema").tempdir()?;
+    let schema_path = output_dir.path().join("schema.json");
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    let contents = r#"+++
+title = "Replacing Sed/Awk With Amber"
+date = "2019-01-25T07:52:40Z"
+author = "Ann"
++++
+Contents here
+"#;
+    writeln!(file, "{}", contents)?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--extra-fields")
+        .arg("author")
+        .arg("--emit-schema")
+        .arg(&schema_path)
+        .arg("--validate");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Validated 1 records"));
+
+    let schema: Value = serde_json::from_str(&read_to_string(&schema_path)?)?;
+    assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+    let record = &schema["items"];
+    assert_eq!(record["additionalProperties"], false);
+    assert_eq!(
+        record["properties"]["extra"]["properties"]["author"],
+        serde_json::json!({})
+    );
+    assert_eq!(record["properties"]["extra"]["additionalProperties"], false);
+
+    file.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn date_formats_normalise_dates() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-date-formats")
+        .suffix(".md")
+        .tempfile()?;
+    let output_file_path = "./date_formats_normalise_dates.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    let contents = r#"+++
+draft = false
+title = "Replacing Sed/Awk With Amber"
+date = "25 janvier 2019"
+slug = "replacing-awk-sed-with-amber"
++++
+Contents here
+"#;
+    writeln!(file, "{}", contents)?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--date-format")
+        .arg("%d %B %Y")
+        .arg("--date-locale")
+        .arg("fr");
+
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let mut buf_reader = BufReader::new(output_file);
+    let mut read_back = String::new();
+    buf_reader.read_to_string(&mut read_back)?;
+    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/replacing-awk-sed-with-amber/","date":"2019-01-25","content":"Contents here","summary":"Contents here","kind":"page","word_count":2,"reading_time":1}]"#;
+    assert_eq!(read_back, expected);
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn index_is_ordered_by_file_name() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-index-is-ordered")
+        .tempdir()?;
+    for name in &["c", "a", "b"] {
+        let mut file = File::create(input_dir.path().join(format!("{}.md", name)))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+            name
+        )?;
+    }
+
+    let output_file_path = "./index_is_ordered_by_file_name.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path()).arg("-o").arg(output_file_path);
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let v: Value = serde_json::from_reader(BufReader::new(output_file))?;
+    let titles: Vec<&str> = v
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|page| page["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["a", "b", "c"]);
+
+    remove_file(output_file_path)?;
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn content_directories_are_merged() -> Result<(), Box<dyn std::error::Error>> {
+    let content_dir = Builder::new().prefix("not-a-dotfile-content").tempdir()?;
+    let mounted_dir = Builder::new().prefix("not-a-dotfile-mounted").tempdir()?;
+    create_dir_all(content_dir.path().join("post"))?;
+    create_dir_all(mounted_dir.path().join("post"))?;
+    for (dir, file_name, title) in &[
+        (content_dir.path(), "post/_index.md", "Posts"),
+        (content_dir.path(), "post/about.md", "About"),
+        (mounted_dir.path(), "post/about.md", "Hidden About"),
+        (mounted_dir.path(), "post/mounted.md", "Mounted"),
+    ] {
+        write(
+            dir.join(file_name),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"2019-01-25\"\n[cascade]\ntags = [\"cascaded\"]\n+++\nContents",
+                title
+            ),
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(content_dir.path())
+        .arg(mounted_dir.path())
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let hrefs: Vec<&str> = v
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|page| page["href"].as_str().unwrap())
+        .collect();
+    assert_eq!(hrefs, ["/post/", "/post/about/", "/post/mounted/"]);
+    assert_eq!(v[1]["title"], "About");
+    // Sections cascade to the pages below them in every contents directory
+    assert_eq!(v[2]["tags"], serde_json::json!(["cascaded"]));
+
+    content_dir.close()?;
+    mounted_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn module_mounts_place_content_at_their_targets() -> Result<(), Box<dyn std::error::Error>> {
+    let site_dir = Builder::new().prefix("not-a-dotfile-site").tempdir()?;
+    create_dir_all(site_dir.path().join("content"))?;
+    create_dir_all(site_dir.path().join("docs/guide"))?;
+    for file_name in &[
+        "content/about.md",
+        "docs/_index.md",
+        "docs/guide/install.md",
+    ] {
+        write(
+            site_dir.path().join(file_name),
+            "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+        )?;
+    }
+    let site_config = site_dir.path().join("config.toml");
+    write(
+        &site_config,
+        "[[module.mounts]]\nsource = \"content\"\ntarget = \"content\"\n[[module.mounts]]\nsource = \"docs\"\ntarget = \"content/docs\"\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(site_dir.path().join("content"))
+        .arg("--site-config")
+        .arg(&site_config)
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let hrefs: Vec<&str> = v
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|page| page["href"].as_str().unwrap())
+        .collect();
+    assert_eq!(hrefs, ["/about/", "/docs/", "/docs/guide/install/"]);
+
+    site_dir.close()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn follows_symlinks_without_looping() -> Result<(), Box<dyn std::error::Error>> {
+    let content_dir = Builder::new().prefix("not-a-dotfile-content").tempdir()?;
+    let shared_dir = Builder::new().prefix("not-a-dotfile-shared").tempdir()?;
+    create_dir_all(shared_dir.path().join("deeper"))?;
+    for path in &[
+        content_dir.path().join("about.md"),
+        shared_dir.path().join("shared.md"),
+        shared_dir.path().join("deeper/deep.md"),
+    ] {
+        write(
+            path,
+            "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+        )?;
+    }
+    std::os::unix::fs::symlink(shared_dir.path(), content_dir.path().join("shared"))?;
+    // A link back to a directory it's in would be followed forever
+    std::os::unix::fs::symlink(shared_dir.path(), shared_dir.path().join("deeper/loop"))?;
+
+    let hrefs = |args: &[&str]| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd.arg(content_dir.path()).args(args).output()?;
+        assert!(output.status.success());
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        Ok(v.as_array()
+            .unwrap()
+            .iter()
+            .map(|page| page["href"].as_str().unwrap().to_owned())
+            .collect())
+    };
+    assert_eq!(hrefs(&[])?, ["/about/"]);
+    assert_eq!(
+        hrefs(&["--follow-symlinks"])?,
+        ["/about/", "/shared/deeper/deep/", "/shared/shared/"]
+    );
+    assert_eq!(
+        hrefs(&["--follow-symlinks", "--max-depth", "2"])?,
+        ["/about/", "/shared/shared/"]
+    );
+
+    content_dir.close()?;
+    shared_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn include_and_exclude_filter_traversal() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-include-exclude")
+        .tempdir()?;
+    for (section, name) in &[
+        ("post", "a"),
+        ("post/drafts", "b"),
+        ("legal", "c"),
+        ("", "d"),
+    ] {
+        let directory = input_dir.path().join(section);
+        create_dir_all(&directory)?;
+        let mut file = File::create(directory.join(format!("{}.md", name)))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+            name
+        )?;
+    }
+
+    let output_file_path = "./include_and_exclude_filter_traversal.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--include")
+        .arg("post/**")
+        .arg("--include")
+        .arg("legal/**")
+        .arg("--exclude")
+        .arg("**/drafts");
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let v: Value = serde_json::from_reader(BufReader::new(output_file))?;
+    let titles: Vec<&str> = v
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|page| page["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["c", "a"]);
+
+    remove_file(output_file_path)?;
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn extra_fields_are_copied_into_extra() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-extra-fields")
+        .suffix(".md")
+        .tempfile()?;
+    let output_file_path = "./extra_fields_are_copied_into_extra.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    let contents = r#"---
+title: "Replacing Sed/Awk With Amber"
+date: "2019-01-25T07:52:40Z"
+slug: "replacing-awk-sed-with-amber"
+author: "Arran"
+cover_image: "/images/amber.png"
+project: "amber"
+---
+Contents here
+"#;
+    writeln!(file, "{}", contents)?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--extra-fields")
+        .arg("author,cover_image");
+
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let mut buf_reader = BufReader::new(output_file);
+    let mut read_back = String::new();
+    buf_reader.read_to_string(&mut read_back)?;
+    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/replacing-awk-sed-with-amber/","date":"2019-01-25T07:52:40Z","content":"Contents here","summary":"Contents here","authors":["Arran"],"kind":"page","word_count":2,"reading_time":1,"extra":{"author":"Arran","cover_image":"/images/amber.png"}}]"#;
+    assert_eq!(read_back, expected);
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn config_file_maps_front_matter_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-config-file")
+        .suffix(".md")
+        .tempfile()?;
+    let mut config_file = Builder::new()
+        .prefix("not-a-dotfile-config-file")
+        .suffix(".toml")
+        .tempfile()?;
+    let output_file_path = "./config_file_maps_front_matter_keys.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    let contents = r#"+++
+title = "Replacing Sed/Awk With Amber"
+date = "2019-01-25T07:52:40Z"
+slug = "replacing-awk-sed-with-amber"
+subtitle = "Amber is a code search and replace tool."
+topics = ["Rust", "Amber"]
++++
+Contents here
+"#;
+    writeln!(file, "{}", contents)?;
+    writeln!(
+        config_file,
+        "[fields]\nsubtitle = \"description\"\ntopics = \"tags\""
+    )?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--config")
+        .arg(config_file.path());
+
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let mut buf_reader = BufReader::new(output_file);
+    let mut read_back = String::new();
+    buf_reader.read_to_string(&mut read_back)?;
+    let expected = r#"[{"title":"Replacing Sed/Awk With Amber","href":"/replacing-awk-sed-with-amber/","date":"2019-01-25T07:52:40Z","content":"Contents here","summary":"Contents here","description":"Amber is a code search and replace tool.","tags":["Rust","Amber"],"kind":"page","word_count":2,"reading_time":1}]"#;
+    assert_eq!(read_back, expected);
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn profiles_set_options() -> Result<(), Box<dyn std::error::Error>> {
+    let site_dir = Builder::new().prefix("not-a-dotfile-profiles").tempdir()?;
+    create_dir_all(site_dir.path().join("content/post"))?;
+    for file_name in &["content/about.md", "content/post/first.md"] {
+        write(
+            site_dir.path().join(file_name),
+            "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+        )?;
+    }
+    write(
+        site_dir.path().join(".hugotojson.toml"),
+        "[profiles.posts]\nformat = \"csv\"\nfields = [\"href\"]\ninclude = [\"post/**\"]\nvalidate = true\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .current_dir(site_dir.path())
+        .arg("content")
+        .arg("--profile")
+        .arg("posts")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "href\n/post/first/\n");
+
+    // The command line takes precedence
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .current_dir(site_dir.path())
+        .arg("content")
+        .arg("--profile=posts")
+        .arg("--fields")
+        .arg("title")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "title\nPage\n");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.current_dir(site_dir.path())
+        .arg("content")
+        .arg("--profile")
+        .arg("feed")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("There's no profile named `feed`"));
+
+    site_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn split_by_section_writes_an_index_per_section() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-split-by-section")
+        .tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-split-by-section-output")
+        .tempdir()?;
+    for (section, name) in &[("post", "a"), ("post/2019", "b"), ("talks", "c"), ("", "d")] {
+        let directory = input_dir.path().join(section);
+        create_dir_all(&directory)?;
+        let mut file = File::create(directory.join(format!("{}.md", name)))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+            name
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_dir.path())
+        .arg("--split-by-section");
+    cmd.assert().success();
+
+    for (index, expected_titles) in &[
+        ("post.json", vec!["b", "a"]),
+        ("talks.json", vec!["c"]),
+        ("index.json", vec!["d"]),
+    ] {
+        let output_file = File::open(output_dir.path().join(index))?;
+        let v: Value = serde_json::from_reader(BufReader::new(output_file))?;
+        let titles: Vec<&str> = v
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|page| page["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(&titles, expected_titles);
+    }
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn split_indexes_are_named_for_their_format() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-split-by-section-csv")
+        .tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-split-by-section-csv-output")
+        .tempdir()?;
+    for section in &["post", "talks"] {
+        let directory = input_dir.path().join(section);
+        create_dir_all(&directory)?;
+        let mut file = File::create(directory.join("a.md"))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+            section
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_dir.path())
+        .arg("--split-by-section")
+        .arg("--format")
+        .arg("csv");
+    cmd.assert().success();
+
+    for section in &["post", "talks"] {
+        let index = read_to_string(output_dir.path().join(format!("{}.csv", section)))?;
+        assert!(index.starts_with("title,"), "{}", index);
+        assert!(index.contains(section));
+        assert!(!output_dir.path().join(format!("{}.json", section)).exists());
+    }
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn algolia_format_splits_large_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-algolia")
+        .suffix(".md")
+        .tempfile()?;
+    let output_file_path = "./algolia_format_splits_large_pages.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    writeln!(
+        file,
+        "+++\ntitle = \"Long\"\ndate = \"2019-01-25T07:52:40Z\"\nslug = \"long\"\n+++\n{}",
+        "word ".repeat(5000)
+    )?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--format")
+        .arg("algolia");
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let v: Value = serde_json::from_reader(BufReader::new(output_file))?;
+    let records = v.as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    for (part, record) in records.iter().enumerate() {
+        assert_eq!(record["objectID"], format!("/long/-{}", part));
+        assert_eq!(record["href"], "/long/");
+        assert!(serde_json::to_string(record)?.len() <= 10_000);
+    }
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn lunr_format_writes_a_prebuilt_index() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = Builder::new()
+        .prefix("not-a-dotfile-lunr")
+        .suffix(".md")
+        .tempfile()?;
+    let output_file_path = "./lunr_format_writes_a_prebuilt_index.json";
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+
+    writeln!(
+        file,
+        "+++\ntitle = \"Searching\"\ndate = \"2019-01-25T07:52:40Z\"\nslug = \"searching\"\ntags = [\"Lunr\"]\n+++\nIndexing documents ahead of time"
+    )?;
+
+    cmd.arg(file.path())
+        .arg("-o")
+        .arg(output_file_path)
+        .arg("--format")
+        .arg("lunr")
+        .arg("--validate");
+    cmd.assert().success();
+
+    let output_file = File::open(output_file_path)?;
+    let v: Value = serde_json::from_reader(BufReader::new(output_file))?;
+    assert_eq!(v["index"]["version"], "2.3.9");
+    assert_eq!(v["index"]["fieldVectors"][0][0], "title//searching/");
+    let terms: Vec<&str> = v["index"]["invertedIndex"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry[0].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        terms,
+        vec!["ahead", "document", "index", "lunr", "search", "time"]
+    );
+    assert_eq!(v["documents"][0]["href"], "/searching/");
+
+    remove_file(output_file_path)?;
+    file.close()?;
+    Ok(())
+}
+
+#[test]
+fn section_pages_are_marked_as_sections() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-section-pages")
+        .tempdir()?;
+    create_dir_all(input_dir.path().join("post"))?;
+    for (file_name, title) in &[("post/_index.md", "Posts"), ("post/a.md", "A")] {
+        let mut file = File::create(input_dir.path().join(file_name))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+            title
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd.arg(input_dir.path()).output()?;
+    assert!(output.status.success());
+
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["href"], "/post/");
+    assert_eq!(v[0]["kind"], "section");
+    assert_eq!(v[1]["href"], "/post/a/");
+    assert_eq!(v[1]["kind"], "page");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn bundles_are_named_after_their_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-bundles").tempdir()?;
+    let bundle = input_dir.path().join("post").join("my-post");
+    create_dir_all(&bundle)?;
+    File::create(bundle.join("cover.jpg"))?;
+    let mut file = File::create(bundle.join("index.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"My Post\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--bundle-resources")
+        .output()?;
+    assert!(output.status.success());
+
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v.as_array().unwrap().len(), 1);
+    assert_eq!(v[0]["href"], "/post/my-post/");
+    assert_eq!(v[0]["resources"], serde_json::json!(["cover.jpg"]));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn split_by_lang_writes_an_index_per_language() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-split-by-lang")
+        .tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-split-by-lang-output")
+        .tempdir()?;
+    create_dir_all(input_dir.path().join("de"))?;
+    for (file_name, title) in &[
+        ("about.md", "About"),
+        ("about.fr.md", "À propos"),
+        ("de/about.md", "Über"),
+    ] {
+        let mut file = File::create(input_dir.path().join(file_name))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+            title
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_dir.path())
+        .arg("--languages")
+        .arg("en,fr,de")
+        .arg("--split-by-lang");
+    cmd.assert().success();
+
+    for (lang, title, href) in &[
+        ("en", "About", "/about/"),
+        ("fr", "À propos", "/fr/about/"),
+        ("de", "Über", "/de/about/"),
+    ] {
+        let output_file = File::open(output_dir.path().join(format!("{}.json", lang)))?;
+        let v: Value = serde_json::from_reader(BufReader::new(output_file))?;
+        assert_eq!(v.as_array().unwrap().len(), 1);
+        assert_eq!(v[0]["title"], *title);
+        assert_eq!(v[0]["href"], *href);
+        assert_eq!(v[0]["lang"], *lang);
+    }
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn site_config_applies_permalinks_taxonomies_and_base_url() -> Result<(), Box<dyn std::error::Error>>
+{
+    let site_dir = Builder::new()
+        .prefix("not-a-dotfile-site-config")
+        .tempdir()?;
+    let content_dir = site_dir.path().join("content");
+    create_dir_all(content_dir.join("post"))?;
+    let site_config_path = site_dir.path().join("config.toml");
+    let mut site_config = File::create(&site_config_path)?;
+    writeln!(
+        site_config,
+        "baseURL = \"https://example.com/\"\n[permalinks]\npost = \"/:year/:month/:slug/\"\n[taxonomies]\ntag = \"tags\"\nauthor = \"authors\""
+    )?;
+    let mut file = File::create(content_dir.join("post").join("example.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Example\"\ndate = \"2019-01-25T07:52:40Z\"\nslug = \"my-example\"\nauthors = [\"Arran\"]\n+++\nContents here"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(&content_dir)
+        .arg("--site-config")
+        .arg(&site_config_path)
+        .arg("--absolute-hrefs")
+        .output()?;
+    assert!(output.status.success());
+
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["href"], "https://example.com/2019/01/my-example/");
+    assert_eq!(v[0]["taxonomies"]["authors"], serde_json::json!(["Arran"]));
+
+    site_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn hrefs_are_slugs_like_hugos() -> Result<(), Box<dyn std::error::Error>> {
+    let site_dir = Builder::new().prefix("not-a-dotfile-slugs").tempdir()?;
+    let content_dir = site_dir.path().join("content");
+    create_dir_all(content_dir.join("Über uns"))?;
+    write(
+        content_dir.join("Über uns").join("Wer wir sind?.md"),
+        "+++\ntitle = \"Wer wir sind\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd.arg(&content_dir).output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["href"], "/über-uns/wer-wir-sind/");
+
+    let site_config = site_dir.path().join("config.toml");
+    write(
+        &site_config,
+        "removePathAccents = true\ndisablePathToLower = true\n",
+    )?;
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(&content_dir)
+        .arg("--site-config")
+        .arg(&site_config)
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["href"], "/Uber-uns/Wer-wir-sind/");
+
+    site_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn ugly_urls_end_hrefs_in_html() -> Result<(), Box<dyn std::error::Error>> {
+    let site_dir = Builder::new().prefix("not-a-dotfile-ugly").tempdir()?;
+    let content_dir = site_dir.path().join("content");
+    create_dir_all(content_dir.join("post"))?;
+    for file_name in &["post/_index.md", "post/example.md"] {
+        write(
+            content_dir.join(file_name),
+            "+++\ntitle = \"Page\"\ndate = \"2019-01-25\"\n+++\nContents",
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd.arg(&content_dir).arg("--ugly-urls").output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["href"], "/post/");
+    assert_eq!(v[1]["href"], "/post/example.html");
+
+    let site_config = site_dir.path().join("config.toml");
+    write(
+        &site_config,
+        "baseURL = \"https://example.com/\"\nuglyURLs = true\ncanonifyURLs = true\n",
+    )?;
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(&content_dir)
+        .arg("--site-config")
+        .arg(&site_config)
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[1]["href"], "https://example.com/post/example.html");
+
+    site_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn cascades_apply_to_descendant_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-cascade").tempdir()?;
+    create_dir_all(input_dir.path().join("post").join("rust"))?;
+    create_dir_all(input_dir.path().join("wip"))?;
+    for (file_name, contents) in &[
+        ("post/_index.md", "---\ntitle: Posts\ndate: 2019-01-25\ncascade:\n  tags: [cascaded]\n  author: Arran\n---\n"),
+        ("post/rust/a.md", "+++\ntitle = \"A\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here"),
+        ("post/b.md", "{\"title\": \"B\", \"date\": \"2019-01-25T07:52:40Z\", \"tags\": [\"own\"]}\nContents here"),
+        ("wip/_index.md", "+++\ntitle = \"Work in Progress\"\ndate = \"2019-01-25\"\n[cascade]\ndraft = true\n+++\n"),
+        ("wip/c.md", "+++\ntitle = \"C\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here"),
+    ] {
+        let mut file = File::create(input_dir.path().join(file_name))?;
+        write!(file, "{}", contents)?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--extra-fields")
+        .arg("author")
+        .output()?;
+    assert!(output.status.success());
+
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let titles: Vec<&str> = v
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|page| page["title"].as_str())
+        .collect();
+    assert_eq!(titles, vec!["Posts", "B", "A", "Work in Progress"]);
+    assert!(v[0].get("tags").is_none());
+    assert_eq!(v[1]["tags"], serde_json::json!(["own"]));
+    assert_eq!(v[1]["extra"]["author"], "Arran");
+    assert_eq!(v[2]["tags"], serde_json::json!(["cascaded"]));
+    assert_eq!(v[2]["extra"]["author"], "Arran");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn stdin_prints_a_single_page() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-stdin").tempdir()?;
+    create_dir_all(input_dir.path().join("post"))?;
+    write(
+        input_dir.path().join("post").join("_index.md"),
+        "+++\ntitle = \"Posts\"\ndate = \"2019-01-25\"\n[cascade]\ntags = [\"cascaded\"]\n+++\n",
+    )?;
+
+    let document = "+++\ntitle = \"Unsaved\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here";
+    // The path can be inside the contents directory or relative to it
+    for path in &[
+        input_dir.path().join("post").join("unsaved.md"),
+        Path::new("post").join("unsaved.md"),
+    ] {
+        let mut child = Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+            .arg(input_dir.path())
+            .arg("--stdin")
+            .arg("--path")
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(document.as_bytes())?;
+        let output = child.wait_with_output()?;
+        assert!(output.status.success());
+
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        assert_eq!(v["title"], "Unsaved");
+        assert_eq!(v["href"], "/post/unsaved/");
+        assert_eq!(v["tags"], serde_json::json!(["cascaded"]));
+    }
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn shortcodes_are_stripped_from_content() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-shortcodes")
+        .tempdir()?;
+    let mut file = File::create(input_dir.path().join("shortcodes.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Shortcodes\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nBefore {{{{< figure src=\"a.png\" >}}}} {{{{% note %}}}}Noted{{{{% /note %}}}} after"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd.arg(input_dir.path()).output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let words: Vec<&str> = v[0]["content"]
+        .as_str()
+        .unwrap()
+        .split_whitespace()
+        .collect();
+    assert_eq!(words, vec!["Before", "after"]);
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--keep-shortcode-text")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let words: Vec<&str> = v[0]["content"]
+        .as_str()
+        .unwrap()
+        .split_whitespace()
+        .collect();
+    assert_eq!(words, vec!["Before", "Noted", "after"]);
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn pulldown_cmark_extractor_handles_tables_and_lists() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-content-extractor")
+        .tempdir()?;
+    let mut file = File::create(input_dir.path().join("table.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Table\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\n| a | b |\n|---|---|\n| 1 | 2 |\n\n- one\n  - nested\n\n```\ncode\n```"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--content-extractor")
+        .arg("pulldown-cmark")
+        .arg("--skip-code-blocks")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["content"], "a b\n1 2\none\nnested");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn raw_extractor_keeps_the_markdown() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-raw-extractor")
+        .tempdir()?;
+    write(
+        input_dir.path().join("raw.md"),
+        "+++\ntitle = \"Raw\"\ndate = \"2019-01-25\"\n+++\n# Heading\n\n*Some* [markdown](/link/) {{< note >}}\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--content-extractor")
+        .arg("raw")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["content"], "# Heading\n\n*Some* [markdown](/link/)");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn code_blocks_can_be_skipped_or_indexed_alone() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-code-blocks")
+        .tempdir()?;
+    let mut file = File::create(input_dir.path().join("code.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Code\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nBuild it:\n\n```sh\ncargo build\n```\n\nThen run it:\n\n    cargo run"
+    )?;
+
+    let content = |flag: &str| -> Result<String, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd.arg(input_dir.path()).arg(flag).output()?;
+        assert!(output.status.success());
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        Ok(v[0]["content"].as_str().unwrap_or_default().to_owned())
+    };
+
+    let skipped = content("--skip-code-blocks")?;
+    assert!(skipped.contains("Build it:") && skipped.contains("Then run it:"));
+    assert!(!skipped.contains("cargo"));
+    assert_eq!(content("--code-blocks-only")?, "cargo build\ncargo run");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn content_length_and_index_size_are_limited() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-limits").tempdir()?;
+    let mut file = File::create(input_dir.path().join("long.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Long\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\n{}",
+        "word ".repeat(100)
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--max-content-length")
+        .arg("3words")
+        .arg("--max-index-size")
+        .arg("1KB")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["content"], "word word word");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--max-index-size")
+        .arg("100")
+        .arg("--enforce-max-index-size")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("IndexSize"));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn compressed_output_is_written_with_an_extension() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-compress").tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-compress-output")
+        .tempdir()?;
+    let mut file = File::create(input_dir.path().join("page.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Compressed\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--compress")
+        .arg("gzip")
+        .arg("--validate")
+        .assert()
+        .success();
+
+    assert!(!output_dir.path().join("index.json").exists());
+    let mut contents = String::new();
+    flate2::read::GzDecoder::new(File::open(output_dir.path().join("index.json.gz"))?)
+        .read_to_string(&mut contents)?;
+    let v: Value = serde_json::from_str(&contents)?;
+    assert_eq!(v[0]["title"], "Compressed");
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn cache_file_is_written_and_changed_files_are_reprocessed(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-cache").tempdir()?;
+    let cache_dir = Builder::new()
+        .prefix("not-a-dotfile-cache-file")
+        .tempdir()?;
+    let cache_file = cache_dir.path().join("hugotojson-cache");
+    let page = input_dir.path().join("page.md");
+    write(
+        &page,
+        "+++\ntitle = \"Before\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+    )?;
+
+    let run = || -> Result<Value, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd
+            .arg(input_dir.path())
+            .arg("--cache-file")
+            .arg(&cache_file)
+            .output()?;
+        assert!(output.status.success());
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+
+    assert_eq!(run()?[0]["title"], "Before");
+    let cache: Value = serde_json::from_str(&read_to_string(&cache_file)?)?;
+    assert_eq!(
+        cache["entries"].as_object().map(|entries| entries.len()),
+        Some(1)
+    );
+    assert_eq!(run()?[0]["title"], "Before");
+
+    write(
+        &page,
+        "+++\ntitle = \"After the change\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here",
+    )?;
+    assert_eq!(run()?[0]["title"], "After the change");
+
+    input_dir.close()?;
+    cache_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn asciidoc_and_rst_files_are_indexed() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-markup").tempdir()?;
+    let mut file = File::create(input_dir.path().join("guide.adoc"))?;
+    writeln!(
+        file,
+        "---\ntitle: AsciiDoc\ndate: 2019-01-25\n---\n== Install\n\n// A comment\nRun *cargo*."
+    )?;
+    let mut file = File::create(input_dir.path().join("notes.rst"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"reStructuredText\"\ndate = \"2019-01-25\"\n+++\nInstall\n=======\n\n.. A comment\n\nRun **cargo**."
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd.arg(input_dir.path()).output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["title"], "AsciiDoc");
+    assert_eq!(v[0]["content"], "Install\nRun cargo.");
+    assert_eq!(v[0]["href"], "/guide/");
+    assert_eq!(v[1]["title"], "reStructuredText");
+    assert_eq!(v[1]["content"], "Install\nRun cargo.");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn error_report_lists_failed_files() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-errors").tempdir()?;
+    let report_dir = Builder::new()
+        .prefix("not-a-dotfile-errors-report")
+        .tempdir()?;
+    let report = report_dir.path().join("errors.json");
+    let mut file = File::create(input_dir.path().join("good.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Good\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here"
+    )?;
+    let mut file = File::create(input_dir.path().join("bad.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Bad\"\ndate = 2019-01-25T07:52:40Z\n+++"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--error-report")
+        .arg(&report)
+        .assert()
+        .failure();
+
+    let v: Value = serde_json::from_str(&read_to_string(&report)?)?;
+    assert_eq!(v.as_array().map(Vec::len), Some(1));
+    assert!(v[0]["path"].as_str().unwrap().ends_with("bad.md"));
+    assert_eq!(v[0]["kind"], "parse");
+    assert_eq!(v[0]["code"], "E002");
+    assert!(v[0]["message"].as_str().is_some());
+
+    input_dir.close()?;
+    report_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn error_report_shows_where_front_matter_is_broken() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-positions").tempdir()?;
+    let report_dir = Builder::new()
+        .prefix("not-a-dotfile-positions-report")
+        .tempdir()?;
+    let report = report_dir.path().join("errors.json");
+    write(
+        input_dir.path().join("broken.md"),
+        "+++\ntitle = \"Broken\"\ndate / \"2019-01-25\"\n+++\nContents here\n",
+    )?;
+
+    let output = Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(input_dir.path())
+        .arg("--error-report")
+        .arg(&report)
+        .output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)?.contains("    date / \"2019-01-25\"\n"));
+
+    let v: Value = serde_json::from_str(&read_to_string(&report)?)?;
+    assert_eq!(v[0]["line"], 3);
+    assert_eq!(v[0]["column"], 6);
+    assert_eq!(v[0]["snippet"], "date / \"2019-01-25\"");
+
+    input_dir.close()?;
+    report_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn fail_on_chooses_which_failures_fail_the_run() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-fail-on").tempdir()?;
+    let mut file = File::create(input_dir.path().join("good.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Good\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nContents here"
+    )?;
+    let mut file = File::create(input_dir.path().join("bad.md"))?;
+    writeln!(file, "Not front matter")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--fail-on")
+        .arg("parse")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("parse: 1"));
+
+    for fail_on in &["io", "none"] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd
+            .arg(input_dir.path())
+            .arg("--fail-on")
+            .arg(fail_on)
+            .output()?;
+        assert!(output.status.success());
+        let v: Value = serde_json::from_slice(&output.stdout)?;
+        assert_eq!(v[0]["title"], "Good");
+    }
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn taxonomy_index_maps_terms_to_hrefs() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-taxonomies")
+        .tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-taxonomies-output")
+        .tempdir()?;
+    let taxonomies = output_dir.path().join("taxonomies.json");
+    let mut file = File::create(input_dir.path().join("first.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25T07:52:40Z\"\ntags = [\"rust\", \"hugo\"]\nseries = [\"Guide\"]\n+++\nContents"
+    )?;
+    let mut file = File::create(input_dir.path().join("second.md"))?;
+    writeln!(
+        file,
+        "+++\ntitle = \"Second\"\ndate = \"2019-01-26T07:52:40Z\"\ntags = [\"rust\"]\n+++\nContents"
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--taxonomies")
+        .arg(&taxonomies)
+        .assert()
+        .success();
+
+    let v: Value = serde_json::from_str(&read_to_string(&taxonomies)?)?;
+    assert_eq!(
+        v["tags"]["rust"],
+        serde_json::json!(["/first/", "/second/"])
+    );
+    assert_eq!(v["tags"]["hugo"], serde_json::json!(["/first/"]));
+    assert_eq!(v["series"]["Guide"], serde_json::json!(["/first/"]));
+    assert_eq!(v["categories"], serde_json::json!({}));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn related_pages_share_the_most_terms() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-related").tempdir()?;
+    for (name, tags, keywords) in &[
+        ("first", "[\"rust\", \"hugo\"]", "[]"),
+        ("second", "[\"rust\"]", "[\"search\"]"),
+        ("third", "[\"hugo\"]", "[\"search\"]"),
+    ] {
+        let mut file = File::create(input_dir.path().join(format!("{}.md", name)))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\ntags = {}\nkeywords = {}\n+++\nContents",
+            name, tags, keywords
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--related")
+        .arg("1")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["related"], serde_json::json!(["/second/"]));
+    assert_eq!(v[1]["related"], serde_json::json!(["/third/"]));
+    assert_eq!(v[2]["related"], serde_json::json!(["/second/"]));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn sort_and_limit_write_the_newest_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-sort").tempdir()?;
+    for (name, date) in &[
+        ("first", "2019-01-25"),
+        ("second", "2019-03-01T10:00:00+01:00"),
+        ("third", "2019-02-14"),
+        ("fourth", "not a date"),
+    ] {
+        let mut file = File::create(input_dir.path().join(format!("{}.md", name)))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"{}\"\n+++\nContents",
+            name, date
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--sort")
+        .arg("date")
+        .arg("--order")
+        .arg("desc")
+        .arg("--limit")
+        .arg("2")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let hrefs: Vec<&str> = v
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|page| page["href"].as_str().unwrap())
+        .collect();
+    assert_eq!(hrefs, ["/second/", "/third/"]);
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--sort")
+        .arg("title")
+        .output()?;
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["title"], "first");
+    assert_eq!(v[3]["title"], "third");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn weights_rank_rare_terms_higher() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-weights").tempdir()?;
+    for (name, content) in &[("first", "Rust searching"), ("second", "Rust borrowing")] {
+        let mut file = File::create(input_dir.path().join(format!("{}.md", name)))?;
+        writeln!(
+            file,
+            "+++\ntitle = \"{}\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nThe {}",
+            name, content
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--weights")
+        .arg("--stem-weights")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    let weights = v[0]["weights"].as_object().unwrap();
+    assert!(weights["search"].as_f64() > weights["rust"].as_f64());
+    assert!(!weights.contains_key("the"));
+    assert!(v[1]["weights"]["borrow"].is_number());
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn normalize_content_uses_the_page_language() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-normalize").tempdir()?;
+    write(
+        input_dir.path().join("cats.md"),
+        "+++\ntitle = \"Cats\"\ndate = \"2019-01-25T07:52:40Z\"\n+++\nThe cats are running",
+    )?;
+    write(
+        input_dir.path().join("chats.md"),
+        "+++\ntitle = \"Chats\"\ndate = \"2019-01-25T07:52:40Z\"\nlanguage = \"fr\"\n+++\nLes chats mangeaient",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--normalize-content")
+        .arg("--language")
+        .arg("en")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v[0]["content"], "cat run");
+    assert_eq!(v[1]["content"], "chat mang");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn rss_lists_the_newest_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-rss").tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-rss-output")
+        .tempdir()?;
+    let site_config = input_dir.path().join("config.toml");
+    write(
+        &site_config,
+        "baseURL = \"https://example.com/\"\ntitle = \"Example\"\n",
+    )?;
+    let content = input_dir.path().join("content");
+    create_dir_all(&content)?;
+    for (name, date) in &[
+        ("old", "2019-01-25"),
+        ("new", "2019-03-01"),
+        ("middle", "2019-02-14"),
+    ] {
+        write(
+            content.join(format!("{}.md", name)),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"{}\"\n+++\nContents",
+                name, date
+            ),
+        )?;
+    }
+    let feed = output_dir.path().join("feed.xml");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(&content)
+        .arg("-o")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--site-config")
+        .arg(&site_config)
+        .arg("--rss")
+        .arg(&feed)
+        .arg("--rss-limit")
+        .arg("2")
+        .assert()
+        .success();
+
+    let rss = read_to_string(&feed)?;
+    assert!(rss.contains("<rss version=\"2.0\">"));
+    assert!(rss.contains("<title>Example</title>"));
+    let new = rss.find("<link>https://example.com/new/</link>").unwrap();
+    let middle = rss
+        .find("<link>https://example.com/middle/</link>")
+        .unwrap();
+    assert!(new < middle);
+    assert!(!rss.contains("/old/"));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn sitemap_lists_every_page() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-sitemap").tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-sitemap-output")
+        .tempdir()?;
+    let site_config = input_dir.path().join("config.toml");
+    write(
+        &site_config,
+        "baseURL = \"https://example.com/\"\n[sitemap]\nchangefreq = \"monthly\"\npriority = -1\n",
+    )?;
+    let content = input_dir.path().join("content");
+    create_dir_all(&content)?;
+    write(
+        content.join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\nlastmod = \"2019-02-14T10:00:00Z\"\n[sitemap]\nchangefreq = \"weekly\"\npriority = 0.8\n+++\nContents",
+    )?;
+    write(
+        content.join("second.md"),
+        "+++\ntitle = \"Second\"\ndate = \"2019-01-26\"\n+++\nContents",
+    )?;
+    let sitemap = output_dir.path().join("sitemap.xml");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(&content)
+        .arg("-o")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--site-config")
+        .arg(&site_config)
+        .arg("--sitemap")
+        .arg(&sitemap)
+        .assert()
+        .success();
+
+    let xml = read_to_string(&sitemap)?;
+    assert!(xml.contains(
+        "<loc>https://example.com/first/</loc>\n    <lastmod>2019-02-14T10:00:00Z</lastmod>\n    <changefreq>weekly</changefreq>\n    <priority>0.8</priority>"
+    ));
+    assert!(xml.contains(
+        "<loc>https://example.com/second/</loc>\n    <lastmod>2019-01-26T00:00:00Z</lastmod>\n    <changefreq>monthly</changefreq>\n  </url>"
+    ));
+    let index = read_to_string(output_dir.path().join("index.json"))?;
+    assert!(!index.contains("changefreq"));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn links_writes_the_link_graph() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-links").tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-links-output")
+        .tempdir()?;
+    let post = input_dir.path().join("post");
+    create_dir_all(&post)?;
+    write(
+        post.join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nSee [the second](../second/#setup) and [Rust](https://www.rust-lang.org).",
+    )?;
+    write(
+        post.join("second.md"),
+        "+++\ntitle = \"Second\"\ndate = \"2019-01-26\"\n+++\nBack to [the `first`](/post/first/).",
+    )?;
+    let links = output_dir.path().join("links.json");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("-o")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--links")
+        .arg(&links)
+        .assert()
+        .success();
+
+    let mut v: Vec<Value> = serde_json::from_str(&read_to_string(&links)?)?;
+    v.sort_by_key(|link| link["text"].to_string());
+    assert_eq!(
+        v,
+        [
+            serde_json::json!({"source": "/post/first/", "target": "https://www.rust-lang.org", "internal": false, "text": "Rust"}),
+            serde_json::json!({"source": "/post/second/", "target": "/post/first/", "internal": true, "text": "the first"}),
+            serde_json::json!({"source": "/post/first/", "target": "/post/second/#setup", "internal": true, "text": "the second"}),
+        ]
+    );
+    let index = read_to_string(output_dir.path().join("index.json"))?;
+    assert!(!index.contains("\"links\""));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn check_links_reports_broken_links() -> Result<(), Box<dyn std::error::Error>> {
+    let site_dir = Builder::new()
+        .prefix("not-a-dotfile-check-links")
+        .tempdir()?;
+    let report_dir = Builder::new()
+        .prefix("not-a-dotfile-check-links-report")
+        .tempdir()?;
+    let report = report_dir.path().join("errors.json");
+    let content = site_dir.path().join("content");
+    create_dir_all(content.join("post"))?;
+    create_dir_all(site_dir.path().join("static").join("images"))?;
+    write(
+        site_dir
+            .path()
+            .join("static")
+            .join("images")
+            .join("logo.png"),
+        "",
+    )?;
+    write(
+        content.join("post").join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\n[Second](../second/), [tagged](/tags/rust/) and ![Logo](/images/logo.png)\n\n[Missing](/post/missing/#setup) and [Rust](https://www.rust-lang.org)\n\n{{< ref \"second.md\" >}} and {{< ref \"gone.md\" >}}\n",
+    )?;
+    write(
+        content.join("post").join("second.md"),
+        "+++\ntitle = \"Second\"\ndate = \"2019-01-26\"\ntags = [\"Rust\"]\n+++\nBack to [the logo](/images/logo.png).\n",
+    )?;
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(&content)
+        .arg("-o")
+        .arg(report_dir.path().join("index.json"))
+        .arg("--check-links")
+        .arg("--error-report")
+        .arg(&report)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "BrokenLinks { links: 2, files: 1 }",
+        ));
+
+    let v: Value = serde_json::from_str(&read_to_string(&report)?)?;
+    assert_eq!(v.as_array().map(Vec::len), Some(2));
+    assert!(v[0]["path"].as_str().unwrap().ends_with("first.md"));
+    assert_eq!(v[0]["kind"], "link");
+    assert_eq!(v[0]["code"], "E014");
+    assert_eq!(v[0]["line"], 7);
+    assert_eq!(
+        v[0]["message"],
+        "Link to `/post/missing/#setup` doesn't go to a page or file"
+    );
+    assert_eq!(v[1]["line"], 9);
+    assert_eq!(
+        v[1]["message"],
+        "Ref to `gone.md` doesn't go to a page or file"
+    );
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(&content)
+        .arg("-o")
+        .arg(report_dir.path().join("index.json"))
+        .arg("--check-links")
+        .arg("--fail-on")
+        .arg("parse")
+        .assert()
+        .success();
+
+    site_dir.close()?;
+    report_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn duplicate_hrefs_are_reported() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-duplicates")
+        .tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-duplicates-output")
+        .tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\nslug = \"same\"\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("second.md"),
+        "+++\ntitle = \"Second\"\ndate = \"2019-01-26\"\nslug = \"same\"\n+++\nContents",
+    )?;
+    let index = output_dir.path().join("index.json");
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(input_dir.path())
+        .arg("-o")
+        .arg(&index)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Has the same href `/same/` as "));
+
+    Command::cargo_bin(env!("CARGO_PKG_NAME"))?
+        .arg(input_dir.path())
+        .arg("-o")
+        .arg(&index)
+        .arg("--fail-on-duplicates")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Duplicates { pages: 1 }"));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn csv_format_writes_the_chosen_fields() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-csv").tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First, again\"\ndate = \"2019-01-25\"\ntags = [\"rust\", \"hugo\"]\nauthor = \"Arran\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--format")
+        .arg("csv")
+        .arg("--fields")
+        .arg("title,tags,author,word_count")
+        .arg("--extra-fields")
+        .arg("author")
+        .arg("--list-delimiter")
+        .arg(";")
+        .arg("--validate")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "title,tags,author,word_count\n\"First, again\",rust;hugo,Arran,1\n"
+    );
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--format")
+        .arg("tsv")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "title\thref\tdate\ttags\nFirst, again\t/first/\t2019-01-25\trust|hugo\n"
+    );
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn ndjson_format_writes_a_page_on_each_line() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-ndjson").tempdir()?;
+    for name in &["first", "second"] {
+        write(
+            input_dir.path().join(format!("{}.md", name)),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"2019-01-25\"\n+++\nContents",
+                name
+            ),
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--format")
+        .arg("ndjson")
+        .arg("--pretty")
+        .arg("--validate")
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut hrefs: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            let page: Value = serde_json::from_str(line).unwrap();
+            page["href"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    hrefs.sort();
+    assert_eq!(hrefs, vec!["/first/", "/second/"]);
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn sqlite_format_writes_a_database() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-sqlite").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\ntags = [\"rust\", \"hugo\"]\n+++\nSearchable contents",
+    )?;
+    let database = output_dir.path().join("index.db");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--format")
+        .arg("sqlite")
+        .arg("--output")
+        .arg(&database)
+        .arg("--validate")
+        .assert()
+        .success();
+    let connection = rusqlite::Connection::open(&database)?;
+    let tags: i64 = connection.query_row(
+        "SELECT count(*) FROM page_tags JOIN pages ON pages.id = page_id WHERE href = '/first/'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(tags, 2);
+    let title: String = connection.query_row(
+        "SELECT title FROM pages_fts WHERE pages_fts MATCH 'searchable'",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(title, "First");
+
+    // A database can't be written to stdout
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--format")
+        .arg("sqlite")
+        .assert()
+        .failure();
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn split_sqlite_indexes_are_written_to_databases() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-split-sqlite")
+        .tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    for section in &["post", "talks"] {
+        create_dir_all(input_dir.path().join(section))?;
+        write(
+            input_dir.path().join(section).join("first.md"),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"2019-01-25\"\n+++\nContents",
+                section
+            ),
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--format")
+        .arg("sqlite")
+        .arg("--output")
+        .arg(output_dir.path())
+        .arg("--split-by-section")
+        .assert()
+        .success();
+    for section in &["post", "talks"] {
+        assert!(!output_dir.path().join(format!("{}.json", section)).exists());
+        let connection =
+            rusqlite::Connection::open(output_dir.path().join(format!("{}.db", section)))?;
+        let title: String =
+            connection.query_row("SELECT title FROM pages", [], |row| row.get(0))?;
+        assert_eq!(&title, section);
+    }
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn stork_format_writes_a_stork_config() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-stork").tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--format")
+        .arg("stork")
+        .arg("--validate")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "[[input.files]]\nurl = \"/first/\"\ntitle = \"First\"\ncontents = \"Contents\"\nfiletype = \"PlainText\"\n"
+    );
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn environment_variables_set_options() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-env").tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .env("HUGOTOJSON_FORMAT", "csv")
+        .env("HUGOTOJSON_FIELDS", "title,href")
+        .env("HUGOTOJSON_VALIDATE", "true")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "title,href\nFirst,/first/\n"
+    );
+
+    // The command line takes precedence
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--fields")
+        .arg("title")
+        .env("HUGOTOJSON_FORMAT", "csv")
+        .env("HUGOTOJSON_FIELDS", "title,href")
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "title\nFirst\n");
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn out_writes_several_outputs_in_one_pass() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-out").tempdir()?;
+    let output_dir = Builder::new()
+        .prefix("not-a-dotfile-out-output")
+        .tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\ntags = [\"rust\"]\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--out")
+        .arg(output_dir.path().join("index.json"))
+        .arg("--out")
+        .arg(output_dir.path().join("index.csv"))
+        .arg(format!(
+            "--out={}:taxonomies",
+            output_dir.path().join("tags.json").display()
+        ))
+        .arg("--out")
+        .arg(format!(
+            "{}:sitemap",
+            output_dir.path().join("sitemap.xml").display()
+        ))
+        .assert()
+        .success()
+        .stdout("");
+
+    let index: Value =
+        serde_json::from_str(&read_to_string(output_dir.path().join("index.json"))?)?;
+    assert_eq!(index[0]["href"], "/first/");
+    assert_eq!(
+        read_to_string(output_dir.path().join("index.csv"))?,
+        "title,href,date,tags\nFirst,/first/,2019-01-25,rust\n"
+    );
+    let taxonomies: Value =
+        serde_json::from_str(&read_to_string(output_dir.path().join("tags.json"))?)?;
+    assert_eq!(taxonomies["tags"]["rust"], serde_json::json!(["/first/"]));
+    assert!(read_to_string(output_dir.path().join("sitemap.xml"))?.contains("/first/"));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn jobs_dont_change_the_index() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-jobs").tempdir()?;
+    for name in &["a", "b", "c", "d"] {
+        write(
+            input_dir.path().join(format!("{}.md", name)),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"2019-01-25\"\n+++\nContents",
+                name
+            ),
+        )?;
+    }
+
+    let mut outputs = Vec::new();
+    for args in &[&[][..], &["--jobs", "2"][..], &["--single-threaded"][..]] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd.arg(input_dir.path()).args(*args).output()?;
+        assert!(output.status.success());
+        outputs.push(output.stdout);
+    }
+    assert_eq!(outputs[0], outputs[1]);
+    assert_eq!(outputs[0], outputs[2]);
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--jobs")
+        .arg("2")
+        .arg("--single-threaded")
+        .assert()
+        .failure();
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn check_reports_broken_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-check").tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\ndescription = \"About Rust\"\ntags = [\"rust\", \"go\"]\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("second.md"),
+        "+++\ntitle = \"Second\"\ndate = \"2019-01-26\"\ndescription = \"About Hugo\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let first = input_dir.path().join("first.md");
+    cmd.arg(input_dir.path())
+        .arg("check")
+        .arg("--allowed-tags")
+        .arg("rust,hugo")
+        .arg("--max-description")
+        .arg("9")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!(
+            "{}:5: tag `go` isn't allowed",
+            first.to_string_lossy()
+        )))
+        .stdout(predicate::str::contains(format!(
+            "{}:4: description is 10 characters, more than 9",
+            first.to_string_lossy()
+        )))
+        .stderr(predicate::str::contains(
+            "Check { violations: 3, files: 2 }",
+        ));
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("check")
+        .arg("--required")
+        .arg("description")
+        .assert()
+        .success();
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn fusejs_preset_writes_fuse_options() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-fusejs").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    let config = output_dir.path().join("hugotojson.toml");
+    write(&config, "[lunr]\nboosts = { title = 20 }\n")?;
+    let index = output_dir.path().join("index.json");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--preset")
+        .arg("fusejs")
+        .arg("--config")
+        .arg(&config)
+        .arg("--output")
+        .arg(&index)
+        .assert()
+        .success();
+    let documents: Value = serde_json::from_str(&read_to_string(&index)?)?;
+    assert_eq!(documents[0]["title"], "First");
+    let options: Value = serde_json::from_str(&read_to_string(
+        output_dir.path().join("fuse-options.json"),
+    )?)?;
+    assert_eq!(
+        options["keys"],
+        serde_json::json!([
+            {"name": "title", "weight": 20.0},
+            {"name": "tags", "weight": 5.0},
+            {"name": "content", "weight": 1.0},
+        ])
+    );
+    assert_eq!(options["ignoreLocation"], true);
+
+    // Fuse.js searches an array of pages
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--preset")
+        .arg("fusejs")
+        .arg("--format")
+        .arg("lunr")
+        .arg("--output")
+        .arg(&index)
+        .assert()
+        .failure();
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn shard_size_splits_the_index_into_shards() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-shards").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    for (name, date) in &[
+        ("one", "2019-01-25"),
+        ("two", "2019-02-14"),
+        ("three", "2019-03-01"),
+    ] {
+        write(
+            input_dir.path().join(format!("{}.md", name)),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"{}\"\n+++\nContents",
+                name, date
+            ),
+        )?;
+    }
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--shard-size")
+        .arg("2")
+        .arg("--sort")
+        .arg("date")
+        .arg("--output")
+        .arg(output_dir.path().join("index.json"))
+        .assert()
+        .success();
+    let manifest: Value =
+        serde_json::from_str(&read_to_string(output_dir.path().join("manifest.json"))?)?;
+    assert_eq!(
+        manifest,
+        serde_json::json!({
+            "pages": 3,
+            "shards": [
+                {"file": "index-000.json", "pages": 2, "oldest": "2019-01-25", "newest": "2019-02-14"},
+                {"file": "index-001.json", "pages": 1, "oldest": "2019-03-01", "newest": "2019-03-01"},
+            ],
+        })
+    );
+    let first: Value =
+        serde_json::from_str(&read_to_string(output_dir.path().join("index-000.json"))?)?;
+    assert_eq!(first[1]["title"], "two");
+    assert!(!output_dir.path().join("index.json").exists());
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn diff_writes_the_changes_since_an_earlier_index() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-diff").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    let index = output_dir.path().join("index.json");
+    let page = |name: &str, title: &str| {
+        write(
+            input_dir.path().join(format!("{}.md", name)),
+            format!(
+                "+++\ntitle = \"{}\"\ndate = \"2019-01-25\"\n+++\nContents",
+                title
+            ),
+        )
+    };
+    page("kept", "Kept")?;
+    page("renamed", "Before")?;
+    page("removed", "Removed")?;
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--output")
+        .arg(&index)
+        .assert()
+        .success();
+
+    page("renamed", "After")?;
+    remove_file(input_dir.path().join("removed.md"))?;
+    page("added", "Added")?;
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--diff")
+        .arg(&index)
+        .arg("--output")
+        .arg(&index)
+        .assert()
+        .success();
+    let changes: Value =
+        serde_json::from_str(&read_to_string(output_dir.path().join("changes.json"))?)?;
+    assert_eq!(
+        changes,
+        serde_json::json!({
+            "added": ["/added/"],
+            "removed": ["/removed/"],
+            "updated": [{"href": "/renamed/", "fields": ["title"]}],
+        })
+    );
+    let pages: Value = serde_json::from_str(&read_to_string(&index)?)?;
+    assert_eq!(pages.as_array().map(Vec::len), Some(3));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn dash_output_writes_only_the_index_to_stdout() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-stdout").tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    write(input_dir.path().join("broken.md"), "+++\ntitle = \n+++\n")?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--output")
+        .arg("-")
+        .arg("--fail-on")
+        .arg("none")
+        .env("HUGO_TO_JSON_LOG", "debug")
+        .output()?;
+    assert!(output.status.success());
+    let index: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(index[0]["title"], "First");
+    assert!(!output.stderr.is_empty());
+
+    // Only errors are logged when quiet
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--output")
+        .arg("-")
+        .arg("--fail-on")
+        .arg("none")
+        .arg("--quiet")
+        .output()?;
+    assert!(output.status.success());
+    serde_json::from_slice::<Value>(&output.stdout)?;
+    assert!(!String::from_utf8(output.stderr)?.contains("INFO"));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn no_clobber_on_error_keeps_the_existing_index() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-clobber").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    let index = output_dir.path().join("index.json");
+    write(&index, "[]")?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    write(input_dir.path().join("broken.md"), "+++\ntitle = \n+++\n")?;
+
+    for sort in &[false, true] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        cmd.arg(input_dir.path())
+            .arg("--no-clobber-on-error")
+            .arg("--output")
+            .arg(&index)
+            .arg("--rss")
+            .arg(output_dir.path().join("feed.xml"));
+        if *sort {
+            cmd.arg("--sort").arg("title");
+        }
+        cmd.assert().failure();
+        assert_eq!(read_to_string(&index)?, "[]");
+    }
+    // Only the index is left in the output directory, without the feed
+    assert_eq!(read_dir(output_dir.path())?.count(), 1);
+
+    let split_dir = Builder::new()
+        .prefix("not-a-dotfile-clobber-split")
+        .tempdir()?;
+    let split_index = split_dir.path().join("index.json");
+    write(&split_index, "[]")?;
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--no-clobber-on-error")
+        .arg("--output")
+        .arg(split_dir.path())
+        .arg("--split-by-section")
+        .arg("--rss")
+        .arg(split_dir.path().join("feed.xml"))
+        .assert()
+        .failure();
+    assert_eq!(read_to_string(&split_index)?, "[]");
+    assert_eq!(read_dir(split_dir.path())?.count(), 1);
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--output")
+        .arg(&index)
+        .assert()
+        .failure();
+    let pages: Value = serde_json::from_str(&read_to_string(&index)?)?;
+    assert_eq!(pages[0]["title"], "First");
+
+    input_dir.close()?;
+    output_dir.close()?;
+    split_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn checksum_is_written_beside_the_index() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-checksum").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    let index = output_dir.path().join("index.json");
+
+    for _ in 0..2 {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        cmd.arg(input_dir.path())
+            .arg("--checksum")
+            .arg("--skip-unchanged")
+            .arg("--output")
+            .arg(&index)
+            .assert()
+            .success();
+    }
+    let checksum = read_to_string(output_dir.path().join("index.json.sha256"))?;
+    assert!(checksum.ends_with("  index.json\n"));
+    assert_eq!(checksum.len(), 64 + "  index.json\n".len());
+
+    // An index that isn't written to a file can't be checksummed or left untouched
+    for (option, output) in &[
+        ("--checksum", None),
+        ("--checksum", Some("-")),
+        ("--skip-unchanged", Some("http://127.0.0.1:9/reindex")),
+    ] {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        cmd.arg(input_dir.path()).arg(option);
+        if let Some(output) = output {
+            cmd.arg("--output").arg(output);
+        }
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "{} needs the index written to a path given with --output",
+                option
+            )));
+    }
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn envelope_is_signed() -> Result<(), Box<dyn std::error::Error>> {
+    use ed25519_dalek::pkcs8::{spki::der::pem::LineEnding, EncodePrivateKey};
+
+    let input_dir = Builder::new().prefix("not-a-dotfile-sign").tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    write(
+        input_dir.path().join("first.md"),
+        "+++\ntitle = \"First\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    let key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+    let key_file = output_dir.path().join("signing.pem");
+    write(&key_file, key.to_pkcs8_pem(LineEnding::LF)?.as_bytes())?;
+    let index = output_dir.path().join("index.json");
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--envelope")
+        .arg("--sign")
+        .arg(format!("ed25519:{}", key_file.to_string_lossy()))
+        .arg("--output")
+        .arg(&index)
+        .assert()
+        .success();
+
+    let envelope: Value = serde_json::from_str(&read_to_string(&index)?)?;
+    assert_eq!(envelope["page_count"], 1);
+    assert_eq!(envelope["pages"][0]["title"], "First");
+    assert_eq!(envelope["generator_version"], env!("CARGO_PKG_VERSION"));
+    assert!(envelope["generated_at"].is_string());
+    let signature = read_to_string(output_dir.path().join("index.json.sig"))?;
+    assert_eq!(signature.trim_end().len(), 88);
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--sign")
+        .arg("rsa:signing.pem")
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg(input_dir.path())
+        .arg("--sign")
+        .arg(format!("ed25519:{}", key_file.to_string_lossy()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--sign needs the index written to a path given with --output",
+        ));
+
+    input_dir.close()?;
+    output_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn drafts_only_indexes_only_drafts() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-drafts").tempdir()?;
+    write(
+        input_dir.path().join("published.md"),
+        "+++\ntitle = \"Published\"\ndate = \"2019-01-25\"\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("draft.md"),
+        "+++\ntitle = \"Draft\"\ndate = \"2019-01-26\"\ndraft = true\n+++\nContents",
+    )?;
+
+    let titles = |flag: &str| -> Result<Vec<(String, bool)>, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+        let output = cmd
+            .arg(input_dir.path())
+            .arg(flag)
+            .arg("--sort")
+            .arg("title")
+            .output()?;
+        let index: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+        Ok(index
+            .iter()
+            .map(|page| {
+                (
+                    page["title"].as_str().unwrap_or_default().to_owned(),
+                    page["draft"].as_bool().unwrap_or(false),
+                )
+            })
+            .collect())
+    };
+    assert_eq!(
+        titles("--drafts")?,
+        vec![
+            (String::from("Draft"), true),
+            (String::from("Published"), false)
+        ]
+    );
+    assert_eq!(
+        titles("--drafts-only")?,
+        vec![(String::from("Draft"), true)]
+    );
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn params_keeps_nested_front_matter() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-params").tempdir()?;
+    write(
+        input_dir.path().join("toml.md"),
+        "+++\ntitle = \"TOML\"\ndate = \"2019-01-25\"\nrating = 4.5\n[params]\nfeatured = true\n[params.cover]\nimage = \"a.png\"\nsizes = [320, 640]\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("yaml.md"),
+        "---\ntitle: YAML\ndate: 2019-01-26\nrating: 4\nparams:\n  cover:\n    image: b.png\n---\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--params")
+        .arg("--sort")
+        .arg("title")
+        .output()?;
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        v[0]["params"],
+        serde_json::json!({"rating": 4.5, "featured": true, "cover": {"image": "a.png", "sizes": [320, 640]}})
+    );
+    assert_eq!(
+        v[1]["params"],
+        serde_json::json!({"rating": 4, "cover": {"image": "b.png"}})
+    );
+    assert!(v[0].get("extra").is_none());
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn menus_are_read_from_front_matter() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-menus").tempdir()?;
+    write(
+        input_dir.path().join("about.md"),
+        "---\ntitle: About this site\nlinkTitle: About\ndate: 2019-01-25\nweight: 3\nmenu:\n  main:\n    parent: company\n  footer:\n    name: Who we are\n    weight: 1\n---\nContents",
+    )?;
+    write(
+        input_dir.path().join("contact.md"),
+        "+++\ntitle = \"Contact\"\ndate = \"2019-01-26\"\nmenu = \"main\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--sort")
+        .arg("title")
+        .output()?;
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        v[0]["menus"],
+        serde_json::json!([
+            {"menu": "footer", "name": "Who we are", "weight": 1},
+            {"menu": "main", "name": "About", "weight": 3, "parent": "company"},
+        ])
+    );
+    assert_eq!(
+        v[1]["menus"],
+        serde_json::json!([{"menu": "main", "name": "Contact"}])
+    );
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn geojson_format_maps_located_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = Builder::new().prefix("not-a-dotfile-geojson").tempdir()?;
+    write(
+        input_dir.path().join("lisbon.md"),
+        "---\ntitle: Lisbon\ndate: 2019-01-25\ntags: [portugal]\nlocation:\n  lat: 38.72\n  lng: -9.14\n  name: Lisbon\n---\nContents",
+    )?;
+    write(
+        input_dir.path().join("porto.md"),
+        "+++\ntitle = \"Porto\"\ndate = \"2019-01-26\"\nlocation = \"41.15, -8.61\"\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("home.md"),
+        "+++\ntitle = \"Home\"\ndate = \"2019-01-27\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--format")
+        .arg("geojson")
+        .arg("--sort")
+        .arg("title")
+        .arg("--validate")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(v["type"], "FeatureCollection");
+    assert_eq!(
+        v["features"][0],
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [-9.14, 38.72]},
+            "properties": {"title": "Lisbon", "href": "/lisbon/", "date": "2019-01-25", "place": "Lisbon", "summary": "Contents", "tags": ["portugal"]},
+        })
+    );
+    assert_eq!(
+        v["features"][1]["geometry"]["coordinates"],
+        serde_json::json!([-8.61, 41.15])
+    );
+    assert_eq!(v["features"].as_array().map(Vec::len), Some(2));
+
+    input_dir.close()?;
+    Ok(())
+}
+
+#[test]
+fn opensearch_suggest_format_is_keyed_by_title_prefixes() -> Result<(), Box<dyn std::error::Error>>
+{
+    let input_dir = Builder::new()
+        .prefix("not-a-dotfile-opensearch")
+        .tempdir()?;
+    write(
+        input_dir.path().join("rust.md"),
+        "+++\ntitle = \"Rust\"\ndate = \"2019-01-25\"\ndescription = \"About Rust\"\n+++\nContents",
+    )?;
+    write(
+        input_dir.path().join("ruby.md"),
+        "+++\ntitle = \"Ruby\"\ndate = \"2019-01-26\"\n+++\nContents",
+    )?;
+
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    let output = cmd
+        .arg(input_dir.path())
+        .arg("--format")
+        .arg("opensearch-suggest")
+        .arg("--sort")
+        .arg("title")
+        .arg("--validate")
+        .output()?;
+    assert!(output.status.success());
+    let v: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        v["ru"],
+        serde_json::json!([
+            "ru",
+            ["Ruby", "Rust"],
+            ["Contents", "About Rust"],
+            ["/ruby/", "/rust/"]
+        ])
+    );
+    assert_eq!(
+        v["rus"],
+        serde_json::json!(["rus", ["Rust"], ["About Rust"], ["/rust/"]])
+    );
+    assert_eq!(v.as_object().map(|prefixes| prefixes.len()), Some(6));
+
+    input_dir.close()?;
+    Ok(())
+}